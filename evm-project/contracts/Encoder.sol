@@ -15,6 +15,21 @@ contract Encoder is Messages {
     uint8 public constant DIRECT_TRANSFER = 17;
     uint8 public constant INITIALIZE_PDA = 18;
     uint8 public constant INITIALIZE_TOKEN_ACCOUNT = 19;
+    uint8 public constant BATCH_STREAM = 20;
+    uint8 public constant TOKEN_WITHDRAW_STREAM_BRIDGE = 21;
+    uint8 public constant SET_PAYOUT_WALLET = 22;
+    uint8 public constant TOKEN_WITHDRAW_STREAM_PAYOUT = 23;
+    uint8 public constant GOVERN_REGISTER_CHAIN = 30;
+    uint8 public constant GOVERN_UPDATE_CHAIN = 31;
+    uint8 public constant GOVERN_SET_CHAIN_LIMIT = 32;
+    uint8 public constant GOVERN_SET_MINT_LIMIT = 33;
+    uint8 public constant GOVERN_REGISTER_TARGET_PROGRAM = 34;
+    uint8 public constant GOVERN_REMOVE_TARGET_PROGRAM = 35;
+    uint8 public constant GOVERN_SET_OWNER = 36;
+
+    // The whole VAA is posted to Solana in one transaction, which limits how
+    // many entries a batch can carry.
+    uint8 public constant MAX_BATCH_ENTRIES = 10;
 
     function encode_initialize_pda(Messages.InitializePDA memory initializePDA) public pure returns (bytes memory encoded){
         encoded = abi.encodePacked(
@@ -33,6 +48,82 @@ contract Encoder is Messages {
         );
     }
 
+    function encode_set_payout_wallet(Messages.SetPayoutWallet memory setPayoutWallet) public pure returns (bytes memory encoded){
+        encoded = abi.encodePacked(
+            SET_PAYOUT_WALLET,
+            setPayoutWallet.account,
+            setPayoutWallet.wallet,
+            setPayoutWallet.toChain
+        );
+    }
+
+    function encode_govern_register_chain(Messages.GovernRegisterChain memory action) public pure returns (bytes memory encoded){
+        require(bytes(action.label).length <= 32, "label too long");
+        encoded = abi.encodePacked(
+            GOVERN_REGISTER_CHAIN,
+            action.proxyProgram,
+            action.chainId,
+            action.emitter,
+            action.addressFormat,
+            action.label
+        );
+    }
+
+    function encode_govern_update_chain(Messages.GovernUpdateChain memory action) public pure returns (bytes memory encoded){
+        encoded = abi.encodePacked(
+            GOVERN_UPDATE_CHAIN,
+            action.proxyProgram,
+            action.chainId,
+            action.enabled
+        );
+    }
+
+    function encode_govern_set_chain_limit(Messages.GovernSetChainLimit memory action) public pure returns (bytes memory encoded){
+        encoded = abi.encodePacked(
+            GOVERN_SET_CHAIN_LIMIT,
+            action.proxyProgram,
+            action.chainId,
+            action.dailyLimit
+        );
+    }
+
+    function encode_govern_set_mint_limit(Messages.GovernSetMintLimit memory action) public pure returns (bytes memory encoded){
+        encoded = abi.encodePacked(
+            GOVERN_SET_MINT_LIMIT,
+            action.proxyProgram,
+            action.mint,
+            action.dailyLimit,
+            action.notionalPrice,
+            action.largeTransferThreshold,
+            action.holdSeconds
+        );
+    }
+
+    function encode_govern_register_target_program(Messages.GovernRegisterTargetProgram memory action) public pure returns (bytes memory encoded){
+        encoded = abi.encodePacked(
+            GOVERN_REGISTER_TARGET_PROGRAM,
+            action.proxyProgram,
+            action.version,
+            action.programId
+        );
+    }
+
+    function encode_govern_remove_target_program(Messages.GovernRemoveTargetProgram memory action) public pure returns (bytes memory encoded){
+        encoded = abi.encodePacked(
+            GOVERN_REMOVE_TARGET_PROGRAM,
+            action.proxyProgram,
+            action.version
+        );
+    }
+
+    function encode_govern_set_owner(Messages.GovernSetOwner memory action) public pure returns (bytes memory encoded){
+        encoded = abi.encodePacked(
+            GOVERN_SET_OWNER,
+            action.proxyProgram,
+            action.owner
+        );
+    }
+
     function encode_token_stream(Messages.ProcessStreamToken memory processStream) public pure returns (bytes memory encoded){
         encoded = abi.encodePacked(
             TOKEN_STREAM,
@@ -73,6 +164,30 @@ contract Encoder is Messages {
         );
     }
 
+    function encode_token_withdraw_stream_bridge(Messages.ProcessWithdrawStreamBridgeToken memory processWithdrawStream) public pure returns (bytes memory encoded){
+        encoded = abi.encodePacked(
+            TOKEN_WITHDRAW_STREAM_BRIDGE,
+            processWithdrawStream.toChain,
+            processWithdrawStream.withdrawer,
+            processWithdrawStream.token_mint,
+            processWithdrawStream.sender_address,
+            processWithdrawStream.data_account_address,
+            processWithdrawStream.target_chain,
+            processWithdrawStream.fee
+        );
+    }
+
+    function encode_token_withdraw_stream_payout(Messages.ProcessWithdrawStreamToken memory processWithdrawStream) public pure returns (bytes memory encoded){
+        encoded = abi.encodePacked(
+            TOKEN_WITHDRAW_STREAM_PAYOUT,
+            processWithdrawStream.toChain,
+            processWithdrawStream.withdrawer,
+            processWithdrawStream.token_mint,
+            processWithdrawStream.sender_address,
+            processWithdrawStream.data_account_address
+        );
+    }
+
     function encode_process_deposit_token(Messages.ProcessDepositToken memory processDeposit) public pure returns (bytes memory encoded){
         encoded = abi.encodePacked(
             DEPOSIT_TOKEN,
@@ -126,14 +241,41 @@ contract Encoder is Messages {
         );
     }
 
-    function encode_process_direct_transfer(Messages.ProcessTransferToken memory processTransfer) public pure returns (bytes memory encoded){
+    function encode_process_direct_transfer(Messages.ProcessDirectTransferToken memory processTransfer) public pure returns (bytes memory encoded){
         encoded = abi.encodePacked(
             DIRECT_TRANSFER,
             processTransfer.amount,
             processTransfer.toChain,
             processTransfer.sender,
             processTransfer.token_mint,
-            processTransfer.receiver
+            processTransfer.receiver,
+            processTransfer.target_chain,
+            processTransfer.max_fee
+        );
+    }
+
+    function encode_batch_stream(Messages.ProcessBatchStream memory processBatch) public pure returns (bytes memory encoded){
+        require(
+            processBatch.entries.length > 0 && processBatch.entries.length <= MAX_BATCH_ENTRIES,
+            "invalid batch size"
+        );
+        encoded = abi.encodePacked(
+            BATCH_STREAM,
+            processBatch.toChain,
+            processBatch.sender,
+            processBatch.token_mint,
+            uint8(processBatch.entries.length)
         );
+        for (uint256 i = 0; i < processBatch.entries.length; i++) {
+            Messages.BatchStreamEntry memory entry = processBatch.entries[i];
+            encoded = abi.encodePacked(
+                encoded,
+                entry.receiver,
+                entry.amount,
+                entry.start_time,
+                entry.end_time,
+                entry.flags
+            );
+        }
     }
 }
\ No newline at end of file
@@ -18,6 +18,14 @@ contract Messages {
         uint256 toChain;
     }
 
+    struct SetPayoutWallet{
+        // The caller, left-padded to 32 bytes
+        bytes32 account;
+        // Solana wallet that receives withdrawals, or zero to unlink
+        bytes32 wallet;
+        uint256 toChain;
+    }
+
     struct UpdateStreamToken {
         uint64 start_time;
         uint64 end_time;
@@ -49,6 +57,18 @@ contract Messages {
         bytes data_account_address;
     }
 
+    struct ProcessWithdrawStreamBridgeToken {
+        uint256 toChain;
+        bytes withdrawer;
+        bytes token_mint;
+        bytes sender_address;
+        bytes data_account_address;
+        // Wormhole chain the withdrawn tokens are sent to
+        uint16 target_chain;
+        // Relayer fee, in the token's base units like amounts
+        uint64 fee;
+    }
+
     struct PauseStreamToken {
         uint256 toChain;
         bytes sender;
@@ -80,6 +100,18 @@ contract Messages {
         bytes receiver;
     }
 
+    struct ProcessDirectTransferToken {
+        uint64 amount;
+        uint256 toChain;
+        bytes sender;
+        bytes token_mint;
+        bytes receiver;
+        // Wormhole chain the tokens are sent on to
+        uint16 target_chain;
+        // Highest relayer fee, in the token's base units like amounts
+        uint64 max_fee;
+    }
+
     struct ProcessWithdrawToken {
         uint64 amount;
         uint256 toChain;
@@ -87,4 +119,69 @@ contract Messages {
         bytes token_mint;
     }
 
+    struct BatchStreamEntry {
+        bytes receiver;
+        uint64 amount;
+        uint64 start_time;
+        uint64 end_time;
+        // Bit 0 can_cancel, bit 1 can_update.
+        uint8 flags;
+    }
+
+    struct ProcessBatchStream {
+        uint256 toChain;
+        bytes sender;
+        bytes token_mint;
+        BatchStreamEntry[] entries;
+    }
+
+    // Governance actions for the Solana proxy. Each names the proxy's
+    // program id, and only VAAs of its governance emitter are applied.
+    struct GovernRegisterChain {
+        bytes32 proxyProgram;
+        uint16 chainId;
+        bytes32 emitter;
+        // 0 EVM, 1 Solana, 2 Aptos, 3 Sui, 4 Near
+        uint8 addressFormat;
+        // Up to 32 bytes
+        string label;
+    }
+
+    struct GovernUpdateChain {
+        bytes32 proxyProgram;
+        uint16 chainId;
+        bool enabled;
+    }
+
+    struct GovernSetChainLimit {
+        bytes32 proxyProgram;
+        uint16 chainId;
+        uint64 dailyLimit;
+    }
+
+    struct GovernSetMintLimit {
+        bytes32 proxyProgram;
+        bytes32 mint;
+        uint64 dailyLimit;
+        uint64 notionalPrice;
+        uint64 largeTransferThreshold;
+        uint32 holdSeconds;
+    }
+
+    struct GovernRegisterTargetProgram {
+        bytes32 proxyProgram;
+        uint8 version;
+        bytes32 programId;
+    }
+
+    struct GovernRemoveTargetProgram {
+        bytes32 proxyProgram;
+        uint8 version;
+    }
+
+    struct GovernSetOwner {
+        bytes32 proxyProgram;
+        bytes32 owner;
+    }
+
 }
\ No newline at end of file
@@ -28,14 +28,18 @@ contract Messenger is Encoder {
     event TokenStream(bytes sender, bytes receiver, bytes tokenMint, uint64 amount, uint32 nonce);
     event TokenStreamUpdate(bytes sender, bytes receiver, bytes tokenMint, uint64 amount, uint32 nonce);
     event WithdrawToken(bytes withdrawer, bytes tokenMint, uint32 nonce);
+    event WithdrawTokenBridge(bytes withdrawer, bytes tokenMint, uint16 targetChain, uint64 fee, uint32 nonce);
     event PauseTokenStream(bytes receiver, bytes tokenMint, uint32 nonce);
     event CancelTokenStream(bytes receiver, bytes tokenMint, uint32 nonce);
     event InstantTokenTransfer(bytes receiver, bytes tokenMint, uint64 amount, uint32 nonce);
     event TokenWithdrawal(bytes withdrawer, bytes tokenMint, uint64 amount, uint32 nonce);
-    event DirectTransfer(bytes sender, bytes receiver, bytes tokenMint, uint64 amount, uint32 nonce);
+    event DirectTransfer(bytes sender, bytes receiver, bytes tokenMint, uint64 amount, uint16 targetChain, uint64 maxFee, uint32 nonce);
+    event BatchStream(bytes sender, bytes tokenMint, uint8 entries, uint32 nonce);
 
     event PDAInitialize(bytes account, uint32 nonce);
     event TokenAccountInitialize(bytes account, bytes tokenMint, uint32 nonce);
+    event PayoutWalletSet(address account, bytes32 wallet, uint32 nonce);
+    event WithdrawTokenPayout(bytes withdrawer, bytes tokenMint, uint32 nonce);
 
     constructor(address wormholeAddress, address weth, uint256 arbiter_fee) {
         _wormhole = IWormhole(wormholeAddress); //0x706abc4E45D419950511e474C7B9Ed348A4a716c
@@ -82,6 +86,28 @@ contract Messenger is Encoder {
         emit TokenAccountInitialize(account, token_mint, nonce); 
     }
 
+    // Links the caller to a Solana wallet that its payout withdrawals are
+    // paid into. Unlike the other messages the account is always the caller,
+    // since the link decides where funds go.
+    function set_payout_wallet(
+        bytes32 wallet
+    ) public payable {
+        nonce++;
+        bytes memory encoded_data = Encoder.encode_set_payout_wallet(
+            Messages.SetPayoutWallet({
+                account: bytes32(uint256(uint160(msg.sender))),
+                wallet: wallet,
+                toChain: getChainId()
+            })
+        );
+        _bridgeInstructionInWormhole(
+            nonce,
+            encoded_data,
+            _arbiter_fee
+        );
+        emit PayoutWalletSet(msg.sender, wallet, nonce);
+    }
+
     function process_deposit_token(
         uint64 amount, 
         bytes memory depositor,
@@ -190,6 +216,60 @@ contract Messenger is Encoder {
         emit WithdrawToken(withdrawer, token_mint, nonce);
     }
 
+    // Withdraws for the caller. The tokens are bridged on to wherever the
+    // receiver asks, so only the receiver can ask.
+    function process_token_withdraw_stream_bridge(
+        bytes memory token_mint,
+        bytes memory sender_address,
+        bytes memory data_account_address,
+        uint16 target_chain,
+        uint64 fee
+    ) public payable  {
+        nonce++;
+        bytes memory withdrawer = abi.encodePacked(bytes32(uint256(uint160(msg.sender))));
+        bytes memory encoded_data = Encoder.encode_token_withdraw_stream_bridge(
+            Messages.ProcessWithdrawStreamBridgeToken({
+                toChain: getChainId(),
+                withdrawer: withdrawer,
+                token_mint: token_mint,
+                sender_address: sender_address,
+                data_account_address: data_account_address,
+                target_chain: target_chain,
+                fee: fee
+            })
+        );
+         _bridgeInstructionInWormhole(
+            nonce,
+            encoded_data,
+            _arbiter_fee
+        );
+        emit WithdrawTokenBridge(withdrawer, token_mint, target_chain, fee, nonce);
+    }
+
+    function process_token_withdraw_stream_payout(
+        bytes memory withdrawer,
+        bytes memory token_mint,
+        bytes memory sender_address,
+        bytes memory data_account_address
+    ) public payable  {
+        nonce++;
+        bytes memory encoded_data = Encoder.encode_token_withdraw_stream_payout(
+            Messages.ProcessWithdrawStreamToken({
+                toChain: getChainId(),
+                withdrawer: withdrawer,
+                token_mint: token_mint,
+                sender_address: sender_address,
+                data_account_address: data_account_address
+            })
+        );
+         _bridgeInstructionInWormhole(
+            nonce,
+            encoded_data,
+            _arbiter_fee
+        );
+        emit WithdrawTokenPayout(withdrawer, token_mint, nonce);
+    }
+
    function process_pause_token_stream(
         bytes memory sender,
         bytes memory token_mint,
@@ -290,16 +370,42 @@ contract Messenger is Encoder {
         uint64 amount, 
         bytes memory sender,
         bytes memory token_mint,
-        bytes memory receiver
+        bytes memory receiver,
+        uint16 target_chain,
+        uint64 max_fee
     ) public payable {
         nonce++;
         bytes memory encoded_data = Encoder.encode_process_direct_transfer(
-            Messages.ProcessTransferToken({
+            Messages.ProcessDirectTransferToken({
                 amount: amount,
                 toChain: getChainId(),
                 receiver: receiver,
                 token_mint: token_mint,
-                sender: sender
+                sender: sender,
+                target_chain: target_chain,
+                max_fee: max_fee
+            })
+        );
+         _bridgeInstructionInWormhole(
+            nonce,
+            encoded_data,
+            _arbiter_fee
+        );
+        emit DirectTransfer(sender, receiver, token_mint, amount, target_chain, max_fee, nonce);
+    }
+
+    function process_batch_stream(
+        bytes memory sender,
+        bytes memory token_mint,
+        Messages.BatchStreamEntry[] memory entries
+    ) public payable {
+        nonce++;
+        bytes memory encoded_data = Encoder.encode_batch_stream(
+            Messages.ProcessBatchStream({
+                toChain: getChainId(),
+                sender: sender,
+                token_mint: token_mint,
+                entries: entries
             })
         );
          _bridgeInstructionInWormhole(
@@ -307,7 +413,7 @@ contract Messenger is Encoder {
             encoded_data,
             _arbiter_fee
         );
-        emit DirectTransfer(sender, receiver, token_mint, amount, nonce);
+        emit BatchStream(sender, token_mint, uint8(entries.length), nonce);
     }
 
     function _bridgeInstructionInWormhole(uint32 nonceValue, bytes memory stream, uint256 arbiterFee) internal returns(uint64 sequence){
@@ -76,6 +76,7 @@ export async function sendMsg() {
             tryNativeToUint8Array(sender, CHAIN_ID),
             mintUint8,
             tryNativeToUint8Array(receiver, CHAIN_ID),
+            CHAIN_ID,
             BigNumber.from("10"),
             {
                 gasLimit: BigNumber.from("10000000"),
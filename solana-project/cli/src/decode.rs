@@ -0,0 +1,583 @@
+//! The `decode` command: a VAA, its Zebec message by field, and the proxy
+//! accounts it touches, optionally checked against live state.
+
+use std::fmt;
+
+use anchor_lang::AccountDeserialize;
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+
+use solana_project::state::{BatchData, Count, ProcessedVAA, TransactionStatus};
+use zebec_wormhole_client::decode::{self, DecodedVaa, DerivedAccounts, VaaSource};
+use zebec_wormhole_client::message::ZebecMessage;
+
+use crate::error::CliError;
+use crate::source::AccountSource;
+
+/// Ordered `name: value` pairs; serializes as a JSON object in field order.
+#[derive(Debug)]
+pub struct Fields(pub Vec<(&'static str, Value)>);
+
+impl Serialize for Fields {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, value) in &self.0 {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl Fields {
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value)
+    }
+}
+
+fn address(bytes: &[u8; 32]) -> Value {
+    json!(format!("0x{}", hex::encode(bytes)))
+}
+
+fn key(key: &Pubkey) -> Value {
+    json!(key.to_string())
+}
+
+/// Name and fields of a message, in `Encoder.sol` order.
+pub fn message_fields(message: &ZebecMessage) -> (&'static str, Fields) {
+    let (name, fields) = match message {
+        ZebecMessage::TokenStream {
+            start_time,
+            end_time,
+            amount,
+            sender,
+            receiver,
+            can_cancel,
+            can_update,
+            token_mint,
+        } => (
+            "token_stream",
+            vec![
+                ("start_time", json!(start_time)),
+                ("end_time", json!(end_time)),
+                ("amount", json!(amount)),
+                ("sender", address(sender)),
+                ("receiver", address(receiver)),
+                ("can_cancel", json!(can_cancel)),
+                ("can_update", json!(can_update)),
+                ("token_mint", key(token_mint)),
+            ],
+        ),
+        ZebecMessage::WithdrawStream {
+            withdrawer,
+            token_mint,
+            sender,
+            data_account,
+        } => (
+            "withdraw_stream",
+            vec![
+                ("withdrawer", address(withdrawer)),
+                ("token_mint", key(token_mint)),
+                ("sender", address(sender)),
+                ("data_account", key(data_account)),
+            ],
+        ),
+        ZebecMessage::WithdrawStreamBridge {
+            withdrawer,
+            token_mint,
+            sender,
+            data_account,
+            target_chain,
+            fee,
+        } => (
+            "withdraw_stream_bridge",
+            vec![
+                ("withdrawer", address(withdrawer)),
+                ("token_mint", key(token_mint)),
+                ("sender", address(sender)),
+                ("data_account", key(data_account)),
+                ("target_chain", json!(target_chain)),
+                ("fee", json!(fee)),
+            ],
+        ),
+        ZebecMessage::WithdrawStreamPayout {
+            withdrawer,
+            token_mint,
+            sender,
+            data_account,
+        } => (
+            "withdraw_stream_payout",
+            vec![
+                ("withdrawer", address(withdrawer)),
+                ("token_mint", key(token_mint)),
+                ("sender", address(sender)),
+                ("data_account", key(data_account)),
+            ],
+        ),
+        ZebecMessage::Deposit {
+            amount,
+            depositor,
+            token_mint,
+        } => (
+            "deposit",
+            vec![
+                ("amount", json!(amount)),
+                ("depositor", address(depositor)),
+                ("token_mint", key(token_mint)),
+            ],
+        ),
+        ZebecMessage::PauseResume {
+            sender,
+            token_mint,
+            receiver,
+            data_account,
+        }
+        | ZebecMessage::Cancel {
+            sender,
+            token_mint,
+            receiver,
+            data_account,
+        } => (
+            if matches!(message, ZebecMessage::Cancel { .. }) {
+                "cancel"
+            } else {
+                "pause_resume"
+            },
+            vec![
+                ("sender", address(sender)),
+                ("token_mint", key(token_mint)),
+                ("receiver", address(receiver)),
+                ("data_account", key(data_account)),
+            ],
+        ),
+        ZebecMessage::SenderWithdraw {
+            amount,
+            withdrawer,
+            token_mint,
+        } => (
+            "sender_withdraw",
+            vec![
+                ("amount", json!(amount)),
+                ("withdrawer", address(withdrawer)),
+                ("token_mint", key(token_mint)),
+            ],
+        ),
+        ZebecMessage::InstantTransfer {
+            amount,
+            sender,
+            token_mint,
+            receiver,
+        } => (
+            "instant_transfer",
+            vec![
+                ("amount", json!(amount)),
+                ("sender", address(sender)),
+                ("token_mint", key(token_mint)),
+                ("receiver", address(receiver)),
+            ],
+        ),
+        ZebecMessage::DirectTransfer {
+            amount,
+            sender,
+            token_mint,
+            receiver,
+            target_chain,
+            max_fee,
+        } => (
+            "direct_transfer",
+            vec![
+                ("amount", json!(amount)),
+                ("sender", address(sender)),
+                ("token_mint", key(token_mint)),
+                ("receiver", address(receiver)),
+                ("target_chain", json!(target_chain)),
+                ("max_fee", json!(max_fee)),
+            ],
+        ),
+        ZebecMessage::StreamUpdate {
+            start_time,
+            end_time,
+            amount,
+            sender,
+            receiver,
+            token_mint,
+            data_account,
+        } => (
+            "stream_update",
+            vec![
+                ("start_time", json!(start_time)),
+                ("end_time", json!(end_time)),
+                ("amount", json!(amount)),
+                ("sender", address(sender)),
+                ("receiver", address(receiver)),
+                ("token_mint", key(token_mint)),
+                ("data_account", key(data_account)),
+            ],
+        ),
+        ZebecMessage::InitializePda { account } => {
+            ("initialize_pda", vec![("account", address(account))])
+        }
+        ZebecMessage::InitializeTokenAccount {
+            account,
+            token_mint,
+        } => (
+            "initialize_token_account",
+            vec![
+                ("account", address(account)),
+                ("token_mint", key(token_mint)),
+            ],
+        ),
+        ZebecMessage::SetPayoutWallet { account, wallet } => (
+            "set_payout_wallet",
+            vec![("account", address(account)), ("wallet", key(wallet))],
+        ),
+        ZebecMessage::BatchStream {
+            sender,
+            token_mint,
+            entries,
+        } => (
+            "batch_stream",
+            vec![
+                ("sender", address(sender)),
+                ("token_mint", key(token_mint)),
+                (
+                    "entries",
+                    entries
+                        .iter()
+                        .map(|entry| {
+                            json!({
+                                "receiver": address(&entry.receiver),
+                                "amount": entry.amount,
+                                "start_time": entry.start_time,
+                                "end_time": entry.end_time,
+                                "can_cancel": entry.can_cancel,
+                                "can_update": entry.can_update,
+                            })
+                        })
+                        .collect(),
+                ),
+            ],
+        ),
+    };
+    (name, Fields(fields))
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignatureView {
+    pub index: u8,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeaderView {
+    pub version: u8,
+    /// `None` for a posted VAA, which does not keep it.
+    pub guardian_set_index: Option<u32>,
+    pub signatures: Vec<SignatureView>,
+    /// Signature set account of a posted VAA.
+    pub signature_set: Option<String>,
+    pub submission_time: Option<u32>,
+    pub timestamp: u32,
+    pub nonce: u32,
+    pub emitter_chain: u16,
+    pub emitter_address: String,
+    pub sequence: u64,
+    pub consistency_level: u8,
+    pub hash: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MessageFieldsView {
+    pub code: u8,
+    pub kind: &'static str,
+    pub fields: Fields,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountsView {
+    pub posted_vaa: String,
+    pub emitter_account: String,
+    pub processed_vaa: String,
+    pub sender_pda: Option<String>,
+    pub stream_sender_pda: Option<String>,
+    pub receiver_pda: Option<String>,
+    pub txn_count: Option<String>,
+    pub data_store: Option<String>,
+    pub txn_status: Option<String>,
+    pub batch_store: Option<String>,
+    pub target_program: Option<String>,
+}
+
+impl From<&DerivedAccounts> for AccountsView {
+    fn from(accounts: &DerivedAccounts) -> Self {
+        let show = |key: &Option<Pubkey>| key.map(|key| key.to_string());
+        AccountsView {
+            posted_vaa: accounts.posted_vaa.to_string(),
+            emitter_account: accounts.emitter_account.to_string(),
+            processed_vaa: accounts.processed_vaa.to_string(),
+            sender_pda: show(&accounts.sender_pda),
+            stream_sender_pda: show(&accounts.stream_sender_pda),
+            receiver_pda: show(&accounts.receiver_pda),
+            txn_count: show(&accounts.txn_count),
+            data_store: show(&accounts.data_store),
+            txn_status: show(&accounts.txn_status),
+            batch_store: show(&accounts.batch_store),
+            target_program: show(&accounts.target_program),
+        }
+    }
+}
+
+/// How far the relayer got with the message, read from account state.
+#[derive(Debug, Serialize)]
+pub struct StatusView {
+    pub posted: bool,
+    pub stored: bool,
+    pub executed: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DecodedView {
+    pub header: HeaderView,
+    pub message: Option<MessageFieldsView>,
+    /// Why the payload is not a Zebec message.
+    pub message_error: Option<String>,
+    /// Count `data_store` and `txn_status` are derived from.
+    pub count: Option<u64>,
+    /// `stored` if read from `processed_vaa`, `next` if predicted from
+    /// `txn_count`, `given` if passed in.
+    pub count_source: Option<&'static str>,
+    pub accounts: AccountsView,
+    pub status: Option<StatusView>,
+}
+
+impl DecodedView {
+    fn new(decoded: &DecodedVaa, count: Option<(u64, &'static str)>) -> Self {
+        let vaa = &decoded.vaa;
+        let (signature_set, submission_time) = match &decoded.source {
+            VaaSource::Signed => (None, None),
+            VaaSource::Posted {
+                signature_set,
+                submission_time,
+            } => (Some(signature_set.to_string()), Some(*submission_time)),
+        };
+        let header = HeaderView {
+            version: vaa.version,
+            guardian_set_index: signature_set.is_none().then_some(vaa.guardian_set_index),
+            signatures: vaa
+                .signatures
+                .iter()
+                .map(|sig| SignatureView {
+                    index: sig.index,
+                    signature: hex::encode(sig.signature),
+                })
+                .collect(),
+            signature_set,
+            submission_time,
+            timestamp: vaa.timestamp,
+            nonce: vaa.nonce,
+            emitter_chain: vaa.emitter_chain,
+            emitter_address: hex::encode(vaa.emitter_address),
+            sequence: vaa.sequence,
+            consistency_level: vaa.consistency_level,
+            hash: hex::encode(decoded.hash),
+        };
+        let (message, message_error) = match &decoded.message {
+            Ok(message) => {
+                let (kind, fields) = message_fields(message);
+                let view = MessageFieldsView {
+                    code: message.code(),
+                    kind,
+                    fields,
+                };
+                (Some(view), None)
+            }
+            Err(e) => (None, Some(e.to_string())),
+        };
+        DecodedView {
+            header,
+            message,
+            message_error,
+            count: count.map(|(count, _)| count),
+            count_source: count.map(|(_, source)| source),
+            accounts: AccountsView::from(&decoded.accounts),
+            status: None,
+        }
+    }
+}
+
+impl fmt::Display for DecodedView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let h = &self.header;
+        writeln!(f, "version:           {}", h.version)?;
+        match (&h.guardian_set_index, &h.signature_set) {
+            (Some(index), _) => writeln!(f, "guardian set:      {}", index)?,
+            (None, Some(set)) => writeln!(f, "signature set:     {}", set)?,
+            (None, None) => {}
+        }
+        if let Some(time) = h.submission_time {
+            writeln!(f, "submission time:   {}", time)?;
+        }
+        for sig in &h.signatures {
+            writeln!(f, "  guardian {:>2}:     {}", sig.index, sig.signature)?;
+        }
+        writeln!(f, "timestamp:         {}", h.timestamp)?;
+        writeln!(f, "nonce:             {}", h.nonce)?;
+        writeln!(
+            f,
+            "emitter:           {} (chain {})",
+            h.emitter_address, h.emitter_chain
+        )?;
+        writeln!(f, "sequence:          {}", h.sequence)?;
+        writeln!(f, "consistency level: {}", h.consistency_level)?;
+        writeln!(f, "hash:              {}", h.hash)?;
+
+        match (&self.message, &self.message_error) {
+            (Some(message), _) => {
+                writeln!(
+                    f,
+                    "message:           {} (code {})",
+                    message.kind, message.code
+                )?;
+                for (name, value) in &message.fields.0 {
+                    let value = match value {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    writeln!(f, "  {:<16} {}", format!("{}:", name), value)?;
+                }
+            }
+            (None, Some(e)) => writeln!(f, "message:           not a Zebec message ({})", e)?,
+            (None, None) => {}
+        }
+
+        let a = &self.accounts;
+        writeln!(f, "accounts:")?;
+        writeln!(f, "  posted_vaa:        {}", a.posted_vaa)?;
+        writeln!(f, "  emitter_account:   {}", a.emitter_account)?;
+        write!(f, "  processed_vaa:     {}", a.processed_vaa)?;
+        let optional = [
+            ("sender_pda", &a.sender_pda),
+            ("stream_sender_pda", &a.stream_sender_pda),
+            ("receiver_pda", &a.receiver_pda),
+            ("txn_count", &a.txn_count),
+            ("data_store", &a.data_store),
+            ("txn_status", &a.txn_status),
+            ("batch_store", &a.batch_store),
+            ("target_program", &a.target_program),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                write!(f, "\n  {:<18} {}", format!("{}:", name), value)?;
+            }
+        }
+        if let (Some(count), Some(source)) = (self.count, self.count_source) {
+            write!(f, "\ncount:             {} ({})", count, source)?;
+        }
+        if let Some(status) = &self.status {
+            write!(
+                f,
+                "\nstatus:            posted {}, stored {}, executed {}",
+                status.posted,
+                status.stored,
+                status
+                    .executed
+                    .map_or("-".to_string(), |executed| executed.to_string())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn fetch<T: AccountDeserialize>(
+    source: &dyn AccountSource,
+    key: &Pubkey,
+) -> Result<Option<T>, CliError> {
+    match source.account(key)? {
+        Some(account) => T::try_deserialize(&mut &account.data[..])
+            .map(Some)
+            .map_err(|_| CliError::Decode(key.to_string())),
+        None => Ok(None),
+    }
+}
+
+/// Decodes `input` without touching any account state. `count` fills in
+/// `data_store` and `txn_status`.
+pub fn decode_offline(
+    program_id: &Pubkey,
+    core_bridge: &Pubkey,
+    input: &[u8],
+    count: Option<u64>,
+) -> Result<DecodedView, CliError> {
+    let decoded = decode::decode(program_id, core_bridge, input, count)
+        .map_err(|e| CliError::Decode(e.to_string()))?;
+    Ok(DecodedView::new(
+        &decoded,
+        count.map(|count| (count, "given")),
+    ))
+}
+
+/// Decodes `input` and reads `source` for how far the message got. Unless
+/// given, the count is read from `processed_vaa` when the message is stored,
+/// or predicted as the next `txn_count` when it is not.
+pub fn decode_with_state(
+    source: &dyn AccountSource,
+    program_id: &Pubkey,
+    core_bridge: &Pubkey,
+    input: &[u8],
+    count: Option<u64>,
+) -> Result<DecodedView, CliError> {
+    let decoded = decode::decode(program_id, core_bridge, input, None)
+        .map_err(|e| CliError::Decode(e.to_string()))?;
+    let message = match &decoded.message {
+        Ok(message) => message,
+        Err(_) => return Ok(DecodedView::new(&decoded, None)),
+    };
+
+    let posted = source.account(&decoded.accounts.posted_vaa)?.is_some();
+    let processed: Option<ProcessedVAA> = fetch(source, &decoded.accounts.processed_vaa)?;
+    let stored = processed.is_some();
+
+    let count = match (count, &processed, decoded.accounts.txn_count) {
+        (Some(count), _, _) => Some((count, "given")),
+        (None, Some(processed), _) => Some((processed.transaction_count, "stored")),
+        (None, None, Some(txn_count)) => {
+            let current = fetch::<Count>(source, &txn_count)?.map_or(0, |count| count.count);
+            current.checked_add(1).map(|next| (next, "next"))
+        }
+        (None, None, None) => None,
+    };
+
+    let accounts = decode::derive_accounts(
+        program_id,
+        core_bridge,
+        &decoded.vaa,
+        Some(message),
+        count.map(|(count, _)| count),
+    );
+    // A batch counts as executed once every entry is executed or failed.
+    let executed = match (&accounts.txn_status, &accounts.batch_store, stored) {
+        (Some(txn_status), _, true) => {
+            fetch::<TransactionStatus>(source, txn_status)?.map(|status| status.executed)
+        }
+        (_, Some(batch_store), true) => {
+            fetch::<BatchData>(source, batch_store)?.map(|batch| batch.is_finished())
+        }
+        _ => None,
+    };
+
+    let decoded = DecodedVaa {
+        accounts,
+        ..decoded
+    };
+    let mut view = DecodedView::new(&decoded, count);
+    view.status = Some(StatusView {
+        posted,
+        stored,
+        executed,
+    });
+    Ok(view)
+}
@@ -0,0 +1,35 @@
+use solana_client::client_error::ClientError as RpcError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("rpc error: {0}")]
+    Rpc(Box<RpcError>),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("snapshot error: {0}")]
+    Snapshot(String),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("missing account {0}")]
+    MissingAccount(String),
+
+    #[error("could not decode {0}")]
+    Decode(String),
+
+    #[error(transparent)]
+    Client(#[from] zebec_wormhole_client::ClientError),
+}
+
+impl From<RpcError> for CliError {
+    fn from(e: RpcError) -> Self {
+        CliError::Rpc(Box::new(e))
+    }
+}
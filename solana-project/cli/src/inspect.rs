@@ -0,0 +1,544 @@
+//! Read-only views of the proxy program's accounts. Every view serializes to
+//! JSON and prints as plain `key: value` lines.
+
+use std::fmt;
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use solana_project::state::{
+    AddressFormat, Config, Count, EmitterAccount, EmitterAddrAccount, Governance, MintRegistry,
+    TargetProgram, TransactionData, TransactionStatus,
+};
+use zebec_wormhole_client::{address, pda};
+
+use crate::error::CliError;
+use crate::source::{AccountSource, Filter};
+
+/// Parses an EVM address (20 bytes) or a Wormhole address (32 bytes) given
+/// in hex, with or without `0x`, into the left-padded 32 byte form the
+/// program uses.
+pub fn parse_address(value: &str) -> Result<[u8; 32], CliError> {
+    let bytes = hex::decode(value.trim_start_matches("0x"))
+        .map_err(|_| CliError::InvalidArgument(format!("{} is not hex", value)))?;
+    let mut address = [0u8; 32];
+    match bytes.len() {
+        20 => address[12..].copy_from_slice(&bytes),
+        32 => address.copy_from_slice(&bytes),
+        len => {
+            return Err(CliError::InvalidArgument(format!(
+                "{} is {} bytes, expected 20 or 32",
+                value, len
+            )))
+        }
+    }
+    Ok(address)
+}
+
+/// The address format registered for `chain_id`, or the usual one for that
+/// chain if it is not registered in the current layout.
+pub fn chain_format(
+    source: &dyn AccountSource,
+    program_id: &Pubkey,
+    chain_id: u16,
+) -> Result<AddressFormat, CliError> {
+    let key = pda::emitter_account(program_id, chain_id);
+    match source.account(&key)? {
+        Some(account) if account.data.starts_with(&EmitterAccount::discriminator()) => {
+            let emitter: EmitterAccount = decode(&key, &account.data)?;
+            Ok(emitter.address_format.resolve())
+        }
+        _ => Ok(address::default_format(chain_id)),
+    }
+}
+
+/// Parses a sender as its chain writes it, in the chain's address format.
+pub fn parse_sender(
+    source: &dyn AccountSource,
+    program_id: &Pubkey,
+    value: &str,
+    chain_id: u16,
+) -> Result<[u8; 32], CliError> {
+    let format = chain_format(source, program_id, chain_id)?;
+    Ok(address::normalize(format, value)?)
+}
+
+fn decode<T: AccountDeserialize>(key: &Pubkey, data: &[u8]) -> Result<T, CliError> {
+    T::try_deserialize(&mut &data[..]).map_err(|_| CliError::Decode(key.to_string()))
+}
+
+fn fetch<T: AccountDeserialize>(
+    source: &dyn AccountSource,
+    key: &Pubkey,
+) -> Result<Option<T>, CliError> {
+    match source.account(key)? {
+        Some(account) => Ok(Some(decode(key, &account.data)?)),
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfigView {
+    pub address: String,
+    pub owner: String,
+    pub nonce: u32,
+    /// Layout version; 0 until the account is migrated.
+    pub account_version: u8,
+    /// Chain and hex address of the governance emitter; `None` while
+    /// governance is off.
+    pub governance: Option<(u16, String)>,
+}
+
+impl fmt::Display for ConfigView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "config: {}", self.address)?;
+        writeln!(f, "owner:  {}", self.owner)?;
+        writeln!(f, "nonce:  {}", self.nonce)?;
+        writeln!(f, "layout: v{}", self.account_version)?;
+        match &self.governance {
+            Some((chain_id, emitter)) => write!(f, "govern: chain {} {}", chain_id, emitter),
+            None => write!(f, "govern: off"),
+        }
+    }
+}
+
+pub fn show_config(
+    source: &dyn AccountSource,
+    program_id: &Pubkey,
+) -> Result<ConfigView, CliError> {
+    let address = pda::config(program_id);
+    let config: Config = fetch(source, &address)?
+        .ok_or_else(|| CliError::MissingAccount(format!("config {}", address)))?;
+    Ok(ConfigView {
+        address: address.to_string(),
+        owner: config.owner.to_string(),
+        nonce: config.nonce,
+        account_version: config.account_version.0,
+        governance: match config.governance.resolve() {
+            Governance::Emitter {
+                chain_id,
+                emitter_address,
+            } => Some((chain_id, hex::encode(emitter_address))),
+            _ => None,
+        },
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChainView {
+    pub address: String,
+    pub chain_id: u16,
+    pub emitter_addr: String,
+    pub label: String,
+    pub enabled: bool,
+    pub address_format: String,
+    /// `None` until a chain registered in the hex string layout is migrated.
+    pub registered_at: Option<i64>,
+    pub migrated: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChainsView {
+    pub chains: Vec<ChainView>,
+}
+
+impl fmt::Display for ChainsView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.chains.is_empty() {
+            return write!(f, "no chains registered");
+        }
+        let lines: Vec<String> = self
+            .chains
+            .iter()
+            .map(|chain| {
+                let mut line = format!(
+                    "{:>5}  {:<6}  {}  {}",
+                    chain.chain_id, chain.address_format, chain.emitter_addr, chain.address
+                );
+                if !chain.label.is_empty() {
+                    line.push_str(&format!("  {}", chain.label));
+                }
+                if !chain.enabled {
+                    line.push_str("  (disabled)");
+                }
+                if !chain.migrated {
+                    line.push_str("  (not migrated)");
+                }
+                line
+            })
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+pub fn list_chains(
+    source: &dyn AccountSource,
+    program_id: &Pubkey,
+) -> Result<ChainsView, CliError> {
+    let accounts = |discriminator: [u8; 8]| {
+        let filters = [Filter::Memcmp {
+            offset: 0,
+            bytes: discriminator.to_vec(),
+        }];
+        source.program_accounts(program_id, &filters)
+    };
+
+    let mut chains = Vec::new();
+    for (key, account) in accounts(EmitterAccount::discriminator())? {
+        let emitter: EmitterAccount = decode(&key, &account.data)?;
+        chains.push(ChainView {
+            address: key.to_string(),
+            chain_id: emitter.chain_id,
+            emitter_addr: hex::encode(emitter.emitter_addr),
+            label: emitter.label,
+            enabled: emitter.enabled,
+            address_format: address::format_name(emitter.address_format).to_string(),
+            registered_at: Some(emitter.registered_at),
+            migrated: true,
+        });
+    }
+    for (key, account) in accounts(EmitterAddrAccount::discriminator())? {
+        let emitter: EmitterAddrAccount = decode(&key, &account.data)?;
+        chains.push(ChainView {
+            address: key.to_string(),
+            chain_id: emitter.chain_id,
+            emitter_addr: emitter.emitter_addr,
+            label: String::new(),
+            enabled: true,
+            address_format: address::format_name(AddressFormat::Evm).to_string(),
+            registered_at: None,
+            migrated: false,
+        });
+    }
+    chains.sort_by_key(|chain| chain.chain_id);
+    Ok(ChainsView { chains })
+}
+
+#[derive(Debug, Serialize)]
+pub struct TargetProgramView {
+    pub address: String,
+    pub version: u8,
+    pub program_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TargetProgramsView {
+    pub target_programs: Vec<TargetProgramView>,
+}
+
+impl fmt::Display for TargetProgramsView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.target_programs.is_empty() {
+            return write!(f, "no target programs registered");
+        }
+        let lines: Vec<String> = self
+            .target_programs
+            .iter()
+            .map(|target| {
+                format!(
+                    "{:>3}  {}  {}",
+                    target.version, target.program_id, target.address
+                )
+            })
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+pub fn list_target_programs(
+    source: &dyn AccountSource,
+    program_id: &Pubkey,
+) -> Result<TargetProgramsView, CliError> {
+    let filters = [Filter::Memcmp {
+        offset: 0,
+        bytes: TargetProgram::discriminator().to_vec(),
+    }];
+    let mut target_programs = source
+        .program_accounts(program_id, &filters)?
+        .into_iter()
+        .map(|(key, account)| {
+            let target: TargetProgram = decode(&key, &account.data)?;
+            Ok(TargetProgramView {
+                address: key.to_string(),
+                version: target.version,
+                program_id: target.program_id.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, CliError>>()?;
+    target_programs.sort_by_key(|target| target.version);
+    Ok(TargetProgramsView { target_programs })
+}
+
+#[derive(Debug, Serialize)]
+pub struct MintView {
+    pub address: String,
+    pub mint: String,
+    pub decimals: u8,
+    /// Bounds of a single amount, in base units.
+    pub min_amount: u64,
+    pub max_amount: u64,
+    pub wrapped: bool,
+    pub origin_chain: u16,
+    pub origin_address: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MintsView {
+    pub mints: Vec<MintView>,
+}
+
+impl fmt::Display for MintsView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.mints.is_empty() {
+            return write!(f, "no mints registered");
+        }
+        let lines: Vec<String> = self
+            .mints
+            .iter()
+            .map(|mint| {
+                let mut line = format!(
+                    "{}  decimals {}  amounts {}..={}",
+                    mint.mint, mint.decimals, mint.min_amount, mint.max_amount
+                );
+                if mint.wrapped {
+                    line.push_str(&format!(
+                        "  (wrapped {} from chain {})",
+                        mint.origin_address, mint.origin_chain
+                    ));
+                }
+                line
+            })
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+pub fn list_mints(source: &dyn AccountSource, program_id: &Pubkey) -> Result<MintsView, CliError> {
+    let filters = [Filter::Memcmp {
+        offset: 0,
+        bytes: MintRegistry::discriminator().to_vec(),
+    }];
+    let mut mints = source
+        .program_accounts(program_id, &filters)?
+        .into_iter()
+        .map(|(key, account)| {
+            let registry: MintRegistry = decode(&key, &account.data)?;
+            Ok(MintView {
+                address: key.to_string(),
+                mint: registry.mint.to_string(),
+                decimals: registry.decimals,
+                min_amount: registry.min_amount,
+                max_amount: registry.max_amount,
+                wrapped: registry.wrapped,
+                origin_chain: registry.origin_chain,
+                origin_address: hex::encode(registry.origin_address),
+            })
+        })
+        .collect::<Result<Vec<_>, CliError>>()?;
+    mints.sort_by(|a, b| a.mint.cmp(&b.mint));
+    Ok(MintsView { mints })
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenAccountView {
+    pub address: String,
+    pub mint: String,
+    pub amount: u64,
+    /// Whether this is the associated token account of the PDA for its mint.
+    pub associated: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SenderView {
+    pub sender: String,
+    pub chain_id: u16,
+    pub pda: String,
+    /// Lamports held by the PDA, `None` if `initialize_pda` has not run.
+    pub pda_lamports: Option<u64>,
+    pub token_accounts: Vec<TokenAccountView>,
+    pub txn_count_address: String,
+    /// Messages stored for this sender so far.
+    pub txn_count: u64,
+}
+
+impl fmt::Display for SenderView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "sender:    {} (chain {})", self.sender, self.chain_id)?;
+        match self.pda_lamports {
+            Some(lamports) => writeln!(f, "pda:       {} ({} lamports)", self.pda, lamports)?,
+            None => writeln!(f, "pda:       {} (not initialized)", self.pda)?,
+        }
+        writeln!(
+            f,
+            "txn_count: {} ({})",
+            self.txn_count, self.txn_count_address
+        )?;
+        if self.token_accounts.is_empty() {
+            return write!(f, "tokens:    none");
+        }
+        write!(f, "tokens:")?;
+        for token in &self.token_accounts {
+            write!(
+                f,
+                "\n  {}  mint {}  amount {}{}",
+                token.address,
+                token.mint,
+                token.amount,
+                if token.associated { "  (ATA)" } else { "" }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub fn show_sender(
+    source: &dyn AccountSource,
+    program_id: &Pubkey,
+    sender: [u8; 32],
+    chain_id: u16,
+) -> Result<SenderView, CliError> {
+    let pda = pda::user_pda(program_id, &sender, chain_id);
+    let pda_lamports = source.account(&pda)?.map(|account| account.lamports);
+
+    let filters = [
+        Filter::DataSize(spl_token::state::Account::LEN as u64),
+        Filter::Memcmp {
+            offset: 32,
+            bytes: pda.to_bytes().to_vec(),
+        },
+    ];
+    let token_accounts = source
+        .program_accounts(&spl_token::id(), &filters)?
+        .into_iter()
+        .map(|(key, account)| {
+            let token = spl_token::state::Account::unpack(&account.data)
+                .map_err(|_| CliError::Decode(key.to_string()))?;
+            Ok(TokenAccountView {
+                address: key.to_string(),
+                mint: token.mint.to_string(),
+                amount: token.amount,
+                associated: key == get_associated_token_address(&pda, &token.mint),
+            })
+        })
+        .collect::<Result<Vec<_>, CliError>>()?;
+
+    let txn_count_address = pda::txn_count(program_id, &sender);
+    let txn_count = fetch::<Count>(source, &txn_count_address)?.map_or(0, |count| count.count);
+
+    Ok(SenderView {
+        sender: hex::encode(sender),
+        chain_id,
+        pda: pda.to_string(),
+        pda_lamports,
+        token_accounts,
+        txn_count_address: txn_count_address.to_string(),
+        txn_count,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransactionDataView {
+    pub sender: String,
+    pub receiver: String,
+    pub data_account: String,
+    pub from_chain_id: u16,
+    pub token_mint: String,
+    pub amount: u64,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub can_update: bool,
+    pub can_cancel: bool,
+    pub code: u8,
+    pub vaa_sequence: u64,
+    pub target_version: u8,
+    pub account_version: u8,
+}
+
+impl From<TransactionData> for TransactionDataView {
+    fn from(data: TransactionData) -> Self {
+        TransactionDataView {
+            sender: hex::encode(data.sender),
+            receiver: hex::encode(data.receiver),
+            data_account: data.data_account.to_string(),
+            from_chain_id: data.from_chain_id,
+            token_mint: data.token_mint.to_string(),
+            amount: data.amount,
+            start_time: data.start_time,
+            end_time: data.end_time,
+            can_update: data.can_update,
+            can_cancel: data.can_cancel,
+            code: data.code,
+            vaa_sequence: data.vaa_sequence,
+            target_version: data.target_version,
+            account_version: data.account_version.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct MessageView {
+    pub sender: String,
+    pub count: u64,
+    pub data_store: String,
+    pub txn_status: String,
+    /// `None` if no message was stored under this count.
+    pub data: Option<TransactionDataView>,
+    pub executed: Option<bool>,
+}
+
+impl fmt::Display for MessageView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "sender:     {}", self.sender)?;
+        writeln!(f, "count:      {}", self.count)?;
+        writeln!(f, "data_store: {}", self.data_store)?;
+        writeln!(f, "txn_status: {}", self.txn_status)?;
+        match self.executed {
+            Some(executed) => writeln!(f, "executed:   {}", executed)?,
+            None => writeln!(f, "executed:   -")?,
+        }
+        let data = match &self.data {
+            Some(data) => data,
+            None => return write!(f, "no message stored"),
+        };
+        writeln!(f, "code:       {}", data.code)?;
+        writeln!(f, "from chain: {}", data.from_chain_id)?;
+        writeln!(f, "sequence:   {}", data.vaa_sequence)?;
+        writeln!(f, "target:     version {}", data.target_version)?;
+        writeln!(f, "layout:     v{}", data.account_version)?;
+        writeln!(f, "receiver:   {}", data.receiver)?;
+        writeln!(f, "mint:       {}", data.token_mint)?;
+        writeln!(f, "amount:     {}", data.amount)?;
+        writeln!(f, "start/end:  {} / {}", data.start_time, data.end_time)?;
+        writeln!(
+            f,
+            "can cancel: {}  can update: {}",
+            data.can_cancel, data.can_update
+        )?;
+        write!(f, "data acct:  {}", data.data_account)
+    }
+}
+
+pub fn show_message(
+    source: &dyn AccountSource,
+    program_id: &Pubkey,
+    sender: [u8; 32],
+    count: u64,
+) -> Result<MessageView, CliError> {
+    let data_store = pda::data_store(program_id, &sender, count);
+    let txn_status = pda::txn_status(program_id, &sender, count);
+    let data: Option<TransactionData> = fetch(source, &data_store)?;
+    let status: Option<TransactionStatus> = fetch(source, &txn_status)?;
+
+    Ok(MessageView {
+        sender: hex::encode(sender),
+        count,
+        data_store: data_store.to_string(),
+        txn_status: txn_status.to_string(),
+        data: data.map(TransactionDataView::from),
+        executed: status.map(|status| status.executed),
+    })
+}
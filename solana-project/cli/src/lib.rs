@@ -0,0 +1,9 @@
+//! Operator tooling for the proxy program: initialization, chain
+//! registration, read-only views of its accounts and VAA decoding.
+
+pub mod decode;
+pub mod error;
+pub mod inspect;
+pub mod source;
+
+pub use error::CliError;
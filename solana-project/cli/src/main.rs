@@ -0,0 +1,510 @@
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use solana_project::constants::{CORE_BRIDGE_ADDRESS, ZEBEC_CONTRACT};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Signer};
+use solana_sdk::transaction::Transaction;
+use zebec_wormhole_client::versioned::{self, EXTEND_LOOKUP_TABLE_CHUNK};
+use zebec_wormhole_client::zebec::ZebecAccounts;
+use zebec_wormhole_client::{address, instructions, pda};
+
+use zebec_admin::error::CliError;
+use zebec_admin::source::{AccountSource, RpcSource, SnapshotSource};
+use zebec_admin::{decode, inspect};
+
+/// Sets up and inspects the Zebec Wormhole proxy program.
+#[derive(Parser)]
+#[clap(name = "zebec-admin")]
+struct Opts {
+    /// RPC endpoint to read from and send transactions to.
+    #[clap(short, long, default_value = "http://localhost:8899")]
+    url: String,
+    /// Read accounts from a snapshot of `solana account --output json`
+    /// dumps instead of RPC. Only the read-only commands accept it.
+    #[clap(long)]
+    snapshot: Option<PathBuf>,
+    /// Keypair that signs and pays. Defaults to ~/.config/solana/id.json.
+    #[clap(short, long)]
+    keypair: Option<PathBuf>,
+    /// Proxy program id.
+    #[clap(long, default_value_t = solana_project::ID)]
+    program_id: Pubkey,
+    /// Print JSON instead of human-readable output.
+    #[clap(long)]
+    json: bool,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the config account, owned by the keypair.
+    Init,
+    /// Register the emitter contract of a chain.
+    RegisterChain {
+        /// Wormhole chain id.
+        chain_id: u16,
+        /// Emitter address as the chain writes it: hex for EVM, Aptos and
+        /// Sui, base58 for Solana, the account id for Near.
+        emitter: String,
+        /// Name to show for the chain, up to 32 bytes.
+        #[clap(long, default_value = "")]
+        label: String,
+        /// Address format: evm, solana, aptos, sui or near. Defaults to the
+        /// one the chain id is known to use, else evm.
+        #[clap(long)]
+        format: Option<String>,
+    },
+    /// Enable or disable a registered chain, and optionally relabel it.
+    UpdateChain {
+        chain_id: u16,
+        /// Whether VAAs from the chain are accepted.
+        #[clap(parse(try_from_str))]
+        enabled: bool,
+        #[clap(long)]
+        label: Option<String>,
+    },
+    /// Cap what goes out to a chain over 24 hours, in notional units.
+    SetChainLimit {
+        chain_id: u16,
+        /// Notional units a day; u64::MAX lifts the cap.
+        daily_limit: u64,
+    },
+    /// Cap what goes out of a mint over 24 hours and set its price and hold.
+    SetMintLimit {
+        mint: Pubkey,
+        /// Base units a day; u64::MAX lifts the cap.
+        daily_limit: u64,
+        /// Notional units per whole token, for chain limits.
+        #[clap(long, default_value_t = 0)]
+        notional_price: u64,
+        /// Transfers of at least this many base units are held instead.
+        #[clap(long, default_value_t = u64::MAX)]
+        large_transfer_threshold: u64,
+        /// Seconds a large transfer is held for.
+        #[clap(long, default_value_t = 0)]
+        hold_seconds: u32,
+    },
+    /// Rewrite a chain registered with the old hex string layout.
+    MigrateChain { chain_id: u16 },
+    /// Rewrite any proxy account in the current layout version.
+    MigrateAccount { address: Pubkey },
+    /// List every registered chain and its emitter.
+    ListChains,
+    /// Allow messages selecting `version` to be executed against a program.
+    RegisterTargetProgram {
+        /// Version byte payloads select the program with.
+        version: u8,
+        /// Program id of that Zebec version.
+        target: Pubkey,
+    },
+    /// Remove a target program version; its transactions can no longer run.
+    RemoveTargetProgram { version: u8 },
+    /// Let VAAs of an emitter make admin changes; chain 0 turns it off.
+    SetGovernance {
+        /// Wormhole chain id of the governance emitter.
+        chain_id: u16,
+        /// Emitter address in hex, 20 or 32 bytes. Not needed for chain 0.
+        emitter: Option<String>,
+    },
+    /// List every registered target program version.
+    ListTargetPrograms,
+    /// Allow messages to name a mint, or update its entry.
+    RegisterMint {
+        mint: Pubkey,
+        /// Smallest single amount, in base units.
+        #[clap(long, default_value_t = 0)]
+        min_amount: u64,
+        /// Largest single amount, in base units.
+        #[clap(long, default_value_t = u64::MAX)]
+        max_amount: u64,
+        /// Wormhole chain of the token a wrapped mint stands for; 1 for a
+        /// Solana mint.
+        #[clap(long, default_value_t = 1)]
+        origin_chain: u16,
+        /// Address of that token in hex, 20 or 32 bytes. Defaults to the
+        /// mint itself.
+        #[clap(long)]
+        origin_address: Option<String>,
+    },
+    /// Remove a mint; messages naming it are refused.
+    RemoveMint { mint: Pubkey },
+    /// List every registered mint.
+    ListMints,
+    /// Show the config account.
+    ShowConfig,
+    /// Show the PDA, token accounts and transaction count of a sender.
+    ShowSender {
+        /// Sender address as its chain writes it.
+        sender: String,
+        /// Wormhole chain id of the sender.
+        chain_id: u16,
+    },
+    /// Show a stored message and whether it was executed.
+    ShowMessage {
+        /// Sender address in hex, 20 or 32 bytes.
+        sender: String,
+        /// Transaction count the message was stored under.
+        count: u64,
+    },
+    /// Decode a signed VAA or PostedVAA account data, its Zebec message and
+    /// the accounts it touches.
+    Decode {
+        /// File holding the VAA, or the VAA itself, as raw bytes, hex or base64.
+        input: String,
+        /// Count the message was stored under; read from chain if omitted.
+        #[clap(long)]
+        count: Option<u64>,
+        /// Do not read any accounts.
+        #[clap(long)]
+        offline: bool,
+        /// Core bridge program id.
+        #[clap(long, default_value = CORE_BRIDGE_ADDRESS)]
+        core_bridge: Pubkey,
+    },
+    /// Create an address lookup table holding the accounts relays share,
+    /// owned by the keypair. Set it as the relayer's `lookup_table`.
+    CreateLookupTable {
+        /// Fee owner of the Zebec fee vault used for streams.
+        fee_owner: Pubkey,
+        /// Zebec program id.
+        #[clap(long, default_value = ZEBEC_CONTRACT)]
+        zebec_program: Pubkey,
+        /// Target program version messages select.
+        #[clap(long, default_value_t = 0)]
+        target_version: u8,
+        /// Wormhole chain ids messages come from; repeat for each.
+        #[clap(long = "chain")]
+        chains: Vec<u16>,
+        /// Mints messages name; repeat for each.
+        #[clap(long = "mint")]
+        mints: Vec<Pubkey>,
+    },
+}
+
+#[derive(Serialize)]
+struct Sent {
+    signature: String,
+    account: String,
+}
+
+impl std::fmt::Display for Sent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "signature: {}", self.signature)?;
+        write!(f, "account:   {}", self.account)
+    }
+}
+
+fn print<T: Serialize + std::fmt::Display>(value: &T, json: bool) -> Result<(), CliError> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(value)?);
+    } else {
+        println!("{}", value);
+    }
+    Ok(())
+}
+
+fn keypair_path(opts: &Opts) -> PathBuf {
+    match &opts.keypair {
+        Some(path) => path.clone(),
+        None => {
+            let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+            PathBuf::from(home).join(".config/solana/id.json")
+        }
+    }
+}
+
+fn send(
+    opts: &Opts,
+    build: impl FnOnce(&Pubkey) -> solana_sdk::instruction::Instruction,
+    account: Pubkey,
+) -> Result<Sent, CliError> {
+    if opts.snapshot.is_some() {
+        return Err(CliError::InvalidArgument(
+            "sending a transaction needs --url, not --snapshot".to_string(),
+        ));
+    }
+    let path = keypair_path(opts);
+    let payer = read_keypair_file(&path)
+        .map_err(|e| CliError::InvalidArgument(format!("keypair {}: {}", path.display(), e)))?;
+    let source = RpcSource::new(&opts.url);
+    let rpc = source.rpc();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[build(&payer.pubkey())],
+        Some(&payer.pubkey()),
+        &[&payer],
+        rpc.get_latest_blockhash()?,
+    );
+    let signature = rpc.send_and_confirm_transaction(&tx)?;
+    Ok(Sent {
+        signature: signature.to_string(),
+        account: account.to_string(),
+    })
+}
+
+fn run(opts: Opts) -> Result<(), CliError> {
+    let program_id = opts.program_id;
+    let source: Box<dyn AccountSource> = match &opts.snapshot {
+        Some(path) => Box::new(SnapshotSource::load(path)?),
+        None => Box::new(RpcSource::new(&opts.url)),
+    };
+
+    match &opts.command {
+        Command::Init => {
+            let sent = send(
+                &opts,
+                |owner| instructions::initialize(&program_id, owner),
+                pda::config(&program_id),
+            )?;
+            print(&sent, opts.json)
+        }
+        Command::RegisterChain {
+            chain_id,
+            emitter,
+            label,
+            format,
+        } => {
+            let format = match format {
+                Some(format) => address::parse_format(format)?,
+                None => address::default_format(*chain_id),
+            };
+            let emitter = address::normalize(format, emitter)?;
+            let sent = send(
+                &opts,
+                |owner| {
+                    instructions::register_chain(
+                        &program_id,
+                        owner,
+                        *chain_id,
+                        emitter,
+                        label.clone(),
+                        format,
+                    )
+                },
+                pda::emitter_account(&program_id, *chain_id),
+            )?;
+            print(&sent, opts.json)
+        }
+        Command::UpdateChain {
+            chain_id,
+            enabled,
+            label,
+        } => {
+            let sent = send(
+                &opts,
+                |owner| {
+                    instructions::update_chain(
+                        &program_id,
+                        owner,
+                        *chain_id,
+                        *enabled,
+                        label.clone(),
+                    )
+                },
+                pda::emitter_account(&program_id, *chain_id),
+            )?;
+            print(&sent, opts.json)
+        }
+        Command::SetChainLimit {
+            chain_id,
+            daily_limit,
+        } => {
+            let sent = send(
+                &opts,
+                |owner| instructions::set_chain_limit(&program_id, owner, *chain_id, *daily_limit),
+                pda::chain_limit(&program_id, *chain_id),
+            )?;
+            print(&sent, opts.json)
+        }
+        Command::SetMintLimit {
+            mint,
+            daily_limit,
+            notional_price,
+            large_transfer_threshold,
+            hold_seconds,
+        } => {
+            let sent = send(
+                &opts,
+                |owner| {
+                    instructions::set_mint_limit(
+                        &program_id,
+                        owner,
+                        mint,
+                        *daily_limit,
+                        *notional_price,
+                        *large_transfer_threshold,
+                        *hold_seconds,
+                    )
+                },
+                pda::mint_limit(&program_id, mint),
+            )?;
+            print(&sent, opts.json)
+        }
+        Command::MigrateChain { chain_id } => {
+            let sent = send(
+                &opts,
+                |owner| instructions::migrate_chain(&program_id, owner, *chain_id),
+                pda::emitter_account(&program_id, *chain_id),
+            )?;
+            print(&sent, opts.json)
+        }
+        Command::MigrateAccount { address } => {
+            let sent = send(
+                &opts,
+                |payer| instructions::migrate_account(&program_id, payer, *address),
+                *address,
+            )?;
+            print(&sent, opts.json)
+        }
+        Command::ListChains => print(&inspect::list_chains(&*source, &program_id)?, opts.json),
+        Command::RegisterTargetProgram { version, target } => {
+            let sent = send(
+                &opts,
+                |owner| {
+                    instructions::register_target_program(&program_id, owner, *version, *target)
+                },
+                pda::target_program(&program_id, *version),
+            )?;
+            print(&sent, opts.json)
+        }
+        Command::RemoveTargetProgram { version } => {
+            let sent = send(
+                &opts,
+                |owner| instructions::remove_target_program(&program_id, owner, *version),
+                pda::target_program(&program_id, *version),
+            )?;
+            print(&sent, opts.json)
+        }
+        Command::SetGovernance { chain_id, emitter } => {
+            let emitter_address = match (chain_id, emitter) {
+                (0, _) => [0u8; 32],
+                (_, Some(emitter)) => inspect::parse_address(emitter)?,
+                (_, None) => {
+                    return Err(CliError::InvalidArgument(
+                        "the governance emitter is missing".to_string(),
+                    ))
+                }
+            };
+            let sent = send(
+                &opts,
+                |owner| {
+                    instructions::set_governance(&program_id, owner, *chain_id, emitter_address)
+                },
+                pda::config(&program_id),
+            )?;
+            print(&sent, opts.json)
+        }
+        Command::ListTargetPrograms => print(
+            &inspect::list_target_programs(&*source, &program_id)?,
+            opts.json,
+        ),
+        Command::RegisterMint {
+            mint,
+            min_amount,
+            max_amount,
+            origin_chain,
+            origin_address,
+        } => {
+            let origin_address = match origin_address {
+                Some(address) => inspect::parse_address(address)?,
+                None => mint.to_bytes(),
+            };
+            let sent = send(
+                &opts,
+                |owner| {
+                    instructions::register_mint(
+                        &program_id,
+                        owner,
+                        mint,
+                        *min_amount,
+                        *max_amount,
+                        *origin_chain,
+                        origin_address,
+                    )
+                },
+                pda::mint_registry(&program_id, mint),
+            )?;
+            print(&sent, opts.json)
+        }
+        Command::RemoveMint { mint } => {
+            let sent = send(
+                &opts,
+                |owner| instructions::remove_mint(&program_id, owner, mint),
+                pda::mint_registry(&program_id, mint),
+            )?;
+            print(&sent, opts.json)
+        }
+        Command::ListMints => print(&inspect::list_mints(&*source, &program_id)?, opts.json),
+        Command::ShowConfig => print(&inspect::show_config(&*source, &program_id)?, opts.json),
+        Command::ShowSender { sender, chain_id } => {
+            let sender = inspect::parse_sender(&*source, &program_id, sender, *chain_id)?;
+            let view = inspect::show_sender(&*source, &program_id, sender, *chain_id)?;
+            print(&view, opts.json)
+        }
+        Command::ShowMessage { sender, count } => {
+            let sender = inspect::parse_address(sender)?;
+            let view = inspect::show_message(&*source, &program_id, sender, *count)?;
+            print(&view, opts.json)
+        }
+        Command::Decode {
+            input,
+            count,
+            offline,
+            core_bridge,
+        } => {
+            let input = match std::fs::read(input) {
+                Ok(bytes) => bytes,
+                Err(_) => input.as_bytes().to_vec(),
+            };
+            let view = if *offline {
+                decode::decode_offline(&program_id, core_bridge, &input, *count)?
+            } else {
+                decode::decode_with_state(&*source, &program_id, core_bridge, &input, *count)?
+            };
+            print(&view, opts.json)
+        }
+        Command::CreateLookupTable {
+            fee_owner,
+            zebec_program,
+            target_version,
+            chains,
+            mints,
+        } => {
+            let path = keypair_path(&opts);
+            let authority = read_keypair_file(&path)
+                .map_err(|e| {
+                    CliError::InvalidArgument(format!("keypair {}: {}", path.display(), e))
+                })?
+                .pubkey();
+            let slot = RpcSource::new(&opts.url).rpc().get_slot()?;
+            let (create, table) = versioned::create_lookup_table(&authority, &authority, slot);
+            let mut sent = send(&opts, |_| create, table)?;
+            let addresses = versioned::lookup_table_addresses(
+                &program_id,
+                &ZebecAccounts::new(*zebec_program, *fee_owner),
+                *target_version,
+                chains,
+                mints,
+            );
+            for chunk in addresses.chunks(EXTEND_LOOKUP_TABLE_CHUNK) {
+                sent = send(
+                    &opts,
+                    |payer| versioned::extend_lookup_table(&table, &authority, payer, chunk),
+                    table,
+                )?;
+            }
+            print(&sent, opts.json)
+        }
+    }
+}
+
+fn main() {
+    if let Err(e) = run(Opts::parse()) {
+        eprintln!("error: {}", e);
+        exit(1);
+    }
+}
@@ -0,0 +1,205 @@
+//! Where account state is read from: a running validator over RPC, or a
+//! snapshot of account dumps taken from one.
+//!
+//! A snapshot is a directory of JSON files in the format written by
+//! `solana account --output json <address>` (and read by
+//! `solana-test-validator --account`), or a single file holding an array of
+//! them. Only base64 account data is supported.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::CliError;
+
+/// A subset of the RPC `getProgramAccounts` filters.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    DataSize(u64),
+    Memcmp { offset: usize, bytes: Vec<u8> },
+}
+
+impl Filter {
+    pub fn matches(&self, data: &[u8]) -> bool {
+        match self {
+            Filter::DataSize(size) => data.len() as u64 == *size,
+            Filter::Memcmp { offset, bytes } => data
+                .get(*offset..offset + bytes.len())
+                .is_some_and(|window| window == bytes.as_slice()),
+        }
+    }
+}
+
+pub trait AccountSource {
+    fn account(&self, key: &Pubkey) -> Result<Option<Account>, CliError>;
+
+    /// Every account owned by `program_id` that passes all `filters`.
+    fn program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[Filter],
+    ) -> Result<Vec<(Pubkey, Account)>, CliError>;
+}
+
+pub struct RpcSource {
+    rpc: RpcClient,
+}
+
+impl RpcSource {
+    pub fn new(url: &str) -> Self {
+        RpcSource {
+            rpc: RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed()),
+        }
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+}
+
+impl AccountSource for RpcSource {
+    fn account(&self, key: &Pubkey) -> Result<Option<Account>, CliError> {
+        Ok(self
+            .rpc
+            .get_account_with_commitment(key, self.rpc.commitment())?
+            .value)
+    }
+
+    fn program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[Filter],
+    ) -> Result<Vec<(Pubkey, Account)>, CliError> {
+        let filters = filters
+            .iter()
+            .map(|filter| match filter {
+                Filter::DataSize(size) => RpcFilterType::DataSize(*size),
+                Filter::Memcmp { offset, bytes } => RpcFilterType::Memcmp(Memcmp {
+                    offset: *offset,
+                    bytes: MemcmpEncodedBytes::Base58(bs58::encode(bytes).into_string()),
+                    encoding: None,
+                }),
+            })
+            .collect();
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(self.rpc.commitment()),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        Ok(self
+            .rpc
+            .get_program_accounts_with_config(program_id, config)?)
+    }
+}
+
+#[derive(Deserialize)]
+struct DumpedAccount {
+    pubkey: String,
+    account: DumpedAccountData,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DumpedAccountData {
+    lamports: u64,
+    data: (String, String),
+    owner: String,
+    executable: bool,
+    rent_epoch: u64,
+}
+
+impl DumpedAccount {
+    fn decode(self) -> Result<(Pubkey, Account), CliError> {
+        let key = Pubkey::from_str(&self.pubkey)
+            .map_err(|_| CliError::Snapshot(format!("invalid pubkey {}", self.pubkey)))?;
+        let owner = Pubkey::from_str(&self.account.owner)
+            .map_err(|_| CliError::Snapshot(format!("invalid owner of {}", key)))?;
+        let (data, encoding) = self.account.data;
+        if encoding != "base64" {
+            return Err(CliError::Snapshot(format!(
+                "{} uses {} data, only base64 is supported",
+                key, encoding
+            )));
+        }
+        let data = base64::decode(data)
+            .map_err(|_| CliError::Snapshot(format!("invalid base64 data in {}", key)))?;
+        Ok((
+            key,
+            Account {
+                lamports: self.account.lamports,
+                data,
+                owner,
+                executable: self.account.executable,
+                rent_epoch: self.account.rent_epoch,
+            },
+        ))
+    }
+}
+
+pub struct SnapshotSource {
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl SnapshotSource {
+    pub fn load(path: &Path) -> Result<Self, CliError> {
+        let mut dumped: Vec<DumpedAccount> = Vec::new();
+        if path.is_dir() {
+            let mut files: Vec<_> = fs::read_dir(path)?
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .map(|entry| entry.path())
+                .filter(|file| file.extension().is_some_and(|ext| ext == "json"))
+                .collect();
+            files.sort();
+            for file in files {
+                dumped.push(serde_json::from_str(&fs::read_to_string(&file)?)?);
+            }
+        } else {
+            dumped = serde_json::from_str(&fs::read_to_string(path)?)?;
+        }
+
+        let accounts = dumped
+            .into_iter()
+            .map(DumpedAccount::decode)
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(SnapshotSource { accounts })
+    }
+}
+
+impl AccountSource for SnapshotSource {
+    fn account(&self, key: &Pubkey) -> Result<Option<Account>, CliError> {
+        Ok(self.accounts.get(key).cloned())
+    }
+
+    fn program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[Filter],
+    ) -> Result<Vec<(Pubkey, Account)>, CliError> {
+        let mut accounts: Vec<(Pubkey, Account)> = self
+            .accounts
+            .iter()
+            .filter(|(_, account)| {
+                account.owner == *program_id
+                    && filters.iter().all(|filter| filter.matches(&account.data))
+            })
+            .map(|(key, account)| (*key, account.clone()))
+            .collect();
+        accounts.sort_by_key(|(key, _)| *key);
+        Ok(accounts)
+    }
+}
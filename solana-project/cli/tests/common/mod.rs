@@ -0,0 +1,88 @@
+#![allow(dead_code)]
+
+use std::path::Path;
+
+use anchor_lang::AccountSerialize;
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+pub fn serialize<T: AccountSerialize>(account: &T) -> Vec<u8> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data
+}
+
+/// Writes an account in the `solana account --output json` format.
+pub fn dump(dir: &Path, key: &Pubkey, owner: &Pubkey, lamports: u64, data: &[u8]) {
+    let value = json!({
+        "pubkey": key.to_string(),
+        "account": {
+            "lamports": lamports,
+            "data": [base64::encode(data), "base64"],
+            "owner": owner.to_string(),
+            "executable": false,
+            "rentEpoch": 0,
+        }
+    });
+    std::fs::write(
+        dir.join(format!("{}.json", key)),
+        serde_json::to_string(&value).unwrap(),
+    )
+    .unwrap();
+}
+
+pub fn evm_address(byte: u8) -> [u8; 32] {
+    let mut address = [0u8; 32];
+    address[12..].copy_from_slice(&[byte; 20]);
+    address
+}
+
+/// A code 2 (token stream) payload as `Encoder.sol` packs it.
+pub fn token_stream_payload(
+    start_time: u64,
+    end_time: u64,
+    amount: u64,
+    sender: [u8; 32],
+    receiver: [u8; 32],
+    token_mint: &Pubkey,
+) -> Vec<u8> {
+    let mut to_chain = [0u8; 32];
+    to_chain[31] = 1;
+    let mut payload = vec![2u8];
+    payload.extend_from_slice(&start_time.to_be_bytes());
+    payload.extend_from_slice(&end_time.to_be_bytes());
+    payload.extend_from_slice(&amount.to_be_bytes());
+    payload.extend_from_slice(&to_chain);
+    payload.extend_from_slice(&sender);
+    payload.extend_from_slice(&receiver);
+    payload.extend_from_slice(&1u64.to_be_bytes());
+    payload.extend_from_slice(&0u64.to_be_bytes());
+    payload.extend_from_slice(token_mint.as_ref());
+    payload
+}
+
+/// A code 20 (batch stream) payload of `(receiver, amount)` entries, each
+/// streamed from 100 to 200 and cancelable, with a trailing target version.
+pub fn batch_stream_payload(
+    sender: [u8; 32],
+    token_mint: &Pubkey,
+    entries: &[([u8; 32], u64)],
+    target_version: u8,
+) -> Vec<u8> {
+    let mut to_chain = [0u8; 32];
+    to_chain[31] = 1;
+    let mut payload = vec![20u8];
+    payload.extend_from_slice(&to_chain);
+    payload.extend_from_slice(&sender);
+    payload.extend_from_slice(token_mint.as_ref());
+    payload.push(entries.len() as u8);
+    for (receiver, amount) in entries {
+        payload.extend_from_slice(receiver);
+        payload.extend_from_slice(&amount.to_be_bytes());
+        payload.extend_from_slice(&100u64.to_be_bytes());
+        payload.extend_from_slice(&200u64.to_be_bytes());
+        payload.push(0b01);
+    }
+    payload.push(target_version);
+    payload
+}
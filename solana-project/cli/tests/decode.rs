@@ -0,0 +1,285 @@
+mod common;
+
+use std::str::FromStr;
+
+use anchor_lang::AnchorSerialize;
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+use solana_project::constants::CORE_BRIDGE_ADDRESS;
+use solana_project::state::{BatchData, BatchEntryStatus, Count, ProcessedVAA, TransactionStatus};
+use solana_project::wormhole::MessageData;
+use zebec_admin::decode::{decode_offline, decode_with_state};
+use zebec_admin::source::SnapshotSource;
+use zebec_wormhole_client::pda;
+use zebec_wormhole_client::vaa::{GuardianSignature, SignedVaa};
+
+use common::{batch_stream_payload, dump, evm_address, serialize, token_stream_payload};
+
+fn core_bridge() -> Pubkey {
+    Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
+}
+
+fn stream_vaa(mint: &Pubkey) -> SignedVaa {
+    SignedVaa {
+        version: 1,
+        guardian_set_index: 2,
+        signatures: vec![GuardianSignature {
+            index: 0,
+            signature: [7; 65],
+        }],
+        timestamp: 1_650_000_000,
+        nonce: 5,
+        emitter_chain: 4,
+        emitter_address: evm_address(0xee),
+        sequence: 9,
+        consistency_level: 15,
+        payload: token_stream_payload(100, 200, 300, evm_address(1), evm_address(2), mint),
+    }
+}
+
+#[test]
+fn decodes_signed_vaa_fields_and_accounts() {
+    let program_id = solana_project::ID;
+    let mint = Pubkey::new_unique();
+    let vaa = stream_vaa(&mint);
+    let input = format!("0x{}", hex::encode(vaa.serialize()));
+
+    let view = decode_offline(&program_id, &core_bridge(), input.as_bytes(), Some(3)).unwrap();
+    assert_eq!(view.header.guardian_set_index, Some(2));
+    assert_eq!(view.header.signatures.len(), 1);
+    assert_eq!(view.header.sequence, 9);
+    assert_eq!(view.header.hash, hex::encode(vaa.hash()));
+
+    let message = view.message.as_ref().unwrap();
+    assert_eq!(message.code, 2);
+    assert_eq!(message.kind, "token_stream");
+    assert_eq!(message.fields.get("start_time"), Some(&json!(100)));
+    assert_eq!(message.fields.get("end_time"), Some(&json!(200)));
+    assert_eq!(message.fields.get("can_cancel"), Some(&json!(true)));
+    assert_eq!(message.fields.get("can_update"), Some(&json!(false)));
+    assert_eq!(
+        message.fields.get("token_mint"),
+        Some(&json!(mint.to_string()))
+    );
+
+    let sender = evm_address(1);
+    let accounts = &view.accounts;
+    assert_eq!(
+        accounts.posted_vaa,
+        pda::posted_vaa(&core_bridge(), &vaa.hash()).to_string()
+    );
+    assert_eq!(
+        accounts.processed_vaa,
+        pda::processed_vaa(&program_id, &evm_address(0xee), 4, 9).to_string()
+    );
+    assert_eq!(
+        accounts.sender_pda,
+        Some(pda::user_pda(&program_id, &sender, 4).to_string())
+    );
+    assert_eq!(
+        accounts.receiver_pda,
+        Some(pda::user_pda(&program_id, &evm_address(2), 4).to_string())
+    );
+    assert_eq!(
+        accounts.data_store,
+        Some(pda::data_store(&program_id, &sender, 3).to_string())
+    );
+    assert_eq!(
+        accounts.txn_status,
+        Some(pda::txn_status(&program_id, &sender, 3).to_string())
+    );
+    assert_eq!(view.count_source, Some("given"));
+
+    let json = serde_json::to_value(&view).unwrap();
+    assert_eq!(json["message"]["fields"]["amount"], 300);
+}
+
+#[test]
+fn decodes_posted_vaa_account_against_state() {
+    let program_id = solana_project::ID;
+    let mint = Pubkey::new_unique();
+    let vaa = stream_vaa(&mint);
+    let sender = evm_address(1);
+
+    let mut data = b"vaa".to_vec();
+    MessageData {
+        vaa_version: vaa.version,
+        consistency_level: vaa.consistency_level,
+        vaa_time: vaa.timestamp,
+        vaa_signature_account: Pubkey::new_unique(),
+        submission_time: 1_650_000_100,
+        nonce: vaa.nonce,
+        sequence: vaa.sequence,
+        emitter_chain: vaa.emitter_chain,
+        emitter_address: vaa.emitter_address,
+        payload: vaa.payload.clone(),
+    }
+    .serialize(&mut data)
+    .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    dump(
+        dir.path(),
+        &pda::posted_vaa(&core_bridge(), &vaa.hash()),
+        &core_bridge(),
+        1,
+        &data,
+    );
+    dump(
+        dir.path(),
+        &pda::processed_vaa(&program_id, &vaa.emitter_address, 4, 9),
+        &program_id,
+        1,
+        &serialize(&ProcessedVAA {
+            transaction_count: 7,
+            ..ProcessedVAA::default()
+        }),
+    );
+    dump(
+        dir.path(),
+        &pda::txn_count(&program_id, &sender),
+        &program_id,
+        1,
+        &serialize(&Count {
+            count: 8,
+            ..Count::default()
+        }),
+    );
+    dump(
+        dir.path(),
+        &pda::txn_status(&program_id, &sender, 7),
+        &program_id,
+        1,
+        &serialize(&TransactionStatus {
+            executed: false,
+            ..TransactionStatus::default()
+        }),
+    );
+    let source = SnapshotSource::load(dir.path()).unwrap();
+
+    let view = decode_with_state(&source, &program_id, &core_bridge(), &data, None).unwrap();
+    assert_eq!(view.header.guardian_set_index, None);
+    assert_eq!(view.header.submission_time, Some(1_650_000_100));
+    assert_eq!(view.header.hash, hex::encode(vaa.hash()));
+    assert_eq!(view.count, Some(7));
+    assert_eq!(view.count_source, Some("stored"));
+    assert_eq!(
+        view.accounts.data_store,
+        Some(pda::data_store(&program_id, &sender, 7).to_string())
+    );
+    let status = view.status.unwrap();
+    assert!(status.posted);
+    assert!(status.stored);
+    assert_eq!(status.executed, Some(false));
+}
+
+#[test]
+fn predicts_next_count_for_unstored_message() {
+    let program_id = solana_project::ID;
+    let vaa = stream_vaa(&Pubkey::new_unique());
+    let sender = evm_address(1);
+
+    let dir = tempfile::tempdir().unwrap();
+    dump(
+        dir.path(),
+        &pda::txn_count(&program_id, &sender),
+        &program_id,
+        1,
+        &serialize(&Count {
+            count: 4,
+            ..Count::default()
+        }),
+    );
+    let source = SnapshotSource::load(dir.path()).unwrap();
+
+    let view =
+        decode_with_state(&source, &program_id, &core_bridge(), &vaa.serialize(), None).unwrap();
+    assert_eq!(view.count, Some(5));
+    assert_eq!(view.count_source, Some("next"));
+    let status = view.status.unwrap();
+    assert!(!status.posted);
+    assert!(!status.stored);
+    assert_eq!(status.executed, None);
+}
+
+#[test]
+fn decodes_batch_stream_against_state() {
+    let program_id = solana_project::ID;
+    let mint = Pubkey::new_unique();
+    let sender = evm_address(1);
+    let vaa = SignedVaa {
+        payload: batch_stream_payload(
+            sender,
+            &mint,
+            &[(evm_address(2), 300), (evm_address(3), 400)],
+            2,
+        ),
+        ..stream_vaa(&mint)
+    };
+
+    let view = decode_offline(&program_id, &core_bridge(), &vaa.serialize(), Some(3)).unwrap();
+    let message = view.message.as_ref().unwrap();
+    assert_eq!(message.code, 20);
+    assert_eq!(message.kind, "batch_stream");
+    let entries = message.fields.get("entries").unwrap().as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1]["amount"], json!(400));
+    assert_eq!(entries[1]["can_cancel"], json!(true));
+    assert_eq!(entries[1]["can_update"], json!(false));
+    assert_eq!(view.accounts.receiver_pda, None);
+    assert_eq!(view.accounts.data_store, None);
+    assert_eq!(
+        view.accounts.batch_store,
+        Some(pda::batch_store(&program_id, &sender, 3).to_string())
+    );
+    assert_eq!(
+        view.accounts.target_program,
+        Some(pda::target_program(&program_id, 2).to_string())
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    dump(
+        dir.path(),
+        &pda::processed_vaa(&program_id, &vaa.emitter_address, 4, 9),
+        &program_id,
+        1,
+        &serialize(&ProcessedVAA {
+            transaction_count: 3,
+            ..ProcessedVAA::default()
+        }),
+    );
+    let mut batch = BatchData {
+        sender,
+        token_mint: mint,
+        entries: vec![Default::default(); 2],
+        ..BatchData::default()
+    };
+    batch.entries[0].status = BatchEntryStatus::Executed;
+    batch.entries[1].status = BatchEntryStatus::Failed;
+    dump(
+        dir.path(),
+        &pda::batch_store(&program_id, &sender, 3),
+        &program_id,
+        1,
+        &serialize(&batch),
+    );
+    let source = SnapshotSource::load(dir.path()).unwrap();
+
+    let view =
+        decode_with_state(&source, &program_id, &core_bridge(), &vaa.serialize(), None).unwrap();
+    assert_eq!(view.count_source, Some("stored"));
+    assert_eq!(view.status.unwrap().executed, Some(true));
+}
+
+#[test]
+fn reports_non_zebec_payloads() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../vaa.txt");
+    let input = std::fs::read(path).unwrap();
+
+    let view = decode_offline(&solana_project::ID, &core_bridge(), &input, None).unwrap();
+    assert_eq!(view.header.emitter_chain, 1);
+    assert!(view.message.is_none());
+    assert!(view.message_error.is_some());
+    assert!(view.accounts.sender_pda.is_none());
+}
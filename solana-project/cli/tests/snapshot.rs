@@ -0,0 +1,235 @@
+mod common;
+
+use solana_sdk::pubkey::Pubkey;
+
+use solana_project::state::{
+    AddressFormat, Config, Count, EmitterAccount, EmitterAddrAccount, Governance, MintRegistry,
+    TargetProgram, TransactionData, TransactionStatus,
+};
+use solana_project::versioning::{AccountVersion, CURRENT_ACCOUNT_VERSION};
+use zebec_admin::inspect::{self, parse_address};
+use zebec_admin::source::SnapshotSource;
+use zebec_wormhole_client::address::normalize;
+use zebec_wormhole_client::pda;
+
+use common::{dump, serialize};
+
+#[test]
+fn parse_address_pads_evm_addresses() {
+    let evm = parse_address("0x00000000000000000000000000000000000000ff").unwrap();
+    assert_eq!(evm[31], 0xff);
+    assert_eq!(evm[..12], [0u8; 12]);
+
+    let full = parse_address(&"11".repeat(32)).unwrap();
+    assert_eq!(full, [0x11; 32]);
+
+    assert!(parse_address("0x1234").is_err());
+    assert!(parse_address("not hex").is_err());
+}
+
+#[test]
+fn normalize_addresses_of_each_chain_family() {
+    let evm = normalize(
+        AddressFormat::Evm,
+        "0x00000000000000000000000000000000000000ff",
+    )
+    .unwrap();
+    assert_eq!(evm[..12], [0u8; 12]);
+    assert!(normalize(AddressFormat::Evm, &"11".repeat(32)).is_err());
+
+    let key = Pubkey::new_unique();
+    let solana = normalize(AddressFormat::Solana, &key.to_string()).unwrap();
+    assert_eq!(solana, key.to_bytes());
+
+    let aptos = normalize(AddressFormat::Aptos, "0x1").unwrap();
+    assert_eq!(aptos[31], 1);
+    assert_eq!(aptos[..31], [0u8; 31]);
+    let sui = normalize(AddressFormat::Sui, &format!("0x{}", "5e".repeat(32))).unwrap();
+    assert_eq!(sui, [0x5e; 32]);
+    assert!(normalize(AddressFormat::Sui, &"5e".repeat(33)).is_err());
+
+    let near = normalize(AddressFormat::Near, "zebec.near").unwrap();
+    assert_eq!(
+        near,
+        anchor_lang::solana_program::hash::hash(b"zebec.near").to_bytes()
+    );
+    assert_eq!(
+        normalize(AddressFormat::Near, &format!("0x{}", hex::encode(near))).unwrap(),
+        near
+    );
+}
+
+#[test]
+fn snapshot_shows_config_chains_and_messages() {
+    let program_id = solana_project::ID;
+    let owner = Pubkey::new_unique();
+    let sender = parse_address("0x1111111111111111111111111111111111111111").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+
+    dump(
+        dir.path(),
+        &pda::config(&program_id),
+        &program_id,
+        1,
+        &serialize(&Config {
+            owner,
+            nonce: 3,
+            account_version: AccountVersion::CURRENT,
+            governance: Governance::Emitter {
+                chain_id: 2,
+                emitter_address: [0xcc; 32],
+            },
+        }),
+    );
+    dump(
+        dir.path(),
+        &pda::emitter_account(&program_id, 4),
+        &program_id,
+        1,
+        &serialize(&EmitterAddrAccount {
+            chain_id: 4,
+            emitter_addr: "bb".repeat(32),
+        }),
+    );
+    dump(
+        dir.path(),
+        &pda::emitter_account(&program_id, 22),
+        &program_id,
+        1,
+        &serialize(&EmitterAccount {
+            chain_id: 22,
+            emitter_addr: [0x22; 32],
+            registered_at: 1_650_000_000,
+            enabled: true,
+            label: "aptos".to_string(),
+            account_version: AccountVersion::CURRENT,
+            address_format: AddressFormat::Aptos,
+        }),
+    );
+    dump(
+        dir.path(),
+        &pda::emitter_account(&program_id, 2),
+        &program_id,
+        1,
+        &serialize(&EmitterAccount {
+            chain_id: 2,
+            emitter_addr: [0xaa; 32],
+            registered_at: 1_650_000_000,
+            enabled: true,
+            label: "ethereum".to_string(),
+            ..EmitterAccount::default()
+        }),
+    );
+    let zebec = Pubkey::new_unique();
+    dump(
+        dir.path(),
+        &pda::target_program(&program_id, 0),
+        &program_id,
+        1,
+        &serialize(&TargetProgram {
+            version: 0,
+            program_id: zebec,
+            ..TargetProgram::default()
+        }),
+    );
+    dump(
+        dir.path(),
+        &pda::txn_count(&program_id, &sender),
+        &program_id,
+        1,
+        &serialize(&Count {
+            count: 1,
+            ..Count::default()
+        }),
+    );
+    let mint = Pubkey::new_unique();
+    dump(
+        dir.path(),
+        &pda::data_store(&program_id, &sender, 0),
+        &program_id,
+        1,
+        &serialize(&TransactionData {
+            sender,
+            from_chain_id: 2,
+            token_mint: mint,
+            amount: 500,
+            ..TransactionData::default()
+        }),
+    );
+    dump(
+        dir.path(),
+        &pda::mint_registry(&program_id, &mint),
+        &program_id,
+        1,
+        &serialize(&MintRegistry {
+            mint,
+            decimals: 6,
+            max_amount: 1_000,
+            origin_chain: 1,
+            origin_address: mint.to_bytes(),
+            ..MintRegistry::default()
+        }),
+    );
+    dump(
+        dir.path(),
+        &pda::txn_status(&program_id, &sender, 0),
+        &program_id,
+        1,
+        &serialize(&TransactionStatus {
+            executed: true,
+            ..TransactionStatus::default()
+        }),
+    );
+
+    let source = SnapshotSource::load(dir.path()).unwrap();
+
+    let config = inspect::show_config(&source, &program_id).unwrap();
+    assert_eq!(config.owner, owner.to_string());
+    assert_eq!(config.nonce, 3);
+    assert_eq!(config.account_version, CURRENT_ACCOUNT_VERSION);
+    assert_eq!(config.governance, Some((2, "cc".repeat(32))));
+
+    let chains = inspect::list_chains(&source, &program_id).unwrap();
+    let ids: Vec<u16> = chains.chains.iter().map(|chain| chain.chain_id).collect();
+    assert_eq!(ids, vec![2, 4, 22]);
+    assert_eq!(chains.chains[0].emitter_addr, "aa".repeat(32));
+    assert_eq!(chains.chains[0].label, "ethereum");
+    assert_eq!(chains.chains[0].address_format, "evm");
+    assert!(chains.chains[0].migrated);
+    assert_eq!(chains.chains[1].emitter_addr, "bb".repeat(32));
+    assert_eq!(chains.chains[1].address_format, "evm");
+    assert!(!chains.chains[1].migrated);
+    assert_eq!(chains.chains[2].address_format, "aptos");
+
+    // Senders are parsed in the format registered for their chain.
+    let aptos_sender = inspect::parse_sender(&source, &program_id, "0x1", 22).unwrap();
+    assert_eq!(aptos_sender[31], 1);
+    assert!(inspect::parse_sender(&source, &program_id, "0x1", 2).is_err());
+
+    let targets = inspect::list_target_programs(&source, &program_id).unwrap();
+    assert_eq!(targets.target_programs.len(), 1);
+    assert_eq!(targets.target_programs[0].program_id, zebec.to_string());
+
+    let mints = inspect::list_mints(&source, &program_id).unwrap();
+    assert_eq!(mints.mints.len(), 1);
+    assert_eq!(mints.mints[0].mint, mint.to_string());
+    assert_eq!(mints.mints[0].max_amount, 1_000);
+    assert!(!mints.mints[0].wrapped);
+
+    let view = inspect::show_sender(&source, &program_id, sender, 2).unwrap();
+    assert_eq!(view.txn_count, 1);
+    assert_eq!(view.pda_lamports, None);
+    assert!(view.token_accounts.is_empty());
+
+    let message = inspect::show_message(&source, &program_id, sender, 0).unwrap();
+    assert_eq!(message.executed, Some(true));
+    let json = serde_json::to_value(&message).unwrap();
+    assert_eq!(json["data"]["amount"], 500);
+    let data = message.data.unwrap();
+    assert_eq!(data.amount, 500);
+    assert_eq!(data.token_mint, mint.to_string());
+
+    let missing = inspect::show_message(&source, &program_id, sender, 1).unwrap();
+    assert!(missing.data.is_none());
+    assert!(missing.executed.is_none());
+}
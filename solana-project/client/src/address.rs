@@ -0,0 +1,100 @@
+//! Source chain addresses in the 32 byte form Wormhole carries and the proxy
+//! seeds sender PDAs with.
+
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::hash;
+use solana_project::state::AddressFormat;
+
+use crate::error::ClientError;
+
+/// The address format of a Wormhole chain id, for chains registered without
+/// one given.
+pub fn default_format(chain_id: u16) -> AddressFormat {
+    match chain_id {
+        1 => AddressFormat::Solana,
+        15 => AddressFormat::Near,
+        21 => AddressFormat::Sui,
+        22 => AddressFormat::Aptos,
+        _ => AddressFormat::Evm,
+    }
+}
+
+pub fn parse_format(value: &str) -> Result<AddressFormat, ClientError> {
+    match value.to_ascii_lowercase().as_str() {
+        "evm" => Ok(AddressFormat::Evm),
+        "solana" => Ok(AddressFormat::Solana),
+        "aptos" => Ok(AddressFormat::Aptos),
+        "sui" => Ok(AddressFormat::Sui),
+        "near" => Ok(AddressFormat::Near),
+        _ => Err(ClientError::InvalidAddress(format!(
+            "unknown address format {}, expected evm, solana, aptos, sui or near",
+            value
+        ))),
+    }
+}
+
+pub fn format_name(format: AddressFormat) -> &'static str {
+    match format.resolve() {
+        AddressFormat::Solana => "solana",
+        AddressFormat::Aptos => "aptos",
+        AddressFormat::Sui => "sui",
+        AddressFormat::Near => "near",
+        _ => "evm",
+    }
+}
+
+fn hex_bytes(value: &str) -> Option<Vec<u8>> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    if digits.len() % 2 == 1 {
+        hex::decode(format!("0{}", digits)).ok()
+    } else {
+        hex::decode(digits).ok()
+    }
+}
+
+fn left_pad(bytes: &[u8]) -> Option<[u8; 32]> {
+    if bytes.len() > 32 {
+        return None;
+    }
+    let mut address = [0u8; 32];
+    address[32 - bytes.len()..].copy_from_slice(bytes);
+    Some(address)
+}
+
+/// Converts an address as the chain writes it into its 32 byte form:
+///
+/// - EVM: 20 byte hex, left-padded, or the padded 32 bytes.
+/// - Solana: base58, or 32 byte hex.
+/// - Aptos and Sui: hex of up to 32 bytes, left-padded, so `0x1` works.
+/// - Near: an account id, hashed with SHA-256, or the hash as `0x` hex.
+///   Implicit account ids are hex themselves, so unprefixed hex is hashed.
+pub fn normalize(format: AddressFormat, value: &str) -> Result<[u8; 32], ClientError> {
+    let address = match format.resolve() {
+        AddressFormat::Evm => hex_bytes(value)
+            .filter(|bytes| bytes.len() == 20 || bytes.len() == 32)
+            .and_then(|bytes| left_pad(&bytes)),
+        AddressFormat::Solana => Pubkey::from_str(value)
+            .map(|key| key.to_bytes())
+            .ok()
+            .or_else(|| hex_bytes(value).and_then(|bytes| bytes.try_into().ok())),
+        AddressFormat::Aptos | AddressFormat::Sui => {
+            hex_bytes(value).and_then(|bytes| left_pad(&bytes))
+        }
+        _ => match value.strip_prefix("0x") {
+            Some(digits) => hex::decode(digits)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok()),
+            None => Some(hash(value.as_bytes()).to_bytes()),
+        },
+    };
+    match address {
+        Some(address) if format.is_normalized(&address) => Ok(address),
+        _ => Err(ClientError::InvalidAddress(format!(
+            "{} is not a {} address",
+            value,
+            format_name(format)
+        ))),
+    }
+}
@@ -0,0 +1,208 @@
+//! Posting guardian-signed VAAs to the Wormhole core bridge.
+//!
+//! The proxy only reads `PostedVAA` accounts, so somebody has to run
+//! `verify_signatures` + `post_vaa` first. This mirrors what the TypeScript
+//! `postVaaSolanaWithRetry` helper does.
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::{
+    instruction::Instruction, secp256k1_program, system_program, sysvar,
+};
+use anchor_lang::{AnchorDeserialize, AnchorSerialize};
+use byteorder::{LittleEndian, WriteBytesExt};
+use solana_project::wormhole::Instruction as CoreInstruction;
+
+use crate::pda;
+use crate::vaa::SignedVaa;
+
+/// Maximum guardian set size the core bridge tracks in `VerifySignaturesData`.
+pub const MAX_GUARDIANS: usize = 19;
+
+/// Signatures that fit in one secp256k1 instruction alongside verify_signatures.
+pub const SIGNATURES_PER_BATCH: usize = 7;
+
+const ETH_ADDRESS_LENGTH: usize = 20;
+const SECP_SIGNATURE_OFFSETS_SIZE: usize = 11;
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+pub struct GuardianSetData {
+    pub index: u32,
+    pub keys: Vec<[u8; 20]>,
+    pub creation_time: u32,
+    pub expiration_time: u32,
+}
+
+/// Token bridge `meta` account of a wrapped mint.
+#[derive(AnchorDeserialize, AnchorSerialize)]
+pub struct WrappedMeta {
+    pub chain: u16,
+    pub token_address: [u8; 32],
+    pub original_decimals: u8,
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+struct VerifySignaturesData {
+    signers: [i8; MAX_GUARDIANS],
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+struct PostVaaData {
+    version: u8,
+    guardian_set_index: u32,
+    timestamp: u32,
+    nonce: u32,
+    emitter_chain: u16,
+    emitter_address: [u8; 32],
+    sequence: u64,
+    consistency_level: u8,
+    payload: Vec<u8>,
+}
+
+/// Builds the secp256k1 instruction for one batch of signatures. The message
+/// is the VAA body hash; the secp program hashes it again, which yields the
+/// digest the guardians signed.
+pub fn secp256k1_instruction(vaa: &SignedVaa, batch: &[(usize, [u8; 20])]) -> Instruction {
+    let count = batch.len();
+    let entry_size = ETH_ADDRESS_LENGTH + 65;
+    let data_start = 1 + count * SECP_SIGNATURE_OFFSETS_SIZE;
+    let message_offset = data_start + count * entry_size;
+
+    let mut data = Vec::with_capacity(message_offset + 32);
+    data.push(count as u8);
+    for i in 0..count {
+        let entry = data_start + i * entry_size;
+        // signature_offset, signature_instruction_index
+        data.write_u16::<LittleEndian>((entry + ETH_ADDRESS_LENGTH) as u16)
+            .unwrap();
+        data.push(0);
+        // eth_address_offset, eth_address_instruction_index
+        data.write_u16::<LittleEndian>(entry as u16).unwrap();
+        data.push(0);
+        // message_data_offset, message_data_size, message_instruction_index
+        data.write_u16::<LittleEndian>(message_offset as u16)
+            .unwrap();
+        data.write_u16::<LittleEndian>(32).unwrap();
+        data.push(0);
+    }
+    for (signature_position, key) in batch.iter() {
+        data.extend_from_slice(key);
+        data.extend_from_slice(&vaa.signatures[*signature_position].signature);
+    }
+    data.extend_from_slice(&vaa.hash());
+
+    Instruction {
+        program_id: secp256k1_program::id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+pub fn verify_signatures_instruction(
+    core_bridge: &Pubkey,
+    payer: &Pubkey,
+    guardian_set_index: u32,
+    signature_set: &Pubkey,
+    batch: &[(usize, u8)],
+) -> Instruction {
+    let mut signers = [-1i8; MAX_GUARDIANS];
+    for (position, (_, guardian_index)) in batch.iter().enumerate() {
+        signers[*guardian_index as usize] = position as i8;
+    }
+
+    Instruction {
+        program_id: *core_bridge,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(pda::guardian_set(core_bridge, guardian_set_index), false),
+            AccountMeta::new(*signature_set, true),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: (
+            CoreInstruction::VerifySignatures,
+            VerifySignaturesData { signers },
+        )
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+pub fn post_vaa_instruction(
+    core_bridge: &Pubkey,
+    payer: &Pubkey,
+    signature_set: &Pubkey,
+    vaa: &SignedVaa,
+) -> Instruction {
+    Instruction {
+        program_id: *core_bridge,
+        accounts: vec![
+            AccountMeta::new_readonly(
+                pda::guardian_set(core_bridge, vaa.guardian_set_index),
+                false,
+            ),
+            AccountMeta::new_readonly(pda::bridge_config(core_bridge), false),
+            AccountMeta::new_readonly(*signature_set, false),
+            AccountMeta::new(pda::posted_vaa(core_bridge, &vaa.hash()), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: (
+            CoreInstruction::PostVAA,
+            PostVaaData {
+                version: vaa.version,
+                guardian_set_index: vaa.guardian_set_index,
+                timestamp: vaa.timestamp,
+                nonce: vaa.nonce,
+                emitter_chain: vaa.emitter_chain,
+                emitter_address: vaa.emitter_address,
+                sequence: vaa.sequence,
+                consistency_level: vaa.consistency_level,
+                payload: vaa.payload.clone(),
+            },
+        )
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Splits the VAA signatures into verify_signatures batches. Each batch is a
+/// pair of instructions (secp256k1, verify_signatures) that must be sent in
+/// one transaction, in that order, signed by the signature set keypair.
+pub fn verify_signatures_batches(
+    core_bridge: &Pubkey,
+    payer: &Pubkey,
+    signature_set: &Pubkey,
+    guardian_set: &GuardianSetData,
+    vaa: &SignedVaa,
+) -> Vec<[Instruction; 2]> {
+    let positions: Vec<(usize, u8)> = vaa
+        .signatures
+        .iter()
+        .enumerate()
+        .map(|(position, sig)| (position, sig.index))
+        .filter(|(_, index)| (*index as usize) < guardian_set.keys.len())
+        .collect();
+
+    positions
+        .chunks(SIGNATURES_PER_BATCH)
+        .map(|batch| {
+            let keys: Vec<(usize, [u8; 20])> = batch
+                .iter()
+                .map(|(position, index)| (*position, guardian_set.keys[*index as usize]))
+                .collect();
+            [
+                secp256k1_instruction(vaa, &keys),
+                verify_signatures_instruction(
+                    core_bridge,
+                    payer,
+                    vaa.guardian_set_index,
+                    signature_set,
+                    batch,
+                ),
+            ]
+        })
+        .collect()
+}
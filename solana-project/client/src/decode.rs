@@ -0,0 +1,183 @@
+//! Decoding VAAs for debugging: a guardian-signed VAA or the data of a core
+//! bridge `PostedVAA` account, the Zebec message it carries, and every proxy
+//! account that message touches.
+
+use anchor_lang::prelude::Pubkey;
+use borsh::BorshDeserialize;
+use solana_project::wormhole::MessageData;
+
+use crate::error::ClientError;
+use crate::message::{target_version, ZebecMessage, BATCH_STREAM, DIRECT_TRANSFER};
+use crate::pda;
+use crate::vaa::{self, SignedVaa};
+
+/// Where a decoded VAA came from. A `PostedVAA` account keeps the body but
+/// not the guardian signatures, only the account they were verified in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VaaSource {
+    Signed,
+    Posted {
+        signature_set: Pubkey,
+        submission_time: u32,
+    },
+}
+
+/// Proxy and core bridge accounts derived from a VAA and its message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DerivedAccounts {
+    pub posted_vaa: Pubkey,
+    pub emitter_account: Pubkey,
+    /// Created by `store_msg`; holds the count the message was stored under.
+    pub processed_vaa: Pubkey,
+    /// `pda_signer` of the message signer.
+    pub sender_pda: Option<Pubkey>,
+    /// `pda_signer` of the stream sender, when it differs from the signer.
+    pub stream_sender_pda: Option<Pubkey>,
+    pub receiver_pda: Option<Pubkey>,
+    pub txn_count: Option<Pubkey>,
+    /// Only known once the count `store_msg` uses is.
+    pub data_store: Option<Pubkey>,
+    pub txn_status: Option<Pubkey>,
+    /// Created by `store_batch_msg` for a batch stream, in place of
+    /// `data_store` and `txn_status`.
+    pub batch_store: Option<Pubkey>,
+    /// Registry entry of the target program version the message selects.
+    pub target_program: Option<Pubkey>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedVaa {
+    pub source: VaaSource,
+    /// For a posted VAA, `guardian_set_index` is 0 and `signatures` is empty.
+    pub vaa: SignedVaa,
+    pub hash: [u8; 32],
+    /// The Zebec message, or why the payload could not be decoded.
+    pub message: Result<ZebecMessage, ClientError>,
+    pub accounts: DerivedAccounts,
+}
+
+/// Whether a message goes through `store_msg`, or `store_batch_msg` for a
+/// batch stream. PDA and token account initialization and payout wallet
+/// links act on the VAA directly and never get a count.
+pub fn is_stored(message: &ZebecMessage) -> bool {
+    !matches!(
+        message,
+        ZebecMessage::InitializePda { .. }
+            | ZebecMessage::InitializeTokenAccount { .. }
+            | ZebecMessage::SetPayoutWallet { .. }
+    )
+}
+
+/// Decodes `input` as a signed VAA or as `PostedVAA` account data (`vaa` or
+/// `msg` followed by the borsh `MessageData`). Raw bytes, hex and base64 are
+/// all accepted.
+pub fn parse_vaa(input: &[u8]) -> Result<(VaaSource, SignedVaa), ClientError> {
+    let bytes = vaa::decode_encoded(input);
+    let posted = bytes
+        .strip_prefix(b"vaa")
+        .or_else(|| bytes.strip_prefix(b"msg"));
+    let mut data = match posted {
+        Some(data) => data,
+        None => return Ok((VaaSource::Signed, SignedVaa::parse(&bytes)?)),
+    };
+
+    let message = MessageData::deserialize(&mut data)
+        .map_err(|e| ClientError::InvalidVaa(format!("posted VAA account: {}", e)))?;
+    let source = VaaSource::Posted {
+        signature_set: message.vaa_signature_account,
+        submission_time: message.submission_time,
+    };
+    let vaa = SignedVaa {
+        version: message.vaa_version,
+        guardian_set_index: 0,
+        signatures: vec![],
+        timestamp: message.vaa_time,
+        nonce: message.nonce,
+        emitter_chain: message.emitter_chain,
+        emitter_address: message.emitter_address,
+        sequence: message.sequence,
+        consistency_level: message.consistency_level,
+        payload: message.payload,
+    };
+    Ok((source, vaa))
+}
+
+/// Derives the accounts a VAA touches. `count` is the count `store_msg` used
+/// (or will use) for the message; without it `data_store` and `txn_status`
+/// are left out.
+pub fn derive_accounts(
+    program_id: &Pubkey,
+    core_bridge: &Pubkey,
+    vaa: &SignedVaa,
+    message: Option<&ZebecMessage>,
+    count: Option<u64>,
+) -> DerivedAccounts {
+    let chain = vaa.emitter_chain;
+    let mut accounts = DerivedAccounts {
+        posted_vaa: pda::posted_vaa(core_bridge, &vaa.hash()),
+        emitter_account: pda::emitter_account(program_id, chain),
+        processed_vaa: pda::processed_vaa(program_id, &vaa.emitter_address, chain, vaa.sequence),
+        sender_pda: None,
+        stream_sender_pda: None,
+        receiver_pda: None,
+        txn_count: None,
+        data_store: None,
+        txn_status: None,
+        batch_store: None,
+        target_program: None,
+    };
+
+    let message = match message {
+        Some(message) => message,
+        None => return accounts,
+    };
+    let signer = message.signer();
+    accounts.sender_pda = Some(pda::user_pda(program_id, &signer, chain));
+    if message.stream_sender() != signer {
+        accounts.stream_sender_pda =
+            Some(pda::user_pda(program_id, &message.stream_sender(), chain));
+    }
+    accounts.receiver_pda = message
+        .receiver()
+        .map(|receiver| pda::user_pda(program_id, &receiver, chain));
+
+    if is_stored(message) {
+        accounts.txn_count = Some(pda::txn_count(program_id, &signer));
+        if message.code() != DIRECT_TRANSFER {
+            let version = target_version(&vaa.payload);
+            accounts.target_program = Some(pda::target_program(program_id, version));
+        }
+        match count {
+            Some(count) if message.code() == BATCH_STREAM => {
+                accounts.batch_store = Some(pda::batch_store(program_id, &signer, count));
+            }
+            Some(count) => {
+                accounts.data_store = Some(pda::data_store(program_id, &signer, count));
+                accounts.txn_status = Some(pda::txn_status(program_id, &signer, count));
+            }
+            None => {}
+        }
+    }
+    accounts
+}
+
+/// Decodes a VAA, its Zebec message and the accounts it touches. A payload
+/// that is not a Zebec message is reported in `message` rather than failing
+/// the whole decode, so the header can still be inspected.
+pub fn decode(
+    program_id: &Pubkey,
+    core_bridge: &Pubkey,
+    input: &[u8],
+    count: Option<u64>,
+) -> Result<DecodedVaa, ClientError> {
+    let (source, vaa) = parse_vaa(input)?;
+    let message = ZebecMessage::decode(&vaa.payload);
+    let accounts = derive_accounts(program_id, core_bridge, &vaa, message.as_ref().ok(), count);
+    Ok(DecodedVaa {
+        source,
+        hash: vaa.hash(),
+        vaa,
+        message,
+        accounts,
+    })
+}
@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum ClientError {
+    #[error("invalid VAA: {0}")]
+    InvalidVaa(String),
+
+    #[error("invalid payload: {0}")]
+    InvalidPayload(String),
+
+    #[error("unknown message code {0}")]
+    UnknownCode(u8),
+
+    #[error("message is addressed to chain {0}, not Solana")]
+    InvalidToChain(u64),
+
+    #[error("invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("invalid lookup table {0}")]
+    InvalidLookupTable(String),
+
+    #[error("no keypair for signer {0}")]
+    MissingSigner(String),
+}
@@ -0,0 +1,1444 @@
+//! Instruction builders for the proxy program.
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::{
+    instruction::Instruction, secp256k1_program, system_program, sysvar,
+};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_project::constants::{RECEIVER_TOKEN_ACCOUNT_INDEX, SOLANA_CHAIN_ID};
+use solana_project::governance::GovernanceAction;
+use solana_project::meta::{signed_message, SignedAction};
+use solana_project::relay::RelayStep;
+use solana_project::state::{AddressFormat, TransactionAccount};
+use solana_project::{accounts, instruction};
+
+use crate::pda;
+use crate::vaa::SignedVaa;
+
+/// Zebec instructions the proxy builds in one step and executes later.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CreateKind {
+    Stream,
+    ReceiverWithdraw,
+    Cancel,
+    SenderWithdraw,
+    InstantTransfer,
+    /// A receiver withdraw that is bridged on once executed; see
+    /// `withdraw_bridge`.
+    WithdrawBridge,
+    /// A receiver withdraw that is paid out to the withdrawer's payout
+    /// wallet once executed; see `withdraw_payout`. Carries the withdrawer's
+    /// chain, which keys its payout profile.
+    WithdrawPayout(u16),
+}
+
+/// Zebec instructions the proxy builds and executes in the same instruction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CreateExecuteKind {
+    Deposit,
+    StreamUpdate,
+    PauseResume,
+}
+
+/// The accounts `perform_cpi` needs: the Zebec accounts with the PDA signer
+/// demoted (the program signs for it) followed by the Zebec program itself.
+pub fn remaining_accounts(
+    accs: &[TransactionAccount],
+    pda_signer: &Pubkey,
+    zebec_program: &Pubkey,
+) -> Vec<AccountMeta> {
+    let mut metas: Vec<AccountMeta> = accs
+        .iter()
+        .map(|acc| {
+            let is_signer = acc.is_signer && acc.pubkey != *pda_signer;
+            if acc.is_writable {
+                AccountMeta::new(acc.pubkey, is_signer)
+            } else {
+                AccountMeta::new_readonly(acc.pubkey, is_signer)
+            }
+        })
+        .collect();
+    metas.push(AccountMeta::new_readonly(*zebec_program, false));
+    metas
+}
+
+pub fn initialize(program_id: &Pubkey, owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::Initialize {
+            config: pda::config(program_id),
+            owner: *owner,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::Initialize {}.data(),
+    }
+}
+
+pub fn register_chain(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    chain_id: u16,
+    emitter_addr: [u8; 32],
+    label: String,
+    address_format: AddressFormat,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::RegisterChain {
+            owner: *owner,
+            system_program: system_program::id(),
+            config: pda::config(program_id),
+            emitter_acc: pda::emitter_account(program_id, chain_id),
+        }
+        .to_account_metas(None),
+        data: instruction::RegisterChain {
+            chain_id,
+            emitter_addr,
+            label,
+            address_format,
+        }
+        .data(),
+    }
+}
+
+pub fn update_chain(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    chain_id: u16,
+    enabled: bool,
+    label: Option<String>,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::UpdateChain {
+            owner: *owner,
+            config: pda::config(program_id),
+            emitter_acc: pda::emitter_account(program_id, chain_id),
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateChain {
+            chain_id,
+            enabled,
+            label,
+        }
+        .data(),
+    }
+}
+
+/// Caps what goes out to `chain_id` over 24 hours, in notional units.
+/// `u64::MAX` lifts the cap.
+pub fn set_chain_limit(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    chain_id: u16,
+    daily_limit: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::SetChainLimit {
+            owner: *owner,
+            system_program: system_program::id(),
+            config: pda::config(program_id),
+            chain_limit: pda::chain_limit(program_id, chain_id),
+        }
+        .to_account_metas(None),
+        data: instruction::SetChainLimit {
+            chain_id,
+            daily_limit,
+        }
+        .data(),
+    }
+}
+
+/// Caps what goes out of `mint` over 24 hours in base units, prices it for
+/// chain limits, and holds transfers of at least `large_transfer_threshold`
+/// for `hold_seconds`.
+pub fn set_mint_limit(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    daily_limit: u64,
+    notional_price: u64,
+    large_transfer_threshold: u64,
+    hold_seconds: u32,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::SetMintLimit {
+            owner: *owner,
+            system_program: system_program::id(),
+            config: pda::config(program_id),
+            mint: *mint,
+            mint_limit: pda::mint_limit(program_id, mint),
+        }
+        .to_account_metas(None),
+        data: instruction::SetMintLimit {
+            daily_limit,
+            notional_price,
+            large_transfer_threshold,
+            hold_seconds,
+        }
+        .data(),
+    }
+}
+
+/// Rewrites a chain registered with the old hex string layout.
+pub fn migrate_chain(program_id: &Pubkey, owner: &Pubkey, chain_id: u16) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::MigrateChain {
+            owner: *owner,
+            system_program: system_program::id(),
+            config: pda::config(program_id),
+            emitter_acc: pda::emitter_account(program_id, chain_id),
+        }
+        .to_account_metas(None),
+        data: instruction::MigrateChain { chain_id }.data(),
+    }
+}
+
+/// Rewrites a state account of the proxy in the current layout.
+pub fn migrate_account(program_id: &Pubkey, payer: &Pubkey, account: Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::MigrateAccount {
+            payer: *payer,
+            system_program: system_program::id(),
+            account,
+        }
+        .to_account_metas(None),
+        data: instruction::MigrateAccount {}.data(),
+    }
+}
+
+pub fn register_target_program(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    version: u8,
+    target_program: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::RegisterTargetProgram {
+            owner: *owner,
+            system_program: system_program::id(),
+            config: pda::config(program_id),
+            target_program: pda::target_program(program_id, version),
+        }
+        .to_account_metas(None),
+        data: instruction::RegisterTargetProgram {
+            version,
+            target_program_id: target_program,
+        }
+        .data(),
+    }
+}
+
+pub fn remove_target_program(program_id: &Pubkey, owner: &Pubkey, version: u8) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::RemoveTargetProgram {
+            owner: *owner,
+            config: pda::config(program_id),
+            target_program: pda::target_program(program_id, version),
+        }
+        .to_account_metas(None),
+        data: instruction::RemoveTargetProgram { version }.data(),
+    }
+}
+
+/// Lets VAAs of `emitter_address` on `chain_id` make admin changes. Chain 0
+/// turns governance off.
+pub fn set_governance(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    chain_id: u16,
+    emitter_address: [u8; 32],
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::SetGovernance {
+            owner: *owner,
+            system_program: system_program::id(),
+            config: pda::config(program_id),
+        }
+        .to_account_metas(None),
+        data: instruction::SetGovernance {
+            chain_id,
+            emitter_address,
+        }
+        .data(),
+    }
+}
+
+/// Applies a posted governance VAA carrying `action`, as
+/// `solana_project::decode_governance` reads its payload.
+pub fn govern(
+    program_id: &Pubkey,
+    core_bridge: &Pubkey,
+    payer: &Pubkey,
+    vaa: &SignedVaa,
+    action: &GovernanceAction,
+) -> Instruction {
+    let payer = *payer;
+    let system_program = system_program::id();
+    let config = pda::config(program_id);
+    let processed_vaa = pda::processed_vaa(
+        program_id,
+        &vaa.emitter_address,
+        vaa.emitter_chain,
+        vaa.sequence,
+    );
+    let core_bridge_vaa = pda::posted_vaa(core_bridge, &vaa.hash());
+
+    let (accounts, data) = match *action {
+        GovernanceAction::RegisterChain { chain_id, .. } => (
+            accounts::GovernRegisterChain {
+                payer,
+                system_program,
+                config,
+                processed_vaa,
+                core_bridge_vaa,
+                emitter_acc: pda::emitter_account(program_id, chain_id),
+            }
+            .to_account_metas(None),
+            instruction::GovernRegisterChain { chain_id }.data(),
+        ),
+        GovernanceAction::UpdateChain { chain_id, .. } => (
+            accounts::GovernUpdateChain {
+                payer,
+                system_program,
+                config,
+                processed_vaa,
+                core_bridge_vaa,
+                emitter_acc: pda::emitter_account(program_id, chain_id),
+            }
+            .to_account_metas(None),
+            instruction::GovernUpdateChain { chain_id }.data(),
+        ),
+        GovernanceAction::SetChainLimit { chain_id, .. } => (
+            accounts::GovernSetChainLimit {
+                payer,
+                system_program,
+                config,
+                processed_vaa,
+                core_bridge_vaa,
+                chain_limit: pda::chain_limit(program_id, chain_id),
+            }
+            .to_account_metas(None),
+            instruction::GovernSetChainLimit { chain_id }.data(),
+        ),
+        GovernanceAction::SetMintLimit { mint, .. } => (
+            accounts::GovernSetMintLimit {
+                payer,
+                system_program,
+                config,
+                processed_vaa,
+                core_bridge_vaa,
+                mint,
+                mint_limit: pda::mint_limit(program_id, &mint),
+            }
+            .to_account_metas(None),
+            instruction::GovernSetMintLimit {}.data(),
+        ),
+        GovernanceAction::RegisterTargetProgram { version, .. } => (
+            accounts::GovernRegisterTargetProgram {
+                payer,
+                system_program,
+                config,
+                processed_vaa,
+                core_bridge_vaa,
+                target_program: pda::target_program(program_id, version),
+            }
+            .to_account_metas(None),
+            instruction::GovernRegisterTargetProgram { version }.data(),
+        ),
+        GovernanceAction::RemoveTargetProgram { version } => (
+            accounts::GovernRemoveTargetProgram {
+                payer,
+                system_program,
+                config,
+                processed_vaa,
+                core_bridge_vaa,
+                target_program: pda::target_program(program_id, version),
+            }
+            .to_account_metas(None),
+            instruction::GovernRemoveTargetProgram { version }.data(),
+        ),
+        GovernanceAction::SetOwner { .. } => (
+            accounts::GovernConfig {
+                payer,
+                system_program,
+                config,
+                processed_vaa,
+                core_bridge_vaa,
+            }
+            .to_account_metas(None),
+            instruction::GovernConfig {}.data(),
+        ),
+    };
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Lets messages name `mint`, or updates its entry. A single amount of it
+/// must lie within `min_amount` and `max_amount`, in base units. A Solana mint
+/// has itself as origin; a wrapped one the token it stands for.
+pub fn register_mint(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    min_amount: u64,
+    max_amount: u64,
+    origin_chain: u16,
+    origin_address: [u8; 32],
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::RegisterMint {
+            owner: *owner,
+            system_program: system_program::id(),
+            config: pda::config(program_id),
+            mint: *mint,
+            mint_registry: pda::mint_registry(program_id, mint),
+        }
+        .to_account_metas(None),
+        data: instruction::RegisterMint {
+            min_amount,
+            max_amount,
+            origin_chain,
+            origin_address,
+        }
+        .data(),
+    }
+}
+
+pub fn remove_mint(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::RemoveMint {
+            owner: *owner,
+            config: pda::config(program_id),
+            mint_registry: pda::mint_registry(program_id, mint),
+        }
+        .to_account_metas(None),
+        data: instruction::RemoveMint { mint: *mint }.data(),
+    }
+}
+
+pub fn initialize_pda(
+    program_id: &Pubkey,
+    core_bridge: &Pubkey,
+    payer: &Pubkey,
+    vaa: &SignedVaa,
+    account: [u8; 32],
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::InitializePDA {
+            zebec_eoa: *payer,
+            system_program: system_program::id(),
+            processed_vaa: pda::processed_vaa(
+                program_id,
+                &vaa.emitter_address,
+                vaa.emitter_chain,
+                vaa.sequence,
+            ),
+            emitter_acc: pda::emitter_account(program_id, vaa.emitter_chain),
+            core_bridge_vaa: pda::posted_vaa(core_bridge, &vaa.hash()),
+            pda_account: pda::user_pda(program_id, &account, vaa.emitter_chain),
+        }
+        .to_account_metas(None),
+        data: instruction::InitializePda {
+            _sender: account,
+            _chain_id: vaa.emitter_chain,
+        }
+        .data(),
+    }
+}
+
+/// Links `account` to the payout wallet the VAA names, or unlinks it.
+pub fn set_payout_wallet(
+    program_id: &Pubkey,
+    core_bridge: &Pubkey,
+    payer: &Pubkey,
+    vaa: &SignedVaa,
+    account: [u8; 32],
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::SetPayoutWallet {
+            zebec_eoa: *payer,
+            system_program: system_program::id(),
+            processed_vaa: pda::processed_vaa(
+                program_id,
+                &vaa.emitter_address,
+                vaa.emitter_chain,
+                vaa.sequence,
+            ),
+            emitter_acc: pda::emitter_account(program_id, vaa.emitter_chain),
+            core_bridge_vaa: pda::posted_vaa(core_bridge, &vaa.hash()),
+            payout_profile: pda::payout_profile(program_id, &account, vaa.emitter_chain),
+        }
+        .to_account_metas(None),
+        data: instruction::SetPayoutWallet {
+            sender: account,
+            chain_id: vaa.emitter_chain,
+        }
+        .data(),
+    }
+}
+
+pub fn initialize_pda_token_account(
+    program_id: &Pubkey,
+    core_bridge: &Pubkey,
+    payer: &Pubkey,
+    vaa: &SignedVaa,
+    account: [u8; 32],
+    token_mint: &Pubkey,
+) -> Instruction {
+    let pda_account = pda::user_pda(program_id, &account, vaa.emitter_chain);
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::InitializePDATokenAccount {
+            zebec_eoa: *payer,
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            processed_vaa: pda::processed_vaa(
+                program_id,
+                &vaa.emitter_address,
+                vaa.emitter_chain,
+                vaa.sequence,
+            ),
+            emitter_acc: pda::emitter_account(program_id, vaa.emitter_chain),
+            core_bridge_vaa: pda::posted_vaa(core_bridge, &vaa.hash()),
+            pda_account,
+            pda_token_account: get_associated_token_address(&pda_account, token_mint),
+            token_mint: *token_mint,
+            mint_registry: pda::mint_registry(program_id, token_mint),
+        }
+        .to_account_metas(None),
+        data: instruction::InitializePdaTokenAccount {
+            _sender: account,
+            _chain_id: vaa.emitter_chain,
+        }
+        .data(),
+    }
+}
+
+/// Stores the message of `vaa`, which names `token_mint`.
+pub fn store_msg(
+    program_id: &Pubkey,
+    core_bridge: &Pubkey,
+    payer: &Pubkey,
+    vaa: &SignedVaa,
+    sender: [u8; 32],
+    token_mint: &Pubkey,
+    current_count: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::StoreMsg {
+            payer: *payer,
+            system_program: system_program::id(),
+            processed_vaa: pda::processed_vaa(
+                program_id,
+                &vaa.emitter_address,
+                vaa.emitter_chain,
+                vaa.sequence,
+            ),
+            emitter_acc: pda::emitter_account(program_id, vaa.emitter_chain),
+            core_bridge_vaa: pda::posted_vaa(core_bridge, &vaa.hash()),
+            data_storage: pda::data_store(program_id, &sender, current_count),
+            txn_count: pda::txn_count(program_id, &sender),
+            txn_status: pda::txn_status(program_id, &sender, current_count),
+            mint_registry: pda::mint_registry(program_id, token_mint),
+        }
+        .to_account_metas(None),
+        data: instruction::StoreMsg {
+            current_count,
+            sender,
+        }
+        .data(),
+    }
+}
+
+/// Has the secp256k1 program check `signature`, as an EVM wallet returns it
+/// from `eth_signTypedData` (r, s and v), over the typed data of `action`
+/// signed by `eth_address`. It has to be the first instruction of the
+/// transaction, right before `store_signed_msg`.
+pub fn verify_signed_action(
+    program_id: &Pubkey,
+    eth_address: [u8; 20],
+    signature: [u8; 65],
+    chain_id: u16,
+    nonce: u64,
+    action: &SignedAction,
+) -> Instruction {
+    let message = signed_message(program_id, chain_id, nonce, action);
+    // One signature: its offsets, then the address, signature, recovery id
+    // and message they point at, all in instruction 0.
+    let eth_address_offset: u16 = 1 + 11;
+    let signature_offset = eth_address_offset + 20;
+    let message_offset = signature_offset + 64 + 1;
+    let recovery_id = signature[64].checked_sub(27).unwrap_or(signature[64]);
+
+    let mut data = vec![1u8];
+    data.extend(signature_offset.to_le_bytes());
+    data.push(0);
+    data.extend(eth_address_offset.to_le_bytes());
+    data.push(0);
+    data.extend(message_offset.to_le_bytes());
+    data.extend((message.len() as u16).to_le_bytes());
+    data.push(0);
+    data.extend(eth_address);
+    data.extend(&signature[..64]);
+    data.push(recovery_id);
+    data.extend(message);
+    Instruction {
+        program_id: secp256k1_program::ID,
+        accounts: vec![],
+        data,
+    }
+}
+
+/// Stores `action` signed by `sender`, an EVM address left-padded to 32
+/// bytes, on `chain_id`. Goes right after `verify_signed_action`.
+pub fn store_signed_msg(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    action: SignedAction,
+    sender: [u8; 32],
+    chain_id: u16,
+    nonce: u64,
+    current_count: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::StoreSignedMsg {
+            payer: *payer,
+            system_program: system_program::id(),
+            emitter_acc: pda::emitter_account(program_id, chain_id),
+            meta_nonce: pda::meta_nonce(program_id, &sender, chain_id),
+            instructions: sysvar::instructions::id(),
+            data_storage: pda::data_store(program_id, &sender, current_count),
+            txn_count: pda::txn_count(program_id, &sender),
+            txn_status: pda::txn_status(program_id, &sender, current_count),
+            mint_registry: pda::mint_registry(program_id, &action.token_mint()),
+        }
+        .to_account_metas(None),
+        data: instruction::StoreSignedMsg {
+            action,
+            sender,
+            chain_id,
+            nonce,
+            current_count,
+        }
+        .data(),
+    }
+}
+
+/// Accounts per `append_accounts` of `upload_transaction`.
+pub const UPLOAD_ACCOUNTS_CHUNK: usize = 20;
+/// Bytes per `append_data` of `upload_transaction`.
+pub const UPLOAD_DATA_CHUNK: usize = 800;
+
+pub fn init_transaction(
+    program_id: &Pubkey,
+    zebec_eoa: &Pubkey,
+    sender: [u8; 32],
+    current_count: u64,
+    accounts_len: u8,
+    data_len: u16,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::InitTransaction {
+            transaction_buffer: pda::transaction_buffer(program_id, &sender, current_count),
+            zebec_eoa: *zebec_eoa,
+            system_program: system_program::id(),
+            txn_status: pda::txn_status(program_id, &sender, current_count),
+            config: pda::config(program_id),
+        }
+        .to_account_metas(None),
+        data: instruction::InitTransaction {
+            _sender: sender,
+            _current_count: current_count,
+            accounts_len,
+            data_len,
+        }
+        .data(),
+    }
+}
+
+fn append_transaction_metas(
+    program_id: &Pubkey,
+    uploader: &Pubkey,
+    sender: [u8; 32],
+    current_count: u64,
+) -> Vec<AccountMeta> {
+    accounts::AppendTransaction {
+        transaction_buffer: pda::transaction_buffer(program_id, &sender, current_count),
+        uploader: *uploader,
+    }
+    .to_account_metas(None)
+}
+
+pub fn append_accounts(
+    program_id: &Pubkey,
+    uploader: &Pubkey,
+    sender: [u8; 32],
+    current_count: u64,
+    accs: Vec<TransactionAccount>,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: append_transaction_metas(program_id, uploader, sender, current_count),
+        data: instruction::AppendAccounts {
+            _sender: sender,
+            _current_count: current_count,
+            accs,
+        }
+        .data(),
+    }
+}
+
+pub fn append_data(
+    program_id: &Pubkey,
+    uploader: &Pubkey,
+    sender: [u8; 32],
+    current_count: u64,
+    data: Vec<u8>,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: append_transaction_metas(program_id, uploader, sender, current_count),
+        data: instruction::AppendData {
+            _sender: sender,
+            _current_count: current_count,
+            data,
+        }
+        .data(),
+    }
+}
+
+pub fn finalize_transaction(
+    program_id: &Pubkey,
+    uploader: &Pubkey,
+    sender: [u8; 32],
+    current_count: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: append_transaction_metas(program_id, uploader, sender, current_count),
+        data: instruction::FinalizeTransaction {
+            _sender: sender,
+            _current_count: current_count,
+        }
+        .data(),
+    }
+}
+
+/// Every instruction that uploads a transaction too large for
+/// `create_transaction` or `create_and_execute_transaction`, each small
+/// enough to send on its own, in order. `create_staged_transaction` or
+/// `create_and_execute_staged_transaction` then builds it.
+pub fn upload_transaction(
+    program_id: &Pubkey,
+    uploader: &Pubkey,
+    accs: &[TransactionAccount],
+    data: &[u8],
+    sender: [u8; 32],
+    current_count: u64,
+) -> Vec<Instruction> {
+    let mut ixs = vec![init_transaction(
+        program_id,
+        uploader,
+        sender,
+        current_count,
+        accs.len() as u8,
+        data.len() as u16,
+    )];
+    for chunk in accs.chunks(UPLOAD_ACCOUNTS_CHUNK) {
+        ixs.push(append_accounts(
+            program_id,
+            uploader,
+            sender,
+            current_count,
+            chunk.to_vec(),
+        ));
+    }
+    for chunk in data.chunks(UPLOAD_DATA_CHUNK) {
+        ixs.push(append_data(
+            program_id,
+            uploader,
+            sender,
+            current_count,
+            chunk.to_vec(),
+        ));
+    }
+    ixs.push(finalize_transaction(
+        program_id,
+        uploader,
+        sender,
+        current_count,
+    ));
+    ixs
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_transaction(
+    kind: CreateKind,
+    program_id: &Pubkey,
+    zebec_eoa: &Pubkey,
+    mint: &Pubkey,
+    accs: Vec<TransactionAccount>,
+    data: Vec<u8>,
+    sender: [u8; 32],
+    current_count: u64,
+    target_version: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: create_transaction_metas(
+            kind,
+            program_id,
+            zebec_eoa,
+            mint,
+            &accs,
+            sender,
+            current_count,
+            target_version,
+        ),
+        data: create_transaction_data(kind, accs, data, sender, current_count),
+    }
+}
+
+/// `create_transaction` for a transaction uploaded with `upload_transaction`.
+/// `accs` are the uploaded accounts, which some kinds name accounts from.
+#[allow(clippy::too_many_arguments)]
+pub fn create_staged_transaction(
+    kind: CreateKind,
+    program_id: &Pubkey,
+    zebec_eoa: &Pubkey,
+    mint: &Pubkey,
+    accs: &[TransactionAccount],
+    sender: [u8; 32],
+    current_count: u64,
+    target_version: u8,
+) -> Instruction {
+    let mut metas = create_transaction_metas(
+        kind,
+        program_id,
+        zebec_eoa,
+        mint,
+        accs,
+        sender,
+        current_count,
+        target_version,
+    );
+    metas.push(AccountMeta::new(
+        pda::transaction_buffer(program_id, &sender, current_count),
+        false,
+    ));
+    Instruction {
+        program_id: *program_id,
+        accounts: metas,
+        data: create_transaction_data(kind, vec![], vec![], sender, current_count),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_transaction_metas(
+    kind: CreateKind,
+    program_id: &Pubkey,
+    zebec_eoa: &Pubkey,
+    mint: &Pubkey,
+    accs: &[TransactionAccount],
+    sender: [u8; 32],
+    current_count: u64,
+    target_version: u8,
+) -> Vec<AccountMeta> {
+    let transaction = pda::transaction(program_id, &sender, current_count);
+    let data_storage = pda::data_store(program_id, &sender, current_count);
+    let txn_status = pda::txn_status(program_id, &sender, current_count);
+    let target_program = pda::target_program(program_id, target_version);
+
+    match kind {
+        CreateKind::ReceiverWithdraw => accounts::CreateTransactionReceiver {
+            transaction,
+            zebec_eoa: *zebec_eoa,
+            system_program: system_program::id(),
+            data_storage,
+            txn_status,
+            target_program,
+            mint: *mint,
+            config: pda::config(program_id),
+        }
+        .to_account_metas(None),
+        CreateKind::WithdrawBridge => accounts::CreateTransactionWithdrawBridge {
+            transaction,
+            zebec_eoa: *zebec_eoa,
+            system_program: system_program::id(),
+            data_storage,
+            txn_status,
+            target_program,
+            mint: *mint,
+            receiver_token_account: accs[RECEIVER_TOKEN_ACCOUNT_INDEX].pubkey,
+            config: pda::config(program_id),
+        }
+        .to_account_metas(None),
+        CreateKind::WithdrawPayout(sender_chain) => accounts::CreateTransactionWithdrawPayout {
+            transaction,
+            zebec_eoa: *zebec_eoa,
+            system_program: system_program::id(),
+            data_storage,
+            txn_status,
+            target_program,
+            mint: *mint,
+            receiver_token_account: accs[RECEIVER_TOKEN_ACCOUNT_INDEX].pubkey,
+            payout_profile: pda::payout_profile(program_id, &sender, sender_chain),
+            config: pda::config(program_id),
+        }
+        .to_account_metas(None),
+        CreateKind::SenderWithdraw | CreateKind::InstantTransfer => {
+            accounts::CreateOutboundTransaction {
+                transaction,
+                zebec_eoa: *zebec_eoa,
+                system_program: system_program::id(),
+                data_storage,
+                txn_status,
+                target_program,
+                mint: *mint,
+                chain_limit: pda::chain_limit(program_id, SOLANA_CHAIN_ID),
+                mint_limit: pda::mint_limit(program_id, mint),
+                config: pda::config(program_id),
+            }
+            .to_account_metas(None)
+        }
+        _ => accounts::CreateTransaction {
+            transaction,
+            zebec_eoa: *zebec_eoa,
+            system_program: system_program::id(),
+            data_storage,
+            txn_status,
+            target_program,
+            mint: *mint,
+            config: pda::config(program_id),
+        }
+        .to_account_metas(None),
+    }
+}
+
+fn create_transaction_data(
+    kind: CreateKind,
+    accs: Vec<TransactionAccount>,
+    data: Vec<u8>,
+    sender: [u8; 32],
+    current_count: u64,
+) -> Vec<u8> {
+    match kind {
+        CreateKind::Stream => instruction::CreateTransactionStream {
+            accs,
+            data,
+            sender,
+            current_count,
+        }
+        .data(),
+        CreateKind::ReceiverWithdraw => instruction::CreateTransactionReceiverWithdraw {
+            accs,
+            data,
+            sender,
+            current_count,
+        }
+        .data(),
+        CreateKind::Cancel => instruction::CreateTransactionCancel {
+            accs,
+            data,
+            sender,
+            current_count,
+        }
+        .data(),
+        CreateKind::SenderWithdraw => instruction::CreateTransactionSenderWithdraw {
+            accs,
+            data,
+            sender,
+            current_count,
+        }
+        .data(),
+        CreateKind::InstantTransfer => instruction::CreateTransactionInstantTransfer {
+            accs,
+            data,
+            sender,
+            current_count,
+        }
+        .data(),
+        CreateKind::WithdrawBridge => instruction::CreateTransactionWithdrawBridge {
+            accs,
+            data,
+            sender,
+            current_count,
+        }
+        .data(),
+        CreateKind::WithdrawPayout(_) => instruction::CreateTransactionWithdrawPayout {
+            accs,
+            data,
+            sender,
+            current_count,
+        }
+        .data(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_and_execute_transaction(
+    kind: CreateExecuteKind,
+    program_id: &Pubkey,
+    zebec_program: &Pubkey,
+    zebec_eoa: &Pubkey,
+    mint: &Pubkey,
+    accs: Vec<TransactionAccount>,
+    data: Vec<u8>,
+    chain_id: u16,
+    sender: [u8; 32],
+    current_count: u64,
+    target_version: u8,
+) -> Instruction {
+    let mut metas = create_and_execute_metas(
+        program_id,
+        zebec_eoa,
+        mint,
+        chain_id,
+        sender,
+        current_count,
+        target_version,
+    );
+    metas.extend(remaining_accounts(
+        &accs,
+        &pda::user_pda(program_id, &sender, chain_id),
+        zebec_program,
+    ));
+    Instruction {
+        program_id: *program_id,
+        accounts: metas,
+        data: create_and_execute_data(kind, accs, data, chain_id, sender, current_count),
+    }
+}
+
+/// `create_and_execute_transaction` for a transaction uploaded with
+/// `upload_transaction`. `accs` are the uploaded accounts, which the Zebec
+/// call is passed.
+#[allow(clippy::too_many_arguments)]
+pub fn create_and_execute_staged_transaction(
+    kind: CreateExecuteKind,
+    program_id: &Pubkey,
+    zebec_program: &Pubkey,
+    zebec_eoa: &Pubkey,
+    mint: &Pubkey,
+    accs: &[TransactionAccount],
+    chain_id: u16,
+    sender: [u8; 32],
+    current_count: u64,
+    target_version: u8,
+) -> Instruction {
+    let mut metas = create_and_execute_metas(
+        program_id,
+        zebec_eoa,
+        mint,
+        chain_id,
+        sender,
+        current_count,
+        target_version,
+    );
+    metas.extend(remaining_accounts(
+        accs,
+        &pda::user_pda(program_id, &sender, chain_id),
+        zebec_program,
+    ));
+    metas.push(AccountMeta::new(
+        pda::transaction_buffer(program_id, &sender, current_count),
+        false,
+    ));
+    Instruction {
+        program_id: *program_id,
+        accounts: metas,
+        data: create_and_execute_data(kind, vec![], vec![], chain_id, sender, current_count),
+    }
+}
+
+fn create_and_execute_metas(
+    program_id: &Pubkey,
+    zebec_eoa: &Pubkey,
+    mint: &Pubkey,
+    chain_id: u16,
+    sender: [u8; 32],
+    current_count: u64,
+    target_version: u8,
+) -> Vec<AccountMeta> {
+    accounts::CETransaction {
+        transaction: pda::transaction(program_id, &sender, current_count),
+        zebec_eoa: *zebec_eoa,
+        system_program: system_program::id(),
+        data_storage: pda::data_store(program_id, &sender, current_count),
+        pda_signer: pda::user_pda(program_id, &sender, chain_id),
+        txn_status: pda::txn_status(program_id, &sender, current_count),
+        target_program: pda::target_program(program_id, target_version),
+        mint: *mint,
+        config: pda::config(program_id),
+    }
+    .to_account_metas(None)
+}
+
+fn create_and_execute_data(
+    kind: CreateExecuteKind,
+    accs: Vec<TransactionAccount>,
+    data: Vec<u8>,
+    chain_id: u16,
+    sender: [u8; 32],
+    current_count: u64,
+) -> Vec<u8> {
+    match kind {
+        CreateExecuteKind::Deposit => instruction::TransactionDeposit {
+            accs,
+            data,
+            chain_id,
+            sender,
+            current_count,
+        }
+        .data(),
+        CreateExecuteKind::StreamUpdate => instruction::TransactionStreamUpdate {
+            accs,
+            data,
+            chain_id,
+            sender,
+            current_count,
+        }
+        .data(),
+        CreateExecuteKind::PauseResume => instruction::TransactionPauseResume {
+            accs,
+            data,
+            chain_id,
+            sender,
+            current_count,
+        }
+        .data(),
+    }
+}
+
+pub fn execute_transaction(
+    program_id: &Pubkey,
+    zebec_program: &Pubkey,
+    accs: &[TransactionAccount],
+    eth_add: [u8; 32],
+    from_chain_id: u16,
+    current_count: u64,
+    target_version: u8,
+) -> Instruction {
+    let pda_signer = pda::user_pda(program_id, &eth_add, from_chain_id);
+    let mut metas = accounts::ExecuteTransaction {
+        system_program: system_program::id(),
+        pda_signer,
+        transaction: pda::transaction(program_id, &eth_add, current_count),
+        txn_status: pda::txn_status(program_id, &eth_add, current_count),
+        data_storage: pda::data_store(program_id, &eth_add, current_count),
+        target_program: pda::target_program(program_id, target_version),
+    }
+    .to_account_metas(None);
+    metas.extend(remaining_accounts(accs, &pda_signer, zebec_program));
+
+    Instruction {
+        program_id: *program_id,
+        accounts: metas,
+        data: instruction::ExecuteTransaction {
+            eth_add,
+            from_chain_id,
+            current_count,
+        }
+        .data(),
+    }
+}
+
+/// Runs `steps`, the instructions that store a message and create and
+/// execute its transaction, as one `relay` instruction.
+pub fn relay(program_id: &Pubkey, payer: &Pubkey, steps: &[Instruction]) -> Instruction {
+    let mut metas = accounts::Relay { payer: *payer }.to_account_metas(None);
+    let mut relay_steps = vec![];
+    for step in steps {
+        metas.extend(step.accounts.iter().cloned());
+        relay_steps.push(RelayStep {
+            accounts: step.accounts.len() as u8,
+            data: step.data.clone(),
+        });
+    }
+    Instruction {
+        program_id: *program_id,
+        accounts: metas,
+        data: instruction::Relay { steps: relay_steps }.data(),
+    }
+}
+
+pub fn store_batch_msg(
+    program_id: &Pubkey,
+    core_bridge: &Pubkey,
+    payer: &Pubkey,
+    vaa: &SignedVaa,
+    sender: [u8; 32],
+    token_mint: &Pubkey,
+    current_count: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::StoreBatchMsg {
+            payer: *payer,
+            system_program: system_program::id(),
+            processed_vaa: pda::processed_vaa(
+                program_id,
+                &vaa.emitter_address,
+                vaa.emitter_chain,
+                vaa.sequence,
+            ),
+            emitter_acc: pda::emitter_account(program_id, vaa.emitter_chain),
+            core_bridge_vaa: pda::posted_vaa(core_bridge, &vaa.hash()),
+            batch_storage: pda::batch_store(program_id, &sender, current_count),
+            txn_count: pda::txn_count(program_id, &sender),
+            mint_registry: pda::mint_registry(program_id, token_mint),
+        }
+        .to_account_metas(None),
+        data: instruction::StoreBatchMsg {
+            current_count,
+            sender,
+        }
+        .data(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_transaction_batch_stream(
+    program_id: &Pubkey,
+    zebec_eoa: &Pubkey,
+    mint: &Pubkey,
+    accs: Vec<TransactionAccount>,
+    data: Vec<u8>,
+    sender: [u8; 32],
+    current_count: u64,
+    index: u8,
+    target_version: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::CreateBatchTransaction {
+            transaction: pda::batch_transaction(program_id, &sender, current_count, index),
+            zebec_eoa: *zebec_eoa,
+            system_program: system_program::id(),
+            batch_storage: pda::batch_store(program_id, &sender, current_count),
+            target_program: pda::target_program(program_id, target_version),
+            mint: *mint,
+            config: pda::config(program_id),
+        }
+        .to_account_metas(None),
+        data: instruction::CreateTransactionBatchStream {
+            accs,
+            data,
+            sender,
+            current_count,
+            index,
+        }
+        .data(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_batch_transaction(
+    program_id: &Pubkey,
+    zebec_program: &Pubkey,
+    accs: &[TransactionAccount],
+    eth_add: [u8; 32],
+    from_chain_id: u16,
+    current_count: u64,
+    index: u8,
+    target_version: u8,
+) -> Instruction {
+    let pda_signer = pda::user_pda(program_id, &eth_add, from_chain_id);
+    let mut metas = accounts::ExecuteBatchTransaction {
+        system_program: system_program::id(),
+        pda_signer,
+        transaction: pda::batch_transaction(program_id, &eth_add, current_count, index),
+        batch_storage: pda::batch_store(program_id, &eth_add, current_count),
+        target_program: pda::target_program(program_id, target_version),
+    }
+    .to_account_metas(None);
+    metas.extend(remaining_accounts(accs, &pda_signer, zebec_program));
+
+    Instruction {
+        program_id: *program_id,
+        accounts: metas,
+        data: instruction::ExecuteBatchTransaction {
+            eth_add,
+            from_chain_id,
+            current_count,
+            index,
+        }
+        .data(),
+    }
+}
+
+/// Marks a batch entry that cannot be executed as failed. `authority` is the
+/// relayer that stored the batch or the config owner.
+pub fn fail_batch_entry(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    sender: [u8; 32],
+    current_count: u64,
+    index: u8,
+    error_code: u32,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::FailBatchEntry {
+            authority: *authority,
+            config: pda::config(program_id),
+            batch_storage: pda::batch_store(program_id, &sender, current_count),
+        }
+        .to_account_metas(None),
+        data: instruction::FailBatchEntry {
+            sender,
+            current_count,
+            index,
+            error_code,
+        }
+        .data(),
+    }
+}
+
+/// Accounts of a direct transfer or a withdraw stream and bridge.
+pub struct DirectTransfer {
+    pub program_id: Pubkey,
+    pub core_bridge: Pubkey,
+    pub token_bridge: Pubkey,
+    pub zebec_eoa: Pubkey,
+    pub portal_message: Pubkey,
+    pub sender: [u8; 32],
+    pub sender_chain: u16,
+    pub current_count: u64,
+    pub target_chain: u16,
+    pub fee: u64,
+}
+
+// Native and wrapped mints take the same accounts; the program tells them
+// apart by the token bridge's meta of `mint`.
+fn transfer_accounts(params: &DirectTransfer, mint: &Pubkey) -> Vec<AccountMeta> {
+    let program_id = &params.program_id;
+    let pda_signer = pda::user_pda(program_id, &params.sender, params.sender_chain);
+    let portal_emitter = pda::portal_emitter(&params.token_bridge);
+
+    accounts::DirectTransfer {
+        zebec_eoa: params.zebec_eoa,
+        data_storage: pda::data_store(program_id, &params.sender, params.current_count),
+        txn_status: pda::txn_status(program_id, &params.sender, params.current_count),
+        chain_limit: pda::chain_limit(program_id, params.target_chain),
+        mint_limit: pda::mint_limit(program_id, mint),
+        mint_registry: pda::mint_registry(program_id, mint),
+        pda_signer,
+        config: pda::config(program_id),
+        portal_config: pda::portal_config(&params.token_bridge),
+        from: get_associated_token_address(&pda_signer, mint),
+        mint: *mint,
+        portal_custody: pda::portal_custody(&params.token_bridge, mint),
+        portal_authority_signer: pda::portal_authority_signer(&params.token_bridge),
+        portal_custody_signer: pda::portal_custody_signer(&params.token_bridge),
+        wrapped_meta: pda::wrapped_meta(&params.token_bridge, mint),
+        bridge_config: pda::bridge_config(&params.core_bridge),
+        portal_message: params.portal_message,
+        portal_emitter,
+        portal_sequence: pda::sequence(&params.core_bridge, &portal_emitter),
+        bridge_fee_collector: pda::fee_collector(&params.core_bridge),
+        clock: sysvar::clock::id(),
+        rent: sysvar::rent::id(),
+        system_program: system_program::id(),
+        portal_bridge_program: params.token_bridge,
+        core_bridge_program: params.core_bridge,
+        token_program: anchor_spl::token::ID,
+    }
+    .to_account_metas(None)
+}
+
+/// Sends the tokens of a direct transfer message, whether `mint` is native
+/// or wrapped by the token bridge.
+pub fn direct_transfer(params: &DirectTransfer, mint: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: params.program_id,
+        accounts: transfer_accounts(params, mint),
+        data: instruction::TransactionDirectTransfer {
+            sender: params.sender,
+            chain_id: params.sender_chain,
+            current_count: params.current_count,
+            target_chain: params.target_chain,
+            fee: params.fee,
+        }
+        .data(),
+    }
+}
+
+/// Bridges what the receiver withdraw of a withdraw stream and bridge message
+/// paid out. `params.sender` is the withdrawer; the target chain and fee come
+/// from the stored message, so `params.fee` is not used and
+/// `params.target_chain` only picks the chain limit, which must be the
+/// stored target chain's.
+pub fn withdraw_bridge(params: &DirectTransfer, mint: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: params.program_id,
+        accounts: transfer_accounts(params, mint),
+        data: instruction::TransactionWithdrawBridge {
+            sender: params.sender,
+            chain_id: params.sender_chain,
+            current_count: params.current_count,
+        }
+        .data(),
+    }
+}
+
+/// Pays what the receiver withdraw of a withdraw stream payout message paid
+/// out on to `wallet`, the payout wallet stored with the message. The
+/// wallet's token account is created if needed.
+pub fn withdraw_payout(
+    program_id: &Pubkey,
+    zebec_eoa: &Pubkey,
+    receiver: [u8; 32],
+    receiver_chain: u16,
+    current_count: u64,
+    mint: &Pubkey,
+    wallet: &Pubkey,
+) -> Instruction {
+    let pda_signer = pda::user_pda(program_id, &receiver, receiver_chain);
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::WithdrawPayout {
+            zebec_eoa: *zebec_eoa,
+            data_storage: pda::data_store(program_id, &receiver, current_count),
+            txn_status: pda::txn_status(program_id, &receiver, current_count),
+            pda_signer,
+            from: get_associated_token_address(&pda_signer, mint),
+            mint: *mint,
+            wallet: *wallet,
+            wallet_token_account: get_associated_token_address(wallet, mint),
+            system_program: system_program::id(),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            rent: sysvar::rent::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::TransactionWithdrawPayout {
+            sender: receiver,
+            chain_id: receiver_chain,
+            current_count,
+        }
+        .data(),
+    }
+}
@@ -0,0 +1,20 @@
+//! Off-chain helpers shared by the relayer and operator tooling.
+//!
+//! Everything here mirrors what the on-chain program expects: the PDA seeds in
+//! `context.rs`, the payload layouts written by `Encoder.sol`, and the Zebec
+//! account lists checked by the `create_transaction_*` handlers.
+
+pub mod address;
+pub mod bridge;
+pub mod decode;
+pub mod error;
+pub mod instructions;
+pub mod message;
+pub mod pda;
+pub mod vaa;
+pub mod versioned;
+pub mod zebec;
+
+pub use error::ClientError;
+pub use message::ZebecMessage;
+pub use vaa::SignedVaa;
@@ -0,0 +1,550 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::error::ClientError;
+
+// Message codes, kept in sync with `Encoder.sol`.
+pub const TOKEN_STREAM: u8 = 2;
+pub const TOKEN_WITHDRAW_STREAM: u8 = 4;
+pub const DEPOSIT_TOKEN: u8 = 6;
+pub const PAUSE_TOKEN: u8 = 8;
+pub const WITHDRAW_TOKEN: u8 = 10;
+pub const INSTANT_TOKEN: u8 = 12;
+pub const TOKEN_STREAM_UPDATE: u8 = 14;
+pub const CANCEL_TOKEN: u8 = 16;
+pub const DIRECT_TRANSFER: u8 = 17;
+pub const INITIALIZE_PDA: u8 = 18;
+pub const INITIALIZE_TOKEN_ACCOUNT: u8 = 19;
+pub const BATCH_STREAM: u8 = 20;
+pub const TOKEN_WITHDRAW_STREAM_BRIDGE: u8 = 21;
+pub const SET_PAYOUT_WALLET: u8 = 22;
+pub const TOKEN_WITHDRAW_STREAM_PAYOUT: u8 = 23;
+
+/// A batch stream payload is a header, ending with the entry count, and then
+/// the entries.
+pub const BATCH_STREAM_HEADER_LENGTH: usize = 98;
+pub const BATCH_STREAM_ENTRY_LENGTH: usize = 57;
+pub const MAX_BATCH_ENTRIES: usize = 10;
+
+/// A direct transfer that also fixes its target chain and maximum fee.
+pub const DIRECT_TRANSFER_WITH_TERMS_LENGTH: usize = 147;
+
+/// Wormhole chain id of Solana; every payload carries it as a uint256.
+pub const SOLANA_CHAIN_ID: u64 = 1;
+
+/// Length of the payload `Encoder.sol` packs for `code`, without the optional
+/// target version byte. `None` for batch streams, whose length depends on
+/// their entries; see `packed_length`.
+pub fn payload_length(code: u8) -> Option<usize> {
+    let length = match code {
+        TOKEN_STREAM => 169,
+        TOKEN_WITHDRAW_STREAM | TOKEN_WITHDRAW_STREAM_PAYOUT | PAUSE_TOKEN | CANCEL_TOKEN => 161,
+        DEPOSIT_TOKEN | WITHDRAW_TOKEN => 105,
+        INSTANT_TOKEN | DIRECT_TRANSFER => 137,
+        TOKEN_STREAM_UPDATE => 185,
+        INITIALIZE_PDA => 65,
+        INITIALIZE_TOKEN_ACCOUNT | SET_PAYOUT_WALLET => 97,
+        TOKEN_WITHDRAW_STREAM_BRIDGE => 171,
+        _ => return None,
+    };
+    Some(length)
+}
+
+/// Length of `payload` without the optional target version byte.
+pub fn packed_length(payload: &[u8]) -> Option<usize> {
+    match *payload.first()? {
+        BATCH_STREAM => payload
+            .get(BATCH_STREAM_HEADER_LENGTH - 1)
+            .map(|count| BATCH_STREAM_HEADER_LENGTH + *count as usize * BATCH_STREAM_ENTRY_LENGTH),
+        DIRECT_TRANSFER if payload.len() >= DIRECT_TRANSFER_WITH_TERMS_LENGTH => {
+            Some(DIRECT_TRANSFER_WITH_TERMS_LENGTH)
+        }
+        code => payload_length(code),
+    }
+}
+
+/// The target program version a stored message selects: the byte after the
+/// packed payload, or 0 when there is none.
+pub fn target_version(payload: &[u8]) -> u8 {
+    packed_length(payload)
+        .and_then(|length| payload.get(length).copied())
+        .unwrap_or_default()
+}
+
+/// One stream of a batch stream message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchStreamEntry {
+    pub receiver: [u8; 32],
+    pub amount: u64,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub can_cancel: bool,
+    pub can_update: bool,
+}
+
+/// A decoded Zebec payload. Field order follows the `abi.encodePacked` calls
+/// in `Encoder.sol`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZebecMessage {
+    TokenStream {
+        start_time: u64,
+        end_time: u64,
+        amount: u64,
+        sender: [u8; 32],
+        receiver: [u8; 32],
+        can_cancel: bool,
+        can_update: bool,
+        token_mint: Pubkey,
+    },
+    WithdrawStream {
+        withdrawer: [u8; 32],
+        token_mint: Pubkey,
+        sender: [u8; 32],
+        data_account: Pubkey,
+    },
+    /// A withdraw stream whose withdrawal is then bridged to the withdrawer
+    /// on `target_chain`, less `fee` (base units) for the relayer there.
+    WithdrawStreamBridge {
+        withdrawer: [u8; 32],
+        token_mint: Pubkey,
+        sender: [u8; 32],
+        data_account: Pubkey,
+        target_chain: u16,
+        fee: u64,
+    },
+    /// A withdraw stream whose withdrawal is then paid out to the
+    /// withdrawer's linked payout wallet.
+    WithdrawStreamPayout {
+        withdrawer: [u8; 32],
+        token_mint: Pubkey,
+        sender: [u8; 32],
+        data_account: Pubkey,
+    },
+    Deposit {
+        amount: u64,
+        depositor: [u8; 32],
+        token_mint: Pubkey,
+    },
+    PauseResume {
+        sender: [u8; 32],
+        token_mint: Pubkey,
+        receiver: [u8; 32],
+        data_account: Pubkey,
+    },
+    SenderWithdraw {
+        amount: u64,
+        withdrawer: [u8; 32],
+        token_mint: Pubkey,
+    },
+    InstantTransfer {
+        amount: u64,
+        sender: [u8; 32],
+        token_mint: Pubkey,
+        receiver: [u8; 32],
+    },
+    StreamUpdate {
+        start_time: u64,
+        end_time: u64,
+        amount: u64,
+        sender: [u8; 32],
+        receiver: [u8; 32],
+        token_mint: Pubkey,
+        data_account: Pubkey,
+    },
+    Cancel {
+        sender: [u8; 32],
+        token_mint: Pubkey,
+        receiver: [u8; 32],
+        data_account: Pubkey,
+    },
+    /// `target_chain` and `max_fee` (base units) are set when the sender
+    /// fixed them; the relayer picks both otherwise.
+    DirectTransfer {
+        amount: u64,
+        sender: [u8; 32],
+        token_mint: Pubkey,
+        receiver: [u8; 32],
+        target_chain: Option<u16>,
+        max_fee: Option<u64>,
+    },
+    InitializePda {
+        account: [u8; 32],
+    },
+    InitializeTokenAccount {
+        account: [u8; 32],
+        token_mint: Pubkey,
+    },
+    /// Links `account` to the Solana `wallet` its payout withdrawals go to;
+    /// the default key unlinks it.
+    SetPayoutWallet {
+        account: [u8; 32],
+        wallet: Pubkey,
+    },
+    BatchStream {
+        sender: [u8; 32],
+        token_mint: Pubkey,
+        entries: Vec<BatchStreamEntry>,
+    },
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ClientError> {
+        let end = self.pos + len;
+        if end > self.buf.len() {
+            return Err(ClientError::InvalidPayload(format!(
+                "expected at least {} bytes, got {}",
+                end,
+                self.buf.len()
+            )));
+        }
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, ClientError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, ClientError> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn flag(&mut self) -> Result<bool, ClientError> {
+        Ok(self.u64()? == 1)
+    }
+
+    fn bytes32(&mut self) -> Result<[u8; 32], ClientError> {
+        Ok(self.take(32)?.try_into().unwrap())
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey, ClientError> {
+        Ok(Pubkey::new_from_array(self.bytes32()?))
+    }
+
+    // uint256 target chain; only Solana is accepted on-chain.
+    fn check_to_chain(&mut self) -> Result<(), ClientError> {
+        let raw = self.bytes32()?;
+        if raw[..24].iter().any(|b| *b != 0) {
+            return Err(ClientError::InvalidToChain(u64::MAX));
+        }
+        let chain = u64::from_be_bytes(raw[24..].try_into().unwrap());
+        if chain != SOLANA_CHAIN_ID {
+            return Err(ClientError::InvalidToChain(chain));
+        }
+        Ok(())
+    }
+}
+
+impl ZebecMessage {
+    pub fn decode(payload: &[u8]) -> Result<Self, ClientError> {
+        let mut r = Reader {
+            buf: payload,
+            pos: 0,
+        };
+        let code = r.take(1)?[0];
+
+        let message = match code {
+            TOKEN_STREAM => {
+                let start_time = r.u64()?;
+                let end_time = r.u64()?;
+                let amount = r.u64()?;
+                r.check_to_chain()?;
+                ZebecMessage::TokenStream {
+                    start_time,
+                    end_time,
+                    amount,
+                    sender: r.bytes32()?,
+                    receiver: r.bytes32()?,
+                    can_cancel: r.flag()?,
+                    can_update: r.flag()?,
+                    token_mint: r.pubkey()?,
+                }
+            }
+            TOKEN_WITHDRAW_STREAM | TOKEN_WITHDRAW_STREAM_PAYOUT => {
+                r.check_to_chain()?;
+                let withdrawer = r.bytes32()?;
+                let token_mint = r.pubkey()?;
+                let sender = r.bytes32()?;
+                let data_account = r.pubkey()?;
+                if code == TOKEN_WITHDRAW_STREAM {
+                    ZebecMessage::WithdrawStream {
+                        withdrawer,
+                        token_mint,
+                        sender,
+                        data_account,
+                    }
+                } else {
+                    ZebecMessage::WithdrawStreamPayout {
+                        withdrawer,
+                        token_mint,
+                        sender,
+                        data_account,
+                    }
+                }
+            }
+            TOKEN_WITHDRAW_STREAM_BRIDGE => {
+                r.check_to_chain()?;
+                ZebecMessage::WithdrawStreamBridge {
+                    withdrawer: r.bytes32()?,
+                    token_mint: r.pubkey()?,
+                    sender: r.bytes32()?,
+                    data_account: r.pubkey()?,
+                    target_chain: r.u16()?,
+                    fee: r.u64()?,
+                }
+            }
+            DEPOSIT_TOKEN => {
+                let amount = r.u64()?;
+                r.check_to_chain()?;
+                ZebecMessage::Deposit {
+                    amount,
+                    depositor: r.bytes32()?,
+                    token_mint: r.pubkey()?,
+                }
+            }
+            PAUSE_TOKEN | CANCEL_TOKEN => {
+                r.check_to_chain()?;
+                let sender = r.bytes32()?;
+                let token_mint = r.pubkey()?;
+                let receiver = r.bytes32()?;
+                let data_account = r.pubkey()?;
+                if code == PAUSE_TOKEN {
+                    ZebecMessage::PauseResume {
+                        sender,
+                        token_mint,
+                        receiver,
+                        data_account,
+                    }
+                } else {
+                    ZebecMessage::Cancel {
+                        sender,
+                        token_mint,
+                        receiver,
+                        data_account,
+                    }
+                }
+            }
+            WITHDRAW_TOKEN => {
+                let amount = r.u64()?;
+                r.check_to_chain()?;
+                ZebecMessage::SenderWithdraw {
+                    amount,
+                    withdrawer: r.bytes32()?,
+                    token_mint: r.pubkey()?,
+                }
+            }
+            INSTANT_TOKEN => {
+                let amount = r.u64()?;
+                r.check_to_chain()?;
+                ZebecMessage::InstantTransfer {
+                    amount,
+                    sender: r.bytes32()?,
+                    token_mint: r.pubkey()?,
+                    receiver: r.bytes32()?,
+                }
+            }
+            DIRECT_TRANSFER => {
+                let amount = r.u64()?;
+                r.check_to_chain()?;
+                let sender = r.bytes32()?;
+                let token_mint = r.pubkey()?;
+                let receiver = r.bytes32()?;
+                let with_terms = payload.len() >= DIRECT_TRANSFER_WITH_TERMS_LENGTH;
+                let (target_chain, max_fee) = if with_terms {
+                    (Some(r.u16()?), Some(r.u64()?))
+                } else {
+                    (None, None)
+                };
+                ZebecMessage::DirectTransfer {
+                    amount,
+                    sender,
+                    token_mint,
+                    receiver,
+                    target_chain,
+                    max_fee,
+                }
+            }
+            TOKEN_STREAM_UPDATE => {
+                let start_time = r.u64()?;
+                let end_time = r.u64()?;
+                let amount = r.u64()?;
+                r.check_to_chain()?;
+                ZebecMessage::StreamUpdate {
+                    start_time,
+                    end_time,
+                    amount,
+                    sender: r.bytes32()?,
+                    receiver: r.bytes32()?,
+                    token_mint: r.pubkey()?,
+                    data_account: r.pubkey()?,
+                }
+            }
+            INITIALIZE_PDA => {
+                let account = r.bytes32()?;
+                r.check_to_chain()?;
+                ZebecMessage::InitializePda { account }
+            }
+            INITIALIZE_TOKEN_ACCOUNT => {
+                let account = r.bytes32()?;
+                let token_mint = r.pubkey()?;
+                r.check_to_chain()?;
+                ZebecMessage::InitializeTokenAccount {
+                    account,
+                    token_mint,
+                }
+            }
+            SET_PAYOUT_WALLET => {
+                let account = r.bytes32()?;
+                let wallet = r.pubkey()?;
+                r.check_to_chain()?;
+                ZebecMessage::SetPayoutWallet { account, wallet }
+            }
+            BATCH_STREAM => {
+                r.check_to_chain()?;
+                let sender = r.bytes32()?;
+                let token_mint = r.pubkey()?;
+                let count = r.take(1)?[0] as usize;
+                if count == 0 || count > MAX_BATCH_ENTRIES {
+                    return Err(ClientError::InvalidPayload(format!(
+                        "batch of {} entries",
+                        count
+                    )));
+                }
+                let mut entries = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let receiver = r.bytes32()?;
+                    let amount = r.u64()?;
+                    let start_time = r.u64()?;
+                    let end_time = r.u64()?;
+                    let flags = r.take(1)?[0];
+                    if flags & !0b11 != 0 {
+                        return Err(ClientError::InvalidPayload(format!(
+                            "batch entry flags {:#04x}",
+                            flags
+                        )));
+                    }
+                    entries.push(BatchStreamEntry {
+                        receiver,
+                        amount,
+                        start_time,
+                        end_time,
+                        can_cancel: flags & 0b01 != 0,
+                        can_update: flags & 0b10 != 0,
+                    });
+                }
+                ZebecMessage::BatchStream {
+                    sender,
+                    token_mint,
+                    entries,
+                }
+            }
+            _ => return Err(ClientError::UnknownCode(code)),
+        };
+        Ok(message)
+    }
+
+    pub fn code(&self) -> u8 {
+        match self {
+            ZebecMessage::TokenStream { .. } => TOKEN_STREAM,
+            ZebecMessage::WithdrawStream { .. } => TOKEN_WITHDRAW_STREAM,
+            ZebecMessage::WithdrawStreamBridge { .. } => TOKEN_WITHDRAW_STREAM_BRIDGE,
+            ZebecMessage::WithdrawStreamPayout { .. } => TOKEN_WITHDRAW_STREAM_PAYOUT,
+            ZebecMessage::Deposit { .. } => DEPOSIT_TOKEN,
+            ZebecMessage::PauseResume { .. } => PAUSE_TOKEN,
+            ZebecMessage::SenderWithdraw { .. } => WITHDRAW_TOKEN,
+            ZebecMessage::InstantTransfer { .. } => INSTANT_TOKEN,
+            ZebecMessage::StreamUpdate { .. } => TOKEN_STREAM_UPDATE,
+            ZebecMessage::Cancel { .. } => CANCEL_TOKEN,
+            ZebecMessage::DirectTransfer { .. } => DIRECT_TRANSFER,
+            ZebecMessage::InitializePda { .. } => INITIALIZE_PDA,
+            ZebecMessage::InitializeTokenAccount { .. } => INITIALIZE_TOKEN_ACCOUNT,
+            ZebecMessage::SetPayoutWallet { .. } => SET_PAYOUT_WALLET,
+            ZebecMessage::BatchStream { .. } => BATCH_STREAM,
+        }
+    }
+
+    /// The source chain identity passed as `sender` to `store_msg`: the address whose
+    /// `txn_count`, `data_store` and `txn_status` accounts the message uses.
+    /// For a receiver withdrawal this is the withdrawer, not the stream sender.
+    pub fn signer(&self) -> [u8; 32] {
+        match self {
+            ZebecMessage::TokenStream { sender, .. }
+            | ZebecMessage::PauseResume { sender, .. }
+            | ZebecMessage::InstantTransfer { sender, .. }
+            | ZebecMessage::StreamUpdate { sender, .. }
+            | ZebecMessage::Cancel { sender, .. }
+            | ZebecMessage::DirectTransfer { sender, .. }
+            | ZebecMessage::BatchStream { sender, .. } => *sender,
+            ZebecMessage::WithdrawStream { withdrawer, .. }
+            | ZebecMessage::WithdrawStreamBridge { withdrawer, .. }
+            | ZebecMessage::WithdrawStreamPayout { withdrawer, .. }
+            | ZebecMessage::SenderWithdraw { withdrawer, .. } => *withdrawer,
+            ZebecMessage::Deposit { depositor, .. } => *depositor,
+            ZebecMessage::InitializePda { account }
+            | ZebecMessage::InitializeTokenAccount { account, .. }
+            | ZebecMessage::SetPayoutWallet { account, .. } => *account,
+        }
+    }
+
+    /// The stream sender, whose PDA owns the Zebec vault and withdraw data.
+    pub fn stream_sender(&self) -> [u8; 32] {
+        match self {
+            ZebecMessage::WithdrawStream { sender, .. }
+            | ZebecMessage::WithdrawStreamBridge { sender, .. }
+            | ZebecMessage::WithdrawStreamPayout { sender, .. } => *sender,
+            _ => self.signer(),
+        }
+    }
+
+    pub fn receiver(&self) -> Option<[u8; 32]> {
+        match self {
+            ZebecMessage::TokenStream { receiver, .. }
+            | ZebecMessage::PauseResume { receiver, .. }
+            | ZebecMessage::InstantTransfer { receiver, .. }
+            | ZebecMessage::StreamUpdate { receiver, .. }
+            | ZebecMessage::Cancel { receiver, .. }
+            | ZebecMessage::DirectTransfer { receiver, .. } => Some(*receiver),
+            ZebecMessage::WithdrawStream { withdrawer, .. }
+            | ZebecMessage::WithdrawStreamBridge { withdrawer, .. }
+            | ZebecMessage::WithdrawStreamPayout { withdrawer, .. } => Some(*withdrawer),
+            // Each entry of a batch has its own receiver.
+            _ => None,
+        }
+    }
+
+    pub fn token_mint(&self) -> Option<Pubkey> {
+        match self {
+            ZebecMessage::TokenStream { token_mint, .. }
+            | ZebecMessage::WithdrawStream { token_mint, .. }
+            | ZebecMessage::WithdrawStreamBridge { token_mint, .. }
+            | ZebecMessage::WithdrawStreamPayout { token_mint, .. }
+            | ZebecMessage::Deposit { token_mint, .. }
+            | ZebecMessage::PauseResume { token_mint, .. }
+            | ZebecMessage::SenderWithdraw { token_mint, .. }
+            | ZebecMessage::InstantTransfer { token_mint, .. }
+            | ZebecMessage::StreamUpdate { token_mint, .. }
+            | ZebecMessage::Cancel { token_mint, .. }
+            | ZebecMessage::DirectTransfer { token_mint, .. }
+            | ZebecMessage::InitializeTokenAccount { token_mint, .. }
+            | ZebecMessage::BatchStream { token_mint, .. } => Some(*token_mint),
+            ZebecMessage::InitializePda { .. } | ZebecMessage::SetPayoutWallet { .. } => None,
+        }
+    }
+
+    pub fn data_account(&self) -> Option<Pubkey> {
+        match self {
+            ZebecMessage::WithdrawStream { data_account, .. }
+            | ZebecMessage::WithdrawStreamBridge { data_account, .. }
+            | ZebecMessage::WithdrawStreamPayout { data_account, .. }
+            | ZebecMessage::PauseResume { data_account, .. }
+            | ZebecMessage::StreamUpdate { data_account, .. }
+            | ZebecMessage::Cancel { data_account, .. } => Some(*data_account),
+            _ => None,
+        }
+    }
+}
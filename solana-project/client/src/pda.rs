@@ -0,0 +1,169 @@
+//! PDA derivations for the proxy program and the Wormhole programs it talks to.
+
+use anchor_lang::prelude::Pubkey;
+
+pub fn config(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], program_id).0
+}
+
+pub fn emitter_account(program_id: &Pubkey, chain_id: u16) -> Pubkey {
+    Pubkey::find_program_address(&[b"EmitterAddress", &chain_id.to_be_bytes()], program_id).0
+}
+
+pub fn processed_vaa(
+    program_id: &Pubkey,
+    emitter_address: &[u8; 32],
+    emitter_chain: u16,
+    sequence: u64,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            emitter_address,
+            &emitter_chain.to_be_bytes(),
+            &sequence.to_be_bytes(),
+        ],
+        program_id,
+    )
+    .0
+}
+
+/// The PDA that stands in for a source chain address on Solana
+/// (`pda_signer`), seeded with its 32 byte form.
+pub fn user_pda(program_id: &Pubkey, user: &[u8; 32], chain_id: u16) -> Pubkey {
+    Pubkey::find_program_address(&[user, &chain_id.to_be_bytes()], program_id).0
+}
+
+pub fn data_store(program_id: &Pubkey, sender: &[u8; 32], count: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"data_store", sender, &count.to_be_bytes()], program_id).0
+}
+
+pub fn txn_status(program_id: &Pubkey, sender: &[u8; 32], count: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"txn_status", sender, &count.to_be_bytes()], program_id).0
+}
+
+/// The `Transaction` a single message is built into.
+pub fn transaction(program_id: &Pubkey, sender: &[u8; 32], count: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"transaction", sender, &count.to_be_bytes()], program_id).0
+}
+
+/// The `Transaction` entry `index` of a batch is built into.
+pub fn batch_transaction(program_id: &Pubkey, sender: &[u8; 32], count: u64, index: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"transaction",
+            sender,
+            &count.to_be_bytes(),
+            &index.to_be_bytes(),
+        ],
+        program_id,
+    )
+    .0
+}
+
+/// Where a message's transaction is uploaded in chunks before it is created.
+pub fn transaction_buffer(program_id: &Pubkey, sender: &[u8; 32], count: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"transaction_buffer", sender, &count.to_be_bytes()],
+        program_id,
+    )
+    .0
+}
+
+/// Created by `store_batch_msg` in place of a `data_store`.
+pub fn batch_store(program_id: &Pubkey, sender: &[u8; 32], count: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"batch_store", sender, &count.to_be_bytes()], program_id).0
+}
+
+/// Registry entry of the Zebec program version messages may target.
+pub fn target_program(program_id: &Pubkey, version: u8) -> Pubkey {
+    Pubkey::find_program_address(&[b"target_program", &version.to_be_bytes()], program_id).0
+}
+
+/// Payout wallet link of a source chain identity, set by `set_payout_wallet`.
+pub fn payout_profile(program_id: &Pubkey, identity: &[u8; 32], chain_id: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"payout_profile", identity, &chain_id.to_be_bytes()],
+        program_id,
+    )
+    .0
+}
+
+/// Nonce of the meta-transactions an EVM account signs, see `store_signed_msg`.
+pub fn meta_nonce(program_id: &Pubkey, sender: &[u8; 32], chain_id: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"meta_nonce", sender, &chain_id.to_be_bytes()],
+        program_id,
+    )
+    .0
+}
+
+/// Rate limit of what goes out to a chain, set by `set_chain_limit`.
+pub fn chain_limit(program_id: &Pubkey, chain_id: u16) -> Pubkey {
+    Pubkey::find_program_address(&[b"chain_limit", &chain_id.to_be_bytes()], program_id).0
+}
+
+/// Rate limit and hold settings of a mint, set by `set_mint_limit`.
+pub fn mint_limit(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"mint_limit", mint.as_ref()], program_id).0
+}
+
+/// Allowlist entry of a mint, written by `register_mint`.
+pub fn mint_registry(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"mint_registry", mint.as_ref()], program_id).0
+}
+
+pub fn txn_count(program_id: &Pubkey, sender: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"txn_count", sender], program_id).0
+}
+
+pub fn posted_vaa(core_bridge: &Pubkey, vaa_hash: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"PostedVAA", vaa_hash], core_bridge).0
+}
+
+pub fn guardian_set(core_bridge: &Pubkey, index: u32) -> Pubkey {
+    Pubkey::find_program_address(&[b"GuardianSet", &index.to_be_bytes()], core_bridge).0
+}
+
+pub fn bridge_config(core_bridge: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"Bridge"], core_bridge).0
+}
+
+pub fn fee_collector(core_bridge: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"fee_collector"], core_bridge).0
+}
+
+pub fn sequence(core_bridge: &Pubkey, emitter: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"Sequence", emitter.as_ref()], core_bridge).0
+}
+
+pub fn portal_config(token_bridge: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], token_bridge).0
+}
+
+pub fn portal_custody(token_bridge: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[mint.as_ref()], token_bridge).0
+}
+
+pub fn portal_authority_signer(token_bridge: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"authority_signer"], token_bridge).0
+}
+
+pub fn portal_custody_signer(token_bridge: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"custody_signer"], token_bridge).0
+}
+
+pub fn portal_emitter(token_bridge: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"emitter"], token_bridge).0
+}
+
+pub fn wrapped_mint(token_bridge: &Pubkey, token_chain: u16, token_address: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"wrapped", &token_chain.to_be_bytes(), token_address],
+        token_bridge,
+    )
+    .0
+}
+
+pub fn wrapped_meta(token_bridge: &Pubkey, wrapped_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"meta", wrapped_mint.as_ref()], token_bridge).0
+}
@@ -0,0 +1,136 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use sha3::{Digest, Keccak256};
+use std::io::{Cursor, Read, Write};
+
+use crate::error::ClientError;
+
+/// Length of a guardian signature (r, s, v).
+pub const SIGNATURE_LENGTH: usize = 65;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuardianSignature {
+    pub index: u8,
+    pub signature: [u8; SIGNATURE_LENGTH],
+}
+
+/// A guardian-signed VAA as produced by the spy or the guardian REST API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedVaa {
+    pub version: u8,
+    pub guardian_set_index: u32,
+    pub signatures: Vec<GuardianSignature>,
+    pub timestamp: u32,
+    pub nonce: u32,
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    pub sequence: u64,
+    pub consistency_level: u8,
+    pub payload: Vec<u8>,
+}
+
+impl SignedVaa {
+    pub fn parse(bytes: &[u8]) -> Result<Self, ClientError> {
+        let truncated = |_| ClientError::InvalidVaa("truncated".to_string());
+        let mut v = Cursor::new(bytes);
+
+        let version = v.read_u8().map_err(truncated)?;
+        if version != 1 {
+            return Err(ClientError::InvalidVaa(format!(
+                "unsupported version {}",
+                version
+            )));
+        }
+        let guardian_set_index = v.read_u32::<BigEndian>().map_err(truncated)?;
+
+        let signature_count = v.read_u8().map_err(truncated)?;
+        let mut signatures = Vec::with_capacity(signature_count as usize);
+        for _ in 0..signature_count {
+            let index = v.read_u8().map_err(truncated)?;
+            let mut signature = [0u8; SIGNATURE_LENGTH];
+            v.read_exact(&mut signature).map_err(truncated)?;
+            signatures.push(GuardianSignature { index, signature });
+        }
+
+        let timestamp = v.read_u32::<BigEndian>().map_err(truncated)?;
+        let nonce = v.read_u32::<BigEndian>().map_err(truncated)?;
+        let emitter_chain = v.read_u16::<BigEndian>().map_err(truncated)?;
+        let mut emitter_address = [0u8; 32];
+        v.read_exact(&mut emitter_address).map_err(truncated)?;
+        let sequence = v.read_u64::<BigEndian>().map_err(truncated)?;
+        let consistency_level = v.read_u8().map_err(truncated)?;
+
+        let mut payload = vec![];
+        v.read_to_end(&mut payload).map_err(truncated)?;
+
+        Ok(SignedVaa {
+            version,
+            guardian_set_index,
+            signatures,
+            timestamp,
+            nonce,
+            emitter_chain,
+            emitter_address,
+            sequence,
+            consistency_level,
+            payload,
+        })
+    }
+
+    /// The signed portion of the VAA. Matches `serialize_vaa` in the program.
+    pub fn body(&self) -> Vec<u8> {
+        let mut v = Cursor::new(Vec::new());
+        v.write_u32::<BigEndian>(self.timestamp).unwrap();
+        v.write_u32::<BigEndian>(self.nonce).unwrap();
+        v.write_u16::<BigEndian>(self.emitter_chain).unwrap();
+        v.write_all(&self.emitter_address).unwrap();
+        v.write_u64::<BigEndian>(self.sequence).unwrap();
+        v.write_u8(self.consistency_level).unwrap();
+        v.write_all(&self.payload).unwrap();
+        v.into_inner()
+    }
+
+    /// keccak256 of the body; seeds the core bridge `PostedVAA` account.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut h = Keccak256::default();
+        h.write_all(&self.body()).unwrap();
+        h.finalize().into()
+    }
+
+    /// keccak256 of `hash()`; this is what guardians actually sign.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut h = Keccak256::default();
+        h.write_all(&self.hash()).unwrap();
+        h.finalize().into()
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut v = Cursor::new(Vec::new());
+        v.write_u8(self.version).unwrap();
+        v.write_u32::<BigEndian>(self.guardian_set_index).unwrap();
+        v.write_u8(self.signatures.len() as u8).unwrap();
+        for sig in self.signatures.iter() {
+            v.write_u8(sig.index).unwrap();
+            v.write_all(&sig.signature).unwrap();
+        }
+        v.write_all(&self.body()).unwrap();
+        v.into_inner()
+    }
+}
+
+/// Accepts a VAA as raw bytes, hex (with or without `0x`) or base64, which
+/// covers the formats the spy, the guardian REST API and `vaa.txt` use.
+pub fn decode_encoded(input: &[u8]) -> Vec<u8> {
+    if let Ok(text) = std::str::from_utf8(input) {
+        let text = text.trim();
+        let stripped = text.strip_prefix("0x").unwrap_or(text);
+        if !stripped.is_empty() && stripped.len() % 2 == 0 {
+            if let Ok(bytes) = hex::decode(stripped) {
+                return bytes;
+            }
+        }
+        if let Ok(bytes) = base64::decode(text) {
+            return bytes;
+        }
+    }
+    input.to_vec()
+}
@@ -0,0 +1,312 @@
+//! Version 0 transactions, which load accounts from address lookup tables.
+//!
+//! `relay` takes the accounts of a store, a create and an execute at once,
+//! which seldom fit a legacy transaction. A version 0 transaction lists only
+//! its signers, the programs it invokes and whatever no table holds; every
+//! other account is one byte of a lookup into a table. The table has to be
+//! active and the cluster has to have version 0 transactions enabled.
+
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{hash::Hash, instruction::Instruction, system_program, sysvar};
+use solana_project::constants::SOLANA_CHAIN_ID;
+use solana_sdk::instruction::{AccountMeta, CompiledInstruction};
+use solana_sdk::message::v0::{self, MessageAddressTableLookup};
+use solana_sdk::message::{Message, MessageHeader, VersionedMessage};
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::error::ClientError;
+use crate::pda;
+use crate::zebec::ZebecAccounts;
+
+pub const LOOKUP_TABLE_PROGRAM: &str = "AddressLookupTab1e1111111111111111111111111";
+
+/// Size of the table state before its addresses.
+const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+/// Addresses one `extend_lookup_table` can add and still fit a transaction.
+pub const EXTEND_LOOKUP_TABLE_CHUNK: usize = 20;
+
+pub fn lookup_table_program() -> Pubkey {
+    Pubkey::from_str(LOOKUP_TABLE_PROGRAM).unwrap()
+}
+
+/// An address lookup table and the addresses it holds, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LookupTable {
+    pub key: Pubkey,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl LookupTable {
+    /// Decodes the account of a table, which has to be initialized and not
+    /// deactivated.
+    pub fn decode(key: Pubkey, data: &[u8]) -> Result<Self, ClientError> {
+        let invalid =
+            |reason: &str| ClientError::InvalidLookupTable(format!("{}: {}", key, reason));
+        if data.len() < LOOKUP_TABLE_META_SIZE
+            || !(data.len() - LOOKUP_TABLE_META_SIZE).is_multiple_of(32)
+        {
+            return Err(invalid("bad length"));
+        }
+        // A bincode enum tag, 1 for an initialized table, then the slot it
+        // was deactivated at, u64::MAX while it is active.
+        if data[..4] != 1u32.to_le_bytes() {
+            return Err(invalid("not initialized"));
+        }
+        if data[4..12] != u64::MAX.to_le_bytes() {
+            return Err(invalid("deactivated"));
+        }
+        let addresses = data[LOOKUP_TABLE_META_SIZE..]
+            .chunks(32)
+            .map(Pubkey::new)
+            .collect();
+        Ok(LookupTable { key, addresses })
+    }
+}
+
+/// Creates a table at the address `authority` and `recent_slot` derive,
+/// which is returned with it.
+pub fn create_lookup_table(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    recent_slot: u64,
+) -> (Instruction, Pubkey) {
+    let (table, bump) = Pubkey::find_program_address(
+        &[authority.as_ref(), &recent_slot.to_le_bytes()],
+        &lookup_table_program(),
+    );
+    let mut data = 0u32.to_le_bytes().to_vec();
+    data.extend(recent_slot.to_le_bytes());
+    data.push(bump);
+    let ix = Instruction {
+        program_id: lookup_table_program(),
+        accounts: vec![
+            AccountMeta::new(table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    };
+    (ix, table)
+}
+
+/// Appends `addresses` to `table`. They can be looked up from the next slot.
+pub fn extend_lookup_table(
+    table: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    addresses: &[Pubkey],
+) -> Instruction {
+    let mut data = 2u32.to_le_bytes().to_vec();
+    data.extend((addresses.len() as u64).to_le_bytes());
+    for address in addresses {
+        data.extend(address.as_ref());
+    }
+    Instruction {
+        program_id: lookup_table_program(),
+        accounts: vec![
+            AccountMeta::new(*table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+/// The accounts every relay of messages from `chains` naming `mints` shares,
+/// which is what a table for `relay` should hold.
+pub fn lookup_table_addresses(
+    program_id: &Pubkey,
+    zebec: &ZebecAccounts,
+    target_version: u8,
+    chains: &[u16],
+    mints: &[Pubkey],
+) -> Vec<Pubkey> {
+    let mut addresses = vec![
+        system_program::id(),
+        sysvar::rent::id(),
+        anchor_spl::token::ID,
+        anchor_spl::associated_token::ID,
+        zebec.program_id,
+        zebec.fee_owner,
+        zebec.fee_vault(),
+        zebec.fee_vault_data(),
+        pda::config(program_id),
+        pda::target_program(program_id, target_version),
+        pda::chain_limit(program_id, SOLANA_CHAIN_ID),
+    ];
+    for chain in chains {
+        addresses.push(pda::emitter_account(program_id, *chain));
+    }
+    for mint in mints {
+        addresses.push(*mint);
+        addresses.push(pda::mint_registry(program_id, mint));
+        addresses.push(pda::mint_limit(program_id, mint));
+    }
+    addresses
+}
+
+// Whether the header of `message` makes its `index`th key writable.
+fn writable_index(message: &Message, index: usize) -> bool {
+    let header = &message.header;
+    let signed = usize::from(header.num_required_signatures);
+    if index < signed {
+        index < signed - usize::from(header.num_readonly_signed_accounts)
+    } else {
+        index < message.account_keys.len() - usize::from(header.num_readonly_unsigned_accounts)
+    }
+}
+
+/// Compiles `ixs` paid by `payer` to a version 0 message. Accounts that
+/// neither sign nor are invoked are looked up from the first of `tables`
+/// holding them; the rest are listed as in a legacy message.
+pub fn compile(
+    ixs: &[Instruction],
+    payer: &Pubkey,
+    tables: &[LookupTable],
+    blockhash: Hash,
+) -> VersionedMessage {
+    let legacy = Message::new_with_blockhash(ixs, Some(payer), &blockhash);
+    let signed = usize::from(legacy.header.num_required_signatures);
+
+    enum Place {
+        Static(usize),
+        Writable(usize, usize),
+        Readonly(usize, usize),
+    }
+    let mut static_keys = vec![];
+    let mut readonly_unsigned = 0;
+    let mut lookups: Vec<MessageAddressTableLookup> = tables
+        .iter()
+        .map(|table| MessageAddressTableLookup {
+            account_key: table.key,
+            writable_indexes: vec![],
+            readonly_indexes: vec![],
+        })
+        .collect();
+    let mut places = vec![];
+    for (index, key) in legacy.account_keys.iter().enumerate() {
+        let writable = writable_index(&legacy, index);
+        let found = if index < signed || legacy.is_key_called_as_program(index) {
+            None
+        } else {
+            tables.iter().enumerate().find_map(|(at, table)| {
+                let position = table.addresses.iter().position(|address| address == key)?;
+                Some((at, position as u8))
+            })
+        };
+        places.push(match found {
+            Some((at, position)) if writable => {
+                lookups[at].writable_indexes.push(position);
+                Place::Writable(at, lookups[at].writable_indexes.len() - 1)
+            }
+            Some((at, position)) => {
+                lookups[at].readonly_indexes.push(position);
+                Place::Readonly(at, lookups[at].readonly_indexes.len() - 1)
+            }
+            None => {
+                if index >= signed && !writable {
+                    readonly_unsigned += 1;
+                }
+                static_keys.push(*key);
+                Place::Static(static_keys.len() - 1)
+            }
+        });
+    }
+
+    // Loaded accounts follow the static ones: every table's writable ones,
+    // then every table's readonly ones.
+    let writable_loaded: usize = lookups.iter().map(|l| l.writable_indexes.len()).sum();
+    let new_index = |place: &Place| -> u8 {
+        let index = match *place {
+            Place::Static(position) => position,
+            Place::Writable(at, position) => {
+                static_keys.len()
+                    + lookups[..at]
+                        .iter()
+                        .map(|l| l.writable_indexes.len())
+                        .sum::<usize>()
+                    + position
+            }
+            Place::Readonly(at, position) => {
+                static_keys.len()
+                    + writable_loaded
+                    + lookups[..at]
+                        .iter()
+                        .map(|l| l.readonly_indexes.len())
+                        .sum::<usize>()
+                    + position
+            }
+        };
+        index as u8
+    };
+    let instructions = legacy
+        .instructions
+        .iter()
+        .map(|ix| CompiledInstruction {
+            program_id_index: new_index(&places[usize::from(ix.program_id_index)]),
+            accounts: ix
+                .accounts
+                .iter()
+                .map(|index| new_index(&places[usize::from(*index)]))
+                .collect(),
+            data: ix.data.clone(),
+        })
+        .collect();
+
+    VersionedMessage::V0(v0::Message {
+        header: MessageHeader {
+            num_required_signatures: legacy.header.num_required_signatures,
+            num_readonly_signed_accounts: legacy.header.num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts: readonly_unsigned,
+        },
+        account_keys: static_keys,
+        recent_blockhash: blockhash,
+        instructions,
+        address_table_lookups: lookups
+            .into_iter()
+            .filter(|l| !l.writable_indexes.is_empty() || !l.readonly_indexes.is_empty())
+            .collect(),
+    })
+}
+
+/// Whether `message` fits in one transaction once signed.
+pub fn fits(message: &VersionedMessage) -> bool {
+    let signatures = usize::from(message.header().num_required_signatures);
+    1 + signatures * 64 + message.serialize().len() <= PACKET_DATA_SIZE
+}
+
+/// Signs `message` with `signers`, which have to include every key it
+/// requires a signature of.
+pub fn sign(
+    message: VersionedMessage,
+    signers: &[&dyn Signer],
+) -> Result<VersionedTransaction, ClientError> {
+    let bytes = message.serialize();
+    let required = usize::from(message.header().num_required_signatures);
+    let signatures = message.static_account_keys()[..required]
+        .iter()
+        .map(|key| {
+            signers
+                .iter()
+                .find(|signer| signer.pubkey() == *key)
+                .map(|signer| signer.sign_message(&bytes))
+                .ok_or_else(|| ClientError::MissingSigner(key.to_string()))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(VersionedTransaction {
+        signatures,
+        message,
+    })
+}
+
+/// The wire format of `tx`, as `sendTransaction` takes it.
+pub fn serialize(tx: &VersionedTransaction) -> Vec<u8> {
+    bincode::serialize(tx).unwrap()
+}
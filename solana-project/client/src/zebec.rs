@@ -0,0 +1,312 @@
+//! Account lists and instruction data for the Zebec instructions the proxy
+//! wraps. Index positions matter: the `create_transaction_*` handlers read the
+//! mint, sender, receiver and data account at fixed offsets.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{hash::hash, system_program, sysvar};
+use anchor_lang::AnchorSerialize;
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_project::state::{Stream, StreamUpdate, TokenAmount, TransactionAccount};
+
+pub const PREFIX_TOKEN: &[u8] = b"withdraw_token";
+pub const OPERATE: &[u8] = b"NewVaultOption";
+pub const OPERATE_DATA: &[u8] = b"NewVaultOptionData";
+
+/// discriminator + start_time + end_time + paused + withdraw_limit + amount + sender + receiver
+/// + token_mint + withdrawn + paused_at + fee_owner + paused_amt + can_cancel + can_update
+pub const STREAM_TOKEN_SIZE: usize = 8 + 8 + 8 + 8 + 8 + 8 + 32 + 32 + 32 + 8 + 8 + 32 + 8 + 1 + 1;
+
+fn account(pubkey: Pubkey, is_signer: bool, is_writable: bool) -> TransactionAccount {
+    TransactionAccount {
+        pubkey,
+        is_signer,
+        is_writable,
+    }
+}
+
+/// Anchor instruction discriminator for a Zebec instruction.
+pub fn sighash(name: &str) -> [u8; 8] {
+    let preimage = format!("global:{}", name);
+    let mut out = [0u8; 8];
+    out.copy_from_slice(&hash(preimage.as_bytes()).to_bytes()[..8]);
+    out
+}
+
+fn instruction_data<T: AnchorSerialize>(name: &str, args: &T) -> Vec<u8> {
+    let mut data = sighash(name).to_vec();
+    data.extend(args.try_to_vec().unwrap());
+    data
+}
+
+#[derive(Debug, Clone)]
+pub struct ZebecAccounts {
+    pub program_id: Pubkey,
+    pub fee_owner: Pubkey,
+}
+
+impl ZebecAccounts {
+    pub fn new(program_id: Pubkey, fee_owner: Pubkey) -> Self {
+        ZebecAccounts {
+            program_id,
+            fee_owner,
+        }
+    }
+
+    pub fn zebec_vault(&self, pda_sender: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[pda_sender.as_ref()], &self.program_id).0
+    }
+
+    pub fn withdraw_data(&self, pda_sender: &Pubkey, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[PREFIX_TOKEN, pda_sender.as_ref(), mint.as_ref()],
+            &self.program_id,
+        )
+        .0
+    }
+
+    pub fn fee_vault(&self) -> Pubkey {
+        Pubkey::find_program_address(&[self.fee_owner.as_ref(), OPERATE], &self.program_id).0
+    }
+
+    pub fn fee_vault_data(&self) -> Pubkey {
+        let fee_vault = self.fee_vault();
+        Pubkey::find_program_address(
+            &[self.fee_owner.as_ref(), OPERATE_DATA, fee_vault.as_ref()],
+            &self.program_id,
+        )
+        .0
+    }
+
+    pub fn deposit(&self, pda_sender: &Pubkey, mint: &Pubkey) -> Vec<TransactionAccount> {
+        let zebec_vault = self.zebec_vault(pda_sender);
+        vec![
+            account(zebec_vault, false, true),
+            account(*pda_sender, true, true),
+            account(system_program::id(), false, false),
+            account(anchor_spl::token::ID, false, false),
+            account(anchor_spl::associated_token::ID, false, false),
+            account(sysvar::rent::id(), false, false),
+            account(*mint, false, false),
+            account(get_associated_token_address(pda_sender, mint), false, true),
+            account(
+                get_associated_token_address(&zebec_vault, mint),
+                false,
+                true,
+            ),
+        ]
+    }
+
+    pub fn stream(
+        &self,
+        data_account: &Pubkey,
+        pda_sender: &Pubkey,
+        pda_receiver: &Pubkey,
+        mint: &Pubkey,
+    ) -> Vec<TransactionAccount> {
+        vec![
+            account(*data_account, false, true),
+            account(self.withdraw_data(pda_sender, mint), false, true),
+            account(self.fee_owner, false, false),
+            account(self.fee_vault_data(), false, false),
+            account(self.fee_vault(), false, false),
+            account(*pda_sender, true, true),
+            account(*pda_receiver, false, false),
+            account(system_program::id(), false, false),
+            account(anchor_spl::token::ID, false, false),
+            account(*mint, false, false),
+            account(sysvar::rent::id(), false, false),
+        ]
+    }
+
+    pub fn stream_update(
+        &self,
+        data_account: &Pubkey,
+        pda_sender: &Pubkey,
+        pda_receiver: &Pubkey,
+        mint: &Pubkey,
+    ) -> Vec<TransactionAccount> {
+        vec![
+            account(*data_account, false, true),
+            account(self.withdraw_data(pda_sender, mint), false, true),
+            account(*pda_sender, true, true),
+            account(*pda_receiver, false, false),
+            account(*mint, false, false),
+        ]
+    }
+
+    pub fn pause_resume(
+        &self,
+        data_account: &Pubkey,
+        pda_sender: &Pubkey,
+        pda_receiver: &Pubkey,
+    ) -> Vec<TransactionAccount> {
+        vec![
+            account(*pda_sender, true, true),
+            account(*pda_receiver, false, false),
+            account(*data_account, false, true),
+        ]
+    }
+
+    /// Shared by receiver withdraw and cancel; only the signer differs.
+    fn settle(
+        &self,
+        data_account: &Pubkey,
+        pda_sender: &Pubkey,
+        pda_receiver: &Pubkey,
+        mint: &Pubkey,
+        receiver_signs: bool,
+    ) -> Vec<TransactionAccount> {
+        let zebec_vault = self.zebec_vault(pda_sender);
+        let fee_vault = self.fee_vault();
+        vec![
+            account(zebec_vault, false, false),
+            account(*pda_receiver, receiver_signs, true),
+            account(*pda_sender, !receiver_signs, true),
+            account(self.fee_owner, false, false),
+            account(self.fee_vault_data(), false, false),
+            account(fee_vault, false, false),
+            account(*data_account, false, true),
+            account(self.withdraw_data(pda_sender, mint), false, true),
+            account(system_program::id(), false, false),
+            account(anchor_spl::token::ID, false, false),
+            account(anchor_spl::associated_token::ID, false, false),
+            account(sysvar::rent::id(), false, false),
+            account(*mint, false, false),
+            account(
+                get_associated_token_address(&zebec_vault, mint),
+                false,
+                true,
+            ),
+            account(
+                get_associated_token_address(pda_receiver, mint),
+                false,
+                true,
+            ),
+            account(get_associated_token_address(&fee_vault, mint), false, true),
+        ]
+    }
+
+    pub fn receiver_withdraw(
+        &self,
+        data_account: &Pubkey,
+        pda_sender: &Pubkey,
+        pda_receiver: &Pubkey,
+        mint: &Pubkey,
+    ) -> Vec<TransactionAccount> {
+        self.settle(data_account, pda_sender, pda_receiver, mint, true)
+    }
+
+    pub fn cancel(
+        &self,
+        data_account: &Pubkey,
+        pda_sender: &Pubkey,
+        pda_receiver: &Pubkey,
+        mint: &Pubkey,
+    ) -> Vec<TransactionAccount> {
+        self.settle(data_account, pda_sender, pda_receiver, mint, false)
+    }
+
+    pub fn instant_transfer(
+        &self,
+        pda_sender: &Pubkey,
+        pda_receiver: &Pubkey,
+        mint: &Pubkey,
+    ) -> Vec<TransactionAccount> {
+        let zebec_vault = self.zebec_vault(pda_sender);
+        vec![
+            account(zebec_vault, false, false),
+            account(*pda_receiver, false, true),
+            account(*pda_sender, true, true),
+            account(self.withdraw_data(pda_sender, mint), false, true),
+            account(system_program::id(), false, false),
+            account(anchor_spl::token::ID, false, false),
+            account(anchor_spl::associated_token::ID, false, false),
+            account(sysvar::rent::id(), false, false),
+            account(*mint, false, false),
+            account(
+                get_associated_token_address(&zebec_vault, mint),
+                false,
+                true,
+            ),
+            account(
+                get_associated_token_address(pda_receiver, mint),
+                false,
+                true,
+            ),
+        ]
+    }
+
+    pub fn sender_withdraw(&self, pda_sender: &Pubkey, mint: &Pubkey) -> Vec<TransactionAccount> {
+        let zebec_vault = self.zebec_vault(pda_sender);
+        vec![
+            account(zebec_vault, false, false),
+            account(self.withdraw_data(pda_sender, mint), false, true),
+            account(*pda_sender, true, true),
+            account(system_program::id(), false, false),
+            account(anchor_spl::token::ID, false, false),
+            account(anchor_spl::associated_token::ID, false, false),
+            account(sysvar::rent::id(), false, false),
+            account(*mint, false, false),
+            account(get_associated_token_address(pda_sender, mint), false, true),
+            account(
+                get_associated_token_address(&zebec_vault, mint),
+                false,
+                true,
+            ),
+        ]
+    }
+}
+
+pub fn deposit_data(amount: u64) -> Vec<u8> {
+    instruction_data("deposit_token", &TokenAmount { amount })
+}
+
+pub fn stream_data(
+    start_time: u64,
+    end_time: u64,
+    amount: u64,
+    can_cancel: bool,
+    can_update: bool,
+) -> Vec<u8> {
+    instruction_data(
+        "token_stream",
+        &Stream {
+            start_time,
+            end_time,
+            amount,
+            can_cancel,
+            can_update,
+        },
+    )
+}
+
+pub fn stream_update_data(start_time: u64, end_time: u64, amount: u64) -> Vec<u8> {
+    instruction_data(
+        "token_stream_update",
+        &StreamUpdate {
+            start_time,
+            end_time,
+            amount,
+        },
+    )
+}
+
+pub fn pause_resume_data() -> Vec<u8> {
+    sighash("pause_resume_token_stream").to_vec()
+}
+
+pub fn receiver_withdraw_data() -> Vec<u8> {
+    sighash("withdraw_token_stream").to_vec()
+}
+
+pub fn cancel_data() -> Vec<u8> {
+    sighash("cancel_token_stream").to_vec()
+}
+
+pub fn instant_transfer_data(amount: u64) -> Vec<u8> {
+    instruction_data("instant_token_transfer", &TokenAmount { amount })
+}
+
+pub fn sender_withdraw_data(amount: u64) -> Vec<u8> {
+    instruction_data("token_withdrawal", &TokenAmount { amount })
+}
@@ -0,0 +1,127 @@
+use std::slice;
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use solana_sdk::message::v0::{LoadedAddresses, LoadedMessage};
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::signature::{Keypair, Signer};
+use zebec_wormhole_client::versioned::{self, LookupTable};
+use zebec_wormhole_client::ClientError;
+
+fn table_data(deactivation_slot: u64, addresses: &[Pubkey]) -> Vec<u8> {
+    let mut data = 1u32.to_le_bytes().to_vec();
+    data.extend(deactivation_slot.to_le_bytes());
+    data.resize(56, 0);
+    for address in addresses {
+        data.extend(address.as_ref());
+    }
+    data
+}
+
+#[test]
+fn lookup_tables_decode_only_when_active() {
+    let key = Pubkey::new_unique();
+    let addresses = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    assert_eq!(
+        LookupTable::decode(key, &table_data(u64::MAX, &addresses)).unwrap(),
+        LookupTable {
+            key,
+            addresses: addresses.clone(),
+        }
+    );
+    assert!(matches!(
+        LookupTable::decode(key, &table_data(7, &addresses)),
+        Err(ClientError::InvalidLookupTable(_))
+    ));
+    let mut uninitialized = table_data(u64::MAX, &addresses);
+    uninitialized[0] = 0;
+    assert!(LookupTable::decode(key, &uninitialized).is_err());
+    assert!(LookupTable::decode(key, &table_data(u64::MAX, &addresses)[..70]).is_err());
+}
+
+#[test]
+fn compiled_messages_load_what_the_table_holds() {
+    let payer = Keypair::new();
+    let extra_signer = Keypair::new();
+    let program = Pubkey::new_unique();
+    let (writable, readonly, unlisted) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let ix = Instruction {
+        program_id: program,
+        accounts: vec![
+            AccountMeta::new(writable, false),
+            AccountMeta::new_readonly(readonly, false),
+            AccountMeta::new(unlisted, false),
+            AccountMeta::new(extra_signer.pubkey(), true),
+            AccountMeta::new_readonly(program, false),
+        ],
+        data: vec![1, 2, 3],
+    };
+    // Signers and invoked programs stay listed even when the table has them.
+    let table = LookupTable {
+        key: Pubkey::new_unique(),
+        addresses: vec![
+            Pubkey::new_unique(),
+            readonly,
+            extra_signer.pubkey(),
+            writable,
+            program,
+        ],
+    };
+
+    let compiled = versioned::compile(
+        slice::from_ref(&ix),
+        &payer.pubkey(),
+        slice::from_ref(&table),
+        Hash::default(),
+    );
+    let message = match &compiled {
+        VersionedMessage::V0(message) => message.clone(),
+        VersionedMessage::Legacy(_) => panic!("expected a version 0 message"),
+    };
+    assert_eq!(
+        message.account_keys,
+        vec![payer.pubkey(), extra_signer.pubkey(), unlisted, program]
+    );
+    assert_eq!(message.header.num_required_signatures, 2);
+    assert_eq!(message.header.num_readonly_unsigned_accounts, 1);
+    assert_eq!(message.address_table_lookups.len(), 1);
+    assert_eq!(message.address_table_lookups[0].account_key, table.key);
+    assert_eq!(message.address_table_lookups[0].writable_indexes, vec![3]);
+    assert_eq!(message.address_table_lookups[0].readonly_indexes, vec![1]);
+
+    // Resolved the way the runtime does, the instruction is unchanged.
+    let loaded = LoadedMessage {
+        message: message.clone(),
+        loaded_addresses: LoadedAddresses {
+            writable: vec![writable],
+            readonly: vec![readonly],
+        },
+    };
+    let compiled_ix = &message.instructions[0];
+    assert_eq!(
+        loaded.get_account_key(usize::from(compiled_ix.program_id_index)),
+        Some(&program)
+    );
+    assert_eq!(compiled_ix.data, ix.data);
+    for (index, meta) in compiled_ix.accounts.iter().zip(&ix.accounts) {
+        let index = usize::from(*index);
+        assert_eq!(loaded.get_account_key(index), Some(&meta.pubkey));
+        if meta.pubkey != program {
+            assert_eq!(loaded.is_writable(index), meta.is_writable);
+        }
+    }
+
+    assert!(versioned::fits(&compiled));
+    let tx = versioned::sign(compiled.clone(), &[&extra_signer, &payer]).unwrap();
+    assert_eq!(tx.signatures.len(), 2);
+    assert!(tx.verify_with_results().iter().all(|valid| *valid));
+    assert!(matches!(
+        versioned::sign(compiled, &[&payer]),
+        Err(ClientError::MissingSigner(_))
+    ));
+}
@@ -0,0 +1,89 @@
+#![no_main]
+
+use anchor_lang::error::Error;
+use libfuzzer_sys::fuzz_target;
+use solana_project::constants::*;
+use solana_project::errors::MessengerError;
+use solana_project::state::TransactionData;
+use solana_project::{
+    decode_initialize_pda, decode_initialize_token_account, decode_payload,
+    decode_set_payout_wallet,
+};
+
+fn expected_length(code: u8) -> Option<usize> {
+    match code {
+        2 => Some(STREAM_PAYLOAD_LENGTH),
+        4 => Some(WITHDRAW_STREAM_PAYLOAD_LENGTH),
+        6 => Some(DEPOSIT_PAYLOAD_LENGTH),
+        8 => Some(PAUSE_PAYLOAD_LENGTH),
+        10 => Some(WITHDRAW_PAYLOAD_LENGTH),
+        12 => Some(INSTANT_TRANSFER_PAYLOAD_LENGTH),
+        14 => Some(STREAM_UPDATE_PAYLOAD_LENGTH),
+        16 => Some(CANCEL_PAYLOAD_LENGTH),
+        17 => Some(DIRECT_TRANSFER_PAYLOAD_LENGTH),
+        18 => Some(INITIALIZE_PDA_PAYLOAD_LENGTH),
+        19 => Some(INITIALIZE_TOKEN_ACCOUNT_PAYLOAD_LENGTH),
+        21 => Some(WITHDRAW_STREAM_BRIDGE_PAYLOAD_LENGTH),
+        22 => Some(SET_PAYOUT_WALLET_PAYLOAD_LENGTH),
+        23 => Some(WITHDRAW_STREAM_PAYOUT_PAYLOAD_LENGTH),
+        _ => None,
+    }
+}
+
+// Direct transfers also have a layout that fixes the target chain and fee.
+fn second_length(code: u8) -> Option<usize> {
+    match code {
+        17 => Some(DIRECT_TRANSFER_V2_PAYLOAD_LENGTH),
+        _ => None,
+    }
+}
+
+fn is_length_error<T>(result: &anchor_lang::Result<T>) -> bool {
+    match result {
+        Err(Error::AnchorError(err)) => {
+            err.error_code_number == u32::from(MessengerError::InvalidPayloadLength)
+        }
+        _ => false,
+    }
+}
+
+fn check(payload: &[u8]) {
+    let mut transaction_data = TransactionData::default();
+    let stored = decode_payload(payload, 4, [0; 32], &mut transaction_data);
+    let initialize_pda = decode_initialize_pda(payload);
+    let initialize_token_account = decode_initialize_token_account(payload);
+    let set_payout_wallet = decode_set_payout_wallet(payload);
+
+    let code = match payload.first() {
+        Some(code) => *code,
+        None => {
+            assert!(is_length_error(&stored));
+            return;
+        }
+    };
+    let lengths = [expected_length(code), second_length(code)];
+    let length_matches = lengths.contains(&Some(payload.len()));
+    // Stored messages may carry one trailing target version byte.
+    let versioned = lengths
+        .iter()
+        .flatten()
+        .any(|length| length + TARGET_VERSION_LENGTH == payload.len());
+    match code {
+        18 => assert_eq!(is_length_error(&initialize_pda), !length_matches),
+        19 => assert_eq!(is_length_error(&initialize_token_account), !length_matches),
+        22 => assert_eq!(is_length_error(&set_payout_wallet), !length_matches),
+        _ if expected_length(code).is_some() => {
+            assert_eq!(is_length_error(&stored), !(length_matches || versioned))
+        }
+        _ => assert!(stored.is_err() && !is_length_error(&stored)),
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    check(data);
+
+    // Reach every decoder, not only the codes the fuzzer happens to find.
+    for code in [2u8, 4, 6, 8, 10, 12, 14, 16, 17, 18, 19, 21, 22, 23] {
+        check(&[&[code][..], data].concat());
+    }
+});
@@ -0,0 +1,33 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solana_project::portal::{
+    DeserializePayload, PayloadAssetMeta, PayloadTransfer, PayloadTransferWithPayload,
+    SerializePayload,
+};
+
+fn check(data: &[u8]) {
+    // Transfers have a fixed layout, so anything that decodes encodes back
+    // to the same bytes.
+    if let Ok(transfer) = PayloadTransfer::deserialize(&mut &data[..]) {
+        assert_eq!(transfer.try_to_vec().unwrap(), data);
+    }
+    if let Ok(transfer) = PayloadTransferWithPayload::deserialize(&mut &data[..]) {
+        assert_eq!(transfer.try_to_vec().unwrap(), data);
+    }
+
+    // Symbol and name drop padding and invalid UTF-8, so only the second
+    // round trip is exact.
+    if let Ok(meta) = PayloadAssetMeta::deserialize(&mut &data[..]) {
+        let encoded = meta.try_to_vec().unwrap();
+        let decoded = PayloadAssetMeta::deserialize(&mut encoded.as_slice()).unwrap();
+        assert_eq!(decoded, meta);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    check(data);
+    for id in [1u8, 2, 3] {
+        check(&[&[id][..], data].concat());
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+
+use anchor_lang::{AnchorDeserialize, AnchorSerialize};
+use libfuzzer_sys::fuzz_target;
+use solana_project::serialize_vaa;
+use solana_project::wormhole::PostedMessageData;
+
+fuzz_target!(|data: &[u8]| {
+    let mut buf = data;
+    let _ = PostedMessageData::deserialize(&mut buf);
+
+    if let Ok(posted) = PostedMessageData::try_from_slice(data) {
+        serialize_vaa(&posted.0);
+
+        // Everything after the three byte prefix survives a round trip.
+        let encoded = posted.try_to_vec().unwrap();
+        assert_eq!(&encoded[3..], &data[3..]);
+    }
+});
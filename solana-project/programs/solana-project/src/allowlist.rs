@@ -0,0 +1,116 @@
+//! The mints messages may name.
+//!
+//! A VAA can name any mint, and every one it names costs the relayer rent: a
+//! token account from `initialize_pda_token_account`, or a stored message.
+//! Only mints the config owner registered are accepted, each with a
+//! `MintRegistry` entry at ["mint_registry", mint]. `store_msg`,
+//! `store_signed_msg`, `store_batch_msg`, `initialize_pda_token_account` and
+//! the direct transfer handlers check it. The entry also bounds single
+//! amounts, in the mint's base units.
+
+use std::str::FromStr;
+
+use anchor_lang::prelude::*;
+
+use crate::constants::TOKEN_BRIDGE_ADDRESS;
+use crate::errors::MessengerError;
+use crate::portal::WrappedMeta;
+use crate::state::{MintRegistry, TransactionData};
+
+pub fn mint_registry_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"mint_registry", mint.as_ref()], program_id).0
+}
+
+/// The mint the token bridge holds for a token of another chain.
+pub fn wrapped_mint_address(origin_chain: u16, origin_address: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"wrapped", &origin_chain.to_be_bytes(), origin_address],
+        &Pubkey::from_str(TOKEN_BRIDGE_ADDRESS).unwrap(),
+    )
+    .0
+}
+
+/// What the token bridge recorded about `mint` in `wrapped_meta`, its
+/// ["meta", mint] account, or `None` for a native mint, which has no meta.
+/// Fails with `WrappedMetaMismatch` unless the recorded origin derives
+/// `mint`.
+#[allow(clippy::result_large_err)]
+pub fn wrapped_origin(wrapped_meta: &AccountInfo, mint: &Pubkey) -> Result<Option<WrappedMeta>> {
+    if wrapped_meta.data_is_empty() {
+        return Ok(None);
+    }
+    require!(
+        *wrapped_meta.owner == Pubkey::from_str(TOKEN_BRIDGE_ADDRESS).unwrap(),
+        MessengerError::WrappedMetaMismatch
+    );
+    let meta = WrappedMeta::deserialize(&mut &wrapped_meta.try_borrow_data()?[..])
+        .map_err(|_| error!(MessengerError::WrappedMetaMismatch))?;
+    require!(
+        wrapped_mint_address(meta.chain, &meta.token_address) == *mint,
+        MessengerError::WrappedMetaMismatch
+    );
+    Ok(Some(meta))
+}
+
+// Whether messages with `code` carry an amount of their mint: streams,
+// deposits, sender withdraws, instant transfers, stream updates and direct
+// transfers.
+fn carries_amount(code: u8) -> bool {
+    matches!(code, 2 | 6 | 10 | 12 | 14 | 17)
+}
+
+/// The registry entry of `mint`, passed unchecked. Fails with
+/// `MintNotAllowed` unless the mint is registered.
+#[allow(clippy::result_large_err)]
+pub fn allowed_mint(
+    mint_registry: &AccountInfo,
+    mint: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<MintRegistry> {
+    require!(
+        mint_registry.key() == mint_registry_address(mint, program_id)
+            && mint_registry.owner == program_id,
+        MessengerError::MintNotAllowed
+    );
+    Ok(Account::<MintRegistry>::try_from(mint_registry)?.into_inner())
+}
+
+/// Checks a payload amount, in the mint's base units, against the bounds of
+/// its mint's entry.
+#[allow(clippy::result_large_err)]
+pub fn check_payload_amount(registry: &MintRegistry, amount: u64) -> Result<()> {
+    registry.check_amount(amount)
+}
+
+/// Checks the mint of a message with `code` decoded into `data`, and its
+/// amount if it carries one.
+#[allow(clippy::result_large_err)]
+pub fn check_stored_mint(
+    mint_registry: &AccountInfo,
+    data: &TransactionData,
+    code: u8,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let registry = allowed_mint(mint_registry, &data.token_mint, program_id)?;
+    if carries_amount(code) {
+        check_payload_amount(&registry, data.amount)?;
+    }
+    Ok(())
+}
+
+/// The registry entry of a mint the token bridge is to take, which has to be
+/// registered as `wrapped` or not, matching the handler.
+#[allow(clippy::result_large_err)]
+pub fn bridged_mint(
+    mint_registry: &AccountInfo,
+    mint: &Pubkey,
+    wrapped: bool,
+    program_id: &Pubkey,
+) -> Result<MintRegistry> {
+    let registry = allowed_mint(mint_registry, mint, program_id)?;
+    require!(
+        registry.wrapped == wrapped,
+        MessengerError::MintOriginMismatch
+    );
+    Ok(registry)
+}
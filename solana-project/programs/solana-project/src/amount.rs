@@ -0,0 +1,58 @@
+//! Token amounts as the Solana mint counts them and as Wormhole carries them.
+//!
+//! `Messenger.sol` sends payload amounts as it is given them, in the base
+//! units of the token, and Zebec and the token bridge take them in the same
+//! units. The token bridge though keeps at most 8 decimals of an amount and
+//! drops the rest, so a mint with more decimals than that has amounts that
+//! could not go back through Wormhole whole.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::constants::WORMHOLE_DECIMALS;
+use crate::errors::MessengerError;
+
+fn scale(decimals: u8) -> Option<u64> {
+    10u64.checked_pow(decimals.saturating_sub(WORMHOLE_DECIMALS) as u32)
+}
+
+/// `normalizeAmount` of the token bridge: drops the decimals past 8.
+pub fn normalize_amount(amount: u64, decimals: u8) -> u64 {
+    // No u64 amount has a whole unit at more than 27 decimals.
+    scale(decimals).map_or(0, |scale| amount / scale)
+}
+
+/// `denormalizeAmount` of the token bridge: an amount with at most 8
+/// decimals in the mint's base units.
+#[allow(clippy::result_large_err)]
+pub fn denormalize_amount(amount: u64, decimals: u8) -> Result<u64> {
+    scale(decimals)
+        .and_then(|scale| amount.checked_mul(scale))
+        .ok_or_else(|| error!(MessengerError::Overflow))
+}
+
+/// Checks that `raw`, the amount passed on to Zebec, is exactly the payload
+/// amount and survives Wormhole, see `check_untruncated`.
+#[allow(clippy::result_large_err)]
+pub fn check_amount(raw: u64, amount: u64, decimals: u8) -> Result<()> {
+    require!(raw == amount, MessengerError::AmountMismatch);
+    check_untruncated(amount, decimals)
+}
+
+/// Checks that `amount`, in the mint's base units, has no digits past the
+/// 8th decimal. They would be lost on the way through Wormhole, so such an
+/// amount is refused rather than rounded.
+#[allow(clippy::result_large_err)]
+pub fn check_untruncated(amount: u64, decimals: u8) -> Result<()> {
+    require!(
+        denormalize_amount(normalize_amount(amount, decimals), decimals)? == amount,
+        MessengerError::AmountWouldTruncate
+    );
+    Ok(())
+}
+
+/// Decimals of a mint passed unchecked, as the token bridge accounts are.
+#[allow(clippy::result_large_err)]
+pub fn mint_decimals(mint: &AccountInfo) -> Result<u8> {
+    Ok(Account::<Mint>::try_from(mint)?.decimals)
+}
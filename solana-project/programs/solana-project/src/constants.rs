@@ -2,4 +2,111 @@ pub const CORE_BRIDGE_ADDRESS: &str = "worm2ZoG2kUd4vFXhvjh93UUH596ayRfgQ2MgjNMT
 pub const TOKEN_BRIDGE_ADDRESS: &str = "wormDTUJ6AWPNvk59vGQbDvGJmqbDTdgWgAqcLBCgUb";
 pub const ZEBEC_CONTRACT: &str = "zbcKGdAmXfthXY3rEPBzexVByT2cqRqCZb9NwWdGQ2T";
 
+// Length of the hex emitter address in legacy `EmitterAddrAccount`s
 pub const EVM_CHAIN_ADDRESS_LENGTH: usize = 64;
+pub const EMITTER_LABEL_MAX_LENGTH: usize = 32;
+pub const ADDRESS_FORMAT_LENGTH: usize = 1;
+pub const EMITTER_ACCOUNT_SPACE: usize = 8
+    + 2
+    + 32
+    + 8
+    + 1
+    + 4
+    + EMITTER_LABEL_MAX_LENGTH
+    + ACCOUNT_VERSION_LENGTH
+    + ADDRESS_FORMAT_LENGTH;
+
+// Trailing layout version byte of every state account, see `versioning`
+pub const ACCOUNT_VERSION_LENGTH: usize = 1;
+// `Governance::Emitter`: variant, chain and address
+pub const GOVERNANCE_LENGTH: usize = 1 + 2 + 32;
+pub const CONFIG_SPACE: usize = 8 + 32 + 4 + ACCOUNT_VERSION_LENGTH + GOVERNANCE_LENGTH;
+pub const TARGET_PROGRAM_SPACE: usize = 8 + 1 + 32 + ACCOUNT_VERSION_LENGTH;
+pub const PROCESSED_VAA_SPACE: usize = 8 + 8 + ACCOUNT_VERSION_LENGTH;
+// `BridgeBack::To`: variant, target chain, fee, balance and bridged flag
+pub const BRIDGE_BACK_LENGTH: usize = 1 + 2 + 8 + 8 + 1;
+// `Payout::To`: variant, wallet, balance and paid flag
+pub const PAYOUT_LENGTH: usize = 1 + 32 + 8 + 1;
+// `Clearance::Held`: variant and release time
+pub const CLEARANCE_LENGTH: usize = 1 + 8;
+// `TransferTerms::Fixed`: variant, target chain and maximum fee
+pub const TRANSFER_TERMS_LENGTH: usize = 1 + 2 + 8;
+pub const TRANSACTION_DATA_SPACE: usize = 8
+    + 166
+    + ACCOUNT_VERSION_LENGTH
+    + BRIDGE_BACK_LENGTH
+    + PAYOUT_LENGTH
+    + CLEARANCE_LENGTH
+    + TRANSFER_TERMS_LENGTH;
+// `MessageBinding::Bound`: variant, sender, chain, count, code and digest
+pub const MESSAGE_BINDING_LENGTH: usize = 1 + 32 + 2 + 8 + 1 + 32;
+pub const COUNT_SPACE: usize = 8 + 8 + ACCOUNT_VERSION_LENGTH;
+pub const TRANSACTION_STATUS_SPACE: usize = 8 + 1 + ACCOUNT_VERSION_LENGTH;
+pub const PAYOUT_PROFILE_SPACE: usize = 8 + 32 + 2 + 32 + 8 + ACCOUNT_VERSION_LENGTH;
+pub const META_NONCE_SPACE: usize = 8 + 8 + ACCOUNT_VERSION_LENGTH;
+pub const RATE_WINDOW_LENGTH: usize = RATE_WINDOW_HOURS * 8 + 8;
+pub const CHAIN_LIMIT_SPACE: usize = 8 + 2 + 8 + RATE_WINDOW_LENGTH + ACCOUNT_VERSION_LENGTH;
+pub const MINT_LIMIT_SPACE: usize =
+    8 + 32 + 8 + 8 + 8 + 4 + RATE_WINDOW_LENGTH + ACCOUNT_VERSION_LENGTH;
+pub const MINT_REGISTRY_SPACE: usize = 8 + 32 + 1 + 8 + 8 + 1 + 2 + 32 + ACCOUNT_VERSION_LENGTH;
+pub const BATCH_ENTRY_SPACE: usize = 32 + 8 + 8 + 8 + 1 + 1 + 1 + 32 + 32 + 4;
+pub const BATCH_DATA_SPACE: usize = 8
+    + 32
+    + 2
+    + 32
+    + 8
+    + 1
+    + 32
+    + 4
+    + MAX_BATCH_ENTRIES * BATCH_ENTRY_SPACE
+    + ACCOUNT_VERSION_LENGTH;
+
+// Lengths of the payloads Encoder.sol packs, by message code
+pub const STREAM_PAYLOAD_LENGTH: usize = 169;
+pub const WITHDRAW_STREAM_PAYLOAD_LENGTH: usize = 161;
+pub const WITHDRAW_STREAM_BRIDGE_PAYLOAD_LENGTH: usize = 171;
+pub const DEPOSIT_PAYLOAD_LENGTH: usize = 105;
+pub const PAUSE_PAYLOAD_LENGTH: usize = 161;
+pub const WITHDRAW_PAYLOAD_LENGTH: usize = 105;
+pub const INSTANT_TRANSFER_PAYLOAD_LENGTH: usize = 137;
+pub const STREAM_UPDATE_PAYLOAD_LENGTH: usize = 185;
+pub const CANCEL_PAYLOAD_LENGTH: usize = 161;
+pub const DIRECT_TRANSFER_PAYLOAD_LENGTH: usize = 137;
+// Direct transfer payloads that also fix the target chain and maximum fee
+pub const DIRECT_TRANSFER_V2_PAYLOAD_LENGTH: usize = 147;
+pub const INITIALIZE_PDA_PAYLOAD_LENGTH: usize = 65;
+pub const INITIALIZE_TOKEN_ACCOUNT_PAYLOAD_LENGTH: usize = 97;
+pub const SET_PAYOUT_WALLET_PAYLOAD_LENGTH: usize = 97;
+pub const WITHDRAW_STREAM_PAYOUT_PAYLOAD_LENGTH: usize = 161;
+// A batch stream (20) payload is a header followed by its entries
+pub const BATCH_STREAM_HEADER_LENGTH: usize = 98;
+pub const BATCH_STREAM_ENTRY_LENGTH: usize = 57;
+// The whole VAA is posted in one transaction, which bounds the entry count
+pub const MAX_BATCH_ENTRIES: usize = 10;
+
+// Governance payloads start with the code and the proxy's program id
+pub const GOVERNANCE_HEADER_LENGTH: usize = 33;
+// A register chain (30) payload ends with a label of up to 32 bytes
+pub const REGISTER_CHAIN_PAYLOAD_LENGTH: usize = 68;
+pub const UPDATE_CHAIN_PAYLOAD_LENGTH: usize = 36;
+pub const SET_CHAIN_LIMIT_PAYLOAD_LENGTH: usize = 43;
+pub const SET_MINT_LIMIT_PAYLOAD_LENGTH: usize = 93;
+pub const REGISTER_TARGET_PROGRAM_PAYLOAD_LENGTH: usize = 66;
+pub const REMOVE_TARGET_PROGRAM_PAYLOAD_LENGTH: usize = 34;
+pub const SET_OWNER_PAYLOAD_LENGTH: usize = 65;
+
+// Index of the receiver's token account in Zebec's withdraw accounts
+pub const RECEIVER_TOKEN_ACCOUNT_INDEX: usize = 14;
+
+// The governor limits what went out over the last 24 hours, by the hour
+pub const RATE_WINDOW_HOURS: usize = 24;
+pub const SECONDS_PER_HOUR: i64 = 3600;
+
+// Wormhole chain id of Solana
+pub const SOLANA_CHAIN_ID: u16 = 1;
+
+// Most decimals an amount keeps through Wormhole, see `amount`
+pub const WORMHOLE_DECIMALS: u8 = 8;
+
+// Optional trailing byte of a Zebec payload selecting the target program version
+pub const TARGET_VERSION_LENGTH: usize = 1;
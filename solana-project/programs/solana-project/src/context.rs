@@ -4,11 +4,11 @@ use anchor_spl::{
     token::{Mint, TokenAccount, Token}
 };
 use crate::constants::*;
+use crate::meta::SignedAction;
 use crate::portal::TokenPortalBridge;
 use crate::state::*;
 use std::str::FromStr;
 use crate::wormhole::*;
-use hex::decode;
 
 #[derive(Accounts)]
 pub struct Initialize<'info> {
@@ -17,7 +17,7 @@ pub struct Initialize<'info> {
         seeds=[b"config".as_ref()],
         payer=owner,
         bump,
-        space=8+32+4
+        space=CONFIG_SPACE
     )]
     pub config: Account<'info, Config>,
     #[account(mut)]
@@ -26,7 +26,7 @@ pub struct Initialize<'info> {
 }
 
 #[derive(Accounts)]
-#[instruction(chain_id:u16, emitter_addr:String)]
+#[instruction(chain_id:u16)]
 pub struct RegisterChain<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
@@ -40,9 +40,450 @@ pub struct RegisterChain<'info> {
         seeds=[b"EmitterAddress".as_ref(), chain_id.to_be_bytes().as_ref()],
         payer=owner,
         bump,
-        space=8 + 2 + 4 + EVM_CHAIN_ADDRESS_LENGTH
+        space=EMITTER_ACCOUNT_SPACE
     )]
-    pub emitter_acc: Account<'info, EmitterAddrAccount>,
+    pub emitter_acc: Account<'info, EmitterAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id:u16)]
+pub struct UpdateChain<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        constraint = config.owner == owner.key()
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds=[b"EmitterAddress".as_ref(), chain_id.to_be_bytes().as_ref()],
+        bump
+    )]
+    pub emitter_acc: Account<'info, EmitterAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id:u16)]
+pub struct SetChainLimit<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        constraint = config.owner == owner.key()
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        seeds=[b"chain_limit".as_ref(), chain_id.to_be_bytes().as_ref()],
+        payer=owner,
+        bump,
+        space=CHAIN_LIMIT_SPACE
+    )]
+    pub chain_limit: Account<'info, ChainLimit>,
+}
+
+#[derive(Accounts)]
+pub struct SetMintLimit<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        constraint = config.owner == owner.key()
+    )]
+    pub config: Account<'info, Config>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        seeds=[b"mint_limit".as_ref(), mint.key().as_ref()],
+        payer=owner,
+        bump,
+        space=MINT_LIMIT_SPACE
+    )]
+    pub mint_limit: Account<'info, MintLimit>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id:u16)]
+pub struct MigrateChain<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        constraint = config.owner == owner.key()
+    )]
+    pub config: Account<'info, Config>,
+    ///CHECK: still in the legacy layout, read in the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds=[b"EmitterAddress".as_ref(), chain_id.to_be_bytes().as_ref()],
+        bump
+    )]
+    pub emitter_acc: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    ///CHECK: any state account of this program, read in the handler
+    #[account(
+        mut,
+        owner = crate::ID
+    )]
+    pub account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(version:u8)]
+pub struct RegisterTargetProgram<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        constraint = config.owner == owner.key()
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        seeds=[b"target_program".as_ref(), version.to_be_bytes().as_ref()],
+        payer=owner,
+        bump,
+        space=TARGET_PROGRAM_SPACE
+    )]
+    pub target_program: Account<'info, TargetProgram>,
+}
+
+#[derive(Accounts)]
+#[instruction(version:u8)]
+pub struct RemoveTargetProgram<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        constraint = config.owner == owner.key()
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        close = owner,
+        seeds=[b"target_program".as_ref(), version.to_be_bytes().as_ref()],
+        bump
+    )]
+    pub target_program: Account<'info, TargetProgram>,
+}
+
+#[derive(Accounts)]
+pub struct SetGovernance<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        constraint = config.owner == owner.key()
+    )]
+    pub config: Account<'info, Config>,
+}
+
+// The governance contexts claim the VAA like `StoreMsg` does, keyed by the
+// governance emitter of the config; see `governance`.
+#[derive(Accounts)]
+pub struct GovernConfig<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds=[b"config".as_ref()],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer=payer,
+        space=PROCESSED_VAA_SPACE,
+        seeds=[
+            config.governance.emitter_address().as_ref(),
+            config.governance.chain_id().to_be_bytes().as_ref(),
+            (PostedMessageData::try_from_slice(&core_bridge_vaa.data.borrow())?.0).sequence.to_be_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub processed_vaa: Account<'info, ProcessedVAA>,
+    /// This requires some fancy hashing, so confirm it's derived address in the function itself.
+    #[account(
+        constraint = core_bridge_vaa.to_account_info().owner == &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
+    )]
+    /// CHECK: This account is owned by Core Bridge so we trust it
+    pub core_bridge_vaa: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id:u16)]
+pub struct GovernRegisterChain<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds=[b"config".as_ref()],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer=payer,
+        space=PROCESSED_VAA_SPACE,
+        seeds=[
+            config.governance.emitter_address().as_ref(),
+            config.governance.chain_id().to_be_bytes().as_ref(),
+            (PostedMessageData::try_from_slice(&core_bridge_vaa.data.borrow())?.0).sequence.to_be_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub processed_vaa: Account<'info, ProcessedVAA>,
+    /// This requires some fancy hashing, so confirm it's derived address in the function itself.
+    #[account(
+        constraint = core_bridge_vaa.to_account_info().owner == &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
+    )]
+    /// CHECK: This account is owned by Core Bridge so we trust it
+    pub core_bridge_vaa: AccountInfo<'info>,
+    #[account(
+        init,
+        seeds=[b"EmitterAddress".as_ref(), chain_id.to_be_bytes().as_ref()],
+        payer=payer,
+        bump,
+        space=EMITTER_ACCOUNT_SPACE
+    )]
+    pub emitter_acc: Account<'info, EmitterAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id:u16)]
+pub struct GovernUpdateChain<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds=[b"config".as_ref()],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer=payer,
+        space=PROCESSED_VAA_SPACE,
+        seeds=[
+            config.governance.emitter_address().as_ref(),
+            config.governance.chain_id().to_be_bytes().as_ref(),
+            (PostedMessageData::try_from_slice(&core_bridge_vaa.data.borrow())?.0).sequence.to_be_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub processed_vaa: Account<'info, ProcessedVAA>,
+    /// This requires some fancy hashing, so confirm it's derived address in the function itself.
+    #[account(
+        constraint = core_bridge_vaa.to_account_info().owner == &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
+    )]
+    /// CHECK: This account is owned by Core Bridge so we trust it
+    pub core_bridge_vaa: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds=[b"EmitterAddress".as_ref(), chain_id.to_be_bytes().as_ref()],
+        bump
+    )]
+    pub emitter_acc: Account<'info, EmitterAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id:u16)]
+pub struct GovernSetChainLimit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds=[b"config".as_ref()],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer=payer,
+        space=PROCESSED_VAA_SPACE,
+        seeds=[
+            config.governance.emitter_address().as_ref(),
+            config.governance.chain_id().to_be_bytes().as_ref(),
+            (PostedMessageData::try_from_slice(&core_bridge_vaa.data.borrow())?.0).sequence.to_be_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub processed_vaa: Account<'info, ProcessedVAA>,
+    /// This requires some fancy hashing, so confirm it's derived address in the function itself.
+    #[account(
+        constraint = core_bridge_vaa.to_account_info().owner == &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
+    )]
+    /// CHECK: This account is owned by Core Bridge so we trust it
+    pub core_bridge_vaa: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        seeds=[b"chain_limit".as_ref(), chain_id.to_be_bytes().as_ref()],
+        payer=payer,
+        bump,
+        space=CHAIN_LIMIT_SPACE
+    )]
+    pub chain_limit: Account<'info, ChainLimit>,
+}
+
+#[derive(Accounts)]
+pub struct GovernSetMintLimit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds=[b"config".as_ref()],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer=payer,
+        space=PROCESSED_VAA_SPACE,
+        seeds=[
+            config.governance.emitter_address().as_ref(),
+            config.governance.chain_id().to_be_bytes().as_ref(),
+            (PostedMessageData::try_from_slice(&core_bridge_vaa.data.borrow())?.0).sequence.to_be_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub processed_vaa: Account<'info, ProcessedVAA>,
+    /// This requires some fancy hashing, so confirm it's derived address in the function itself.
+    #[account(
+        constraint = core_bridge_vaa.to_account_info().owner == &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
+    )]
+    /// CHECK: This account is owned by Core Bridge so we trust it
+    pub core_bridge_vaa: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        seeds=[b"mint_limit".as_ref(), mint.key().as_ref()],
+        payer=payer,
+        bump,
+        space=MINT_LIMIT_SPACE
+    )]
+    pub mint_limit: Account<'info, MintLimit>,
+}
+
+#[derive(Accounts)]
+#[instruction(version:u8)]
+pub struct GovernRegisterTargetProgram<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds=[b"config".as_ref()],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer=payer,
+        space=PROCESSED_VAA_SPACE,
+        seeds=[
+            config.governance.emitter_address().as_ref(),
+            config.governance.chain_id().to_be_bytes().as_ref(),
+            (PostedMessageData::try_from_slice(&core_bridge_vaa.data.borrow())?.0).sequence.to_be_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub processed_vaa: Account<'info, ProcessedVAA>,
+    /// This requires some fancy hashing, so confirm it's derived address in the function itself.
+    #[account(
+        constraint = core_bridge_vaa.to_account_info().owner == &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
+    )]
+    /// CHECK: This account is owned by Core Bridge so we trust it
+    pub core_bridge_vaa: AccountInfo<'info>,
+    #[account(
+        init,
+        seeds=[b"target_program".as_ref(), version.to_be_bytes().as_ref()],
+        payer=payer,
+        bump,
+        space=TARGET_PROGRAM_SPACE
+    )]
+    pub target_program: Account<'info, TargetProgram>,
+}
+
+#[derive(Accounts)]
+#[instruction(version:u8)]
+pub struct GovernRemoveTargetProgram<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds=[b"config".as_ref()],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer=payer,
+        space=PROCESSED_VAA_SPACE,
+        seeds=[
+            config.governance.emitter_address().as_ref(),
+            config.governance.chain_id().to_be_bytes().as_ref(),
+            (PostedMessageData::try_from_slice(&core_bridge_vaa.data.borrow())?.0).sequence.to_be_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub processed_vaa: Account<'info, ProcessedVAA>,
+    /// This requires some fancy hashing, so confirm it's derived address in the function itself.
+    #[account(
+        constraint = core_bridge_vaa.to_account_info().owner == &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
+    )]
+    /// CHECK: This account is owned by Core Bridge so we trust it
+    pub core_bridge_vaa: AccountInfo<'info>,
+    #[account(
+        mut,
+        close = payer,
+        seeds=[b"target_program".as_ref(), version.to_be_bytes().as_ref()],
+        bump
+    )]
+    pub target_program: Account<'info, TargetProgram>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterMint<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        constraint = config.owner == owner.key()
+    )]
+    pub config: Account<'info, Config>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        seeds=[b"mint_registry".as_ref(), mint.key().as_ref()],
+        payer=owner,
+        bump,
+        space=MINT_REGISTRY_SPACE
+    )]
+    pub mint_registry: Account<'info, MintRegistry>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint:Pubkey)]
+pub struct RemoveMint<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        constraint = config.owner == owner.key()
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        close = owner,
+        seeds=[b"mint_registry".as_ref(), mint.as_ref()],
+        bump
+    )]
+    pub mint_registry: Account<'info, MintRegistry>,
 }
 
 #[derive(Accounts)]
@@ -55,9 +496,9 @@ pub struct InitializePDA<'info> {
     #[account(
         init,
         payer=zebec_eoa,
-        space=8 + 8,
+        space=PROCESSED_VAA_SPACE,
         seeds=[
-            &decode(&emitter_acc.emitter_addr.as_str()).unwrap()[..],
+            emitter_acc.emitter_addr.as_ref(),
             emitter_acc.chain_id.to_be_bytes().as_ref(),
             (PostedMessageData::try_from_slice(&core_bridge_vaa.data.borrow())?.0).sequence.to_be_bytes().as_ref()
         ],
@@ -65,7 +506,7 @@ pub struct InitializePDA<'info> {
         
     )]
     pub processed_vaa: Account<'info, ProcessedVAA>,
-    pub emitter_acc: Account<'info, EmitterAddrAccount>,
+    pub emitter_acc: Account<'info, EmitterAccount>,
     /// This requires some fancy hashing, so confirm it's derived address in the function itself.
     #[account(
         constraint = core_bridge_vaa.to_account_info().owner == &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
@@ -98,16 +539,16 @@ pub struct InitializePDATokenAccount<'info> {
     #[account(
         init,
         payer=zebec_eoa,
-        space= 8 + 8,
+        space= PROCESSED_VAA_SPACE,
         seeds=[
-            &decode(&emitter_acc.emitter_addr.as_str()).unwrap()[..],
+            emitter_acc.emitter_addr.as_ref(),
             emitter_acc.chain_id.to_be_bytes().as_ref(),
             (PostedMessageData::try_from_slice(&core_bridge_vaa.data.borrow())?.0).sequence.to_be_bytes().as_ref()
         ],
         bump,
     )]
     pub processed_vaa: Account<'info, ProcessedVAA>,
-    pub emitter_acc: Account<'info, EmitterAddrAccount>,
+    pub emitter_acc: Account<'info, EmitterAccount>,
     /// This requires some fancy hashing, so confirm it's derived address in the function itself.
     #[account(
         constraint = core_bridge_vaa.to_account_info().owner == &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
@@ -130,7 +571,114 @@ pub struct InitializePDATokenAccount<'info> {
         associated_token::authority = pda_account,
     )]
     pub pda_token_account: Box <Account<'info, TokenAccount>>,
-    pub token_mint: Account<'info, Mint>
+    pub token_mint: Account<'info, Mint>,
+
+    ///CHECK: the mint's registry entry; checked in the handler
+    pub mint_registry: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(sender:[u8;32], chain_id:u16)]
+pub struct SetPayoutWallet<'info> {
+    #[account(mut)]
+    pub zebec_eoa: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        init,
+        payer=zebec_eoa,
+        space=PROCESSED_VAA_SPACE,
+        seeds=[
+            emitter_acc.emitter_addr.as_ref(),
+            emitter_acc.chain_id.to_be_bytes().as_ref(),
+            (PostedMessageData::try_from_slice(&core_bridge_vaa.data.borrow())?.0).sequence.to_be_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub processed_vaa: Account<'info, ProcessedVAA>,
+    pub emitter_acc: Account<'info, EmitterAccount>,
+    /// This requires some fancy hashing, so confirm it's derived address in the function itself.
+    #[account(
+        constraint = core_bridge_vaa.to_account_info().owner == &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
+    )]
+    /// CHECK: This account is owned by Core Bridge so we trust it
+    pub core_bridge_vaa: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer=zebec_eoa,
+        space=PAYOUT_PROFILE_SPACE,
+        seeds=[
+            b"payout_profile".as_ref(),
+            &sender,
+            &chain_id.to_be_bytes()
+        ],
+        bump
+    )]
+    pub payout_profile: Account<'info, PayoutProfile>,
+}
+
+// Starts a chunked upload of a message's transaction, see `staging`.
+#[derive(Accounts)]
+#[instruction(
+    sender: [u8; 32],
+    current_count: u64,
+    accounts_len: u8,
+    data_len: u16
+)]
+pub struct InitTransaction<'info> {
+    #[account(
+        init,
+        payer = zebec_eoa,
+        space = TransactionBuffer::space(accounts_len, data_len),
+        seeds = [
+            b"transaction_buffer".as_ref(),
+            &sender,
+            &current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub transaction_buffer: Box<Account<'info, TransactionBuffer>>,
+    #[account(mut)]
+    pub zebec_eoa: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [
+            b"txn_status".as_ref(),
+            &sender,
+            &current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub txn_status: Account<'info, TransactionStatus>,
+    // Only the owner uploads, like it builds the transaction.
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = config.owner == zebec_eoa.key()
+    )]
+    pub config: Box<Account<'info, Config>>,
+}
+
+// Adds to or finalizes a transaction buffer.
+#[derive(Accounts)]
+#[instruction(
+    sender: [u8; 32],
+    current_count: u64
+)]
+pub struct AppendTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"transaction_buffer".as_ref(),
+            &sender,
+            &current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub transaction_buffer: Box<Account<'info, TransactionBuffer>>,
+    pub uploader: Signer<'info>,
 }
 
 #[derive(Accounts)]
@@ -141,7 +689,89 @@ pub struct InitializePDATokenAccount<'info> {
     current_count: u64
 )]
 pub struct CreateTransaction<'info> {
-    #[account(zero, signer)]
+    #[account(
+        init,
+        payer = zebec_eoa,
+        space = Transaction::space(accs.len(), data.len()),
+        seeds = [
+            b"transaction".as_ref(),
+            &sender,
+            &current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub transaction: Box<Account<'info, Transaction>>,
+    #[account(mut)]
+    pub zebec_eoa: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+   #[account(
+        mut,
+        seeds = [
+            b"data_store".as_ref(),
+            &sender, 
+            &current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub data_storage: Account<'info, TransactionData>,
+
+    #[account(
+        mut, 
+        seeds = [
+            b"txn_status".as_ref(),
+            &sender,
+            &current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub txn_status: Account<'info, TransactionStatus>,
+
+    #[account(
+        seeds = [
+            b"target_program".as_ref(),
+            data_storage.target_version.to_be_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub target_program: Account<'info, TargetProgram>,
+
+    // Read for its decimals, see `amount`
+    #[account(
+        address = data_storage.token_mint
+    )]
+    pub mint: Account<'info, Mint>,
+    // Only the owner builds transactions, so nobody else can create one at
+    // a message's seeds with accounts that make it fail.
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = config.owner == zebec_eoa.key()
+    )]
+    pub config: Box<Account<'info, Config>>,
+}
+
+// `CreateTransaction` for the sender withdraws and instant transfers the
+// governor limits, see `governor`.
+#[derive(Accounts)]
+#[instruction( 
+    accs: Vec<TransactionAccount>,
+    data: Vec<u8>,
+    sender: [u8; 32],
+    current_count: u64
+)]
+pub struct CreateOutboundTransaction<'info> {
+    #[account(
+        init,
+        payer = zebec_eoa,
+        space = Transaction::space(accs.len(), data.len()),
+        seeds = [
+            b"transaction".as_ref(),
+            &sender,
+            &current_count.to_be_bytes()
+        ],
+        bump
+    )]
     pub transaction: Box<Account<'info, Transaction>>,
     #[account(mut)]
     pub zebec_eoa: Signer<'info>,
@@ -168,6 +798,35 @@ pub struct CreateTransaction<'info> {
         bump
     )]
     pub txn_status: Account<'info, TransactionStatus>,
+
+    #[account(
+        seeds = [
+            b"target_program".as_ref(),
+            data_storage.target_version.to_be_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub target_program: Account<'info, TargetProgram>,
+
+    // Read for its decimals, see `amount`
+    #[account(
+        address = data_storage.token_mint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    ///CHECK: Solana's chain limit, which need not exist; checked by the governor
+    #[account(mut)]
+    pub chain_limit: UncheckedAccount<'info>,
+
+    ///CHECK: the mint's limit, which need not exist; checked by the governor
+    #[account(mut)]
+    pub mint_limit: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = config.owner == zebec_eoa.key()
+    )]
+    pub config: Box<Account<'info, Config>>,
 }
 
 #[derive(Accounts)]
@@ -179,7 +838,17 @@ pub struct CreateTransaction<'info> {
     current_count: u64
 )]
 pub struct CETransaction<'info> {
-    #[account(zero, signer)]
+    #[account(
+        init,
+        payer = zebec_eoa,
+        space = Transaction::space(accs.len(), data.len()),
+        seeds = [
+            b"transaction".as_ref(),
+            &sender,
+            &current_count.to_be_bytes()
+        ],
+        bump
+    )]
     pub transaction: Box<Account<'info, Transaction>>,
     
     #[account(mut)]
@@ -218,6 +887,27 @@ pub struct CETransaction<'info> {
         bump
     )]
     pub txn_status: Account<'info, TransactionStatus>,
+
+    #[account(
+        seeds = [
+            b"target_program".as_ref(),
+            data_storage.target_version.to_be_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub target_program: Account<'info, TargetProgram>,
+
+    // Read for its decimals, see `amount`
+    #[account(
+        address = data_storage.token_mint
+    )]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = config.owner == zebec_eoa.key()
+    )]
+    pub config: Box<Account<'info, Config>>,
 }
 
 #[derive(Accounts)]
@@ -226,7 +916,7 @@ pub struct CETransaction<'info> {
     chain_id: u16,
     current_count: u64,
 )]
-pub struct DirectTransferNative<'info> {
+pub struct DirectTransfer<'info> {
     
     #[account(mut)]
     pub zebec_eoa: Signer<'info>,
@@ -253,6 +943,17 @@ pub struct DirectTransferNative<'info> {
     )]
     pub txn_status: Account<'info, TransactionStatus>,
 
+    ///CHECK: the target chain's limit, which need not exist; checked by the governor
+    #[account(mut)]
+    pub chain_limit: UncheckedAccount<'info>,
+
+    ///CHECK: the mint's limit, which need not exist; checked by the governor
+    #[account(mut)]
+    pub mint_limit: UncheckedAccount<'info>,
+
+    ///CHECK: the mint's registry entry; checked in the handler
+    pub mint_registry: UncheckedAccount<'info>,
+
     ///CHECK: pda seeds checked
     #[account(
         mut,
@@ -264,7 +965,6 @@ pub struct DirectTransferNative<'info> {
     )]
     pub pda_signer: UncheckedAccount<'info>,
 
-    //Native Transfer
     #[account(
         mut,
         seeds = [b"config"],
@@ -289,7 +989,7 @@ pub struct DirectTransferNative<'info> {
     pub from: Box<Account<'info, TokenAccount>>,
 
     #[account(mut)]
-    /// CHECK: No need of data
+    /// CHECK: native or wrapped, told apart by wrapped_meta in the handler
     pub mint: AccountInfo<'info>,
 
     #[account(
@@ -317,6 +1017,17 @@ pub struct DirectTransferNative<'info> {
     /// CHECK: portal custody signer
     pub portal_custody_signer: AccountInfo<'info>,
 
+    #[account(
+        seeds = [
+            b"meta",
+            mint.key().as_ref()
+        ],
+        seeds::program = portal_bridge_program.key(),
+        bump,
+    )]
+    /// CHECK: the token bridge's meta of a wrapped mint; empty for a native one
+    pub wrapped_meta: AccountInfo<'info>,
+
     #[account(
         mut,
         seeds = [b"Bridge"],
@@ -377,13 +1088,10 @@ pub struct DirectTransferNative<'info> {
 #[derive(Accounts)]
 #[instruction( 
     sender: [u8; 32],
-    sender_chain: u16,
-    _token_address: Vec<u8>,
-    _token_chain: u16,
+    chain_id: u16,
     current_count: u64,
 )]
-pub struct DirectTransferWrapped<'info> {
-    
+pub struct WithdrawPayout<'info> {
     #[account(mut)]
     pub zebec_eoa: Signer<'info>,
 
@@ -396,10 +1104,9 @@ pub struct DirectTransferWrapped<'info> {
         ],
         bump
     )]
-    pub data_storage: Account<'info, TransactionData>,
+    pub data_storage: Box<Account<'info, TransactionData>>,
 
     #[account(
-        mut, 
         seeds = [
             b"txn_status".as_ref(),
             &sender,
@@ -411,176 +1118,494 @@ pub struct DirectTransferWrapped<'info> {
 
     ///CHECK: pda seeds checked
     #[account(
-        mut,
         seeds = [
             &sender,
-            &sender_chain.to_be_bytes()
+            &chain_id.to_be_bytes()
         ],
         bump
     )]
     pub pda_signer: UncheckedAccount<'info>,
 
-    //Wrapped Transfer
     #[account(
         mut,
-        seeds = [b"config"],
+        associated_token::mint = mint,
+        associated_token::authority = pda_signer,
+    )]
+    pub from: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        address = data_storage.token_mint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    ///CHECK: the wallet stored with the message, checked inside
+    pub wallet: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = zebec_eoa,
+        associated_token::mint = mint,
+        associated_token::authority = wallet,
+    )]
+    pub wallet_token_account: Box<Account<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction( 
+    accs: Vec<TransactionAccount>,
+    data: Vec<u8>,
+    sender: [u8; 32],
+    _current_count: u64
+)]
+pub struct CreateTransactionReceiver<'info> {
+    #[account(
+        init,
+        payer = zebec_eoa,
+        space = Transaction::space(accs.len(), data.len()),
+        seeds = [
+            b"transaction".as_ref(),
+            &sender,
+            &_current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub transaction: Box<Account<'info, Transaction>>,
+    
+    #[account(mut)]
+    pub zebec_eoa: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+   #[account(
+        mut,
+        seeds = [
+            b"data_store".as_ref(),
+            &sender, 
+            &_current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub data_storage: Account<'info, TransactionData>,
+
+    #[account(
+        mut, 
+        seeds = [
+            b"txn_status".as_ref(),
+            &sender,
+            &_current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub txn_status: Account<'info, TransactionStatus>,
+
+    #[account(
+        seeds = [
+            b"target_program".as_ref(),
+            data_storage.target_version.to_be_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub target_program: Account<'info, TargetProgram>,
+
+    // Read for its decimals, see `amount`
+    #[account(
+        address = data_storage.token_mint
+    )]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [b"config".as_ref()],
         bump,
+        constraint = config.owner == zebec_eoa.key()
     )]
-    pub config: Account<'info, Config>,
-        
+    pub config: Box<Account<'info, Config>>,
+}
+
+#[derive(Accounts)]
+#[instruction( 
+    accs: Vec<TransactionAccount>,
+    data: Vec<u8>,
+    sender: [u8; 32],
+    _current_count: u64
+)]
+pub struct CreateTransactionWithdrawBridge<'info> {
     #[account(
+        init,
+        payer = zebec_eoa,
+        space = Transaction::space(accs.len(), data.len()),
+        seeds = [
+            b"transaction".as_ref(),
+            &sender,
+            &_current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub transaction: Box<Account<'info, Transaction>>,
+    
+    #[account(mut)]
+    pub zebec_eoa: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+   #[account(
         mut,
-        constraint = from.owner == pda_signer.key(),
-        constraint = from.mint == wrapped_mint.key(),
+        seeds = [
+            b"data_store".as_ref(),
+            &sender, 
+            &_current_count.to_be_bytes()
+        ],
+        bump
     )]
-    pub from: Box<Account<'info, TokenAccount>>,
+    pub data_storage: Box<Account<'info, TransactionData>>,
+
+    #[account(
+        mut, 
+        seeds = [
+            b"txn_status".as_ref(),
+            &sender,
+            &_current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub txn_status: Account<'info, TransactionStatus>,
+
+    #[account(
+        seeds = [
+            b"target_program".as_ref(),
+            data_storage.target_version.to_be_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub target_program: Account<'info, TargetProgram>,
 
+    // Read for its decimals, see `amount`
+    #[account(
+        address = data_storage.token_mint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    // Where Zebec pays the withdrawal. Its balance now is left behind when
+    // the withdrawal is bridged.
+    #[account(
+        constraint = receiver_token_account.mint == data_storage.token_mint
+    )]
+    pub receiver_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = config.owner == zebec_eoa.key()
+    )]
+    pub config: Box<Account<'info, Config>>,
+}
+
+#[derive(Accounts)]
+#[instruction( 
+    accs: Vec<TransactionAccount>,
+    data: Vec<u8>,
+    sender: [u8; 32],
+    _current_count: u64
+)]
+pub struct CreateTransactionWithdrawPayout<'info> {
     #[account(
+        init,
+        payer = zebec_eoa,
+        space = Transaction::space(accs.len(), data.len()),
+        seeds = [
+            b"transaction".as_ref(),
+            &sender,
+            &_current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub transaction: Box<Account<'info, Transaction>>,
+    
+    #[account(mut)]
+    pub zebec_eoa: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+   #[account(
         mut,
-        seeds = [b"config"],
-        seeds::program = portal_bridge_program.key(),
+        seeds = [
+            b"data_store".as_ref(),
+            &sender, 
+            &_current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub data_storage: Box<Account<'info, TransactionData>>,
+
+    #[account(
+        mut, 
+        seeds = [
+            b"txn_status".as_ref(),
+            &sender,
+            &_current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub txn_status: Account<'info, TransactionStatus>,
+
+    #[account(
+        seeds = [
+            b"target_program".as_ref(),
+            data_storage.target_version.to_be_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub target_program: Account<'info, TargetProgram>,
+
+    // Read for its decimals, see `amount`
+    #[account(
+        address = data_storage.token_mint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    // Where Zebec pays the withdrawal, the receiver PDA's associated token
+    // account. Its balance now is left behind when the withdrawal is paid out.
+    #[account(
+        constraint = receiver_token_account.mint == data_storage.token_mint
+    )]
+    pub receiver_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [
+            b"payout_profile".as_ref(),
+            &sender,
+            &data_storage.from_chain_id.to_be_bytes()
+        ],
+        bump
+    )]
+    pub payout_profile: Account<'info, PayoutProfile>,
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = config.owner == zebec_eoa.key()
+    )]
+    pub config: Box<Account<'info, Config>>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    current_count: u64, 
+    sender: [u8; 32], 
+)]
+pub struct StoreMsg<'info>{
+
+    // // ZEBEC's EOA.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        init,
+        payer=payer,
+        space= PROCESSED_VAA_SPACE,
+        seeds=[
+            emitter_acc.emitter_addr.as_ref(),
+            emitter_acc.chain_id.to_be_bytes().as_ref(),
+            (PostedMessageData::try_from_slice(&core_bridge_vaa.data.borrow())?.0).sequence.to_be_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub processed_vaa: Account<'info, ProcessedVAA>,
+    pub emitter_acc: Account<'info, EmitterAccount>,
+    /// This requires some fancy hashing, so confirm it's derived address in the function itself.
+    #[account(
+        constraint = core_bridge_vaa.to_account_info().owner == &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
+    )]
+    /// CHECK: This account is owned by Core Bridge so we trust it
+    pub core_bridge_vaa: AccountInfo<'info>,
+
+    #[account(
+        init,
+        space = TRANSACTION_DATA_SPACE,
+        payer = payer,
+        seeds = [
+            b"data_store".as_ref(),
+            &sender, 
+            &current_count.to_be_bytes()
+        ],
         bump,
     )]
-    /// CHECK: portal config
-    pub portal_config: AccountInfo<'info>,
+    pub data_storage: Account<'info, TransactionData>,
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = payer, 
+        space = COUNT_SPACE,
         seeds = [
-            b"wrapped",
-            _token_chain.to_be_bytes().as_ref(),
-            _token_address.as_ref()
+            b"txn_count".as_ref(),
+            &sender,
         ],
-        seeds::program = portal_bridge_program.key(),
-        bump,
+        bump
     )]
-    /// CHECK: portal config
-    pub wrapped_mint: AccountInfo<'info>,
+    pub txn_count: Account<'info, Count>,
 
     #[account(
-        mut,
+        init, 
+        payer = payer,
+        space = TRANSACTION_STATUS_SPACE,
         seeds = [
-            b"meta",
-            wrapped_mint.key().as_ref()
+            b"txn_status".as_ref(),
+            &sender,
+            &current_count.to_be_bytes()
         ],
-        seeds::program = portal_bridge_program.key(),
-        bump,
+        bump
     )]
-    /// CHECK: portal config
-    pub wrapped_meta: AccountInfo<'info>,
+    pub txn_status: Account<'info, TransactionStatus>,
+
+    ///CHECK: the mint's registry entry; checked in the handler
+    pub mint_registry: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    action: SignedAction,
+    sender: [u8; 32],
+    chain_id: u16,
+    nonce: u64,
+    current_count: u64,
+)]
+pub struct StoreSignedMsg<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
 
     #[account(
-        seeds = [b"authority_signer"],
-        seeds::program = portal_bridge_program.key(),
+        seeds=[b"EmitterAddress".as_ref(), chain_id.to_be_bytes().as_ref()],
         bump
     )]
-    /// CHECK: portal authority signer
-    pub portal_authority_signer: AccountInfo<'info>,
+    pub emitter_acc: Account<'info, EmitterAccount>,
 
     #[account(
-        mut,
-        seeds = [b"Bridge"],
-        seeds::program = core_bridge_program.key(),
+        init_if_needed,
+        payer = payer,
+        space = META_NONCE_SPACE,
+        seeds = [
+            b"meta_nonce".as_ref(),
+            &sender,
+            &chain_id.to_be_bytes()
+        ],
         bump
     )]
-    /// CHECK: bridge config
-    pub bridge_config: AccountInfo<'info>,
+    pub meta_nonce: Account<'info, MetaNonce>,
 
-    #[account(
-        mut,
-        signer
-    )]
-    /// CHECK: portal message
-    pub portal_message: AccountInfo<'info>,
+    ///CHECK: the signature is read from it in the handler
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
 
     #[account(
-        mut,
-        seeds = [b"emitter"],
-        seeds::program = portal_bridge_program.key(),
-        bump
+        init,
+        space = TRANSACTION_DATA_SPACE,
+        payer = payer,
+        seeds = [
+            b"data_store".as_ref(),
+            &sender,
+            &current_count.to_be_bytes()
+        ],
+        bump,
     )]
-    /// CHECK: portal emitter
-    pub portal_emitter: AccountInfo<'info>,
+    pub data_storage: Account<'info, TransactionData>,
 
     #[account(
-        mut,
-        seeds = [b"Sequence", portal_emitter.key().as_ref()],
-        seeds::program = core_bridge_program.key(),
+        init_if_needed,
+        payer = payer,
+        space = COUNT_SPACE,
+        seeds = [
+            b"txn_count".as_ref(),
+            &sender,
+        ],
         bump
     )]
-    /// CHECK: portal sequence
-    pub portal_sequence: AccountInfo<'info>,
+    pub txn_count: Account<'info, Count>,
 
     #[account(
-        mut,
-        seeds = [b"fee_collector"],
-        seeds::program = core_bridge_program.key(),
+        init,
+        payer = payer,
+        space = TRANSACTION_STATUS_SPACE,
+        seeds = [
+            b"txn_status".as_ref(),
+            &sender,
+            &current_count.to_be_bytes()
+        ],
         bump
     )]
-    /// CHECK: bridge fee collector
-    pub bridge_fee_collector: AccountInfo<'info>,
-
-    pub clock: Sysvar<'info, Clock>,
-
-    pub rent: Sysvar<'info, Rent>,
-
-    pub system_program: Program<'info, System>,
-
-    pub portal_bridge_program: Program<'info, TokenPortalBridge>,
-
-    pub core_bridge_program: Program<'info, WormholeCoreBridge>,
-
-    pub token_program: Program<'info, Token>
+    pub txn_status: Account<'info, TransactionStatus>,
 
+    ///CHECK: the mint's registry entry; checked in the handler
+    pub mint_registry: UncheckedAccount<'info>,
 }
 
-
 #[derive(Accounts)]
-#[instruction( 
-    accs: Vec<TransactionAccount>,
-    data: Vec<u8>,
-    sender: [u8; 32],
+#[instruction(  
+    eth_add:[u8; 32],
+    from_chain_id: u16,
     _current_count: u64
 )]
-pub struct CreateTransactionReceiver<'info> {
-    #[account(zero, signer)]
-    pub transaction: Box<Account<'info, Transaction>>,
-    
-    #[account(mut)]
-    pub zebec_eoa: Signer<'info>,
+pub struct ExecuteTransaction<'info> {
     pub system_program: Program<'info, System>,
-
-   #[account(
+    ///CHECK: seeds are checked while creating transaction,
+    /// if different seeds passed the signature will not match
+    #[account(
         mut,
         seeds = [
-            b"data_store".as_ref(),
-            &sender, 
+            &eth_add,
+            &from_chain_id.to_be_bytes()
+        ],
+        bump
+    )]
+    pub pda_signer: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"transaction".as_ref(),
+            &eth_add,
             &_current_count.to_be_bytes()
         ],
         bump
     )]
-    pub data_storage: Account<'info, TransactionData>,
+    pub transaction: Box<Account<'info, Transaction>>,
 
     #[account(
         mut, 
         seeds = [
             b"txn_status".as_ref(),
-            &sender,
+            &eth_add,
             &_current_count.to_be_bytes()
         ],
         bump
     )]
     pub txn_status: Account<'info, TransactionStatus>,
+
+    // Read for a transfer the governor holds.
+    #[account(
+        seeds = [
+            b"data_store".as_ref(),
+            &eth_add,
+            &_current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub data_storage: Account<'info, TransactionData>,
+
+    // Any registered entry; perform_cpi checks it is the transaction's program.
+    pub target_program: Account<'info, TargetProgram>,
+}
+
+// The steps' accounts are remaining accounts; each step checks its own.
+#[derive(Accounts)]
+pub struct Relay<'info> {
+    pub payer: Signer<'info>,
 }
 
 #[derive(Accounts)]
 #[instruction(
-    current_count: u64, 
-    sender: [u8; 32], 
+    current_count: u64,
+    sender: [u8; 32]
 )]
-pub struct StoreMsg<'info>{
-
-    // // ZEBEC's EOA.
+pub struct StoreBatchMsg<'info>{
     #[account(mut)]
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -588,16 +1613,16 @@ pub struct StoreMsg<'info>{
     #[account(
         init,
         payer=payer,
-        space= 8 + 8,
+        space= PROCESSED_VAA_SPACE,
         seeds=[
-            &decode(&emitter_acc.emitter_addr.as_str()).unwrap()[..],
+            emitter_acc.emitter_addr.as_ref(),
             emitter_acc.chain_id.to_be_bytes().as_ref(),
             (PostedMessageData::try_from_slice(&core_bridge_vaa.data.borrow())?.0).sequence.to_be_bytes().as_ref()
         ],
         bump,
     )]
     pub processed_vaa: Account<'info, ProcessedVAA>,
-    pub emitter_acc: Account<'info, EmitterAddrAccount>,
+    pub emitter_acc: Account<'info, EmitterAccount>,
     /// This requires some fancy hashing, so confirm it's derived address in the function itself.
     #[account(
         constraint = core_bridge_vaa.to_account_info().owner == &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
@@ -607,21 +1632,22 @@ pub struct StoreMsg<'info>{
 
     #[account(
         init,
-        space = 8 + 156,
+        space = BATCH_DATA_SPACE,
         payer = payer,
         seeds = [
-            b"data_store".as_ref(),
-            &sender, 
+            b"batch_store".as_ref(),
+            &sender,
             &current_count.to_be_bytes()
         ],
         bump,
     )]
-    pub data_storage: Account<'info, TransactionData>,
+    pub batch_storage: Box<Account<'info, BatchData>>,
 
+    // Shared with `store_msg`, so batches and single messages take turns.
     #[account(
         init_if_needed,
-        payer = payer, 
-        space = 8 + 8,
+        payer = payer,
+        space = COUNT_SPACE,
         seeds = [
             b"txn_count".as_ref(),
             &sender,
@@ -630,27 +1656,77 @@ pub struct StoreMsg<'info>{
     )]
     pub txn_count: Account<'info, Count>,
 
+    ///CHECK: the mint's registry entry; checked in the handler
+    pub mint_registry: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    accs: Vec<TransactionAccount>,
+    data: Vec<u8>,
+    sender: [u8; 32],
+    current_count: u64,
+    index: u8
+)]
+pub struct CreateBatchTransaction<'info> {
     #[account(
-        init, 
-        payer = payer,
-        space = 8 + 1,
+        init,
+        payer = zebec_eoa,
+        space = Transaction::space(accs.len(), data.len()),
         seeds = [
-            b"txn_status".as_ref(),
+            b"transaction".as_ref(),
+            &sender,
+            &current_count.to_be_bytes(),
+            index.to_be_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub transaction: Box<Account<'info, Transaction>>,
+    #[account(mut)]
+    pub zebec_eoa: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"batch_store".as_ref(),
             &sender,
             &current_count.to_be_bytes()
         ],
         bump
     )]
-    pub txn_status: Account<'info, TransactionStatus>,
+    pub batch_storage: Box<Account<'info, BatchData>>,
+
+    #[account(
+        seeds = [
+            b"target_program".as_ref(),
+            batch_storage.target_version.to_be_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub target_program: Account<'info, TargetProgram>,
+
+    // Read for its decimals, see `amount`
+    #[account(
+        address = batch_storage.token_mint
+    )]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = config.owner == zebec_eoa.key()
+    )]
+    pub config: Box<Account<'info, Config>>,
 }
 
 #[derive(Accounts)]
-#[instruction(  
-    eth_add:[u8; 32],
+#[instruction(
+    eth_add: [u8; 32],
     from_chain_id: u16,
-    _current_count: u64
+    current_count: u64,
+    index: u8
 )]
-pub struct ExecuteTransaction<'info> {
+pub struct ExecuteBatchTransaction<'info> {
     pub system_program: Program<'info, System>,
     ///CHECK: seeds are checked while creating transaction,
     /// if different seeds passed the signature will not match
@@ -663,17 +1739,54 @@ pub struct ExecuteTransaction<'info> {
         bump
     )]
     pub pda_signer: UncheckedAccount<'info>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [
+            b"transaction".as_ref(),
+            &eth_add,
+            &current_count.to_be_bytes(),
+            index.to_be_bytes().as_ref()
+        ],
+        bump
+    )]
     pub transaction: Box<Account<'info, Transaction>>,
 
     #[account(
-        mut, 
+        mut,
         seeds = [
-            b"txn_status".as_ref(),
+            b"batch_store".as_ref(),
             &eth_add,
-            &_current_count.to_be_bytes()
+            &current_count.to_be_bytes()
         ],
         bump
     )]
-    pub txn_status: Account<'info, TransactionStatus>,
+    pub batch_storage: Box<Account<'info, BatchData>>,
+
+    // Any registered entry; perform_cpi checks it is the transaction's program.
+    pub target_program: Account<'info, TargetProgram>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    sender: [u8; 32],
+    current_count: u64
+)]
+pub struct FailBatchEntry<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"batch_store".as_ref(),
+            &sender,
+            &current_count.to_be_bytes()
+        ],
+        bump
+    )]
+    pub batch_storage: Box<Account<'info, BatchData>>,
 }
@@ -79,4 +79,160 @@ pub enum MessengerError {
 
     #[msg("Invalid Count")]
     InvalidCount,
+
+    #[msg("Invalid Payload Length")]
+    InvalidPayloadLength,
+
+    #[msg("Unregistered Target Program")]
+    UnregisteredTargetProgram,
+
+    #[msg("Emitter Disabled")]
+    EmitterDisabled,
+
+    #[msg("Invalid Emitter Label")]
+    InvalidEmitterLabel,
+
+    #[msg("Emitter Already Migrated")]
+    EmitterAlreadyMigrated,
+
+    #[msg("Account Already Migrated")]
+    AccountAlreadyMigrated,
+
+    #[msg("Unversioned Account")]
+    UnversionedAccount,
+
+    #[msg("Invalid Sender Address")]
+    InvalidSenderAddress,
+
+    #[msg("Amount Would Truncate")]
+    AmountWouldTruncate,
+
+    #[msg("Invalid Batch Size")]
+    InvalidBatchSize,
+
+    #[msg("Invalid Batch Entry")]
+    InvalidBatchEntry,
+
+    #[msg("Batch Entry Not Pending")]
+    BatchEntryNotPending,
+
+    #[msg("Batch Entry Not Created")]
+    BatchEntryNotCreated,
+
+    #[msg("Batch Entry Already Finished")]
+    BatchEntryFinished,
+
+    #[msg("Batch Transaction Mismatch")]
+    BatchTransactionMismatch,
+
+    #[msg("Bridge Back Mismatch")]
+    BridgeBackMismatch,
+
+    #[msg("Transaction Not Executed")]
+    TransactionNotExecuted,
+
+    #[msg("Withdrawal Already Bridged")]
+    AlreadyBridged,
+
+    #[msg("Nothing Withdrawn")]
+    NothingWithdrawn,
+
+    #[msg("Fee Exceeds Amount")]
+    FeeExceedsAmount,
+
+    #[msg("Invalid Receiver Token Account")]
+    ReceiverTokenAccountMismatch,
+
+    #[msg("Payout Mismatch")]
+    PayoutMismatch,
+
+    #[msg("No Payout Wallet Linked")]
+    NoPayoutWallet,
+
+    #[msg("Stale Payout Wallet")]
+    StalePayoutWallet,
+
+    #[msg("Withdrawal Already Paid Out")]
+    AlreadyPaidOut,
+
+    #[msg("Invalid Signature Instruction")]
+    InvalidSignatureInstruction,
+
+    #[msg("Signature Mismatch")]
+    SignatureMismatch,
+
+    #[msg("Meta Transaction Nonce Mismatch")]
+    MetaNonceMismatch,
+
+    #[msg("Rate Limit Exceeded")]
+    RateLimitExceeded,
+
+    #[msg("Rate Limit Account Mismatch")]
+    RateLimitAccountMismatch,
+
+    #[msg("Mint Not Priced")]
+    UnpricedMint,
+
+    #[msg("Transfer Held")]
+    TransferHeld,
+
+    #[msg("Account Not Migrated")]
+    AccountNotMigrated,
+
+    #[msg("Mint Not Allowed")]
+    MintNotAllowed,
+
+    #[msg("Amount Out Of Bounds")]
+    AmountOutOfBounds,
+
+    #[msg("Mint Origin Mismatch")]
+    MintOriginMismatch,
+
+    #[msg("Target Chain Mismatch")]
+    TargetChainMismatch,
+
+    #[msg("Fee Exceeds Maximum")]
+    FeeExceedsMaximum,
+
+    #[msg("Wrapped Meta Mismatch")]
+    WrappedMetaMismatch,
+
+    #[msg("Invalid Relay Step")]
+    InvalidRelayStep,
+
+    #[msg("Unbound Transaction")]
+    UnboundTransaction,
+
+    #[msg("Transaction Message Mismatch")]
+    TransactionMessageMismatch,
+
+    #[msg("Transaction Digest Mismatch")]
+    TransactionDigestMismatch,
+
+    #[msg("Transaction Too Large")]
+    TransactionTooLarge,
+
+    #[msg("Transaction Buffer Mismatch")]
+    TransactionBufferMismatch,
+
+    #[msg("Transaction Buffer Finalized")]
+    TransactionBufferFinalized,
+
+    #[msg("Transaction Incomplete")]
+    TransactionIncomplete,
+
+    #[msg("Transaction Not Finalized")]
+    TransactionNotFinalized,
+
+    #[msg("Governance Not Set")]
+    GovernanceNotSet,
+
+    #[msg("Governance Emitter Mismatch")]
+    GovernanceEmitterMismatch,
+
+    #[msg("Governance Action Mismatch")]
+    GovernanceActionMismatch,
+
+    #[msg("Message Code Mismatch")]
+    MessageCodeMismatch,
 }
\ No newline at end of file
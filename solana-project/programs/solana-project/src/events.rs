@@ -1,5 +1,38 @@
 use anchor_lang::prelude::*;
 
+use crate::state::AddressFormat;
+
+// Version of the message event layouts below. Fields are only ever appended
+// to an event, and every time one is, this is bumped. A consumer reads the
+// fields it knows and uses `version` to tell whether more follow.
+pub const EVENT_VERSION: u8 = 3;
+
+// Everything a stored message asks for, so indexers need not fetch the
+// `data_store` account, which may be gone by then.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MessageDetails {
+    pub code: u8,
+    pub from_chain: u16,
+    pub vaa_sequence: u64,
+    pub receiver: [u8; 32],
+    pub mint: Pubkey,
+    // As the payload carries it, in the mint's base units.
+    pub amount: u64,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub data_account: Pubkey,
+    // Zebec, or the token bridge for direct transfers. Default when the
+    // program is not known yet.
+    pub target_program: Pubkey,
+    // The relayer that signed the instruction.
+    pub relayer: Pubkey,
+    // Registry entry the message selects. Since version 2.
+    pub target_version: u8,
+    // `amount` as the token bridge carries it, with at most 8 decimals. None
+    // in `StoredMsg`, which does not read the mint. Since version 3.
+    pub normalized_amount: Option<u64>,
+}
+
 #[event]
 pub struct InitializedPDA {
     pub pda: Pubkey,
@@ -11,6 +44,16 @@ pub struct InitializedPDATokenAccount {
     pub token_mint: Pubkey,
 }
 
+// A set payout wallet (22) message linked `wallet`, or unlinked it when
+// `wallet` is the default key.
+#[event]
+pub struct PayoutWalletSet {
+    pub identity: [u8; 32],
+    pub chain_id: u16,
+    pub wallet: Pubkey,
+    pub vaa_sequence: u64,
+}
+
 #[event]
 pub struct Initialized {
     pub owner: Pubkey,
@@ -20,7 +63,94 @@ pub struct Initialized {
 #[event]
 pub struct RegisteredChain {
     pub chain_id: u16,
+    // Hex, as before emitter addresses were stored as bytes.
     pub emitter_addr: String,
+    pub label: String,
+    pub address_format: AddressFormat,
+    pub emitter_address: [u8; 32],
+}
+
+#[event]
+pub struct UpdatedChain {
+    pub chain_id: u16,
+    pub enabled: bool,
+    pub label: String,
+}
+
+#[event]
+pub struct ChainLimitSet {
+    pub chain_id: u16,
+    pub daily_limit: u64,
+}
+
+#[event]
+pub struct MintLimitSet {
+    pub mint: Pubkey,
+    pub daily_limit: u64,
+    pub notional_price: u64,
+    pub large_transfer_threshold: u64,
+    pub hold_seconds: u32,
+}
+
+#[event]
+pub struct RegisteredMint {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub min_amount: u64,
+    pub max_amount: u64,
+    pub wrapped: bool,
+    pub origin_chain: u16,
+    pub origin_address: [u8; 32],
+}
+
+#[event]
+pub struct RemovedMint {
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct MigratedChain {
+    pub chain_id: u16,
+    pub emitter_addr: [u8; 32],
+}
+
+#[event]
+pub struct MigratedAccount {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct RegisteredTargetProgram {
+    pub version: u8,
+    pub program_id: Pubkey,
+}
+
+#[event]
+pub struct RemovedTargetProgram {
+    pub version: u8,
+    pub program_id: Pubkey,
+}
+
+// Chain 0 and the zero address when governance was turned off.
+#[event]
+pub struct GovernanceSet {
+    pub chain_id: u16,
+    pub emitter_address: [u8; 32],
+}
+
+#[event]
+pub struct OwnerSet {
+    pub previous_owner: Pubkey,
+    pub owner: Pubkey,
+}
+
+// A governance VAA was applied. The event of the change itself follows.
+#[event]
+pub struct Governed {
+    pub code: u8,
+    pub vaa_sequence: u64,
 }
 
 #[event]
@@ -28,24 +158,44 @@ pub struct StoredMsg {
     pub msg_type: u64,
     pub sender: [u8; 32],
     pub count: u64,
+    pub version: u8,
+    pub message: MessageDetails,
+}
+
+// A message an EVM account signed instead of sending it through Wormhole.
+#[event]
+pub struct StoredSignedMsg {
+    pub msg_type: u64,
+    pub sender: [u8; 32],
+    pub from_chain: u16,
+    pub count: u64,
+    pub nonce: u64,
+    pub version: u8,
+    pub message: MessageDetails,
 }
 
 #[event]
 pub struct Deposited {
     pub sender: [u8; 32],
     pub current_count: u64,
+    pub version: u8,
+    pub message: MessageDetails,
 }
 
 #[event]
 pub struct StreamUpdated {
     pub sender: [u8; 32],
     pub current_count: u64,
+    pub version: u8,
+    pub message: MessageDetails,
 }
 
 #[event]
 pub struct PausedResumed {
     pub sender: [u8; 32],
     pub current_count: u64,
+    pub version: u8,
+    pub message: MessageDetails,
 }
 
 #[event]
@@ -54,8 +204,9 @@ pub struct DirectTransferredNative {
     pub sender_chain: u16,
     pub target_chain: u16,
     pub receiver: [u8; 32],
-    pub current_count: u64
-
+    pub current_count: u64,
+    pub version: u8,
+    pub message: MessageDetails,
 }
 
 #[event]
@@ -65,36 +216,106 @@ pub struct DirectTransferredWrapped {
     pub target_chain: u16,
     pub receiver: [u8; 32],
     pub current_count: u64,
+    pub version: u8,
+    pub message: MessageDetails,
 }
 
 #[event]
 pub struct StreamCreated {
     pub sender: [u8; 32],
     pub current_count: u64,
+    pub version: u8,
+    pub message: MessageDetails,
 }
 
 #[event]
 pub struct CancelCreated {
     pub sender: [u8; 32],
     pub current_count: u64,
+    pub version: u8,
+    pub message: MessageDetails,
 }
 
 #[event]
 pub struct SenderWithdrawCreated {
     pub sender: [u8; 32],
     pub current_count: u64,
+    pub version: u8,
+    pub message: MessageDetails,
 }
 
 #[event]
 pub struct InstantTransferCreated {
     pub sender: [u8; 32],
     pub current_count: u64,
+    pub version: u8,
+    pub message: MessageDetails,
 }
 
 #[event]
 pub struct ReceiverWithdrawCreated {
     pub sender: [u8; 32],
     pub current_count: u64,
+    pub version: u8,
+    pub message: MessageDetails,
+}
+
+#[event]
+pub struct WithdrawBridgeCreated {
+    pub sender: [u8; 32],
+    pub current_count: u64,
+    pub version: u8,
+    pub message: MessageDetails,
+}
+
+// A withdraw stream and bridge (21) message sent its withdrawal back.
+// `amount` and `fee` are in the mint's base units.
+#[event]
+pub struct WithdrawalBridged {
+    pub receiver: [u8; 32],
+    pub sender_chain: u16,
+    pub target_chain: u16,
+    pub current_count: u64,
+    pub amount: u64,
+    pub fee: u64,
+    pub wrapped: bool,
+    pub version: u8,
+    pub message: MessageDetails,
+}
+
+// The governor held a large transfer of a stored message until
+// `release_at`. `amount` is in the mint's base units.
+#[event]
+pub struct TransferHeld {
+    pub sender: [u8; 32],
+    pub current_count: u64,
+    pub target_chain: u16,
+    pub amount: u64,
+    pub release_at: i64,
+    pub version: u8,
+    pub message: MessageDetails,
+}
+
+#[event]
+pub struct WithdrawPayoutCreated {
+    pub sender: [u8; 32],
+    pub current_count: u64,
+    pub wallet: Pubkey,
+    pub version: u8,
+    pub message: MessageDetails,
+}
+
+// A withdraw stream payout (23) message paid its withdrawal to the
+// receiver's wallet. `amount` is in the mint's base units.
+#[event]
+pub struct WithdrawalPaidOut {
+    pub receiver: [u8; 32],
+    pub sender_chain: u16,
+    pub current_count: u64,
+    pub wallet: Pubkey,
+    pub amount: u64,
+    pub version: u8,
+    pub message: MessageDetails,
 }
 
 #[event]
@@ -102,4 +323,63 @@ pub struct ExecutedTransaction {
     pub from_chain_id: u16,
     pub eth_add: [u8; 32],
     pub transaction: Pubkey,
+    pub version: u8,
+    pub current_count: u64,
+    pub target_program: Pubkey,
+}
+
+#[event]
+pub struct StoredBatch {
+    pub sender: [u8; 32],
+    pub count: u64,
+    pub version: u8,
+    pub from_chain: u16,
+    pub vaa_sequence: u64,
+    pub mint: Pubkey,
+    pub entries: u8,
+    pub target_version: u8,
+    pub relayer: Pubkey,
+}
+
+#[event]
+pub struct BatchEntryCreated {
+    pub sender: [u8; 32],
+    pub current_count: u64,
+    pub index: u8,
+    pub version: u8,
+    pub message: MessageDetails,
+}
+
+// `finished` is set once every entry of the batch is executed or failed.
+#[event]
+pub struct BatchEntryExecuted {
+    pub sender: [u8; 32],
+    pub current_count: u64,
+    pub index: u8,
+    pub version: u8,
+    pub transaction: Pubkey,
+    pub target_program: Pubkey,
+    pub finished: bool,
+}
+
+#[event]
+pub struct BatchEntryFailed {
+    pub sender: [u8; 32],
+    pub current_count: u64,
+    pub index: u8,
+    pub version: u8,
+    pub error_code: u32,
+    pub finished: bool,
+}
+
+// Emitted right before a message is rejected while it is stored, so it only
+// shows up in the logs of the failed transaction. `error_code` is the Anchor error
+// number, or the raw `ProgramError` value for runtime errors.
+#[event]
+pub struct MessageFailed {
+    pub sender: [u8; 32],
+    pub current_count: u64,
+    pub version: u8,
+    pub from_chain: u16,
+    pub error_code: u64,
 }
@@ -0,0 +1,94 @@
+//! Admin changes made by Wormhole VAAs.
+//!
+//! Next to the config owner, the config can name a governance emitter, a
+//! chain and address, typically a DAO on an EVM chain. The owner sets it with
+//! `set_governance`. A VAA the emitter posts carries one `GovernanceAction`
+//! and is applied by the `govern_*` instruction of that action, which anyone
+//! may call. Each VAA is claimed with a `ProcessedVAA` at [emitter address,
+//! emitter chain, sequence], so it applies once.
+//!
+//! The payload is code, the proxy's program id and the action's fields, big
+//! endian like message payloads:
+//!
+//! - 30 register chain: chain (2), emitter (32), address format (1), label
+//!   (the rest, at most 32 bytes of UTF-8)
+//! - 31 update chain: chain (2), enabled (1)
+//! - 32 set chain limit: chain (2), daily limit (8)
+//! - 33 set mint limit: mint (32), daily limit (8), notional price (8),
+//!   large transfer threshold (8), hold seconds (4)
+//! - 34 register target program: version (1), program id (32)
+//! - 35 remove target program: version (1)
+//! - 36 set owner: owner (32)
+
+use anchor_lang::prelude::*;
+
+use crate::errors::MessengerError;
+use crate::state::{AddressFormat, Governance};
+use crate::wormhole::MessageData;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GovernanceAction {
+    RegisterChain {
+        chain_id: u16,
+        emitter_addr: [u8; 32],
+        address_format: AddressFormat,
+        label: String,
+    },
+    UpdateChain {
+        chain_id: u16,
+        enabled: bool,
+    },
+    SetChainLimit {
+        chain_id: u16,
+        daily_limit: u64,
+    },
+    SetMintLimit {
+        mint: Pubkey,
+        daily_limit: u64,
+        notional_price: u64,
+        large_transfer_threshold: u64,
+        hold_seconds: u32,
+    },
+    RegisterTargetProgram {
+        version: u8,
+        program_id: Pubkey,
+    },
+    RemoveTargetProgram {
+        version: u8,
+    },
+    SetOwner {
+        owner: Pubkey,
+    },
+}
+
+impl GovernanceAction {
+    pub fn code(&self) -> u8 {
+        match self {
+            GovernanceAction::RegisterChain { .. } => 30,
+            GovernanceAction::UpdateChain { .. } => 31,
+            GovernanceAction::SetChainLimit { .. } => 32,
+            GovernanceAction::SetMintLimit { .. } => 33,
+            GovernanceAction::RegisterTargetProgram { .. } => 34,
+            GovernanceAction::RemoveTargetProgram { .. } => 35,
+            GovernanceAction::SetOwner { .. } => 36,
+        }
+    }
+}
+
+/// Checks a posted VAA came from the governance emitter of the config.
+#[allow(clippy::result_large_err)]
+pub fn check_governance_emitter(governance: Governance, vaa: &MessageData) -> Result<()> {
+    match governance.resolve() {
+        Governance::Emitter {
+            chain_id,
+            emitter_address,
+        } => {
+            require!(
+                vaa.emitter_chain == chain_id && vaa.emitter_address == emitter_address,
+                MessengerError::GovernanceEmitterMismatch
+            );
+            Ok(())
+        }
+        _ => err!(MessengerError::GovernanceNotSet),
+    }
+}
@@ -0,0 +1,122 @@
+//! Rate limits on what leaves through the proxy.
+//!
+//! The config owner can cap what goes out over a rolling 24 hours, per
+//! destination chain with a `ChainLimit` at ["chain_limit", chain_id] and
+//! per mint with a `MintLimit` at ["mint_limit", mint]. A mint limit counts
+//! the mint's base units; a chain limit counts notional units, valuing each
+//! mint at its mint limit's price. Without the accounts nothing is limited.
+//!
+//! Direct transfers, withdrawals bridged back, sender withdraws and instant
+//! transfers are cleared once. A transfer of at least the mint's large
+//! transfer threshold does not count against the limits; it is held for the
+//! mint's hold time instead, and the relayer sends it on after that. Sender
+//! withdraws and instant transfers stay on Solana, so they count against the
+//! limit of chain 1.
+
+use anchor_lang::prelude::*;
+
+use crate::errors::MessengerError;
+use crate::state::{ChainLimit, Clearance, MintLimit, TransactionData};
+
+pub fn chain_limit_address(chain_id: u16, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"chain_limit", &chain_id.to_be_bytes()], program_id).0
+}
+
+pub fn mint_limit_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"mint_limit", mint.as_ref()], program_id).0
+}
+
+// A limit account passed unchecked, or `None` if it was never set.
+#[allow(clippy::result_large_err)]
+fn load_limit<'info, T>(
+    info: &AccountInfo<'info>,
+    program_id: &Pubkey,
+) -> Result<Option<Account<'info, T>>>
+where
+    T: AccountSerialize + AccountDeserialize + Owner + Clone,
+{
+    if info.owner != program_id {
+        return Ok(None);
+    }
+    Account::try_from(info).map(Some)
+}
+
+/// Value of `amount` base units of a mint with `decimals` at `price` per
+/// whole token.
+#[allow(clippy::result_large_err)]
+pub fn notional_value(amount: u64, decimals: u8, price: u64) -> Result<u64> {
+    10u128
+        .checked_pow(decimals as u32)
+        .map(|scale| amount as u128 * price as u128 / scale)
+        .and_then(|value| u64::try_from(value).ok())
+        .ok_or_else(|| error!(MessengerError::Overflow))
+}
+
+/// Runs a transfer of `amount` base units of the stored message's mint to
+/// `target_chain` through the limits, once. Returns whether it can go out
+/// now; `false` means it was just held, and `clearance` says until when.
+/// Fails while a held transfer still has to wait, or if the transfer does
+/// not fit the limits.
+#[allow(clippy::result_large_err)]
+pub fn clear<'info>(
+    data_storage: &mut TransactionData,
+    chain_limit: &AccountInfo<'info>,
+    mint_limit: &AccountInfo<'info>,
+    target_chain: u16,
+    amount: u64,
+    decimals: u8,
+    program_id: &Pubkey,
+) -> Result<bool> {
+    require!(
+        chain_limit.key() == chain_limit_address(target_chain, program_id)
+            && mint_limit.key() == mint_limit_address(&data_storage.token_mint, program_id),
+        MessengerError::RateLimitAccountMismatch
+    );
+    let now = Clock::get()?.unix_timestamp;
+    match data_storage.clearance.resolve() {
+        Clearance::Counted => return Ok(true),
+        Clearance::Held { release_at } => {
+            require!(now >= release_at, MessengerError::TransferHeld);
+            return Ok(true);
+        }
+        _ => {}
+    }
+
+    let mut chain_limit = load_limit::<ChainLimit>(chain_limit, program_id)?;
+    let mut mint_limit = load_limit::<MintLimit>(mint_limit, program_id)?;
+    if chain_limit.is_none() && mint_limit.is_none() {
+        return Ok(true);
+    }
+    // Older stored messages have no room to keep the outcome in.
+    require!(
+        data_storage.clearance != Clearance::Unspecified,
+        MessengerError::AccountNotMigrated
+    );
+
+    if let Some(mint_limit) = mint_limit.as_deref() {
+        if amount >= mint_limit.large_transfer_threshold {
+            let release_at = now + mint_limit.hold_seconds as i64;
+            data_storage.clearance = Clearance::Held { release_at };
+            return Ok(release_at <= now);
+        }
+    }
+    if let Some(mint_limit) = mint_limit.as_mut() {
+        let daily_limit = mint_limit.daily_limit;
+        mint_limit.window.add(now, amount, daily_limit)?;
+        mint_limit.exit(program_id)?;
+    }
+    if let Some(chain_limit) = chain_limit.as_mut() {
+        let price = mint_limit
+            .as_deref()
+            .map(|mint_limit| mint_limit.notional_price)
+            .filter(|price| *price > 0)
+            .ok_or(MessengerError::UnpricedMint)?;
+        let daily_limit = chain_limit.daily_limit;
+        chain_limit
+            .window
+            .add(now, notional_value(amount, decimals, price)?, daily_limit)?;
+        chain_limit.exit(program_id)?;
+    }
+    data_storage.clearance = Clearance::Counted;
+    Ok(true)
+}
@@ -1,12 +1,15 @@
+
 use anchor_lang::prelude::*;
 use anchor_lang::system_program::{transfer as transfer_sol, Transfer as TransferSol};
 
 use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::Discriminator;
 
 use anchor_lang::solana_program;
 
 
-use anchor_spl::token::{approve, Approve};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::{approve, transfer, Approve, TokenAccount, Transfer};
 
 use primitive_types::U256;
 use sha3::Digest;
@@ -14,23 +17,39 @@ use sha3::Digest;
 use std::collections::BTreeMap;
 
 use byteorder::{BigEndian, WriteBytesExt};
-use hex::decode;
+use hex::{decode, encode};
 use std::io::{Cursor, Write};
 use std::str::FromStr;
-mod constants;
+pub mod allowlist;
+pub mod amount;
+pub mod constants;
 mod context;
-mod errors;
-mod events;
-mod portal;
-mod state;
-mod wormhole;
-
+pub mod errors;
+pub mod events;
+pub mod governance;
+pub mod governor;
+pub mod meta;
+pub mod portal;
+pub mod relay;
+pub mod staging;
+pub mod state;
+pub mod versioning;
+pub mod wormhole;
+
+use allowlist::*;
+use amount::*;
 use constants::*;
 use context::*;
 use errors::*;
 use events::*;
+use governance::*;
+use governor::*;
+use meta::*;
 use portal::*;
+use relay::*;
+use staging::*;
 use state::*;
+use versioning::*;
 use wormhole::*;
 
 use std::ops::Deref;
@@ -39,6 +58,9 @@ use anchor_lang::solana_program::program::invoke_signed;
 
 declare_id!("2w1QMXDTsWCmpdPHfjrL9SbkKeh3K37WMqJQUfnXHzRh");
 
+// Anchor fixes `anchor_lang::error::Error` as the error of every
+// instruction. Helpers allowed the same way return it through them.
+#[allow(clippy::result_large_err)]
 #[program]
 pub mod solana_project {
 
@@ -47,6 +69,7 @@ pub mod solana_project {
     pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
         ctx.accounts.config.owner = ctx.accounts.owner.key();
         ctx.accounts.config.nonce = 1;
+        stamp(&mut ctx.accounts.config);
 
         emit!(Initialized {
             owner: ctx.accounts.config.owner,
@@ -58,23 +81,414 @@ pub mod solana_project {
     pub fn register_chain(
         ctx: Context<RegisterChain>,
         chain_id: u16,
-        emitter_addr: String,
+        emitter_addr: [u8; 32],
+        label: String,
+        address_format: AddressFormat,
+    ) -> Result<()> {
+        register_emitter(
+            &mut ctx.accounts.emitter_acc,
+            chain_id,
+            emitter_addr,
+            label,
+            address_format,
+        )
+    }
+
+    pub fn update_chain(
+        ctx: Context<UpdateChain>,
+        chain_id: u16,
+        enabled: bool,
+        label: Option<String>,
+    ) -> Result<()> {
+        update_emitter(&mut ctx.accounts.emitter_acc, chain_id, enabled, label)
+    }
+
+    // Sets what the governor lets out to `chain_id` over 24 hours, in
+    // notional units. u64::MAX leaves the chain unlimited.
+    pub fn set_chain_limit(
+        ctx: Context<SetChainLimit>,
+        chain_id: u16,
+        daily_limit: u64,
+    ) -> Result<()> {
+        write_chain_limit(&mut ctx.accounts.chain_limit, chain_id, daily_limit)
+    }
+
+    // Sets what the governor lets out of a mint over 24 hours, in its base
+    // units, what a whole token is worth in notional units, and how long
+    // transfers of at least `large_transfer_threshold` are held. What went
+    // out already keeps counting.
+    pub fn set_mint_limit(
+        ctx: Context<SetMintLimit>,
+        daily_limit: u64,
+        notional_price: u64,
+        large_transfer_threshold: u64,
+        hold_seconds: u32,
+    ) -> Result<()> {
+        write_mint_limit(
+            &mut ctx.accounts.mint_limit,
+            ctx.accounts.mint.key(),
+            daily_limit,
+            notional_price,
+            large_transfer_threshold,
+            hold_seconds,
+        )
+    }
+
+    // Rewrites an `EmitterAddress` account registered with the hex string
+    // layout as an `EmitterAccount`, at the same address.
+    pub fn migrate_chain(ctx: Context<MigrateChain>, chain_id: u16) -> Result<()> {
+        let emitter_info = ctx.accounts.emitter_acc.to_account_info();
+        require!(
+            !emitter_info
+                .data
+                .borrow()
+                .starts_with(&EmitterAccount::discriminator()),
+            MessengerError::EmitterAlreadyMigrated
+        );
+        let legacy = EmitterAddrAccount::try_deserialize(&mut &emitter_info.data.borrow()[..])?;
+        require!(
+            legacy.chain_id == chain_id && legacy.emitter_addr.len() == EVM_CHAIN_ADDRESS_LENGTH,
+            MessengerError::InvalidEmitterAddress
+        );
+        let emitter_addr: [u8; 32] = decode(legacy.emitter_addr.as_str())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(MessengerError::InvalidEmitterAddress)?;
+
+        let rent = Rent::get()?.minimum_balance(EMITTER_ACCOUNT_SPACE);
+        if emitter_info.lamports() < rent {
+            transfer_sol(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    TransferSol {
+                        from: ctx.accounts.owner.to_account_info(),
+                        to: emitter_info.clone(),
+                    },
+                ),
+                rent - emitter_info.lamports(),
+            )?;
+        }
+        emitter_info.realloc(EMITTER_ACCOUNT_SPACE, false)?;
+
+        let emitter_acc = EmitterAccount {
+            chain_id,
+            emitter_addr,
+            registered_at: Clock::get()?.unix_timestamp,
+            enabled: true,
+            label: String::new(),
+            account_version: AccountVersion::CURRENT,
+            address_format: AddressFormat::Evm,
+        };
+        emitter_acc.try_serialize(&mut &mut emitter_info.data.borrow_mut()[..])?;
+
+        emit!(MigratedChain {
+            chain_id,
+            emitter_addr
+        });
+        Ok(())
+    }
+
+    // Rewrites a state account of this program in the current layout. Anyone
+    // may pay for it; the account keeps its address and contents.
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        let from_version = migrate(
+            &ctx.accounts.account.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        emit!(MigratedAccount {
+            account: ctx.accounts.account.key(),
+            from_version: from_version.0,
+            to_version: CURRENT_ACCOUNT_VERSION
+        });
+        Ok(())
+    }
+
+    pub fn register_target_program(
+        ctx: Context<RegisterTargetProgram>,
+        version: u8,
+        target_program_id: Pubkey,
+    ) -> Result<()> {
+        write_target_program(
+            &mut ctx.accounts.target_program,
+            version,
+            target_program_id,
+            ctx.program_id,
+        )
+    }
+
+    pub fn remove_target_program(ctx: Context<RemoveTargetProgram>, version: u8) -> Result<()> {
+        emit!(RemovedTargetProgram {
+            version,
+            program_id: ctx.accounts.target_program.program_id
+        });
+        Ok(())
+    }
+
+    // Names the Wormhole emitter whose VAAs may make admin changes, see
+    // `governance`. Chain 0 turns governance off.
+    pub fn set_governance(
+        ctx: Context<SetGovernance>,
+        chain_id: u16,
+        emitter_address: [u8; 32],
     ) -> Result<()> {
         require!(
-            emitter_addr.len() == EVM_CHAIN_ADDRESS_LENGTH,
+            ctx.accounts.config.governance != Governance::Unspecified,
+            MessengerError::AccountNotMigrated
+        );
+        require!(
+            chain_id == 0 || emitter_address != [0u8; 32],
             MessengerError::InvalidEmitterAddress
         );
 
-        ctx.accounts.emitter_acc.chain_id = chain_id;
-        ctx.accounts.emitter_acc.emitter_addr = emitter_addr.clone();
+        // Migrated configs only have room for governance turned off.
+        let config_info = ctx.accounts.config.to_account_info();
+        if config_info.data_len() < CONFIG_SPACE {
+            let rent = Rent::get()?.minimum_balance(CONFIG_SPACE);
+            if config_info.lamports() < rent {
+                transfer_sol(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        TransferSol {
+                            from: ctx.accounts.owner.to_account_info(),
+                            to: config_info.clone(),
+                        },
+                    ),
+                    rent - config_info.lamports(),
+                )?;
+            }
+            config_info.realloc(CONFIG_SPACE, false)?;
+        }
+
+        let config = &mut ctx.accounts.config;
+        config.governance = if chain_id == 0 {
+            Governance::Off
+        } else {
+            Governance::Emitter {
+                chain_id,
+                emitter_address,
+            }
+        };
+
+        emit!(GovernanceSet {
+            chain_id: config.governance.chain_id(),
+            emitter_address: config.governance.emitter_address()
+        });
+        Ok(())
+    }
+
+    // Applies a set owner (36) governance VAA.
+    pub fn govern_config(ctx: Context<GovernConfig>) -> Result<()> {
+        match governed(
+            &ctx.accounts.core_bridge_vaa,
+            &ctx.accounts.config,
+            &mut ctx.accounts.processed_vaa,
+            ctx.program_id,
+        )? {
+            GovernanceAction::SetOwner { owner } => {
+                let config = &mut ctx.accounts.config;
+                let previous_owner = config.owner;
+                config.owner = owner;
+
+                emit!(OwnerSet {
+                    previous_owner,
+                    owner
+                });
+                Ok(())
+            }
+            _ => err!(MessengerError::GovernanceActionMismatch),
+        }
+    }
+
+    // Applies a register chain (30) governance VAA for `chain_id`.
+    pub fn govern_register_chain(ctx: Context<GovernRegisterChain>, chain_id: u16) -> Result<()> {
+        match governed(
+            &ctx.accounts.core_bridge_vaa,
+            &ctx.accounts.config,
+            &mut ctx.accounts.processed_vaa,
+            ctx.program_id,
+        )? {
+            GovernanceAction::RegisterChain {
+                chain_id: action_chain_id,
+                emitter_addr,
+                address_format,
+                label,
+            } if action_chain_id == chain_id => register_emitter(
+                &mut ctx.accounts.emitter_acc,
+                chain_id,
+                emitter_addr,
+                label,
+                address_format,
+            ),
+            _ => err!(MessengerError::GovernanceActionMismatch),
+        }
+    }
+
+    // Applies an update chain (31) governance VAA for `chain_id`. The label
+    // is kept.
+    pub fn govern_update_chain(ctx: Context<GovernUpdateChain>, chain_id: u16) -> Result<()> {
+        match governed(
+            &ctx.accounts.core_bridge_vaa,
+            &ctx.accounts.config,
+            &mut ctx.accounts.processed_vaa,
+            ctx.program_id,
+        )? {
+            GovernanceAction::UpdateChain {
+                chain_id: action_chain_id,
+                enabled,
+            } if action_chain_id == chain_id => {
+                update_emitter(&mut ctx.accounts.emitter_acc, chain_id, enabled, None)
+            }
+            _ => err!(MessengerError::GovernanceActionMismatch),
+        }
+    }
+
+    // Applies a set chain limit (32) governance VAA for `chain_id`.
+    pub fn govern_set_chain_limit(ctx: Context<GovernSetChainLimit>, chain_id: u16) -> Result<()> {
+        match governed(
+            &ctx.accounts.core_bridge_vaa,
+            &ctx.accounts.config,
+            &mut ctx.accounts.processed_vaa,
+            ctx.program_id,
+        )? {
+            GovernanceAction::SetChainLimit {
+                chain_id: action_chain_id,
+                daily_limit,
+            } if action_chain_id == chain_id => {
+                write_chain_limit(&mut ctx.accounts.chain_limit, chain_id, daily_limit)
+            }
+            _ => err!(MessengerError::GovernanceActionMismatch),
+        }
+    }
+
+    // Applies a set mint limit (33) governance VAA for the mint passed.
+    pub fn govern_set_mint_limit(ctx: Context<GovernSetMintLimit>) -> Result<()> {
+        match governed(
+            &ctx.accounts.core_bridge_vaa,
+            &ctx.accounts.config,
+            &mut ctx.accounts.processed_vaa,
+            ctx.program_id,
+        )? {
+            GovernanceAction::SetMintLimit {
+                mint,
+                daily_limit,
+                notional_price,
+                large_transfer_threshold,
+                hold_seconds,
+            } if mint == ctx.accounts.mint.key() => write_mint_limit(
+                &mut ctx.accounts.mint_limit,
+                mint,
+                daily_limit,
+                notional_price,
+                large_transfer_threshold,
+                hold_seconds,
+            ),
+            _ => err!(MessengerError::GovernanceActionMismatch),
+        }
+    }
+
+    // Applies a register target program (34) governance VAA for `version`.
+    pub fn govern_register_target_program(
+        ctx: Context<GovernRegisterTargetProgram>,
+        version: u8,
+    ) -> Result<()> {
+        match governed(
+            &ctx.accounts.core_bridge_vaa,
+            &ctx.accounts.config,
+            &mut ctx.accounts.processed_vaa,
+            ctx.program_id,
+        )? {
+            GovernanceAction::RegisterTargetProgram {
+                version: action_version,
+                program_id,
+            } if action_version == version => write_target_program(
+                &mut ctx.accounts.target_program,
+                version,
+                program_id,
+                ctx.program_id,
+            ),
+            _ => err!(MessengerError::GovernanceActionMismatch),
+        }
+    }
+
+    // Applies a remove target program (35) governance VAA for `version`. The
+    // entry's rent goes to the payer.
+    pub fn govern_remove_target_program(
+        ctx: Context<GovernRemoveTargetProgram>,
+        version: u8,
+    ) -> Result<()> {
+        match governed(
+            &ctx.accounts.core_bridge_vaa,
+            &ctx.accounts.config,
+            &mut ctx.accounts.processed_vaa,
+            ctx.program_id,
+        )? {
+            GovernanceAction::RemoveTargetProgram {
+                version: action_version,
+            } if action_version == version => {
+                emit!(RemovedTargetProgram {
+                    version,
+                    program_id: ctx.accounts.target_program.program_id
+                });
+                Ok(())
+            }
+            _ => err!(MessengerError::GovernanceActionMismatch),
+        }
+    }
+
+    // Allows messages to name a mint, or updates what the registry knows
+    // about it. `origin_chain` and `origin_address` name the token a wrapped
+    // mint stands for; a Solana mint is its own origin.
+    pub fn register_mint(
+        ctx: Context<RegisterMint>,
+        min_amount: u64,
+        max_amount: u64,
+        origin_chain: u16,
+        origin_address: [u8; 32],
+    ) -> Result<()> {
+        require!(min_amount <= max_amount, MessengerError::AmountOutOfBounds);
+        let mint = ctx.accounts.mint.key();
+        let wrapped = origin_chain != SOLANA_CHAIN_ID;
+        if wrapped {
+            require!(
+                mint == wrapped_mint_address(origin_chain, &origin_address),
+                MessengerError::MintOriginMismatch
+            );
+        } else {
+            require!(
+                origin_address == mint.to_bytes(),
+                MessengerError::MintOriginMismatch
+            );
+        }
 
-        emit!(RegisteredChain {
-            chain_id: chain_id,
-            emitter_addr: emitter_addr
+        let mint_registry = &mut ctx.accounts.mint_registry;
+        mint_registry.mint = mint;
+        mint_registry.decimals = ctx.accounts.mint.decimals;
+        mint_registry.min_amount = min_amount;
+        mint_registry.max_amount = max_amount;
+        mint_registry.wrapped = wrapped;
+        mint_registry.origin_chain = origin_chain;
+        mint_registry.origin_address = origin_address;
+        stamp(mint_registry);
+
+        emit!(RegisteredMint {
+            mint,
+            decimals: mint_registry.decimals,
+            min_amount,
+            max_amount,
+            wrapped,
+            origin_chain,
+            origin_address
         });
         Ok(())
     }
 
+    pub fn remove_mint(_ctx: Context<RemoveMint>, mint: Pubkey) -> Result<()> {
+        emit!(RemovedMint { mint });
+        Ok(())
+    }
+
     pub fn initialize_pda(
         ctx: Context<InitializePDA>,
         _sender: [u8; 32],
@@ -102,20 +516,26 @@ pub mod solana_project {
         // Check that the emitter chain and address match up with the vaa
         require!(
             vaa.emitter_chain == ctx.accounts.emitter_acc.chain_id
-                && vaa.emitter_address
-                    == decode(ctx.accounts.emitter_acc.emitter_addr.as_str()).unwrap()[..],
+                && vaa.emitter_address == ctx.accounts.emitter_acc.emitter_addr,
             MessengerError::VAAEmitterMismatch
         );
+        require!(
+            ctx.accounts.emitter_acc.enabled,
+            MessengerError::EmitterDisabled
+        );
 
         // Encoded String
         let encoded_str = vaa.payload.clone();
 
         // Decode Encoded String and Store Value based upon the code sent on message passing
-        let code = get_u8(encoded_str[0..1].to_vec());
+        let (account, to_chain_id) = decode_initialize_pda(&encoded_str)?;
+        require!(
+            ctx.accounts.emitter_acc.address_format.is_normalized(&account),
+            MessengerError::InvalidSenderAddress
+        );
 
-        require!(code == 18, MessengerError::InvalidPayload);
         let account_pda = Pubkey::find_program_address(
-            &[&encoded_str[1..33], &vaa.emitter_chain.to_be_bytes()],
+            &[&account, &vaa.emitter_chain.to_be_bytes()],
             ctx.program_id,
         )
         .0;
@@ -124,8 +544,6 @@ pub mod solana_project {
             MessengerError::InvalidPDAAccount
         );
 
-        let to_chain_id = get_u256(encoded_str[33..65].to_vec());
-
         require!(
             to_chain_id == U256::from_str("1").unwrap(),
             MessengerError::InvalidToChainId
@@ -143,6 +561,7 @@ pub mod solana_project {
         );
         transfer_sol(cpi_transfer_sol_ctx, rent_lamport + 5000000)?;
         
+        stamp(&mut ctx.accounts.processed_vaa);
         emit!(InitializedPDA { pda: account_pda });
 
         Ok(())
@@ -176,26 +595,29 @@ pub mod solana_project {
         // Check that the emitter chain and address match up with the vaa
         require!(
             vaa.emitter_chain == ctx.accounts.emitter_acc.chain_id
-                && vaa.emitter_address
-                    == decode(ctx.accounts.emitter_acc.emitter_addr.as_str()).unwrap()[..],
+                && vaa.emitter_address == ctx.accounts.emitter_acc.emitter_addr,
             MessengerError::VAAEmitterMismatch
         );
+        require!(
+            ctx.accounts.emitter_acc.enabled,
+            MessengerError::EmitterDisabled
+        );
 
         // Encoded String
         let encoded_str = vaa.payload.clone();
 
         // Decode Encoded String and Store   Value based upon the code sent on message passing
-        let code = get_u8(encoded_str[0..1].to_vec());
+        let (account, token_mint, to_chain_id) = decode_initialize_token_account(&encoded_str)?;
+        require!(
+            ctx.accounts.emitter_acc.address_format.is_normalized(&account),
+            MessengerError::InvalidSenderAddress
+        );
 
-        require!(code == 19, MessengerError::InvalidPayload);
         let account_pda = Pubkey::find_program_address(
-            &[&encoded_str[1..33], &vaa.emitter_chain.to_be_bytes()],
+            &[&account, &vaa.emitter_chain.to_be_bytes()],
             ctx.program_id,
         )
         .0;
-        let token_mint_array: [u8; 32] = encoded_str[33..65].try_into().unwrap();
-        let token_mint = Pubkey::new_from_array(token_mint_array);
-        let to_chain_id = get_u256(encoded_str[65..97].to_vec());
 
         require!(
             to_chain_id == U256::from_str("1").unwrap(),
@@ -210,80 +632,263 @@ pub mod solana_project {
             token_mint == ctx.accounts.token_mint.key(),
             MessengerError::MintKeyMismatch
         );
+        allowed_mint(&ctx.accounts.mint_registry, &token_mint, ctx.program_id)?;
 
+        stamp(&mut ctx.accounts.processed_vaa);
         emit!(InitializedPDATokenAccount {
             pda: account_pda,
-            token_mint: token_mint,
+            token_mint,
         });
         Ok(())
     }
 
-    pub fn store_msg(ctx: Context<StoreMsg>, current_count: u64, sender: [u8; 32]) -> Result<()> {
-        //Hash a VAA Extract and derive a VAA Key
-        let vaa = PostedMessageData::try_from_slice(&ctx.accounts.core_bridge_vaa.data.borrow())?.0;
-        let serialized_vaa = serialize_vaa(&vaa);
-
-        let mut h = sha3::Keccak256::default();
-        h.write_all(serialized_vaa.as_slice()).unwrap();
-        let vaa_hash: [u8; 32] = h.finalize().into();
-
-        let vaa_key = Pubkey::find_program_address(
-            &[b"PostedVAA", &vaa_hash],
-            &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap(),
-        )
-        .0;
+    // Links a source chain identity to the Solana wallet its payout
+    // withdrawals go to, or unlinks it for the default key.
+    pub fn set_payout_wallet(
+        ctx: Context<SetPayoutWallet>,
+        sender: [u8; 32],
+        chain_id: u16,
+    ) -> Result<()> {
+        let vaa = verified_vaa(&ctx.accounts.core_bridge_vaa, &ctx.accounts.emitter_acc, &sender)?;
+        require!(
+            vaa.emitter_chain == chain_id,
+            MessengerError::VAAEmitterMismatch
+        );
 
+        let (account, wallet, to_chain_id) = decode_set_payout_wallet(&vaa.payload)?;
+        require!(account == sender, MessengerError::InvalidSenderWallet);
         require!(
-            ctx.accounts.core_bridge_vaa.key() == vaa_key,
-            MessengerError::VAAKeyMismatch
+            ctx.accounts.emitter_acc.address_format.is_normalized(&account),
+            MessengerError::InvalidSenderAddress
+        );
+        require!(
+            to_chain_id == U256::from_str("1").unwrap(),
+            MessengerError::InvalidToChainId
         );
 
-        // Already checked that the SignedVaa is owned by core bridge in account constraint logic
-        // Check that the emitter chain and address match up with the vaa
+        // VAAs can be relayed out of order; the latest link wins.
+        let profile = &mut ctx.accounts.payout_profile;
         require!(
-            vaa.emitter_chain == ctx.accounts.emitter_acc.chain_id
-                && vaa.emitter_address
-                    == decode(ctx.accounts.emitter_acc.emitter_addr.as_str()).unwrap()[..],
-            MessengerError::VAAEmitterMismatch
+            profile.identity == [0u8; 32] || vaa.sequence > profile.vaa_sequence,
+            MessengerError::StalePayoutWallet
         );
+        profile.identity = sender;
+        profile.chain_id = chain_id;
+        profile.wallet = wallet;
+        profile.vaa_sequence = vaa.sequence;
+        stamp(profile);
+        stamp(&mut ctx.accounts.processed_vaa);
+
+        emit!(PayoutWalletSet {
+            identity: sender,
+            chain_id,
+            wallet,
+            vaa_sequence: vaa.sequence,
+        });
+        Ok(())
+    }
+
+    pub fn store_msg(ctx: Context<StoreMsg>, current_count: u64, sender: [u8; 32]) -> Result<()> {
+        let vaa = verified_vaa(&ctx.accounts.core_bridge_vaa, &ctx.accounts.emitter_acc, &sender)?;
 
         // Encoded String
         let encoded_str = vaa.payload.clone();
 
         // Decode Encoded String and Store Value based upon the code sent on message passing
-        let code = get_u8(encoded_str[0..1].to_vec());
+        let code = get_code(&encoded_str)?;
 
         // Change Transaction Count to Current Count
         let txn_count = &mut ctx.accounts.txn_count;
-        let sum = txn_count.count.checked_add(1);
-
-        match sum {
-            None => return Err(MessengerError::Overflow.into()),
-            Some(val) => txn_count.count = val,
-        }
-        require!(txn_count.count == current_count, MessengerError::InvalidCount);
+        next_count(txn_count, current_count)?;
 
+        stamp(txn_count);
         ctx.accounts.processed_vaa.transaction_count = txn_count.count;
+        stamp(&mut ctx.accounts.processed_vaa);
+        stamp(&mut ctx.accounts.txn_status);
 
+        // Switch Based on the code
+        if let Err(error) = decode_payload(
+            &encoded_str,
+            vaa.emitter_chain,
+            sender,
+            &mut ctx.accounts.data_storage,
+        )
+        .and_then(|()| {
+            check_stored_mint(
+                &ctx.accounts.mint_registry,
+                &ctx.accounts.data_storage,
+                code as u8,
+                ctx.program_id,
+            )
+        }) {
+            return Err(message_failed(sender, current_count, vaa.emitter_chain, error));
+        }
+        let data_storage = &mut ctx.accounts.data_storage;
+        data_storage.code = code as u8;
+        data_storage.vaa_sequence = vaa.sequence;
+        stamp(data_storage);
+
+        // The registry is not read here, so only direct transfers have a
+        // known target; `target_version` says which entry the rest will use.
+        let target_program = if code == 17 {
+            Pubkey::from_str(TOKEN_BRIDGE_ADDRESS).unwrap()
+        } else {
+            Pubkey::default()
+        };
         emit!(StoredMsg {
             msg_type: code,
-            sender: sender,
-            count: current_count
+            sender,
+            count: current_count,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                target_program,
+                ctx.accounts.payer.key(),
+                None,
+            )?,
         });
+        Ok(())
+    }
 
-        // Switch Based on the code
-        match code {
-            2 => process_stream(encoded_str, vaa.emitter_chain, ctx, sender),
-            4 => process_withdraw_stream(encoded_str, vaa.emitter_chain, ctx, sender),
-            6 => process_deposit(encoded_str, vaa.emitter_chain, ctx, sender),
-            8 => process_pause(encoded_str, vaa.emitter_chain, ctx, sender),
-            10 => process_withdraw(encoded_str, vaa.emitter_chain, ctx, sender),
-            12 => process_instant_transfer(encoded_str, vaa.emitter_chain, ctx, sender),
-            14 => process_update_stream(encoded_str, vaa.emitter_chain, ctx, sender),
-            16 => process_cancel_stream(encoded_str, vaa.emitter_chain, ctx, sender),
-            17 => process_direct_transfer(encoded_str, vaa.emitter_chain, ctx, sender),
-            _ =>  Err(MessengerError::InvalidPayload.into()),
+    // Stores a pause, withdraw or update an EVM account signed, as if it had
+    // come from its chain; see `meta`. The instruction before this one has to
+    // verify the signature with the secp256k1 program.
+    pub fn store_signed_msg(
+        ctx: Context<StoreSignedMsg>,
+        action: SignedAction,
+        sender: [u8; 32],
+        chain_id: u16,
+        nonce: u64,
+        current_count: u64,
+    ) -> Result<()> {
+        let emitter_acc = &ctx.accounts.emitter_acc;
+        require!(emitter_acc.enabled, MessengerError::EmitterDisabled);
+        // Only EVM accounts sign with secp256k1 keys.
+        require!(
+            emitter_acc.address_format.resolve() == AddressFormat::Evm
+                && emitter_acc.address_format.is_normalized(&sender),
+            MessengerError::InvalidSenderAddress
+        );
+
+        let meta_nonce = &mut ctx.accounts.meta_nonce;
+        require!(nonce == meta_nonce.nonce, MessengerError::MetaNonceMismatch);
+        let message = signed_message(ctx.program_id, chain_id, nonce, &action);
+        verify_signature(&ctx.accounts.instructions, &sender[12..], &message)?;
+        meta_nonce.nonce = nonce.checked_add(1).ok_or(MessengerError::Overflow)?;
+        stamp(meta_nonce);
+
+        let txn_count = &mut ctx.accounts.txn_count;
+        next_count(txn_count, current_count)?;
+        stamp(txn_count);
+        stamp(&mut ctx.accounts.txn_status);
+
+        let payload = action.payload(sender);
+        if let Err(error) = decode_payload(
+            &payload,
+            chain_id,
+            sender,
+            &mut ctx.accounts.data_storage,
+        )
+        .and_then(|()| {
+            check_stored_mint(
+                &ctx.accounts.mint_registry,
+                &ctx.accounts.data_storage,
+                action.code(),
+                ctx.program_id,
+            )
+        }) {
+            return Err(message_failed(sender, current_count, chain_id, error));
         }
+        let data_storage = &mut ctx.accounts.data_storage;
+        data_storage.code = action.code();
+        // Not from a VAA.
+        data_storage.vaa_sequence = 0;
+        stamp(data_storage);
+
+        emit!(StoredSignedMsg {
+            msg_type: action.code() as u64,
+            sender,
+            from_chain: chain_id,
+            count: current_count,
+            nonce,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                Pubkey::default(),
+                ctx.accounts.payer.key(),
+                None,
+            )?,
+        });
+        Ok(())
+    }
+
+    // Starts uploading the transaction of a message in chunks, for Zebec
+    // instructions too large to pass to a create instruction whole. See
+    // `staging`.
+    pub fn init_transaction(
+        ctx: Context<InitTransaction>,
+        _sender: [u8; 32],
+        _current_count: u64,
+        accounts_len: u8,
+        data_len: u16,
+    ) -> Result<()> {
+        require!(
+            !ctx.accounts.txn_status.executed,
+            MessengerError::TransactionAlreadyCreated
+        );
+
+        let buffer = &mut ctx.accounts.transaction_buffer;
+        buffer.uploader = ctx.accounts.zebec_eoa.key();
+        buffer.accounts_len = accounts_len;
+        buffer.data_len = data_len;
+        stamp(buffer);
+        Ok(())
+    }
+
+    pub fn append_accounts(
+        ctx: Context<AppendTransaction>,
+        _sender: [u8; 32],
+        _current_count: u64,
+        accs: Vec<TransactionAccount>,
+    ) -> Result<()> {
+        let buffer = &mut ctx.accounts.transaction_buffer;
+        check_uploader(buffer, ctx.accounts.uploader.key())?;
+        require!(
+            buffer.accounts.len() + accs.len() <= buffer.accounts_len as usize,
+            MessengerError::TransactionTooLarge
+        );
+        buffer.accounts.extend(accs);
+        Ok(())
+    }
+
+    pub fn append_data(
+        ctx: Context<AppendTransaction>,
+        _sender: [u8; 32],
+        _current_count: u64,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let buffer = &mut ctx.accounts.transaction_buffer;
+        check_uploader(buffer, ctx.accounts.uploader.key())?;
+        require!(
+            buffer.data.len() + data.len() <= buffer.data_len as usize,
+            MessengerError::TransactionTooLarge
+        );
+        buffer.data.extend(data);
+        Ok(())
+    }
+
+    // Fixes a complete buffer for the message's create instruction, which
+    // checks and takes it.
+    pub fn finalize_transaction(
+        ctx: Context<AppendTransaction>,
+        _sender: [u8; 32],
+        _current_count: u64,
+    ) -> Result<()> {
+        let buffer = &mut ctx.accounts.transaction_buffer;
+        check_uploader(buffer, ctx.accounts.uploader.key())?;
+        require!(buffer.is_complete(), MessengerError::TransactionIncomplete);
+        buffer.finalized = true;
+        Ok(())
     }
 
     //creates and executes deposit transaction
@@ -300,13 +905,30 @@ pub mod solana_project {
             !ctx.accounts.txn_status.executed,
             MessengerError::TransactionAlreadyCreated
         );
+        require!(
+            ctx.accounts.data_storage.code == 6,
+            MessengerError::MessageCodeMismatch
+        );
         let transaction_status = &mut ctx.accounts.txn_status;
         transaction_status.executed = true;
+        let (accs, data) = unstage(
+            accs,
+            data,
+            &ctx.accounts.transaction.to_account_info(),
+            &ctx.accounts.zebec_eoa.to_account_info(),
+            ctx.remaining_accounts,
+            sender,
+            current_count,
+            ctx.program_id,
+        )?;
+
         //Build Transactions
         let tx = &mut ctx.accounts.transaction;
-        tx.program_id = Pubkey::from_str(ZEBEC_CONTRACT).unwrap();
+        tx.program_id = ctx.accounts.target_program.program_id;
         tx.accounts = accs.clone();
         tx.data = data.clone();
+        tx.bind(sender, ctx.accounts.data_storage.from_chain_id, current_count, 6)?;
+        stamp(tx);
 
         //check Mint passed
         let mint_pubkey_passed: Pubkey = accs[6].pubkey;
@@ -338,28 +960,33 @@ pub mod solana_project {
         let data_slice = &data[8..];
         let decode_data = TokenAmount::try_from_slice(data_slice)?;
         let amount_passed = decode_data.amount;
-        require!(
-            amount_passed == ctx.accounts.data_storage.amount,
-            MessengerError::AmountMismatch
-        );
+        check_amount(
+            amount_passed,
+            ctx.accounts.data_storage.amount,
+            ctx.accounts.mint.decimals,
+        )?;
 
         // Burn the transaction to ensure one time use.
         ctx.accounts.transaction.did_execute = true;
-        require!(
-            perform_cpi(
-                chain_id,
-                sender,
-                *ctx.accounts.transaction.clone(),
-                ctx.accounts.pda_signer.clone(),
-                ctx.bumps,
-                ctx.remaining_accounts
-            )
-            .is_ok(),
-            MessengerError::InvalidCPI
-        );
+        perform_cpi(
+            chain_id,
+            sender,
+            *ctx.accounts.transaction.clone(),
+            &ctx.accounts.target_program,
+            ctx.accounts.pda_signer.clone(),
+            ctx.bumps,
+            ctx.remaining_accounts,
+        )?;
         emit!(Deposited {
-            sender: sender,
-            current_count: current_count,
+            sender,
+            current_count,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                ctx.accounts.target_program.program_id,
+                ctx.accounts.zebec_eoa.key(),
+                Some(ctx.accounts.mint.decimals),
+            )?,
         });
         Ok(())
     }
@@ -379,13 +1006,30 @@ pub mod solana_project {
             !ctx.accounts.txn_status.executed,
             MessengerError::TransactionAlreadyCreated
         );
+        require!(
+            ctx.accounts.data_storage.code == 2,
+            MessengerError::MessageCodeMismatch
+        );
+
+        let (accs, data) = unstage(
+            accs,
+            data,
+            &ctx.accounts.transaction.to_account_info(),
+            &ctx.accounts.zebec_eoa.to_account_info(),
+            ctx.remaining_accounts,
+            sender,
+            current_count,
+            ctx.program_id,
+        )?;
 
         //Build Transactions
         let tx = &mut ctx.accounts.transaction;
-        tx.program_id = Pubkey::from_str(ZEBEC_CONTRACT).unwrap();
+        tx.program_id = ctx.accounts.target_program.program_id;
         tx.accounts = accs.clone();
         tx.did_execute = false;
         tx.data = data.clone();
+        tx.bind(sender, ctx.accounts.data_storage.from_chain_id, current_count, 2)?;
+        stamp(tx);
 
         //check Mint passed
         let mint_pubkey_passed: Pubkey = accs[9].pubkey;
@@ -430,10 +1074,11 @@ pub mod solana_project {
         let data: &[u8] = data.as_slice();
         let data_slice = &data[8..];
         let decode_data = Stream::try_from_slice(data_slice)?;
-        require!(
-            decode_data.amount == ctx.accounts.data_storage.amount,
-            MessengerError::AmountMismatch
-        );
+        check_amount(
+            decode_data.amount,
+            ctx.accounts.data_storage.amount,
+            ctx.accounts.mint.decimals,
+        )?;
         require!(
             decode_data.start_time == ctx.accounts.data_storage.start_time,
             MessengerError::StartTimeMismatch
@@ -452,8 +1097,15 @@ pub mod solana_project {
         );
 
         emit!(StreamCreated {
-            sender: sender,
-            current_count: current_count,
+            sender,
+            current_count,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                ctx.accounts.target_program.program_id,
+                ctx.accounts.zebec_eoa.key(),
+                Some(ctx.accounts.mint.decimals),
+            )?,
         });
         Ok(())
     }
@@ -472,14 +1124,31 @@ pub mod solana_project {
             !ctx.accounts.txn_status.executed,
             MessengerError::TransactionAlreadyCreated
         );
+        require!(
+            ctx.accounts.data_storage.code == 14,
+            MessengerError::MessageCodeMismatch
+        );
         let transaction_status = &mut ctx.accounts.txn_status;
         transaction_status.executed = true;
 
+        let (accs, data) = unstage(
+            accs,
+            data,
+            &ctx.accounts.transaction.to_account_info(),
+            &ctx.accounts.zebec_eoa.to_account_info(),
+            ctx.remaining_accounts,
+            sender,
+            current_count,
+            ctx.program_id,
+        )?;
+
         //Build Transactions
         let tx = &mut ctx.accounts.transaction;
-        tx.program_id = Pubkey::from_str(ZEBEC_CONTRACT).unwrap();
+        tx.program_id = ctx.accounts.target_program.program_id;
         tx.accounts = accs.clone();
         tx.data = data.clone();
+        tx.bind(sender, ctx.accounts.data_storage.from_chain_id, current_count, 14)?;
+        stamp(tx);
 
         //check Mint passed
         let mint_pubkey_passed: Pubkey = accs[4].pubkey;
@@ -531,10 +1200,11 @@ pub mod solana_project {
         let data: &[u8] = data.as_slice();
         let data_slice = &data[8..];
         let decode_data = StreamUpdate::try_from_slice(data_slice)?;
-        require!(
-            decode_data.amount == ctx.accounts.data_storage.amount,
-            MessengerError::AmountMismatch
-        );
+        check_amount(
+            decode_data.amount,
+            ctx.accounts.data_storage.amount,
+            ctx.accounts.mint.decimals,
+        )?;
         require!(
             decode_data.start_time == ctx.accounts.data_storage.start_time,
             MessengerError::StartTimeMismatch
@@ -545,21 +1215,25 @@ pub mod solana_project {
         );
         // Burn the transaction to ensure one time use.
         ctx.accounts.transaction.did_execute = true;
-        require!(
-            perform_cpi(
-                chain_id,
-                sender,
-                *ctx.accounts.transaction.clone(),
-                ctx.accounts.pda_signer.clone(),
-                ctx.bumps,
-                ctx.remaining_accounts
-            )
-            .is_ok(),
-            MessengerError::InvalidCPI
-        );
+        perform_cpi(
+            chain_id,
+            sender,
+            *ctx.accounts.transaction.clone(),
+            &ctx.accounts.target_program,
+            ctx.accounts.pda_signer.clone(),
+            ctx.bumps,
+            ctx.remaining_accounts,
+        )?;
         emit!(StreamUpdated {
-            sender: sender,
-            current_count: current_count,
+            sender,
+            current_count,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                ctx.accounts.target_program.program_id,
+                ctx.accounts.zebec_eoa.key(),
+                Some(ctx.accounts.mint.decimals),
+            )?,
         });
         Ok(())
     }
@@ -578,13 +1252,30 @@ pub mod solana_project {
             !ctx.accounts.txn_status.executed,
             MessengerError::TransactionAlreadyCreated
         );
+        require!(
+            ctx.accounts.data_storage.code == 8,
+            MessengerError::MessageCodeMismatch
+        );
         let transaction_status = &mut ctx.accounts.txn_status;
         transaction_status.executed = true;
+        let (accs, data) = unstage(
+            accs,
+            data,
+            &ctx.accounts.transaction.to_account_info(),
+            &ctx.accounts.zebec_eoa.to_account_info(),
+            ctx.remaining_accounts,
+            sender,
+            current_count,
+            ctx.program_id,
+        )?;
+
         //Build Transactions
         let tx = &mut ctx.accounts.transaction;
-        tx.program_id = Pubkey::from_str(ZEBEC_CONTRACT).unwrap();
+        tx.program_id = ctx.accounts.target_program.program_id;
         tx.accounts = accs.clone();
         tx.data = data;
+        tx.bind(sender, ctx.accounts.data_storage.from_chain_id, current_count, 8)?;
+        stamp(tx);
 
         //check data account
         let data_account_passed: Pubkey = accs[2].pubkey;
@@ -626,21 +1317,25 @@ pub mod solana_project {
         );
         // Burn the transaction to ensure one time use.
         ctx.accounts.transaction.did_execute = true;
-        require!(
-            perform_cpi(
-                chain_id,
-                sender,
-                *ctx.accounts.transaction.clone(),
-                ctx.accounts.pda_signer.clone(),
-                ctx.bumps,
-                ctx.remaining_accounts
-            )
-            .is_ok(),
-            MessengerError::InvalidCPI
-        );
+        perform_cpi(
+            chain_id,
+            sender,
+            *ctx.accounts.transaction.clone(),
+            &ctx.accounts.target_program,
+            ctx.accounts.pda_signer.clone(),
+            ctx.bumps,
+            ctx.remaining_accounts,
+        )?;
         emit!(PausedResumed {
-            sender: sender,
-            current_count: current_count
+            sender,
+            current_count,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                ctx.accounts.target_program.program_id,
+                ctx.accounts.zebec_eoa.key(),
+                Some(ctx.accounts.mint.decimals),
+            )?,
         });
         Ok(())
     }
@@ -653,64 +1348,197 @@ pub mod solana_project {
         accs: Vec<TransactionAccount>,
         data: Vec<u8>,
         sender: [u8; 32],
-        _current_count: u64,
+        current_count: u64,
     ) -> Result<()> {
         require!(
             !ctx.accounts.txn_status.executed,
             MessengerError::TransactionAlreadyCreated
         );
+        // Withdraws that are bridged back (21) or paid out (23) record the
+        // receiver's balance first, see `create_transaction_withdraw_bridge`
+        // and `create_transaction_withdraw_payout`.
+        require!(
+            ctx.accounts.data_storage.code == 4,
+            MessengerError::MessageCodeMismatch
+        );
 
-        //Build Transactions
+        let (accs, data) = unstage(
+            accs,
+            data,
+            &ctx.accounts.transaction.to_account_info(),
+            &ctx.accounts.zebec_eoa.to_account_info(),
+            ctx.remaining_accounts,
+            sender,
+            current_count,
+            ctx.program_id,
+        )?;
+
+        //Build Transactions
         let tx = &mut ctx.accounts.transaction;
-        tx.program_id = Pubkey::from_str(ZEBEC_CONTRACT).unwrap();
+        tx.program_id = ctx.accounts.target_program.program_id;
         tx.accounts = accs.clone();
         tx.did_execute = false;
         tx.data = data;
+        tx.bind(sender, ctx.accounts.data_storage.from_chain_id, current_count, 4)?;
+        stamp(tx);
 
-        //check Mint passed
-        let mint_pubkey_passed: Pubkey = accs[12].pubkey;
+        check_receiver_withdraw(&accs, &ctx.accounts.data_storage, sender, ctx.program_id)?;
+
+        emit!(ReceiverWithdrawCreated {
+            sender,
+            current_count,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                ctx.accounts.target_program.program_id,
+                ctx.accounts.zebec_eoa.key(),
+                Some(ctx.accounts.mint.decimals),
+            )?,
+        });
+        Ok(())
+    }
+
+    // receiver withdraw of a withdraw stream and bridge (21) message. Once
+    // executed, `transaction_withdraw_bridge_*` sends what it paid out back
+    // through the token bridge.
+    pub fn create_transaction_withdraw_bridge(
+        ctx: Context<CreateTransactionWithdrawBridge>,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+        sender: [u8; 32],
+        current_count: u64,
+    ) -> Result<()> {
         require!(
-            mint_pubkey_passed == ctx.accounts.data_storage.token_mint,
-            MessengerError::MintKeyMismatch
+            !ctx.accounts.txn_status.executed,
+            MessengerError::TransactionAlreadyCreated
         );
-
-        //check data account
-        let data_account_passed: Pubkey = accs[6].pubkey;
         require!(
-            data_account_passed == ctx.accounts.data_storage.data_account,
-            MessengerError::DataAccountMismatch
+            ctx.accounts.data_storage.code == 21,
+            MessengerError::MessageCodeMismatch
         );
 
-        //check sender
-        let pda_sender_passed: Pubkey = accs[2].pubkey;
-        let sender_stored = ctx.accounts.data_storage.sender;
+        let (accs, data) = unstage(
+            accs,
+            data,
+            &ctx.accounts.transaction.to_account_info(),
+            &ctx.accounts.zebec_eoa.to_account_info(),
+            ctx.remaining_accounts,
+            sender,
+            current_count,
+            ctx.program_id,
+        )?;
 
-        //check receiver
-        let pda_receiver_passed: Pubkey = accs[1].pubkey;
-        let receiver_stored = ctx.accounts.data_storage.receiver;
+        //Build Transactions
+        let tx = &mut ctx.accounts.transaction;
+        tx.program_id = ctx.accounts.target_program.program_id;
+        tx.accounts = accs.clone();
+        tx.did_execute = false;
+        tx.data = data;
+        tx.bind(sender, ctx.accounts.data_storage.from_chain_id, current_count, 21)?;
+        stamp(tx);
+
+        check_receiver_withdraw(&accs, &ctx.accounts.data_storage, sender, ctx.program_id)?;
+
+        let balance = receiver_token_balance(&accs, &ctx.accounts.receiver_token_account)?;
+        match &mut ctx.accounts.data_storage.bridge_back {
+            BridgeBack::To { balance_before, .. } => *balance_before = balance,
+            _ => return err!(MessengerError::BridgeBackMismatch),
+        }
+
+        emit!(WithdrawBridgeCreated {
+            sender,
+            current_count,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                ctx.accounts.target_program.program_id,
+                ctx.accounts.zebec_eoa.key(),
+                Some(ctx.accounts.mint.decimals),
+            )?,
+        });
+        Ok(())
+    }
+
+    // receiver withdraw of a withdraw stream payout (23) message. Once
+    // executed, `transaction_withdraw_payout` sends what it paid out to the
+    // receiver's payout wallet.
+    pub fn create_transaction_withdraw_payout(
+        ctx: Context<CreateTransactionWithdrawPayout>,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+        sender: [u8; 32],
+        current_count: u64,
+    ) -> Result<()> {
         require!(
-            sender == receiver_stored,
-            MessengerError::PdaReceiverMismatch
+            !ctx.accounts.txn_status.executed,
+            MessengerError::TransactionAlreadyCreated
+        );
+        require!(
+            ctx.accounts.data_storage.code == 23,
+            MessengerError::MessageCodeMismatch
         );
 
-        //check pdaSender
-        let chain_id_stored = ctx.accounts.data_storage.from_chain_id;
-        let chain_id_seed = chain_id_stored.to_be_bytes();
-        let sender_derived_pubkey: (Pubkey, u8) =
-            Pubkey::find_program_address(&[&sender_stored, &chain_id_seed], ctx.program_id);
+        let (accs, data) = unstage(
+            accs,
+            data,
+            &ctx.accounts.transaction.to_account_info(),
+            &ctx.accounts.zebec_eoa.to_account_info(),
+            ctx.remaining_accounts,
+            sender,
+            current_count,
+            ctx.program_id,
+        )?;
+
+        //Build Transactions
+        let tx = &mut ctx.accounts.transaction;
+        tx.program_id = ctx.accounts.target_program.program_id;
+        tx.accounts = accs.clone();
+        tx.did_execute = false;
+        tx.data = data;
+        tx.bind(sender, ctx.accounts.data_storage.from_chain_id, current_count, 23)?;
+        stamp(tx);
+
+        check_receiver_withdraw(&accs, &ctx.accounts.data_storage, sender, ctx.program_id)?;
+
+        // The payout reads the receiver PDA's associated token account, so
+        // Zebec has to pay into that one.
+        let receiver_token_account = &ctx.accounts.receiver_token_account;
         require!(
-            pda_sender_passed == sender_derived_pubkey.0,
-            MessengerError::SenderDerivedKeyMismatch
+            receiver_token_account.key()
+                == get_associated_token_address(&accs[1].pubkey, &ctx.accounts.mint.key()),
+            MessengerError::ReceiverTokenAccountMismatch
         );
+        let balance = receiver_token_balance(&accs, receiver_token_account)?;
 
-        //check pdaReceiver
-        let receiver_derived_pubkey: (Pubkey, u8) =
-            Pubkey::find_program_address(&[&receiver_stored, &chain_id_seed], ctx.program_id);
+        let profile_wallet = ctx.accounts.payout_profile.wallet;
         require!(
-            pda_receiver_passed == receiver_derived_pubkey.0,
-            MessengerError::ReceiverDerivedKeyMismatch
+            profile_wallet != Pubkey::default(),
+            MessengerError::NoPayoutWallet
         );
+        match &mut ctx.accounts.data_storage.payout {
+            Payout::To {
+                wallet,
+                balance_before,
+                ..
+            } => {
+                *wallet = profile_wallet;
+                *balance_before = balance;
+            }
+            _ => return err!(MessengerError::PayoutMismatch),
+        }
 
+        emit!(WithdrawPayoutCreated {
+            sender,
+            current_count,
+            wallet: profile_wallet,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                ctx.accounts.target_program.program_id,
+                ctx.accounts.zebec_eoa.key(),
+                Some(ctx.accounts.mint.decimals),
+            )?,
+        });
         Ok(())
     }
 
@@ -727,13 +1555,30 @@ pub mod solana_project {
             !ctx.accounts.txn_status.executed,
             MessengerError::TransactionAlreadyCreated
         );
+        require!(
+            ctx.accounts.data_storage.code == 16,
+            MessengerError::MessageCodeMismatch
+        );
+
+        let (accs, data) = unstage(
+            accs,
+            data,
+            &ctx.accounts.transaction.to_account_info(),
+            &ctx.accounts.zebec_eoa.to_account_info(),
+            ctx.remaining_accounts,
+            sender,
+            current_count,
+            ctx.program_id,
+        )?;
 
         //Build Transactions
         let tx = &mut ctx.accounts.transaction;
-        tx.program_id = Pubkey::from_str(ZEBEC_CONTRACT).unwrap();
+        tx.program_id = ctx.accounts.target_program.program_id;
         tx.accounts = accs.clone();
         tx.did_execute = false;
         tx.data = data;
+        tx.bind(sender, ctx.accounts.data_storage.from_chain_id, current_count, 16)?;
+        stamp(tx);
 
         //check Mint passed
         let mint_pubkey_passed: Pubkey = accs[12].pubkey;
@@ -782,15 +1627,22 @@ pub mod solana_project {
         );
 
         emit!(CancelCreated {
-            sender: sender,
-            current_count: current_count,
+            sender,
+            current_count,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                ctx.accounts.target_program.program_id,
+                ctx.accounts.zebec_eoa.key(),
+                Some(ctx.accounts.mint.decimals),
+            )?,
         });
         Ok(())
     }
 
     // create transaction
     pub fn create_transaction_sender_withdraw(
-        ctx: Context<CreateTransaction>,
+        ctx: Context<CreateOutboundTransaction>,
         
         accs: Vec<TransactionAccount>,
         data: Vec<u8>,
@@ -802,13 +1654,30 @@ pub mod solana_project {
             !ctx.accounts.txn_status.executed,
             MessengerError::TransactionAlreadyCreated
         );
+        require!(
+            ctx.accounts.data_storage.code == 10,
+            MessengerError::MessageCodeMismatch
+        );
+
+        let (accs, data) = unstage(
+            accs,
+            data,
+            &ctx.accounts.transaction.to_account_info(),
+            &ctx.accounts.zebec_eoa.to_account_info(),
+            ctx.remaining_accounts,
+            sender,
+            current_count,
+            ctx.program_id,
+        )?;
 
         //Build Transactions
         let tx = &mut ctx.accounts.transaction;
-        tx.program_id = Pubkey::from_str(ZEBEC_CONTRACT).unwrap();
+        tx.program_id = ctx.accounts.target_program.program_id;
         tx.accounts = accs.clone();
         tx.did_execute = false;
         tx.data = data.clone();
+        tx.bind(sender, ctx.accounts.data_storage.from_chain_id, current_count, 10)?;
+        stamp(tx);
 
         //check Mint passed
         let mint_pubkey_passed: Pubkey = accs[7].pubkey;
@@ -839,21 +1708,52 @@ pub mod solana_project {
         let data: &[u8] = data.as_slice();
         let data_slice = &data[8..];
         let decode_data = TokenAmount::try_from_slice(data_slice)?;
-        require!(
-            decode_data.amount == ctx.accounts.data_storage.amount,
-            MessengerError::AmountMismatch
-        );
+        check_amount(
+            decode_data.amount,
+            ctx.accounts.data_storage.amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        // A held transfer is created all the same; it just cannot execute
+        // before it is released.
+        if !clear(
+            &mut ctx.accounts.data_storage,
+            &ctx.accounts.chain_limit,
+            &ctx.accounts.mint_limit,
+            SOLANA_CHAIN_ID,
+            decode_data.amount,
+            ctx.accounts.mint.decimals,
+            ctx.program_id,
+        )? {
+            emit_held(
+                &ctx.accounts.data_storage,
+                sender,
+                current_count,
+                SOLANA_CHAIN_ID,
+                decode_data.amount,
+                ctx.accounts.target_program.program_id,
+                ctx.accounts.zebec_eoa.key(),
+                ctx.accounts.mint.decimals,
+            )?;
+        }
 
         emit!(SenderWithdrawCreated {
-            sender: sender,
-            current_count: current_count,
+            sender,
+            current_count,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                ctx.accounts.target_program.program_id,
+                ctx.accounts.zebec_eoa.key(),
+                Some(ctx.accounts.mint.decimals),
+            )?,
         });
         Ok(())
     }
 
     // create transaction
     pub fn create_transaction_instant_transfer(
-        ctx: Context<CreateTransaction>,
+        ctx: Context<CreateOutboundTransaction>,
         
         accs: Vec<TransactionAccount>,
         data: Vec<u8>,
@@ -864,13 +1764,30 @@ pub mod solana_project {
             !ctx.accounts.txn_status.executed,
             MessengerError::TransactionAlreadyCreated
         );
+        require!(
+            ctx.accounts.data_storage.code == 12,
+            MessengerError::MessageCodeMismatch
+        );
+
+        let (accs, data) = unstage(
+            accs,
+            data,
+            &ctx.accounts.transaction.to_account_info(),
+            &ctx.accounts.zebec_eoa.to_account_info(),
+            ctx.remaining_accounts,
+            sender,
+            current_count,
+            ctx.program_id,
+        )?;
 
         //Build Transactions
         let tx = &mut ctx.accounts.transaction;
-        tx.program_id = Pubkey::from_str(ZEBEC_CONTRACT).unwrap();
+        tx.program_id = ctx.accounts.target_program.program_id;
         tx.accounts = accs.clone();
         tx.did_execute = false;
         tx.data = data.clone();
+        tx.bind(sender, ctx.accounts.data_storage.from_chain_id, current_count, 12)?;
+        stamp(tx);
 
         //check Mint passed
         let mint_pubkey_passed: Pubkey = accs[8].pubkey;
@@ -915,21 +1832,53 @@ pub mod solana_project {
         let data: &[u8] = data.as_slice();
         let data_slice = &data[8..];
         let decode_data = TokenAmount::try_from_slice(data_slice)?;
-        require!(
-            decode_data.amount == ctx.accounts.data_storage.amount,
-            MessengerError::AmountMismatch
-        );
+        check_amount(
+            decode_data.amount,
+            ctx.accounts.data_storage.amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        // A held transfer is created all the same; it just cannot execute
+        // before it is released.
+        if !clear(
+            &mut ctx.accounts.data_storage,
+            &ctx.accounts.chain_limit,
+            &ctx.accounts.mint_limit,
+            SOLANA_CHAIN_ID,
+            decode_data.amount,
+            ctx.accounts.mint.decimals,
+            ctx.program_id,
+        )? {
+            emit_held(
+                &ctx.accounts.data_storage,
+                sender,
+                current_count,
+                SOLANA_CHAIN_ID,
+                decode_data.amount,
+                ctx.accounts.target_program.program_id,
+                ctx.accounts.zebec_eoa.key(),
+                ctx.accounts.mint.decimals,
+            )?;
+        }
 
         emit!(InstantTransferCreated {
-            sender: sender,
-            current_count: current_count,
+            sender,
+            current_count,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                ctx.accounts.target_program.program_id,
+                ctx.accounts.zebec_eoa.key(),
+                Some(ctx.accounts.mint.decimals),
+            )?,
         });
         Ok(())
     }
 
-    //create and execute direct transfer native
-    pub fn transaction_direct_transfer_native(
-        ctx: Context<DirectTransferNative>,
+    //create and execute direct transfer, native or wrapped as the
+    //token bridge's meta of the stored mint says
+    pub fn transaction_direct_transfer(
+        ctx: Context<DirectTransfer>,
         sender: [u8; 32],
         chain_id: u16,
         current_count: u64,
@@ -940,13 +1889,28 @@ pub mod solana_project {
             !ctx.accounts.txn_status.executed,
             MessengerError::TransactionAlreadyExecuted
         );
-        let transaction_status = &mut ctx.accounts.txn_status;
-        transaction_status.executed = true;
+        require!(
+            ctx.accounts.data_storage.code == 17,
+            MessengerError::MessageCodeMismatch
+        );
 
         require!(
             ctx.accounts.data_storage.token_mint == ctx.accounts.mint.key(),
-            MessengerError::DataAccountMismatch
+            MessengerError::MintKeyMismatch
         );
+        let wrapped = wrapped_origin(&ctx.accounts.wrapped_meta, &ctx.accounts.mint.key())?
+            .is_some();
+        let decimals = mint_decimals(&ctx.accounts.mint)?;
+        let amount = ctx.accounts.data_storage.amount;
+        check_untruncated(amount, decimals)?;
+        bridged_mint(
+            &ctx.accounts.mint_registry,
+            &ctx.accounts.mint.key(),
+            wrapped,
+            ctx.program_id,
+        )?
+        .check_amount(amount)?;
+        check_transfer_terms(&ctx.accounts.data_storage, target_chain, fee, amount)?;
 
         //check sender
         let sender_stored = ctx.accounts.data_storage.sender;
@@ -968,128 +1932,542 @@ pub mod solana_project {
             MessengerError::SenderDerivedKeyMismatch
         );
 
-        emit!(DirectTransferredNative {
-            sender: sender,
+        //Check EOA before anything is held
+        require!(
+            ctx.accounts.config.owner == ctx.accounts.zebec_eoa.key(),
+            MessengerError::InvalidCaller
+        );
+        // Held transfers go out when the relayer calls again after release.
+        if !clear(
+            &mut ctx.accounts.data_storage,
+            &ctx.accounts.chain_limit,
+            &ctx.accounts.mint_limit,
+            target_chain,
+            amount,
+            decimals,
+            ctx.program_id,
+        )? {
+            return emit_held(
+                &ctx.accounts.data_storage,
+                sender,
+                current_count,
+                target_chain,
+                amount,
+                ctx.accounts.portal_bridge_program.key(),
+                ctx.accounts.zebec_eoa.key(),
+                decimals,
+            );
+        }
+        let transaction_status = &mut ctx.accounts.txn_status;
+        transaction_status.executed = true;
+
+        let message = message_details(
+            &ctx.accounts.data_storage,
+            ctx.accounts.portal_bridge_program.key(),
+            ctx.accounts.zebec_eoa.key(),
+            Some(decimals),
+        )?;
+        if wrapped {
+            emit!(DirectTransferredWrapped {
+                sender,
+                sender_chain: chain_id,
+                target_chain,
+                receiver: receiver_stored,
+                current_count,
+                version: EVENT_VERSION,
+                message,
+            });
+            transfer_wrapped(ctx, sender, chain_id, target_chain, amount, fee, receiver_stored)
+        } else {
+            emit!(DirectTransferredNative {
+                sender,
+                sender_chain: chain_id,
+                target_chain,
+                receiver: receiver_stored,
+                current_count,
+                version: EVENT_VERSION,
+                message,
+            });
+            transfer_native(ctx, sender, chain_id, target_chain, amount, fee, receiver_stored)
+        }
+    }
+
+    // Sends what the receiver withdraw of a withdraw stream and bridge (21)
+    // message paid out to the receiver on the chain the message names,
+    // native or wrapped like a direct transfer. Runs once, after
+    // `execute_transaction`.
+    pub fn transaction_withdraw_bridge(
+        ctx: Context<DirectTransfer>,
+        sender: [u8; 32],
+        chain_id: u16,
+        current_count: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.data_storage.token_mint == ctx.accounts.mint.key(),
+            MessengerError::MintKeyMismatch
+        );
+        let wrapped = wrapped_origin(&ctx.accounts.wrapped_meta, &ctx.accounts.mint.key())?
+            .is_some();
+        bridged_mint(
+            &ctx.accounts.mint_registry,
+            &ctx.accounts.mint.key(),
+            wrapped,
+            ctx.program_id,
+        )?;
+        let decimals = mint_decimals(&ctx.accounts.mint)?;
+        let (target_chain, amount, fee) = bridge_withdrawal(
+            &ctx.accounts.data_storage,
+            &ctx.accounts.txn_status,
+            &ctx.accounts.from,
+            &ctx.accounts.pda_signer.key(),
+            sender,
+            ctx.program_id,
+        )?;
+        //Check EOA before anything is held
+        require!(
+            ctx.accounts.config.owner == ctx.accounts.zebec_eoa.key(),
+            MessengerError::InvalidCaller
+        );
+        // Held transfers go out when the relayer calls again after release.
+        if !clear(
+            &mut ctx.accounts.data_storage,
+            &ctx.accounts.chain_limit,
+            &ctx.accounts.mint_limit,
+            target_chain,
+            amount,
+            decimals,
+            ctx.program_id,
+        )? {
+            return emit_held(
+                &ctx.accounts.data_storage,
+                sender,
+                current_count,
+                target_chain,
+                amount,
+                ctx.accounts.portal_bridge_program.key(),
+                ctx.accounts.zebec_eoa.key(),
+                decimals,
+            );
+        }
+        mark_bridged(&mut ctx.accounts.data_storage);
+
+        emit!(WithdrawalBridged {
+            receiver: sender,
             sender_chain: chain_id,
-            target_chain: target_chain,
-            receiver: receiver_stored,
-            current_count: current_count
+            target_chain,
+            current_count,
+            amount,
+            fee,
+            wrapped,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                ctx.accounts.portal_bridge_program.key(),
+                ctx.accounts.zebec_eoa.key(),
+                Some(decimals),
+            )?,
         });
 
-        transfer_native(ctx, sender, chain_id, target_chain, fee, receiver_stored)
+        if wrapped {
+            transfer_wrapped(ctx, sender, chain_id, target_chain, amount, fee, sender)
+        } else {
+            transfer_native(ctx, sender, chain_id, target_chain, amount, fee, sender)
+        }
     }
 
-    //create and execute direct transfer wrapped
-    pub fn transaction_direct_transfer_wrapped(
-        ctx: Context<DirectTransferWrapped>,
+    // Pays what the receiver withdraw of a withdraw stream payout (23)
+    // message paid out on to the receiver's payout wallet, creating the
+    // wallet's token account if needed. Runs once, after
+    // `execute_transaction`.
+    pub fn transaction_withdraw_payout(
+        ctx: Context<WithdrawPayout>,
         sender: [u8; 32],
-        sender_chain: u16,
-        _token_address: Vec<u8>,
-        _token_chain: u16,
+        chain_id: u16,
+        current_count: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.txn_status.executed,
+            MessengerError::TransactionNotExecuted
+        );
+        let data_storage = &mut ctx.accounts.data_storage;
+        let (wallet, balance_before) = match data_storage.payout {
+            Payout::To { paid: true, .. } => return err!(MessengerError::AlreadyPaidOut),
+            Payout::To {
+                wallet,
+                balance_before,
+                ..
+            } => (wallet, balance_before),
+            _ => return err!(MessengerError::PayoutMismatch),
+        };
+        require!(
+            ctx.accounts.wallet.key() == wallet,
+            MessengerError::PayoutMismatch
+        );
+        let amount = withdrawn_amount(
+            data_storage,
+            &ctx.accounts.from,
+            &ctx.accounts.pda_signer.key(),
+            sender,
+            balance_before,
+            ctx.program_id,
+        )?;
+        data_storage.payout = Payout::To {
+            wallet,
+            balance_before,
+            paid: true,
+        };
+
+        let bump = ctx.bumps.get("pda_signer").unwrap().to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[&sender, &chain_id.to_be_bytes(), &bump]];
+        transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.from.to_account_info(),
+                    to: ctx.accounts.wallet_token_account.to_account_info(),
+                    authority: ctx.accounts.pda_signer.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        emit!(WithdrawalPaidOut {
+            receiver: sender,
+            sender_chain: chain_id,
+            current_count,
+            wallet,
+            amount,
+            version: EVENT_VERSION,
+            message: message_details(
+                &ctx.accounts.data_storage,
+                ctx.accounts.token_program.key(),
+                ctx.accounts.zebec_eoa.key(),
+                Some(ctx.accounts.mint.decimals),
+            )?,
+        });
+        Ok(())
+    }
+
+    pub fn execute_transaction(
+        ctx: Context<ExecuteTransaction>,
+        eth_add: [u8; 32],
+
+        from_chain_id: u16,
         current_count: u64,
-        target_chain: u16,
-        fee: u64,
     ) -> Result<()> {
         require!(
             !ctx.accounts.txn_status.executed,
             MessengerError::TransactionAlreadyExecuted
         );
+        require!(
+            !ctx.accounts
+                .data_storage
+                .clearance
+                .is_held(Clock::get()?.unix_timestamp),
+            MessengerError::TransferHeld
+        );
         let transaction_status = &mut ctx.accounts.txn_status;
         transaction_status.executed = true;
 
+        // params if passed incorrecrtly the signature will not work and the txn will panic.
+        // Has this been executed already?
+        require!(
+            !ctx.accounts.transaction.did_execute,
+            MessengerError::AlreadyExecuted
+        );
+
+        ctx.accounts.transaction.check_binding(
+            eth_add,
+            from_chain_id,
+            current_count,
+            ctx.accounts.data_storage.code,
+        )?;
+
+        // Burn the transaction to ensure one time use.
+        ctx.accounts.transaction.did_execute = true;
+        perform_cpi(
+            from_chain_id,
+            eth_add,
+            *ctx.accounts.transaction.clone(),
+            &ctx.accounts.target_program,
+            ctx.accounts.pda_signer.clone(),
+            ctx.bumps,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(ExecutedTransaction {
+            from_chain_id,
+            eth_add,
+            transaction: ctx.accounts.transaction.to_account_info().key(),
+            version: EVENT_VERSION,
+            current_count,
+            target_program: ctx.accounts.transaction.program_id,
+        });
+        Ok(())
+    }
+
+    // Stores, creates and executes a message in one instruction; see `relay`.
+    pub fn relay(ctx: Context<Relay>, steps: Vec<RelayStep>) -> Result<()> {
+        check_steps(&steps)?;
+        run_steps(ctx.program_id, &steps, ctx.remaining_accounts)
+    }
+
+    // Stores a batch stream (20) message. Its entries are created and
+    // executed one at a time, each into its own Zebec stream.
+    pub fn store_batch_msg(
+        ctx: Context<StoreBatchMsg>,
+        current_count: u64,
+        sender: [u8; 32],
+    ) -> Result<()> {
+        let vaa = verified_vaa(&ctx.accounts.core_bridge_vaa, &ctx.accounts.emitter_acc, &sender)?;
+
+        let txn_count = &mut ctx.accounts.txn_count;
+        next_count(txn_count, current_count)?;
+        stamp(txn_count);
+        ctx.accounts.processed_vaa.transaction_count = current_count;
+        stamp(&mut ctx.accounts.processed_vaa);
+
+        let batch_storage = &mut ctx.accounts.batch_storage;
+        if let Err(error) = decode_batch_stream(&vaa.payload, vaa.emitter_chain, sender, batch_storage)
+            .and_then(|()| {
+                let registry = allowed_mint(
+                    &ctx.accounts.mint_registry,
+                    &batch_storage.token_mint,
+                    ctx.program_id,
+                )?;
+                batch_storage
+                    .entries
+                    .iter()
+                    .try_for_each(|entry| check_payload_amount(&registry, entry.amount))
+            })
+        {
+            return Err(message_failed(sender, current_count, vaa.emitter_chain, error));
+        }
+        batch_storage.vaa_sequence = vaa.sequence;
+        batch_storage.relayer = ctx.accounts.payer.key();
+        stamp(batch_storage);
+
+        emit!(StoredBatch {
+            sender,
+            count: current_count,
+            version: EVENT_VERSION,
+            from_chain: vaa.emitter_chain,
+            vaa_sequence: vaa.sequence,
+            mint: batch_storage.token_mint,
+            entries: batch_storage.entries.len() as u8,
+            target_version: batch_storage.target_version,
+            relayer: batch_storage.relayer,
+        });
+        Ok(())
+    }
+
+    // Builds the stream of one batch entry, checked the way
+    // `create_transaction_stream` checks a single stream.
+    pub fn create_transaction_batch_stream(
+        ctx: Context<CreateBatchTransaction>,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+        sender: [u8; 32],
+        current_count: u64,
+        index: u8,
+    ) -> Result<()> {
+        let batch_storage = &ctx.accounts.batch_storage;
+        let entry = batch_storage
+            .entries
+            .get(index as usize)
+            .ok_or(MessengerError::InvalidBatchEntry)?
+            .clone();
+        require!(
+            entry.status == BatchEntryStatus::Pending,
+            MessengerError::BatchEntryNotPending
+        );
+
+        //Build Transactions
+        let tx = &mut ctx.accounts.transaction;
+        tx.program_id = ctx.accounts.target_program.program_id;
+        tx.accounts = accs.clone();
+        tx.did_execute = false;
+        tx.data = data.clone();
+        // Bound to the batch stream (20) message the entry is part of.
+        tx.bind(sender, batch_storage.from_chain_id, current_count, 20)?;
+        stamp(tx);
+
+        //check Mint passed
+        require!(
+            accs[9].pubkey == batch_storage.token_mint,
+            MessengerError::MintKeyMismatch
+        );
+
         //check sender
-        let sender_stored = ctx.accounts.data_storage.sender;
         require!(
-            sender == sender_stored,
+            sender == batch_storage.sender,
             MessengerError::PdaSenderMismatch
         );
 
-        //check receiver
-        let receiver_stored = ctx.accounts.data_storage.receiver;
-
-        //check pdaSender
-        let chain_id_stored = ctx.accounts.data_storage.from_chain_id;
-        let chain_id_seed = &chain_id_stored.to_be_bytes();
-        let (sender_derived_pubkey, _): (Pubkey, u8) =
-            Pubkey::find_program_address(&[&sender, chain_id_seed], ctx.program_id);
+        //check pdaSender and pdaReceiver
+        let chain_id_seed = batch_storage.from_chain_id.to_be_bytes();
+        let sender_derived_pubkey =
+            Pubkey::find_program_address(&[&sender, &chain_id_seed], ctx.program_id).0;
         require!(
-            ctx.accounts.pda_signer.key() == sender_derived_pubkey,
+            accs[5].pubkey == sender_derived_pubkey,
             MessengerError::SenderDerivedKeyMismatch
         );
+        let receiver_derived_pubkey =
+            Pubkey::find_program_address(&[&entry.receiver, &chain_id_seed], ctx.program_id).0;
+        require!(
+            accs[6].pubkey == receiver_derived_pubkey,
+            MessengerError::ReceiverDerivedKeyMismatch
+        );
 
-        emit!(DirectTransferredWrapped {
-            sender: sender,
-            sender_chain: sender_chain,
-            target_chain: target_chain,
-            receiver: receiver_stored,
-            current_count: current_count,
-        });
+        //check data params passed
+        let decode_data = Stream::try_from_slice(&data[8..])?;
+        check_amount(decode_data.amount, entry.amount, ctx.accounts.mint.decimals)?;
+        require!(
+            decode_data.start_time == entry.start_time,
+            MessengerError::StartTimeMismatch
+        );
+        require!(
+            decode_data.end_time == entry.end_time,
+            MessengerError::EndTimeMismatch
+        );
+        require!(
+            decode_data.can_cancel == entry.can_cancel,
+            MessengerError::CanCancelMismatch
+        );
+        require!(
+            decode_data.can_update == entry.can_update,
+            MessengerError::CanUpdateMismatch
+        );
+
+        let transaction = ctx.accounts.transaction.key();
+        let batch_storage = &mut ctx.accounts.batch_storage;
+        let entry = &mut batch_storage.entries[index as usize];
+        entry.status = BatchEntryStatus::Created;
+        entry.transaction = transaction;
+        entry.data_account = accs[0].pubkey;
 
-        transfer_wrapped(
-            ctx,
+        emit!(BatchEntryCreated {
             sender,
-            sender_chain,
-            target_chain,
-            fee,
-            receiver_stored,
-        )
+            current_count,
+            index,
+            version: EVENT_VERSION,
+            message: batch_entry_details(
+                &ctx.accounts.batch_storage,
+                index,
+                ctx.accounts.target_program.program_id,
+                ctx.accounts.zebec_eoa.key(),
+                ctx.accounts.mint.decimals,
+            )?,
+        });
+        Ok(())
     }
 
-    pub fn execute_transaction(
-        ctx: Context<ExecuteTransaction>,
+    pub fn execute_batch_transaction(
+        ctx: Context<ExecuteBatchTransaction>,
         eth_add: [u8; 32],
-
         from_chain_id: u16,
-        _current_count: u64,
+        current_count: u64,
+        index: u8,
     ) -> Result<()> {
+        let entry = ctx
+            .accounts
+            .batch_storage
+            .entries
+            .get(index as usize)
+            .ok_or(MessengerError::InvalidBatchEntry)?;
         require!(
-            !ctx.accounts.txn_status.executed,
-            MessengerError::TransactionAlreadyExecuted
+            entry.status == BatchEntryStatus::Created,
+            MessengerError::BatchEntryNotCreated
+        );
+        require!(
+            entry.transaction == ctx.accounts.transaction.key(),
+            MessengerError::BatchTransactionMismatch
         );
-        let transaction_status = &mut ctx.accounts.txn_status;
-        transaction_status.executed = true;
-
-        // params if passed incorrecrtly the signature will not work and the txn will panic.
-        // Has this been executed already?
         require!(
             !ctx.accounts.transaction.did_execute,
             MessengerError::AlreadyExecuted
         );
+        ctx.accounts
+            .transaction
+            .check_binding(eth_add, from_chain_id, current_count, 20)?;
 
         // Burn the transaction to ensure one time use.
         ctx.accounts.transaction.did_execute = true;
+        ctx.accounts.batch_storage.entries[index as usize].status = BatchEntryStatus::Executed;
+        perform_cpi(
+            from_chain_id,
+            eth_add,
+            *ctx.accounts.transaction.clone(),
+            &ctx.accounts.target_program,
+            ctx.accounts.pda_signer.clone(),
+            ctx.bumps,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(BatchEntryExecuted {
+            sender: eth_add,
+            current_count,
+            index,
+            version: EVENT_VERSION,
+            transaction: ctx.accounts.transaction.key(),
+            target_program: ctx.accounts.transaction.program_id,
+            finished: ctx.accounts.batch_storage.is_finished(),
+        });
+        Ok(())
+    }
+
+    // A failed CPI reverts the whole transaction, so an entry that cannot be
+    // executed is marked failed here, by the relayer that stored the batch or
+    // the config owner. The other entries are not affected.
+    pub fn fail_batch_entry(
+        ctx: Context<FailBatchEntry>,
+        sender: [u8; 32],
+        current_count: u64,
+        index: u8,
+        error_code: u32,
+    ) -> Result<()> {
+        // Only the owner: the entry's failure is not on chain to check, and
+        // whoever stored the batch need not be trusted.
         require!(
-            perform_cpi(
-                from_chain_id,
-                eth_add,
-                *ctx.accounts.transaction.clone(),
-                ctx.accounts.pda_signer.clone(),
-                ctx.bumps,
-                ctx.remaining_accounts
-            )
-            .is_ok(),
-            MessengerError::InvalidCPI
+            ctx.accounts.authority.key() == ctx.accounts.config.owner,
+            MessengerError::InvalidCaller
+        );
+        let batch_storage = &mut ctx.accounts.batch_storage;
+        let entry = batch_storage
+            .entries
+            .get_mut(index as usize)
+            .ok_or(MessengerError::InvalidBatchEntry)?;
+        require!(
+            !entry.status.is_finished(),
+            MessengerError::BatchEntryFinished
         );
+        entry.status = BatchEntryStatus::Failed;
+        entry.error_code = error_code;
 
-        emit!(ExecutedTransaction {
-            from_chain_id: from_chain_id,
-            eth_add: eth_add,
-            transaction: ctx.accounts.transaction.to_account_info().key(),
+        emit!(BatchEntryFailed {
+            sender,
+            current_count,
+            index,
+            version: EVENT_VERSION,
+            error_code,
+            finished: batch_storage.is_finished(),
         });
         Ok(())
     }
 
 }
 
+#[allow(clippy::result_large_err)]
 fn transfer_wrapped(
-    ctx: Context<DirectTransferWrapped>,
+    ctx: Context<DirectTransfer>,
     sender: [u8; 32],
     sender_chain: u16,
     target_chain: u16,
+    amount: u64,
     fee: u64,
     receiver: [u8; 32],
 ) -> Result<()> {
-    let amount = ctx.accounts.data_storage.amount;
 
     //Check EOA
     require!(
@@ -1122,7 +2500,7 @@ fn transfer_wrapped(
             AccountMeta::new_readonly(ctx.accounts.portal_config.key(), false),
             AccountMeta::new(ctx.accounts.from.key(), false),
             AccountMeta::new_readonly(ctx.accounts.pda_signer.key(), true),
-            AccountMeta::new(ctx.accounts.wrapped_mint.key(), false),
+            AccountMeta::new(ctx.accounts.mint.key(), false),
             AccountMeta::new_readonly(ctx.accounts.wrapped_meta.key(), false),
             AccountMeta::new_readonly(ctx.accounts.portal_authority_signer.key(), false),
             AccountMeta::new(ctx.accounts.bridge_config.key(), false),
@@ -1157,7 +2535,7 @@ fn transfer_wrapped(
         ctx.accounts.portal_config.to_account_info(),
         ctx.accounts.from.to_account_info(),
         ctx.accounts.pda_signer.to_account_info(),
-        ctx.accounts.wrapped_mint.to_account_info(),
+        ctx.accounts.mint.to_account_info(),
         ctx.accounts.wrapped_meta.to_account_info(),
         ctx.accounts.portal_authority_signer.to_account_info(),
         ctx.accounts.bridge_config.to_account_info(),
@@ -1186,15 +2564,16 @@ fn transfer_wrapped(
 }
 
 //transfer
+#[allow(clippy::result_large_err)]
 fn transfer_native(
-    ctx: Context<DirectTransferNative>,
+    ctx: Context<DirectTransfer>,
     sender: [u8; 32],
     sender_chain: u16,
     target_chain: u16,
+    amount: u64,
     fee: u64,
     receiver: [u8;32],
 ) -> Result<()> {
-    let amount = ctx.accounts.data_storage.amount;
     //Check EOA
     require!(
         ctx.accounts.config.owner == ctx.accounts.zebec_eoa.key(),
@@ -1290,26 +2669,264 @@ fn transfer_native(
     Ok(())
 }
 
-fn get_u64(data_bytes: Vec<u8>) -> u64 {
-    let data_u8 = <[u8; 8]>::try_from(data_bytes).unwrap();
-    u64::from_be_bytes(data_u8)
+#[allow(clippy::result_large_err)]
+fn get_u64(data_bytes: &[u8]) -> Result<u64> {
+    let data_u8 = <[u8; 8]>::try_from(data_bytes)
+        .map_err(|_| error!(MessengerError::InvalidPayloadLength))?;
+    Ok(u64::from_be_bytes(data_u8))
+}
+
+#[allow(clippy::result_large_err)]
+fn get_u32(data_bytes: &[u8]) -> Result<u32> {
+    let data_u8 = <[u8; 4]>::try_from(data_bytes)
+        .map_err(|_| error!(MessengerError::InvalidPayloadLength))?;
+    Ok(u32::from_be_bytes(data_u8))
+}
+
+#[allow(clippy::result_large_err)]
+fn get_u16(data_bytes: &[u8]) -> Result<u16> {
+    let data_u8 = <[u8; 2]>::try_from(data_bytes)
+        .map_err(|_| error!(MessengerError::InvalidPayloadLength))?;
+    Ok(u16::from_be_bytes(data_u8))
+}
+
+#[allow(clippy::result_large_err)]
+fn get_u256(data_bytes: &[u8]) -> Result<U256> {
+    let data_u8 = <[u8; 32]>::try_from(data_bytes)
+        .map_err(|_| error!(MessengerError::InvalidPayloadLength))?;
+    Ok(U256::from_big_endian(&data_u8))
+}
+
+#[allow(clippy::result_large_err)]
+fn get_code(payload: &[u8]) -> Result<u64> {
+    match payload.first() {
+        Some(code) => Ok(*code as u64),
+        None => err!(MessengerError::InvalidPayloadLength),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn get_u32_array(data_bytes: &[u8]) -> Result<[u8; 32]> {
+    data_bytes
+        .try_into()
+        .map_err(|_| error!(MessengerError::InvalidPayloadLength))
+}
+
+#[allow(clippy::result_large_err)]
+fn check_payload_length(payload: &[u8], length: usize) -> Result<()> {
+    require!(
+        payload.len() == length,
+        MessengerError::InvalidPayloadLength
+    );
+    Ok(())
+}
+
+// A Zebec payload may end with one extra byte selecting the version of the
+// target program in the registry. Without it the message targets version 0.
+#[allow(clippy::result_large_err)]
+fn check_message_length(payload: &[u8], length: usize) -> Result<u8> {
+    if payload.len() == length + TARGET_VERSION_LENGTH {
+        return Ok(payload[length]);
+    }
+    check_payload_length(payload, length)?;
+    Ok(0)
+}
+
+/// Returns the account and the target chain of an initialize PDA (18) payload.
+#[allow(clippy::result_large_err)]
+pub fn decode_initialize_pda(payload: &[u8]) -> Result<([u8; 32], U256)> {
+    require!(get_code(payload)? == 18, MessengerError::InvalidPayload);
+    check_payload_length(payload, INITIALIZE_PDA_PAYLOAD_LENGTH)?;
+
+    let account = get_u32_array(&payload[1..33])?;
+    let to_chain_id = get_u256(&payload[33..65])?;
+    Ok((account, to_chain_id))
+}
+
+/// Returns the account, the mint and the target chain of an initialize token
+/// account (19) payload.
+#[allow(clippy::result_large_err)]
+pub fn decode_initialize_token_account(payload: &[u8]) -> Result<([u8; 32], Pubkey, U256)> {
+    require!(get_code(payload)? == 19, MessengerError::InvalidPayload);
+    check_payload_length(payload, INITIALIZE_TOKEN_ACCOUNT_PAYLOAD_LENGTH)?;
+
+    let account = get_u32_array(&payload[1..33])?;
+    let token_mint = Pubkey::new_from_array(get_u32_array(&payload[33..65])?);
+    let to_chain_id = get_u256(&payload[65..97])?;
+    Ok((account, token_mint, to_chain_id))
+}
+
+/// Returns the account, the payout wallet and the target chain of a set
+/// payout wallet (22) payload.
+#[allow(clippy::result_large_err)]
+pub fn decode_set_payout_wallet(payload: &[u8]) -> Result<([u8; 32], Pubkey, U256)> {
+    require!(get_code(payload)? == 22, MessengerError::InvalidPayload);
+    check_payload_length(payload, SET_PAYOUT_WALLET_PAYLOAD_LENGTH)?;
+
+    let account = get_u32_array(&payload[1..33])?;
+    let wallet = Pubkey::new_from_array(get_u32_array(&payload[33..65])?);
+    let to_chain_id = get_u256(&payload[65..97])?;
+    Ok((account, wallet, to_chain_id))
 }
 
-fn get_u256(data_bytes: Vec<u8>) -> U256 {
-    let data_u8 = <[u8; 32]>::try_from(data_bytes).unwrap();
-    U256::from_big_endian(&data_u8)
+/// Decodes a governance payload addressed to `program_id`, see `governance`.
+#[allow(clippy::result_large_err)]
+pub fn decode_governance(payload: &[u8], program_id: &Pubkey) -> Result<GovernanceAction> {
+    require!(
+        payload.len() >= GOVERNANCE_HEADER_LENGTH,
+        MessengerError::InvalidPayloadLength
+    );
+    require!(
+        payload[1..33] == program_id.to_bytes(),
+        MessengerError::InvalidPayload
+    );
+
+    match get_code(payload)? {
+        30 => {
+            require!(
+                payload.len() >= REGISTER_CHAIN_PAYLOAD_LENGTH
+                    && payload.len() <= REGISTER_CHAIN_PAYLOAD_LENGTH + EMITTER_LABEL_MAX_LENGTH,
+                MessengerError::InvalidPayloadLength
+            );
+            let address_format = AddressFormat::try_from_slice(&payload[67..68])
+                .map_err(|_| error!(MessengerError::InvalidPayload))?;
+            let label = String::from_utf8(payload[68..].to_vec())
+                .map_err(|_| error!(MessengerError::InvalidEmitterLabel))?;
+            Ok(GovernanceAction::RegisterChain {
+                chain_id: get_u16(&payload[33..35])?,
+                emitter_addr: get_u32_array(&payload[35..67])?,
+                address_format,
+                label,
+            })
+        }
+        31 => {
+            check_payload_length(payload, UPDATE_CHAIN_PAYLOAD_LENGTH)?;
+            Ok(GovernanceAction::UpdateChain {
+                chain_id: get_u16(&payload[33..35])?,
+                enabled: payload[35] != 0,
+            })
+        }
+        32 => {
+            check_payload_length(payload, SET_CHAIN_LIMIT_PAYLOAD_LENGTH)?;
+            Ok(GovernanceAction::SetChainLimit {
+                chain_id: get_u16(&payload[33..35])?,
+                daily_limit: get_u64(&payload[35..43])?,
+            })
+        }
+        33 => {
+            check_payload_length(payload, SET_MINT_LIMIT_PAYLOAD_LENGTH)?;
+            Ok(GovernanceAction::SetMintLimit {
+                mint: Pubkey::new_from_array(get_u32_array(&payload[33..65])?),
+                daily_limit: get_u64(&payload[65..73])?,
+                notional_price: get_u64(&payload[73..81])?,
+                large_transfer_threshold: get_u64(&payload[81..89])?,
+                hold_seconds: get_u32(&payload[89..93])?,
+            })
+        }
+        34 => {
+            check_payload_length(payload, REGISTER_TARGET_PROGRAM_PAYLOAD_LENGTH)?;
+            Ok(GovernanceAction::RegisterTargetProgram {
+                version: payload[33],
+                program_id: Pubkey::new_from_array(get_u32_array(&payload[34..66])?),
+            })
+        }
+        35 => {
+            check_payload_length(payload, REMOVE_TARGET_PROGRAM_PAYLOAD_LENGTH)?;
+            Ok(GovernanceAction::RemoveTargetProgram {
+                version: payload[33],
+            })
+        }
+        36 => {
+            check_payload_length(payload, SET_OWNER_PAYLOAD_LENGTH)?;
+            Ok(GovernanceAction::SetOwner {
+                owner: Pubkey::new_from_array(get_u32_array(&payload[33..65])?),
+            })
+        }
+        _ => err!(MessengerError::InvalidPayload),
+    }
 }
 
-fn get_u8(data_bytes: Vec<u8>) -> u64 {
-    let prefix_bytes = vec![0; 7];
-    let joined_bytes = [prefix_bytes, data_bytes].concat();
-    let data_u8 = <[u8; 8]>::try_from(joined_bytes).unwrap();
-    u64::from_be_bytes(data_u8)
+/// Decodes a transaction payload into `transaction_data` the way `store_msg`
+/// records it.
+#[allow(clippy::result_large_err)]
+pub fn decode_payload(
+    payload: &[u8],
+    from_chain_id: u16,
+    sender: [u8; 32],
+    transaction_data: &mut TransactionData,
+) -> Result<()> {
+    match get_code(payload)? {
+        2 => process_stream(payload, from_chain_id, transaction_data, sender),
+        4 => process_withdraw_stream(payload, from_chain_id, transaction_data, sender),
+        6 => process_deposit(payload, from_chain_id, transaction_data, sender),
+        8 => process_pause(payload, from_chain_id, transaction_data, sender),
+        10 => process_withdraw(payload, from_chain_id, transaction_data, sender),
+        12 => process_instant_transfer(payload, from_chain_id, transaction_data, sender),
+        14 => process_update_stream(payload, from_chain_id, transaction_data, sender),
+        16 => process_cancel_stream(payload, from_chain_id, transaction_data, sender),
+        17 => process_direct_transfer(payload, from_chain_id, transaction_data, sender),
+        21 => process_withdraw_stream_bridge(payload, from_chain_id, transaction_data, sender),
+        23 => process_withdraw_stream_payout(payload, from_chain_id, transaction_data, sender),
+        _ => Err(MessengerError::InvalidPayload.into()),
+    }
 }
 
-fn get_u32_array(data_bytes: Vec<u8>) -> [u8;32]{
-    let data_result = data_bytes.try_into().unwrap();
-    return data_result;
+/// Decodes a batch stream (20) payload into `batch_data` the way
+/// `store_batch_msg` records it, every entry pending.
+#[allow(clippy::result_large_err)]
+pub fn decode_batch_stream(
+    payload: &[u8],
+    from_chain_id: u16,
+    sender: [u8; 32],
+    batch_data: &mut BatchData,
+) -> Result<()> {
+    require!(get_code(payload)? == 20, MessengerError::InvalidPayload);
+    let entry_count = *payload
+        .get(BATCH_STREAM_HEADER_LENGTH - 1)
+        .ok_or(MessengerError::InvalidPayloadLength)? as usize;
+    require!(
+        entry_count > 0 && entry_count <= MAX_BATCH_ENTRIES,
+        MessengerError::InvalidBatchSize
+    );
+    batch_data.target_version = check_message_length(
+        payload,
+        BATCH_STREAM_HEADER_LENGTH + entry_count * BATCH_STREAM_ENTRY_LENGTH,
+    )?;
+
+    let to_chain_id = get_u256(&payload[1..33])?;
+    let senderwallet_bytes = get_u32_array(&payload[33..65])?;
+    let token_mint_bytes = get_u32_array(&payload[65..97])?;
+
+    batch_data.sender = senderwallet_bytes;
+    batch_data.from_chain_id = from_chain_id;
+    batch_data.token_mint = Pubkey::new_from_array(token_mint_bytes);
+    batch_data.entries = payload[BATCH_STREAM_HEADER_LENGTH..]
+        .chunks_exact(BATCH_STREAM_ENTRY_LENGTH)
+        .take(entry_count)
+        .map(|entry| {
+            let flags = entry[56];
+            require!(flags & !0b11 == 0, MessengerError::InvalidBatchEntry);
+            Ok(BatchEntry {
+                receiver: get_u32_array(&entry[0..32])?,
+                amount: get_u64(&entry[32..40])?,
+                start_time: get_u64(&entry[40..48])?,
+                end_time: get_u64(&entry[48..56])?,
+                can_cancel: flags & 0b01 != 0,
+                can_update: flags & 0b10 != 0,
+                ..Default::default()
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    require!(
+        senderwallet_bytes == sender,
+        MessengerError::InvalidSenderWallet
+    );
+    require!(
+        to_chain_id == U256::from_str("1").unwrap(),
+        MessengerError::InvalidToChainId
+    );
+    Ok(())
 }
 
 // Convert a full VAA structure into the serialization of its unique components, this structure is
@@ -1318,7 +2935,7 @@ pub fn serialize_vaa(vaa: &MessageData) -> Vec<u8> {
     let mut v = Cursor::new(Vec::new());
     v.write_u32::<BigEndian>(vaa.vaa_time).unwrap();
     v.write_u32::<BigEndian>(vaa.nonce).unwrap();
-    v.write_u16::<BigEndian>(vaa.emitter_chain as u16).unwrap();
+    v.write_u16::<BigEndian>(vaa.emitter_chain).unwrap();
     v.write_all(&vaa.emitter_address).unwrap();
     v.write_u64::<BigEndian>(vaa.sequence).unwrap();
     v.write_u8(vaa.consistency_level).unwrap();
@@ -1326,24 +2943,24 @@ pub fn serialize_vaa(vaa: &MessageData) -> Vec<u8> {
     v.into_inner()
 }
 
+#[allow(clippy::result_large_err)]
 fn process_deposit(
-    encoded_str: Vec<u8>,
+    encoded_str: &[u8],
     from_chain_id: u16,
-    ctx: Context<StoreMsg>,
+    transaction_data: &mut TransactionData,
     sender: [u8;32],
 ) -> Result<()> {
+    transaction_data.target_version = check_message_length(encoded_str, DEPOSIT_PAYLOAD_LENGTH)?;
 
-    let transaction_data = &mut ctx.accounts.data_storage;
-
-    let amount = get_u64(encoded_str[1..9].to_vec());
-    let to_chain_id = get_u256(encoded_str[9..41].to_vec());
-    let senderbytes = get_u32_array(encoded_str[41..73].to_vec());
-    let token_mint_bytes = &encoded_str[73..105].to_vec();
+    let amount = get_u64(&encoded_str[1..9])?;
+    let to_chain_id = get_u256(&encoded_str[9..41])?;
+    let senderbytes = get_u32_array(&encoded_str[41..73])?;
+    let token_mint_bytes = get_u32_array(&encoded_str[73..105])?;
 
     transaction_data.amount = amount;
     transaction_data.sender = senderbytes;
     transaction_data.from_chain_id = from_chain_id;
-    transaction_data.token_mint = Pubkey::new(token_mint_bytes);
+    transaction_data.token_mint = Pubkey::new_from_array(token_mint_bytes);
 
     require!(senderbytes == sender, MessengerError::InvalidSenderWallet);
     require!(
@@ -1353,22 +2970,24 @@ fn process_deposit(
     Ok(())
 }
 
+#[allow(clippy::result_large_err)]
 fn process_stream(
-    encoded_str: Vec<u8>,
+    encoded_str: &[u8],
     from_chain_id: u16,
-    ctx: Context<StoreMsg>,
+    transaction_data: &mut TransactionData,
     sender: [u8;32]
 ) -> Result<()> {
-    let transaction_data = &mut ctx.accounts.data_storage;
-    let start_time = get_u64(encoded_str[1..9].to_vec());
-    let end_time = get_u64(encoded_str[9..17].to_vec());
-    let amount = get_u64(encoded_str[17..25].to_vec());
-    let to_chain_id = get_u256(encoded_str[25..57].to_vec());
-    let senderwallet_bytes = get_u32_array(encoded_str[57..89].to_vec());
-    let receiver_wallet_bytes = get_u32_array(encoded_str[89..121].to_vec());
-    let can_cancel = get_u64(encoded_str[121..129].to_vec());
-    let can_update = get_u64(encoded_str[129..137].to_vec());
-    let token_mint_bytes = &encoded_str[137..169].to_vec();
+    transaction_data.target_version = check_message_length(encoded_str, STREAM_PAYLOAD_LENGTH)?;
+
+    let start_time = get_u64(&encoded_str[1..9])?;
+    let end_time = get_u64(&encoded_str[9..17])?;
+    let amount = get_u64(&encoded_str[17..25])?;
+    let to_chain_id = get_u256(&encoded_str[25..57])?;
+    let senderwallet_bytes = get_u32_array(&encoded_str[57..89])?;
+    let receiver_wallet_bytes = get_u32_array(&encoded_str[89..121])?;
+    let can_cancel = get_u64(&encoded_str[121..129])?;
+    let can_update = get_u64(&encoded_str[129..137])?;
+    let token_mint_bytes = get_u32_array(&encoded_str[137..169])?;
 
     transaction_data.start_time = start_time;
     transaction_data.end_time = end_time;
@@ -1380,7 +2999,7 @@ fn process_stream(
     transaction_data.sender = senderwallet_bytes;
     transaction_data.receiver = receiver_wallet_bytes;
     transaction_data.from_chain_id = from_chain_id;
-    transaction_data.token_mint = Pubkey::new(token_mint_bytes);
+    transaction_data.token_mint = Pubkey::new_from_array(token_mint_bytes);
 
     require!(
         senderwallet_bytes == sender,
@@ -1393,21 +3012,23 @@ fn process_stream(
     Ok(())
 }
 
+#[allow(clippy::result_large_err)]
 fn process_update_stream(
-    encoded_str: Vec<u8>,
+    encoded_str: &[u8],
     from_chain_id: u16,
-    ctx: Context<StoreMsg>,
+    transaction_data: &mut TransactionData,
     sender: [u8;32],
 ) -> Result<()> {
-    let transaction_data = &mut ctx.accounts.data_storage;
-    let start_time = get_u64(encoded_str[1..9].to_vec());
-    let end_time = get_u64(encoded_str[9..17].to_vec());
-    let amount = get_u64(encoded_str[17..25].to_vec());
-    let to_chain_id = get_u256(encoded_str[25..57].to_vec());
-    let senderwallet_bytes = get_u32_array(encoded_str[57..89].to_vec());
-    let receiver_wallet_bytes = get_u32_array(encoded_str[89..121].to_vec());
-    let token_mint = &encoded_str[121..153].to_vec();
-    let data_account = &encoded_str[153..185].to_vec();
+    transaction_data.target_version = check_message_length(encoded_str, STREAM_UPDATE_PAYLOAD_LENGTH)?;
+
+    let start_time = get_u64(&encoded_str[1..9])?;
+    let end_time = get_u64(&encoded_str[9..17])?;
+    let amount = get_u64(&encoded_str[17..25])?;
+    let to_chain_id = get_u256(&encoded_str[25..57])?;
+    let senderwallet_bytes = get_u32_array(&encoded_str[57..89])?;
+    let receiver_wallet_bytes = get_u32_array(&encoded_str[89..121])?;
+    let token_mint = get_u32_array(&encoded_str[121..153])?;
+    let data_account = get_u32_array(&encoded_str[153..185])?;
 
     transaction_data.start_time = start_time;
     transaction_data.end_time = end_time;
@@ -1415,8 +3036,8 @@ fn process_update_stream(
     transaction_data.sender = senderwallet_bytes;
     transaction_data.receiver = receiver_wallet_bytes;
     transaction_data.from_chain_id = from_chain_id;
-    transaction_data.token_mint = Pubkey::new(token_mint);
-    transaction_data.data_account = Pubkey::new(data_account);
+    transaction_data.token_mint = Pubkey::new_from_array(token_mint);
+    transaction_data.data_account = Pubkey::new_from_array(data_account);
 
     require!(
         senderwallet_bytes == sender,
@@ -1429,24 +3050,26 @@ fn process_update_stream(
     Ok(())
 }
 
+#[allow(clippy::result_large_err)]
 fn process_pause(
-    encoded_str: Vec<u8>,
+    encoded_str: &[u8],
     from_chain_id: u16,
-    ctx: Context<StoreMsg>,
+    transaction_data: &mut TransactionData,
     sender: [u8;32],
 ) -> Result<()> {
-    let transaction_data = &mut ctx.accounts.data_storage;
-    let to_chain_id = get_u256(encoded_str[1..33].to_vec());
-    let depositor_wallet_bytes = get_u32_array(encoded_str[33..65].to_vec());
-    let token_mint = &encoded_str[65..97].to_vec();
-    let receiver_wallet_bytes = get_u32_array(encoded_str[97..129].to_vec());
-    let data_account = &encoded_str[129..161].to_vec();
+    transaction_data.target_version = check_message_length(encoded_str, PAUSE_PAYLOAD_LENGTH)?;
+
+    let to_chain_id = get_u256(&encoded_str[1..33])?;
+    let depositor_wallet_bytes = get_u32_array(&encoded_str[33..65])?;
+    let token_mint = get_u32_array(&encoded_str[65..97])?;
+    let receiver_wallet_bytes = get_u32_array(&encoded_str[97..129])?;
+    let data_account = get_u32_array(&encoded_str[129..161])?;
 
     transaction_data.sender = depositor_wallet_bytes;
     transaction_data.receiver = receiver_wallet_bytes;
     transaction_data.from_chain_id = from_chain_id;
-    transaction_data.token_mint = Pubkey::new(token_mint);
-    transaction_data.data_account = Pubkey::new(data_account);
+    transaction_data.token_mint = Pubkey::new_from_array(token_mint);
+    transaction_data.data_account = Pubkey::new_from_array(data_account);
 
     require!(
         depositor_wallet_bytes == sender,
@@ -1460,24 +3083,68 @@ fn process_pause(
 }
 
 //receiver will withdraw streamed tokens (receiver == withdrawer)
+#[allow(clippy::result_large_err)]
 fn process_withdraw_stream(
-    encoded_str: Vec<u8>,
+    encoded_str: &[u8],
+    from_chain_id: u16,
+    transaction_data: &mut TransactionData,
+    receiver: [u8; 32],
+) -> Result<()> {
+    transaction_data.target_version = check_message_length(encoded_str, WITHDRAW_STREAM_PAYLOAD_LENGTH)?;
+
+    let to_chain_id = get_u256(&encoded_str[1..33])?;
+    let withdrawer_wallet_bytes = get_u32_array(&encoded_str[33..65])?;
+    let token_mint = get_u32_array(&encoded_str[65..97])?;
+    let depositor_wallet_bytes = get_u32_array(&encoded_str[97..129])?;
+    let data_account = get_u32_array(&encoded_str[129..161])?;
+
+    transaction_data.sender = depositor_wallet_bytes;
+    transaction_data.receiver = withdrawer_wallet_bytes;
+    transaction_data.from_chain_id = from_chain_id;
+    transaction_data.token_mint = Pubkey::new_from_array(token_mint);
+    transaction_data.data_account = Pubkey::new_from_array(data_account);
+
+    require!(
+        withdrawer_wallet_bytes == receiver,
+        MessengerError::InvalidSenderWallet
+    );
+    require!(
+        to_chain_id == U256::from_str("1").unwrap(),
+        MessengerError::InvalidToChainId
+    );
+
+    Ok(())
+}
+
+//receiver will withdraw streamed tokens and bridge them back to itself
+#[allow(clippy::result_large_err)]
+fn process_withdraw_stream_bridge(
+    encoded_str: &[u8],
     from_chain_id: u16,
-    ctx: Context<StoreMsg>,
+    transaction_data: &mut TransactionData,
     receiver: [u8; 32],
 ) -> Result<()> {
-    let transaction_data = &mut ctx.accounts.data_storage;
-    let to_chain_id = get_u256(encoded_str[1..33].to_vec());
-    let withdrawer_wallet_bytes = get_u32_array(encoded_str[33..65].to_vec());
-    let token_mint = &encoded_str[65..97].to_vec();
-    let depositor_wallet_bytes = get_u32_array(encoded_str[97..129].to_vec());
-    let data_account = &encoded_str[129..161].to_vec();
+    transaction_data.target_version = check_message_length(encoded_str, WITHDRAW_STREAM_BRIDGE_PAYLOAD_LENGTH)?;
+
+    let to_chain_id = get_u256(&encoded_str[1..33])?;
+    let withdrawer_wallet_bytes = get_u32_array(&encoded_str[33..65])?;
+    let token_mint = get_u32_array(&encoded_str[65..97])?;
+    let depositor_wallet_bytes = get_u32_array(&encoded_str[97..129])?;
+    let data_account = get_u32_array(&encoded_str[129..161])?;
+    let target_chain = get_u16(&encoded_str[161..163])?;
+    let fee = get_u64(&encoded_str[163..171])?;
 
     transaction_data.sender = depositor_wallet_bytes;
     transaction_data.receiver = withdrawer_wallet_bytes;
     transaction_data.from_chain_id = from_chain_id;
-    transaction_data.token_mint = Pubkey::new(token_mint);
-    transaction_data.data_account = Pubkey::new(data_account);
+    transaction_data.token_mint = Pubkey::new_from_array(token_mint);
+    transaction_data.data_account = Pubkey::new_from_array(data_account);
+    transaction_data.bridge_back = BridgeBack::To {
+        target_chain,
+        fee,
+        balance_before: 0,
+        bridged: false,
+    };
 
     require!(
         withdrawer_wallet_bytes == receiver,
@@ -1487,28 +3154,53 @@ fn process_withdraw_stream(
         to_chain_id == U256::from_str("1").unwrap(),
         MessengerError::InvalidToChainId
     );
+    // Chain 0 is no chain, and the tokens are on Solana already.
+    require!(
+        target_chain != 0 && target_chain != SOLANA_CHAIN_ID,
+        MessengerError::InvalidToChainId
+    );
+
+    Ok(())
+}
 
+//receiver will withdraw streamed tokens to its linked payout wallet
+#[allow(clippy::result_large_err)]
+fn process_withdraw_stream_payout(
+    encoded_str: &[u8],
+    from_chain_id: u16,
+    transaction_data: &mut TransactionData,
+    receiver: [u8; 32],
+) -> Result<()> {
+    // Laid out like a withdraw stream (4) payload.
+    process_withdraw_stream(encoded_str, from_chain_id, transaction_data, receiver)?;
+    transaction_data.payout = Payout::To {
+        wallet: Pubkey::default(),
+        balance_before: 0,
+        paid: false,
+    };
     Ok(())
 }
 
+#[allow(clippy::result_large_err)]
 fn process_cancel_stream(
-    encoded_str: Vec<u8>,
+    encoded_str: &[u8],
     from_chain_id: u16,
-    ctx: Context<StoreMsg>,
+    transaction_data: &mut TransactionData,
     sender: [u8;32],
 ) -> Result<()> {
-    let transaction_data = &mut ctx.accounts.data_storage;
-    let to_chain_id = get_u256(encoded_str[1..33].to_vec());
-    let depositor_wallet_bytes = get_u32_array(encoded_str[33..65].to_vec());
-    let token_mint = &encoded_str[65..97].to_vec();
-    let receiver_wallet_bytes = get_u32_array(encoded_str[97..129].to_vec());
-    let data_account = &encoded_str[129..161].to_vec();
+    transaction_data.target_version = check_message_length(encoded_str, CANCEL_PAYLOAD_LENGTH)?;
+
+    let to_chain_id = get_u256(&encoded_str[1..33])?;
+    let depositor_wallet_bytes = get_u32_array(&encoded_str[33..65])?;
+    let token_mint = get_u32_array(&encoded_str[65..97])?;
+    let receiver_wallet_bytes = get_u32_array(&encoded_str[97..129])?;
+    let data_account = get_u32_array(&encoded_str[129..161])?;
 
     transaction_data.sender = depositor_wallet_bytes;
     transaction_data.receiver = receiver_wallet_bytes;
     transaction_data.from_chain_id = from_chain_id;
-    transaction_data.token_mint = Pubkey::new(token_mint);
-    transaction_data.data_account = Pubkey::new(data_account);
+    transaction_data.token_mint = Pubkey::new_from_array(token_mint);
+    transaction_data.data_account = Pubkey::new_from_array(data_account);
 
     require!(
         depositor_wallet_bytes == sender,
@@ -1523,21 +3215,23 @@ fn process_cancel_stream(
 }
 
 //sender will withdraw deposited token
+#[allow(clippy::result_large_err)]
 fn process_withdraw(
-    encoded_str: Vec<u8>,
+    encoded_str: &[u8],
     from_chain_id: u16,
-    ctx: Context<StoreMsg>,
+    transaction_data: &mut TransactionData,
     sender: [u8;32],
 ) -> Result<()> {
-    let transaction_data = &mut ctx.accounts.data_storage;
-    let amount = get_u64(encoded_str[1..9].to_vec());
-    let to_chain_id = get_u256(encoded_str[9..41].to_vec());
-    let withdrawer_wallet_bytes = get_u32_array(encoded_str[41..73].to_vec());
-    let token_mint = &encoded_str[73..105].to_vec();
+    transaction_data.target_version = check_message_length(encoded_str, WITHDRAW_PAYLOAD_LENGTH)?;
+
+    let amount = get_u64(&encoded_str[1..9])?;
+    let to_chain_id = get_u256(&encoded_str[9..41])?;
+    let withdrawer_wallet_bytes = get_u32_array(&encoded_str[41..73])?;
+    let token_mint = get_u32_array(&encoded_str[73..105])?;
 
     transaction_data.sender = withdrawer_wallet_bytes;
     transaction_data.from_chain_id = from_chain_id;
-    transaction_data.token_mint = Pubkey::new(token_mint);
+    transaction_data.token_mint = Pubkey::new_from_array(token_mint);
     transaction_data.amount = amount;
 
     require!(
@@ -1551,24 +3245,25 @@ fn process_withdraw(
     Ok(())
 }
 
+#[allow(clippy::result_large_err)]
 fn process_instant_transfer(
-    encoded_str: Vec<u8>,
+    encoded_str: &[u8],
     from_chain_id: u16,
-    ctx: Context<StoreMsg>,
+    transaction_data: &mut TransactionData,
     sender: [u8;32],
 ) -> Result<()> {
-    let transaction_data = &mut ctx.accounts.data_storage;
+    transaction_data.target_version = check_message_length(encoded_str, INSTANT_TRANSFER_PAYLOAD_LENGTH)?;
 
-    let amount = get_u64(encoded_str[1..9].to_vec());
-    let to_chain_id = get_u256(encoded_str[9..41].to_vec());
-    let senderwallet_bytes = get_u32_array(encoded_str[41..73].to_vec());
-    let token_mint = &encoded_str[73..105].to_vec();
-    let withdrawer_wallet_bytes = get_u32_array(encoded_str[105..137].to_vec());
+    let amount = get_u64(&encoded_str[1..9])?;
+    let to_chain_id = get_u256(&encoded_str[9..41])?;
+    let senderwallet_bytes = get_u32_array(&encoded_str[41..73])?;
+    let token_mint = get_u32_array(&encoded_str[73..105])?;
+    let withdrawer_wallet_bytes = get_u32_array(&encoded_str[105..137])?;
 
     transaction_data.sender = senderwallet_bytes;
     transaction_data.receiver = withdrawer_wallet_bytes;
     transaction_data.from_chain_id = from_chain_id;
-    transaction_data.token_mint = Pubkey::new(token_mint);
+    transaction_data.token_mint = Pubkey::new_from_array(token_mint);
     transaction_data.amount = amount;
 
     require!(
@@ -1582,25 +3277,48 @@ fn process_instant_transfer(
     Ok(())
 }
 
+#[allow(clippy::result_large_err)]
 fn process_direct_transfer(
-    encoded_str: Vec<u8>,
+    encoded_str: &[u8],
     from_chain_id: u16,
-    ctx: Context<StoreMsg>,
+    transaction_data: &mut TransactionData,
     sender: [u8;32],
 ) -> Result<()> {
-    let transaction_data = &mut ctx.accounts.data_storage;
+    // The second layout appends the target chain and maximum fee.
+    let fixed = encoded_str.len() >= DIRECT_TRANSFER_V2_PAYLOAD_LENGTH;
+    transaction_data.target_version = check_message_length(
+        encoded_str,
+        if fixed {
+            DIRECT_TRANSFER_V2_PAYLOAD_LENGTH
+        } else {
+            DIRECT_TRANSFER_PAYLOAD_LENGTH
+        },
+    )?;
 
-    let amount = get_u64(encoded_str[1..9].to_vec());
-    let to_chain_id = get_u256(encoded_str[9..41].to_vec());
-    let senderwallet_bytes = get_u32_array(encoded_str[41..73].to_vec());
-    let token_mint = &encoded_str[73..105].to_vec();
-    let withdrawer_wallet_bytes = get_u32_array(encoded_str[105..137].to_vec());
+    let amount = get_u64(&encoded_str[1..9])?;
+    let to_chain_id = get_u256(&encoded_str[9..41])?;
+    let senderwallet_bytes = get_u32_array(&encoded_str[41..73])?;
+    let token_mint = get_u32_array(&encoded_str[73..105])?;
+    let withdrawer_wallet_bytes = get_u32_array(&encoded_str[105..137])?;
 
     transaction_data.sender = senderwallet_bytes;
     transaction_data.receiver = withdrawer_wallet_bytes;
     transaction_data.from_chain_id = from_chain_id;
-    transaction_data.token_mint = Pubkey::new(token_mint);
+    transaction_data.token_mint = Pubkey::new_from_array(token_mint);
     transaction_data.amount = amount;
+    transaction_data.transfer_terms = TransferTerms::Open;
+    if fixed {
+        let target_chain = get_u16(&encoded_str[137..139])?;
+        let max_fee = get_u64(&encoded_str[139..147])?;
+        require!(
+            target_chain != 0 && target_chain != SOLANA_CHAIN_ID,
+            MessengerError::InvalidToChainId
+        );
+        transaction_data.transfer_terms = TransferTerms::Fixed {
+            target_chain,
+            max_fee,
+        };
+    }
 
     require!(
         senderwallet_bytes == sender,
@@ -1613,14 +3331,499 @@ fn process_direct_transfer(
     Ok(())
 }
 
+#[allow(clippy::result_large_err)]
+fn message_details(
+    data: &TransactionData,
+    target_program: Pubkey,
+    relayer: Pubkey,
+    decimals: Option<u8>,
+) -> Result<MessageDetails> {
+    Ok(MessageDetails {
+        code: data.code,
+        from_chain: data.from_chain_id,
+        vaa_sequence: data.vaa_sequence,
+        receiver: data.receiver,
+        mint: data.token_mint,
+        amount: data.amount,
+        start_time: data.start_time,
+        end_time: data.end_time,
+        data_account: data.data_account,
+        target_program,
+        relayer,
+        target_version: data.target_version,
+        normalized_amount: decimals.map(|decimals| normalize_amount(data.amount, decimals)),
+    })
+}
+
+#[allow(clippy::result_large_err)]
+fn batch_entry_details(
+    batch: &BatchData,
+    index: u8,
+    target_program: Pubkey,
+    relayer: Pubkey,
+    decimals: u8,
+) -> Result<MessageDetails> {
+    let entry = &batch.entries[index as usize];
+    Ok(MessageDetails {
+        code: 20,
+        from_chain: batch.from_chain_id,
+        vaa_sequence: batch.vaa_sequence,
+        receiver: entry.receiver,
+        mint: batch.token_mint,
+        amount: entry.amount,
+        start_time: entry.start_time,
+        end_time: entry.end_time,
+        data_account: entry.data_account,
+        target_program,
+        relayer,
+        target_version: batch.target_version,
+        normalized_amount: Some(normalize_amount(entry.amount, decimals)),
+    })
+}
+
+// Checks the Zebec accounts of a receiver withdraw against the stored
+// message. `sender` is the receiver, who sent the message.
+#[allow(clippy::result_large_err)]
+fn check_receiver_withdraw(
+    accs: &[TransactionAccount],
+    data_storage: &TransactionData,
+    sender: [u8; 32],
+    program_id: &Pubkey,
+) -> Result<()> {
+    //check Mint passed
+    let mint_pubkey_passed: Pubkey = accs[12].pubkey;
+    require!(
+        mint_pubkey_passed == data_storage.token_mint,
+        MessengerError::MintKeyMismatch
+    );
+
+    //check data account
+    let data_account_passed: Pubkey = accs[6].pubkey;
+    require!(
+        data_account_passed == data_storage.data_account,
+        MessengerError::DataAccountMismatch
+    );
+
+    //check sender
+    let pda_sender_passed: Pubkey = accs[2].pubkey;
+    let sender_stored = data_storage.sender;
+
+    //check receiver
+    let pda_receiver_passed: Pubkey = accs[1].pubkey;
+    let receiver_stored = data_storage.receiver;
+    require!(
+        sender == receiver_stored,
+        MessengerError::PdaReceiverMismatch
+    );
+
+    //check pdaSender
+    let chain_id_stored = data_storage.from_chain_id;
+    let chain_id_seed = chain_id_stored.to_be_bytes();
+    let sender_derived_pubkey: (Pubkey, u8) =
+        Pubkey::find_program_address(&[&sender_stored, &chain_id_seed], program_id);
+    require!(
+        pda_sender_passed == sender_derived_pubkey.0,
+        MessengerError::SenderDerivedKeyMismatch
+    );
+
+    //check pdaReceiver
+    let receiver_derived_pubkey: (Pubkey, u8) =
+        Pubkey::find_program_address(&[&receiver_stored, &chain_id_seed], program_id);
+    require!(
+        pda_receiver_passed == receiver_derived_pubkey.0,
+        MessengerError::ReceiverDerivedKeyMismatch
+    );
+    Ok(())
+}
+
+// Checks a withdraw stream and bridge (21) message can be bridged. Returns the
+// target chain, then what Zebec paid the receiver and the fee, both in the
+// mint's base units.
+#[allow(clippy::result_large_err)]
+fn bridge_withdrawal(
+    data_storage: &TransactionData,
+    txn_status: &TransactionStatus,
+    from: &TokenAccount,
+    pda_signer: &Pubkey,
+    receiver: [u8; 32],
+    program_id: &Pubkey,
+) -> Result<(u16, u64, u64)> {
+    require!(txn_status.executed, MessengerError::TransactionNotExecuted);
+    let (target_chain, fee, balance_before) = match data_storage.bridge_back {
+        BridgeBack::To { bridged: true, .. } => return err!(MessengerError::AlreadyBridged),
+        BridgeBack::To {
+            target_chain,
+            fee,
+            balance_before,
+            ..
+        } => (target_chain, fee, balance_before),
+        _ => return err!(MessengerError::BridgeBackMismatch),
+    };
+
+    let amount = withdrawn_amount(
+        data_storage,
+        from,
+        pda_signer,
+        receiver,
+        balance_before,
+        program_id,
+    )?;
+    require!(fee <= amount, MessengerError::FeeExceedsAmount);
+    Ok((target_chain, amount, fee))
+}
+
+// Checks the target chain and fee the relayer passed to a direct transfer
+// against what its payload fixed, if anything. `fee` is in base units.
+#[allow(clippy::result_large_err)]
+fn check_transfer_terms(
+    data_storage: &TransactionData,
+    target_chain: u16,
+    fee: u64,
+    amount: u64,
+) -> Result<()> {
+    require!(fee <= amount, MessengerError::FeeExceedsAmount);
+    // Payloads without terms only go back to the chain they came from, and
+    // for free.
+    let (fixed_chain, max_fee) = match data_storage.transfer_terms.resolve() {
+        TransferTerms::Fixed {
+            target_chain,
+            max_fee,
+        } => (target_chain, max_fee),
+        _ => (data_storage.from_chain_id, 0),
+    };
+    require!(
+        target_chain == fixed_chain,
+        MessengerError::TargetChainMismatch
+    );
+    require!(fee <= max_fee, MessengerError::FeeExceedsMaximum);
+    Ok(())
+}
+
+fn mark_bridged(data_storage: &mut TransactionData) {
+    if let BridgeBack::To { bridged, .. } = &mut data_storage.bridge_back {
+        *bridged = true;
+    }
+}
+
+// Emits `TransferHeld` for a transfer `clear` just held.
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::result_large_err)]
+fn emit_held(
+    data_storage: &TransactionData,
+    sender: [u8; 32],
+    current_count: u64,
+    target_chain: u16,
+    amount: u64,
+    target_program: Pubkey,
+    relayer: Pubkey,
+    decimals: u8,
+) -> Result<()> {
+    if let Clearance::Held { release_at } = data_storage.clearance {
+        emit!(TransferHeld {
+            sender,
+            current_count,
+            target_chain,
+            amount,
+            release_at,
+            version: EVENT_VERSION,
+            message: message_details(data_storage, target_program, relayer, Some(decimals))?,
+        });
+    }
+    Ok(())
+}
+
+// What the receiver withdraw of `data_storage` paid into `from`, the token
+// account of the receiver PDA `pda_signer`. Only what the withdraw added
+// counts; anything there before stays.
+#[allow(clippy::result_large_err)]
+fn withdrawn_amount(
+    data_storage: &TransactionData,
+    from: &TokenAccount,
+    pda_signer: &Pubkey,
+    receiver: [u8; 32],
+    balance_before: u64,
+    program_id: &Pubkey,
+) -> Result<u64> {
+    //check receiver
+    require!(
+        receiver == data_storage.receiver,
+        MessengerError::PdaReceiverMismatch
+    );
+
+    //check pdaReceiver
+    let chain_id_seed = data_storage.from_chain_id.to_be_bytes();
+    let (receiver_derived_pubkey, _): (Pubkey, u8) =
+        Pubkey::find_program_address(&[&receiver, &chain_id_seed], program_id);
+    require!(
+        *pda_signer == receiver_derived_pubkey,
+        MessengerError::ReceiverDerivedKeyMismatch
+    );
+
+    let amount = from
+        .amount
+        .checked_sub(balance_before)
+        .filter(|amount| *amount > 0)
+        .ok_or(MessengerError::NothingWithdrawn)?;
+    Ok(amount)
+}
+
+// Checks Zebec pays a receiver withdraw into `receiver_token_account`, owned
+// by the receiver PDA, and returns its balance before the withdraw.
+#[allow(clippy::result_large_err)]
+fn receiver_token_balance(
+    accs: &[TransactionAccount],
+    receiver_token_account: &Account<TokenAccount>,
+) -> Result<u64> {
+    require!(
+        accs[RECEIVER_TOKEN_ACCOUNT_INDEX].pubkey == receiver_token_account.key()
+            && receiver_token_account.owner == accs[1].pubkey,
+        MessengerError::ReceiverTokenAccountMismatch
+    );
+    Ok(receiver_token_account.amount)
+}
+
+// Reads a posted VAA and checks it is the core bridge's account for it.
+#[allow(clippy::result_large_err)]
+fn posted_vaa(core_bridge_vaa: &AccountInfo) -> Result<MessageData> {
+    //Hash a VAA Extract and derive a VAA Key
+    let vaa = PostedMessageData::try_from_slice(&core_bridge_vaa.data.borrow())?.0;
+    let serialized_vaa = serialize_vaa(&vaa);
+
+    let mut h = sha3::Keccak256::default();
+    h.write_all(serialized_vaa.as_slice()).unwrap();
+    let vaa_hash: [u8; 32] = h.finalize().into();
+
+    let vaa_key = Pubkey::find_program_address(
+        &[b"PostedVAA", &vaa_hash],
+        &Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap(),
+    )
+    .0;
+
+    require!(
+        core_bridge_vaa.key() == vaa_key,
+        MessengerError::VAAKeyMismatch
+    );
+    Ok(vaa)
+}
+
+// Reads the posted VAA a message is stored from and checks it came through
+// the core bridge from the registered, enabled emitter of its chain.
+#[allow(clippy::result_large_err)]
+fn verified_vaa(
+    core_bridge_vaa: &AccountInfo,
+    emitter_acc: &EmitterAccount,
+    sender: &[u8; 32],
+) -> Result<MessageData> {
+    let vaa = posted_vaa(core_bridge_vaa)?;
+
+    // Already checked that the SignedVaa is owned by core bridge in account constraint logic
+    // Check that the emitter chain and address match up with the vaa
+    require!(
+        vaa.emitter_chain == emitter_acc.chain_id
+            && vaa.emitter_address == emitter_acc.emitter_addr,
+        MessengerError::VAAEmitterMismatch
+    );
+    require!(emitter_acc.enabled, MessengerError::EmitterDisabled);
+    // The sender's accounts are keyed by its address in the chain's own form.
+    require!(
+        emitter_acc.address_format.is_normalized(sender),
+        MessengerError::InvalidSenderAddress
+    );
+    Ok(vaa)
+}
+
+// Reads a governance VAA, checks it came from the governance emitter of
+// `config` and returns its action. `processed_vaa` is the claim of the VAA.
+#[allow(clippy::result_large_err)]
+fn governed(
+    core_bridge_vaa: &AccountInfo,
+    config: &Config,
+    processed_vaa: &mut Account<ProcessedVAA>,
+    program_id: &Pubkey,
+) -> Result<GovernanceAction> {
+    let vaa = posted_vaa(core_bridge_vaa)?;
+    check_governance_emitter(config.governance, &vaa)?;
+    let action = decode_governance(&vaa.payload, program_id)?;
+    stamp(processed_vaa);
+
+    emit!(Governed {
+        code: action.code(),
+        vaa_sequence: vaa.sequence
+    });
+    Ok(action)
+}
+
+// Registers a chain for `register_chain` and its governance action.
+#[allow(clippy::result_large_err)]
+fn register_emitter(
+    emitter_acc: &mut Account<EmitterAccount>,
+    chain_id: u16,
+    emitter_addr: [u8; 32],
+    label: String,
+    address_format: AddressFormat,
+) -> Result<()> {
+    // Older clients leave the format out; they only registered EVM chains.
+    let address_format = address_format.resolve();
+    require!(
+        address_format.is_normalized(&emitter_addr),
+        MessengerError::InvalidEmitterAddress
+    );
+    require!(
+        label.len() <= EMITTER_LABEL_MAX_LENGTH,
+        MessengerError::InvalidEmitterLabel
+    );
+
+    emitter_acc.chain_id = chain_id;
+    emitter_acc.emitter_addr = emitter_addr;
+    emitter_acc.registered_at = Clock::get()?.unix_timestamp;
+    emitter_acc.enabled = true;
+    emitter_acc.label = label.clone();
+    emitter_acc.address_format = address_format;
+    stamp(emitter_acc);
+
+    emit!(RegisteredChain {
+        chain_id,
+        emitter_addr: encode(emitter_addr),
+        label,
+        address_format,
+        emitter_address: emitter_addr
+    });
+    Ok(())
+}
+
+#[allow(clippy::result_large_err)]
+fn update_emitter(
+    emitter_acc: &mut Account<EmitterAccount>,
+    chain_id: u16,
+    enabled: bool,
+    label: Option<String>,
+) -> Result<()> {
+    if let Some(label) = label {
+        require!(
+            label.len() <= EMITTER_LABEL_MAX_LENGTH,
+            MessengerError::InvalidEmitterLabel
+        );
+        emitter_acc.label = label;
+    }
+    emitter_acc.enabled = enabled;
+
+    emit!(UpdatedChain {
+        chain_id,
+        enabled,
+        label: emitter_acc.label.clone()
+    });
+    Ok(())
+}
+
+#[allow(clippy::result_large_err)]
+fn write_chain_limit(
+    chain_limit: &mut Account<ChainLimit>,
+    chain_id: u16,
+    daily_limit: u64,
+) -> Result<()> {
+    chain_limit.chain_id = chain_id;
+    chain_limit.daily_limit = daily_limit;
+    stamp(chain_limit);
+
+    emit!(ChainLimitSet {
+        chain_id,
+        daily_limit
+    });
+    Ok(())
+}
+
+#[allow(clippy::result_large_err)]
+fn write_mint_limit(
+    mint_limit: &mut Account<MintLimit>,
+    mint: Pubkey,
+    daily_limit: u64,
+    notional_price: u64,
+    large_transfer_threshold: u64,
+    hold_seconds: u32,
+) -> Result<()> {
+    mint_limit.mint = mint;
+    mint_limit.daily_limit = daily_limit;
+    mint_limit.notional_price = notional_price;
+    mint_limit.large_transfer_threshold = large_transfer_threshold;
+    mint_limit.hold_seconds = hold_seconds;
+    stamp(mint_limit);
+
+    emit!(MintLimitSet {
+        mint,
+        daily_limit,
+        notional_price,
+        large_transfer_threshold,
+        hold_seconds
+    });
+    Ok(())
+}
+
+#[allow(clippy::result_large_err)]
+fn write_target_program(
+    target_program: &mut Account<TargetProgram>,
+    version: u8,
+    program_id: Pubkey,
+    own_program_id: &Pubkey,
+) -> Result<()> {
+    require!(
+        program_id != *own_program_id,
+        MessengerError::UnregisteredTargetProgram
+    );
+    target_program.version = version;
+    target_program.program_id = program_id;
+    stamp(target_program);
+
+    emit!(RegisteredTargetProgram {
+        version,
+        program_id
+    });
+    Ok(())
+}
+
+// Moves the sender's count on to `current_count`, which must be the next one.
+#[allow(clippy::result_large_err)]
+fn next_count(txn_count: &mut Count, current_count: u64) -> Result<()> {
+    txn_count.count = txn_count
+        .count
+        .checked_add(1)
+        .ok_or(MessengerError::Overflow)?;
+    require!(txn_count.count == current_count, MessengerError::InvalidCount);
+    Ok(())
+}
+
+// Emits `MessageFailed` and hands `error` back for the caller to return.
+fn message_failed(sender: [u8; 32], current_count: u64, from_chain: u16, error: Error) -> Error {
+    let error_code = match &error {
+        Error::AnchorError(error) => error.error_code_number as u64,
+        Error::ProgramError(error) => u64::from(error.program_error.clone()),
+    };
+    emit!(MessageFailed {
+        sender,
+        current_count,
+        version: EVENT_VERSION,
+        from_chain,
+        error_code,
+    });
+    error
+}
+
+#[allow(clippy::result_large_err)]
 fn perform_cpi(
     chain_id: u16,
     sender: [u8; 32],
     transaction: Account<Transaction>,
+    target_program: &TargetProgram,
     pda_signer: UncheckedAccount,
     bumps: BTreeMap<String, u8>,
     remaining_accounts: &[AccountInfo],
-) -> std::result::Result<(), anchor_lang::prelude::ProgramError> {
+) -> Result<()> {
+    // Only programs in the registry can be called, even if the transaction
+    // was built before its entry was removed or replaced.
+    require!(
+        transaction.program_id == target_program.program_id,
+        MessengerError::UnregisteredTargetProgram
+    );
+
     // Execute the transaction signed by the pdasender/pdareceiver.
     let mut ix: Instruction = (transaction).deref().into();
     ix.accounts = ix
@@ -1637,8 +3840,9 @@ fn perform_cpi(
 
     let bump = bumps.get("pda_signer").unwrap().to_le_bytes();
     let seeds: &[&[_]] = &[&sender, &chain_id.to_be_bytes(), bump.as_ref()];
-    let signer = &[&seeds[..]];
+    let signer = &[seeds];
     let accounts = remaining_accounts;
 
     solana_program::program::invoke_signed(&ix, accounts, signer)
+        .map_err(|_| error!(MessengerError::InvalidCPI))
 }
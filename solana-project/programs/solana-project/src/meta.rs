@@ -0,0 +1,261 @@
+//! Meta-transactions signed by EVM accounts.
+//!
+//! Instead of sending a pause, withdraw or update through `Messenger.sol`
+//! and waiting for the guardians, an EVM account can sign it as EIP-712
+//! typed data. The relayer puts a secp256k1 program instruction checking the
+//! signature right before `store_signed_msg`, which reads it back from the
+//! instructions sysvar. The recovered address is the sender, left-padded to
+//! 32 bytes, so the message uses the same PDA and accounts as one from the
+//! sender's chain. Each sender has a nonce, signed along with the action, so
+//! a signature is only good once.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::secp256k1_program;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+use sha3::Digest;
+
+use crate::constants::SOLANA_CHAIN_ID;
+use crate::errors::MessengerError;
+
+pub const DOMAIN_NAME: &str = "Zebec Wormhole Proxy";
+pub const DOMAIN_VERSION: &str = "1";
+
+// The program id stands in for `verifyingContract` as the domain's salt.
+const DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,bytes32 salt)";
+const PAUSE_TYPE: &str =
+    "Pause(uint16 sourceChain,uint64 nonce,bytes32 tokenMint,bytes32 receiver,bytes32 dataAccount)";
+const WITHDRAW_TYPE: &str =
+    "Withdraw(uint16 sourceChain,uint64 nonce,bytes32 tokenMint,bytes32 sender,bytes32 dataAccount)";
+const UPDATE_TYPE: &str = "Update(uint16 sourceChain,uint64 nonce,uint64 startTime,uint64 endTime,uint64 amount,bytes32 receiver,bytes32 tokenMint,bytes32 dataAccount)";
+
+// `SecpSignatureOffsets` of the secp256k1 program, after the signature count.
+const SIGNATURE_OFFSETS_START: usize = 1;
+const SIGNATURE_OFFSETS_LENGTH: usize = 11;
+const ETH_ADDRESS_LENGTH: usize = 20;
+
+/// A Zebec action an EVM account signs. Each is stored as the payload
+/// `Messenger.sol` would have sent for it, with the signer as its sender.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum SignedAction {
+    /// Pauses or resumes a stream of the signer (8).
+    Pause {
+        token_mint: Pubkey,
+        receiver: [u8; 32],
+        data_account: Pubkey,
+    },
+    /// Withdraws from a stream to the signer (4).
+    Withdraw {
+        token_mint: Pubkey,
+        sender: [u8; 32],
+        data_account: Pubkey,
+    },
+    /// Updates a stream of the signer (14).
+    Update {
+        start_time: u64,
+        end_time: u64,
+        amount: u64,
+        receiver: [u8; 32],
+        token_mint: Pubkey,
+        data_account: Pubkey,
+    },
+}
+
+fn keccak(data: &[u8]) -> [u8; 32] {
+    sha3::Keccak256::digest(data).into()
+}
+
+fn word(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+pub fn domain_separator(program_id: &Pubkey) -> [u8; 32] {
+    keccak(
+        &[
+            keccak(DOMAIN_TYPE.as_bytes()),
+            keccak(DOMAIN_NAME.as_bytes()),
+            keccak(DOMAIN_VERSION.as_bytes()),
+            program_id.to_bytes(),
+        ]
+        .concat(),
+    )
+}
+
+impl SignedAction {
+    pub fn code(&self) -> u8 {
+        match self {
+            SignedAction::Pause { .. } => 8,
+            SignedAction::Withdraw { .. } => 4,
+            SignedAction::Update { .. } => 14,
+        }
+    }
+
+    pub fn token_mint(&self) -> Pubkey {
+        match self {
+            SignedAction::Pause { token_mint, .. }
+            | SignedAction::Withdraw { token_mint, .. }
+            | SignedAction::Update { token_mint, .. } => *token_mint,
+        }
+    }
+
+    /// `hashStruct` of the action, signed from `source_chain` with `nonce`.
+    pub fn struct_hash(&self, source_chain: u16, nonce: u64) -> [u8; 32] {
+        let (type_hash, fields) = match self {
+            SignedAction::Pause {
+                token_mint,
+                receiver,
+                data_account,
+            } => (
+                keccak(PAUSE_TYPE.as_bytes()),
+                vec![token_mint.to_bytes(), *receiver, data_account.to_bytes()],
+            ),
+            SignedAction::Withdraw {
+                token_mint,
+                sender,
+                data_account,
+            } => (
+                keccak(WITHDRAW_TYPE.as_bytes()),
+                vec![token_mint.to_bytes(), *sender, data_account.to_bytes()],
+            ),
+            SignedAction::Update {
+                start_time,
+                end_time,
+                amount,
+                receiver,
+                token_mint,
+                data_account,
+            } => (
+                keccak(UPDATE_TYPE.as_bytes()),
+                vec![
+                    word(*start_time),
+                    word(*end_time),
+                    word(*amount),
+                    *receiver,
+                    token_mint.to_bytes(),
+                    data_account.to_bytes(),
+                ],
+            ),
+        };
+        keccak(
+            &[
+                &[type_hash, word(source_chain as u64), word(nonce)][..],
+                &fields[..],
+            ]
+            .concat()
+            .concat(),
+        )
+    }
+
+    /// The payload `Encoder.sol` packs for the action when `signer` sends it.
+    pub fn payload(&self, signer: [u8; 32]) -> Vec<u8> {
+        let to_chain = word(SOLANA_CHAIN_ID as u64);
+        match self {
+            SignedAction::Pause {
+                token_mint,
+                receiver,
+                data_account,
+            } => [
+                &[8u8][..],
+                &to_chain,
+                &signer,
+                token_mint.as_ref(),
+                receiver,
+                data_account.as_ref(),
+            ]
+            .concat(),
+            SignedAction::Withdraw {
+                token_mint,
+                sender,
+                data_account,
+            } => [
+                &[4u8][..],
+                &to_chain,
+                &signer,
+                token_mint.as_ref(),
+                sender,
+                data_account.as_ref(),
+            ]
+            .concat(),
+            SignedAction::Update {
+                start_time,
+                end_time,
+                amount,
+                receiver,
+                token_mint,
+                data_account,
+            } => [
+                &[14u8][..],
+                &start_time.to_be_bytes(),
+                &end_time.to_be_bytes(),
+                &amount.to_be_bytes(),
+                &to_chain,
+                &signer,
+                receiver,
+                token_mint.as_ref(),
+                data_account.as_ref(),
+            ]
+            .concat(),
+        }
+    }
+}
+
+/// What the secp256k1 program checks the signature against. It hashes this
+/// with keccak256 itself, which gives the EIP-712 digest.
+pub fn signed_message(
+    program_id: &Pubkey,
+    source_chain: u16,
+    nonce: u64,
+    action: &SignedAction,
+) -> Vec<u8> {
+    [
+        &[0x19u8, 0x01][..],
+        &domain_separator(program_id),
+        &action.struct_hash(source_chain, nonce),
+    ]
+    .concat()
+}
+
+/// Checks that the instruction before the current one has the secp256k1
+/// program verify one signature by `signer` over `message`, all within its
+/// own data.
+#[allow(clippy::result_large_err)]
+pub fn verify_signature(instructions: &AccountInfo, signer: &[u8], message: &[u8]) -> Result<()> {
+    let current = load_current_index_checked(instructions)?;
+    let index = current
+        .checked_sub(1)
+        .ok_or(MessengerError::InvalidSignatureInstruction)?;
+    let ix = load_instruction_at_checked(index as usize, instructions)?;
+    require!(
+        ix.program_id == secp256k1_program::ID,
+        MessengerError::InvalidSignatureInstruction
+    );
+
+    let data = &ix.data;
+    let offsets = data
+        .get(SIGNATURE_OFFSETS_START..SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LENGTH)
+        .ok_or(MessengerError::InvalidSignatureInstruction)?;
+    let at = |offset: usize| u16::from_le_bytes([offsets[offset], offsets[offset + 1]]) as usize;
+    require!(
+        data[0] == 1
+            && [offsets[2], offsets[5], offsets[10]]
+                .iter()
+                .all(|ix_index| *ix_index as u16 == index),
+        MessengerError::InvalidSignatureInstruction
+    );
+
+    let (eth_address, message_start, message_length) = (at(3), at(6), at(8));
+    let signed_by = data
+        .get(eth_address..eth_address + ETH_ADDRESS_LENGTH)
+        .ok_or(MessengerError::InvalidSignatureInstruction)?;
+    let signed = data
+        .get(message_start..message_start + message_length)
+        .ok_or(MessengerError::InvalidSignatureInstruction)?;
+    require!(
+        signed_by == signer && signed == message,
+        MessengerError::SignatureMismatch
+    );
+    Ok(())
+}
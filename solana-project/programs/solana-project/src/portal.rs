@@ -12,8 +12,10 @@ use std::{
 use crate::constants::TOKEN_BRIDGE_ADDRESS;
 
 pub trait SerializePayload: Sized {
+    #[allow(clippy::result_large_err)]
     fn serialize<W: Write>(&self, writer: &mut W) -> std::result::Result<(), Error>;
 
+    #[allow(clippy::result_large_err)]
     fn try_to_vec(&self) -> std::result::Result<Vec<u8>, Error> {
         let mut result = Vec::with_capacity(256);
         self.serialize(&mut result)?;
@@ -22,6 +24,7 @@ pub trait SerializePayload: Sized {
 }
 
 pub trait DeserializePayload: Sized {
+    #[allow(clippy::result_large_err)]
     fn deserialize(buf: &mut &[u8]) -> std::result::Result<Self, Error>;
 }
 
@@ -269,22 +272,25 @@ impl SerializePayload for PayloadAssetMeta {
 
         writer.write_u8(self.decimals)?;
 
-        let mut symbol: [u8; 32] = [0; 32];
-        let count = cmp::min(symbol.len(), self.symbol.len());
-        symbol[..count].copy_from_slice(self.symbol[..count].as_bytes());
-
-        writer.write_all(&symbol)?;
-
-        let mut name: [u8; 32] = [0; 32];
-        let count = cmp::min(name.len(), self.name.len());
-        name[..count].copy_from_slice(self.name[..count].as_bytes());
-
-        writer.write_all(&name)?;
+        writer.write_all(&padded_string(&self.symbol))?;
+        writer.write_all(&padded_string(&self.name))?;
 
         Ok(())
     }
 }
 
+// Zero-pads `value` to 32 bytes, truncating it at a character boundary so a
+// multi-byte character is never split.
+fn padded_string(value: &str) -> [u8; 32] {
+    let mut padded: [u8; 32] = [0; 32];
+    let mut count = cmp::min(padded.len(), value.len());
+    while !value.is_char_boundary(count) {
+        count -= 1;
+    }
+    padded[..count].copy_from_slice(&value.as_bytes()[..count]);
+    padded
+}
+
 #[error_code]
 pub enum PortalError {
     #[msg("Solitare Custom(0)")]
@@ -309,6 +315,15 @@ pub struct TransferNativeData {
     pub target_chain: u16,
 }
 
+/// Contents of the token bridge's ["meta", wrapped_mint] account, which
+/// records where a wrapped token comes from.
+#[derive(AnchorDeserialize, AnchorSerialize, PartialEq, Debug)]
+pub struct WrappedMeta {
+    pub chain: u16,
+    pub token_address: [u8; 32],
+    pub original_decimals: u8,
+}
+
 #[derive(Clone)]
 pub struct TokenPortalBridge;
 
@@ -0,0 +1,186 @@
+//! Messages relayed in one instruction.
+//!
+//! A message normally takes three transactions: `store_msg`, a
+//! `create_transaction_*` or `transaction_*` instruction, and for the former
+//! `execute_transaction`. `relay` runs them as steps of a single instruction
+//! instead, once the core bridge has posted the VAA. Each step is the data of
+//! one of those instructions and the number of accounts it takes; the
+//! accounts of all steps follow the payer in order, as the steps would have
+//! been sent. A step is handled exactly as the instruction would be, so a
+//! relay fails or succeeds as a whole and leaves the same accounts behind.
+//!
+//! Only whole messages are relayed: a store followed by a create and an
+//! execute, or by a create and execute in one. Their accounts rarely fit a
+//! legacy transaction; the client compiles relays to version 0 transactions
+//! that load the rest from an address lookup table, and stages the message
+//! as before when even that does not fit.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+
+use crate::errors::MessengerError;
+use crate::instruction as ix;
+
+/// One instruction of a relay: its data, discriminator included, and how
+/// many of the remaining accounts are its own.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RelayStep {
+    pub accounts: u8,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StepKind {
+    Store,
+    Create,
+    CreateExecute,
+    Execute,
+}
+
+// What a step does and the sender and count of the message it does it for.
+struct StepMessage {
+    kind: StepKind,
+    sender: [u8; 32],
+    current_count: u64,
+}
+
+type Decode = fn(&[u8]) -> Result<([u8; 32], u64)>;
+
+// The sighash Anchor prefixes the instruction `name` with.
+fn sighash(name: &str) -> [u8; 8] {
+    let mut sighash = [0; 8];
+    sighash.copy_from_slice(&hash(format!("global:{}", name).as_bytes()).to_bytes()[..8]);
+    sighash
+}
+
+#[allow(clippy::result_large_err)]
+fn args<T: AnchorDeserialize>(mut data: &[u8]) -> Result<T> {
+    T::deserialize(&mut data).map_err(|_| error!(MessengerError::InvalidRelayStep))
+}
+
+// Reads the sender and count from the arguments of the instruction `$ix`,
+// where the sender is named `$sender`.
+macro_rules! message_of {
+    ($ix:ident) => {
+        message_of!($ix, sender)
+    };
+    ($ix:ident, $sender:ident) => {
+        |data| args::<ix::$ix>(data).map(|ix| (ix.$sender, ix.current_count))
+    };
+}
+
+#[allow(clippy::result_large_err)]
+fn step_message(data: &[u8]) -> Result<StepMessage> {
+    require!(data.len() >= 8, MessengerError::InvalidRelayStep);
+    let kinds: [(&str, StepKind, Decode); 12] = [
+        ("store_msg", StepKind::Store, message_of!(StoreMsg)),
+        (
+            "create_transaction_stream",
+            StepKind::Create,
+            message_of!(CreateTransactionStream),
+        ),
+        (
+            "create_transaction_receiver_withdraw",
+            StepKind::Create,
+            message_of!(CreateTransactionReceiverWithdraw),
+        ),
+        (
+            "create_transaction_withdraw_bridge",
+            StepKind::Create,
+            message_of!(CreateTransactionWithdrawBridge),
+        ),
+        (
+            "create_transaction_withdraw_payout",
+            StepKind::Create,
+            message_of!(CreateTransactionWithdrawPayout),
+        ),
+        (
+            "create_transaction_cancel",
+            StepKind::Create,
+            message_of!(CreateTransactionCancel),
+        ),
+        (
+            "create_transaction_sender_withdraw",
+            StepKind::Create,
+            message_of!(CreateTransactionSenderWithdraw),
+        ),
+        (
+            "create_transaction_instant_transfer",
+            StepKind::Create,
+            message_of!(CreateTransactionInstantTransfer),
+        ),
+        (
+            "transaction_deposit",
+            StepKind::CreateExecute,
+            message_of!(TransactionDeposit),
+        ),
+        (
+            "transaction_stream_update",
+            StepKind::CreateExecute,
+            message_of!(TransactionStreamUpdate),
+        ),
+        (
+            "transaction_pause_resume",
+            StepKind::CreateExecute,
+            message_of!(TransactionPauseResume),
+        ),
+        (
+            "execute_transaction",
+            StepKind::Execute,
+            message_of!(ExecuteTransaction, eth_add),
+        ),
+    ];
+    let (_, kind, decode) = kinds
+        .iter()
+        .find(|(name, _, _)| sighash(name)[..] == data[..8])
+        .ok_or_else(|| error!(MessengerError::InvalidRelayStep))?;
+    let (sender, current_count) = decode(&data[8..])?;
+    Ok(StepMessage {
+        kind: *kind,
+        sender,
+        current_count,
+    })
+}
+
+/// Fails with `InvalidRelayStep` unless `steps` store a message and then
+/// create and execute its transaction, all for the same sender and count.
+#[allow(clippy::result_large_err)]
+pub fn check_steps(steps: &[RelayStep]) -> Result<()> {
+    let messages = steps
+        .iter()
+        .map(|step| step_message(&step.data))
+        .collect::<Result<Vec<_>>>()?;
+    let kinds: Vec<_> = messages.iter().map(|message| message.kind).collect();
+    require!(
+        matches!(
+            kinds[..],
+            [StepKind::Store, StepKind::CreateExecute]
+                | [StepKind::Store, StepKind::Create, StepKind::Execute]
+        ),
+        MessengerError::InvalidRelayStep
+    );
+    // The transaction account is derived from these too, so every step
+    // handles the one message.
+    let first = &messages[0];
+    require!(
+        messages.iter().all(|message| message.sender == first.sender
+            && message.current_count == first.current_count),
+        MessengerError::InvalidRelayStep
+    );
+    Ok(())
+}
+
+/// Runs each of `steps` with its share of `accounts`, which it has to use up.
+#[allow(clippy::result_large_err)]
+pub fn run_steps(program_id: &Pubkey, steps: &[RelayStep], accounts: &[AccountInfo]) -> Result<()> {
+    let mut rest = accounts;
+    for step in steps {
+        let count = usize::from(step.accounts);
+        require!(count <= rest.len(), MessengerError::InvalidRelayStep);
+        let (own, next) = rest.split_at(count);
+        crate::dispatch(program_id, own, &step.data)?;
+        rest = next;
+    }
+    require!(rest.is_empty(), MessengerError::InvalidRelayStep);
+    Ok(())
+}
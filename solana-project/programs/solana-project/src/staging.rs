@@ -0,0 +1,93 @@
+//! Transactions uploaded in chunks.
+//!
+//! The create instructions take a message's Zebec accounts and instruction
+//! data as arguments, so what fits in one Solana transaction bounds them.
+//! Larger ones are uploaded to a `TransactionBuffer` at
+//! ["transaction_buffer", sender, count] first: `init_transaction` declares
+//! their lengths, `append_accounts` and `append_data` add them in any number
+//! of chunks and `finalize_transaction` fixes the buffer once it is complete.
+//! Like any account an instruction creates, a buffer is at most 10 KiB, which
+//! is also as much as the create instruction can grow a transaction by.
+//!
+//! Nothing in the buffer is checked until it is used. Calling the message's
+//! `create_transaction_*` or `transaction_*` instruction with empty accounts
+//! and data and the finalized buffer as its last remaining account builds
+//! the transaction from the buffer, checks it like any other and closes the
+//! buffer.
+
+use anchor_lang::prelude::*;
+
+use crate::errors::MessengerError;
+use crate::state::{Transaction, TransactionAccount, TransactionBuffer};
+
+pub fn transaction_buffer_address(
+    sender: &[u8; 32],
+    current_count: u64,
+    program_id: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"transaction_buffer", sender, &current_count.to_be_bytes()],
+        program_id,
+    )
+    .0
+}
+
+// Only the relayer that started an upload may add to the buffer, and only
+// until it is finalized.
+#[allow(clippy::result_large_err)]
+pub fn check_uploader(buffer: &TransactionBuffer, uploader: Pubkey) -> Result<()> {
+    require!(uploader == buffer.uploader, MessengerError::InvalidCaller);
+    require!(
+        !buffer.finalized,
+        MessengerError::TransactionBufferFinalized
+    );
+    Ok(())
+}
+
+/// The accounts and data a create instruction builds its transaction from:
+/// `accs` and `data`, or if both are empty those of the message's finalized
+/// buffer, passed as the last remaining account. The buffer's rent pays for
+/// growing `transaction` to fit them and the rest goes back to `payer`, which
+/// has to be the uploader.
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::result_large_err)]
+pub fn unstage(
+    accs: Vec<TransactionAccount>,
+    data: Vec<u8>,
+    transaction: &AccountInfo,
+    payer: &AccountInfo,
+    remaining_accounts: &[AccountInfo],
+    sender: [u8; 32],
+    current_count: u64,
+    program_id: &Pubkey,
+) -> Result<(Vec<TransactionAccount>, Vec<u8>)> {
+    if !accs.is_empty() || !data.is_empty() {
+        return Ok((accs, data));
+    }
+    let info = remaining_accounts
+        .last()
+        .ok_or(MessengerError::TransactionBufferMismatch)?;
+    require!(
+        info.key() == transaction_buffer_address(&sender, current_count, program_id),
+        MessengerError::TransactionBufferMismatch
+    );
+    let buffer: Account<TransactionBuffer> = Account::try_from(info)?;
+    require!(buffer.finalized, MessengerError::TransactionNotFinalized);
+    require!(
+        payer.key() == buffer.uploader,
+        MessengerError::InvalidCaller
+    );
+
+    let space = Transaction::space(buffer.accounts.len(), buffer.data.len());
+    let top_up = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(transaction.lamports());
+    let lamports = info.lamports();
+    **transaction.try_borrow_mut_lamports()? += top_up;
+    **payer.try_borrow_mut_lamports()? += lamports - top_up;
+    **info.try_borrow_mut_lamports()? = 0;
+    info.try_borrow_mut_data()?.fill(0);
+    transaction.realloc(space, false)?;
+
+    Ok((buffer.accounts.clone(), buffer.data.clone()))
+}
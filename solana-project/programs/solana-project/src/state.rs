@@ -1,14 +1,125 @@
+use std::io::{self, Write};
+
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::instruction::Instruction;
 use borsh::{BorshDeserialize, BorshSerialize};
+use sha3::Digest;
+
+use crate::constants::{
+    ACCOUNT_VERSION_LENGTH, MESSAGE_BINDING_LENGTH, RATE_WINDOW_HOURS, SECONDS_PER_HOUR,
+};
+use crate::errors::MessengerError;
+use crate::versioning::AccountVersion;
+
+// Declares an enum appended to an account layout, with `Default`, `resolve`
+// and its serialization. `Unspecified` is written as nothing, so accounts
+// from before the field read it, and `resolve` turns it into the variant it
+// meant then. Any other variant is written as its index and fields, as Borsh
+// does, which is the layout the mirror in `versioning::idl` describes.
+macro_rules! appended_enum {
+    (
+        pub enum $name:ident, $what:literal, Unspecified => $default:ident {
+            $($index:literal => $variant:ident $({ $($field:ident: $ty:ty),* $(,)? })?),* $(,)?
+        }
+    ) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum $name {
+            Unspecified,
+            $($variant $({ $($field: $ty),* })?),*
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name::Unspecified
+            }
+        }
+
+        impl $name {
+            pub fn resolve(self) -> $name {
+                match self {
+                    $name::Unspecified => $name::$default,
+                    value => value,
+                }
+            }
+        }
+
+        impl AnchorSerialize for $name {
+            fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                match self {
+                    $name::Unspecified => Ok(()),
+                    $($name::$variant $({ $($field),* })? => {
+                        let index: u8 = $index;
+                        index.serialize(writer)?;
+                        $($($field.serialize(writer)?;)*)?
+                        Ok(())
+                    })*
+                }
+            }
+        }
+
+        impl AnchorDeserialize for $name {
+            fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+                if buf.is_empty() {
+                    return Ok($name::Unspecified);
+                }
+                match u8::deserialize(buf)? {
+                    $($index => Ok($name::$variant $({ $($field: <$ty>::deserialize(buf)?),* })?),)*
+                    index => Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(concat!("invalid ", $what, " {}"), index),
+                    )),
+                }
+            }
+        }
+    };
+}
 
 #[account]
 #[derive(Default)]
 pub struct Config {
     pub owner: Pubkey,
     pub nonce: u32,
+    // Layout version; fields added since go after it.
+    pub account_version: AccountVersion,
+    // Since version 8.
+    pub governance: Governance,
 }
 
+// The Wormhole emitter whose VAAs may make admin changes next to the owner,
+// see `governance`. Configs from before version 8 read `Unspecified`, which
+// is `Off`.
+appended_enum! {
+    pub enum Governance, "governance", Unspecified => Off {
+        0 => Off,
+        1 => Emitter {
+            chain_id: u16,
+            emitter_address: [u8; 32],
+        },
+    }
+}
+
+impl Governance {
+    // The emitter's chain, 0 while governance is off. Part of the seeds of
+    // the VAAs it claims.
+    pub fn chain_id(self) -> u16 {
+        match self {
+            Governance::Emitter { chain_id, .. } => chain_id,
+            _ => 0,
+        }
+    }
+
+    pub fn emitter_address(self) -> [u8; 32] {
+        match self {
+            Governance::Emitter {
+                emitter_address, ..
+            } => emitter_address,
+            _ => [0u8; 32],
+        }
+    }
+}
+
+// Layout of `EmitterAddress` accounts registered before the address was kept
+// as bytes. Only read by `migrate_emitter`.
 #[account]
 #[derive(Default)]
 pub struct EmitterAddrAccount {
@@ -16,13 +127,73 @@ pub struct EmitterAddrAccount {
     pub emitter_addr: String,
 }
 
+#[account]
+#[derive(Default)]
+pub struct EmitterAccount {
+    pub chain_id: u16,
+    pub emitter_addr: [u8; 32],
+    // Unix time of registration, or of the migration for older accounts.
+    pub registered_at: i64,
+    // VAAs from a disabled emitter are refused.
+    pub enabled: bool,
+    pub label: String,
+    // Layout version; fields added since go after it.
+    pub account_version: AccountVersion,
+    // Since version 2.
+    pub address_format: AddressFormat,
+}
+
+// How a chain writes addresses, which decides the 32 byte form its emitter and
+// senders must be in. Accounts from before version 2 read `Unspecified`, and
+// every chain registered then was EVM.
+appended_enum! {
+    pub enum AddressFormat, "address format", Unspecified => Evm {
+        // 20 byte address left-padded with zeros.
+        0 => Evm,
+        // Native 32 byte addresses.
+        1 => Solana,
+        2 => Aptos,
+        3 => Sui,
+        // SHA-256 of the account id.
+        4 => Near,
+    }
+}
+
+impl AddressFormat {
+    // Whether `address` is a valid address of this format in the 32 byte
+    // form Wormhole and the PDA seeds use.
+    pub fn is_normalized(self, address: &[u8; 32]) -> bool {
+        if *address == [0u8; 32] {
+            return false;
+        }
+        match self.resolve() {
+            AddressFormat::Evm => address[..12] == [0u8; 12],
+            _ => true,
+        }
+    }
+}
+
+// A program stored messages may be executed against, keyed by version.
+#[account]
+#[derive(Default)]
+pub struct TargetProgram {
+    pub version: u8,
+    pub program_id: Pubkey,
+    // Layout version; must stay the last field.
+    pub account_version: AccountVersion,
+}
+
 //Empty account, we just need to check that it *exists*
 #[account]
+#[derive(Default)]
 pub struct ProcessedVAA {
     pub transaction_count: u64,
+    // Layout version; must stay the last field.
+    pub account_version: AccountVersion,
 }
 
 #[account]
+#[derive(Default)]
 pub struct Transaction {
     //450
     // Target program to execute against.32
@@ -33,9 +204,107 @@ pub struct Transaction {
     pub data: Vec<u8>,
     // Boolean ensuring one time execution.1+8
     pub did_execute: bool,
+    // Layout version; fields added since go after it.
+    pub account_version: AccountVersion,
+    // Since version 7.
+    pub binding: MessageBinding,
+}
+
+impl Transaction {
+    // Exact size of a transaction with `accounts_len` accounts and
+    // `data_len` bytes of instruction data.
+    pub fn space(accounts_len: usize, data_len: usize) -> usize {
+        8 + 32
+            + (4 + accounts_len * 34)
+            + (4 + data_len)
+            + 1
+            + ACCOUNT_VERSION_LENGTH
+            + MESSAGE_BINDING_LENGTH
+    }
+
+    // Hash of the program, accounts and data the building handler checked.
+    #[allow(clippy::result_large_err)]
+    pub fn digest(&self) -> Result<[u8; 32]> {
+        let mut h = sha3::Keccak256::default();
+        h.update(self.program_id.as_ref());
+        h.update(&self.accounts.try_to_vec()?);
+        h.update(&self.data);
+        Ok(h.finalize().into())
+    }
+
+    // Ties the transaction to the message it was checked against.
+    #[allow(clippy::result_large_err)]
+    pub fn bind(
+        &mut self,
+        sender: [u8; 32],
+        from_chain_id: u16,
+        current_count: u64,
+        code: u8,
+    ) -> Result<()> {
+        self.binding = MessageBinding::Bound {
+            sender,
+            from_chain_id,
+            current_count,
+            code,
+            digest: self.digest()?,
+        };
+        Ok(())
+    }
+
+    // Fails unless the transaction was built from this message and has not
+    // changed since.
+    #[allow(clippy::result_large_err)]
+    pub fn check_binding(
+        &self,
+        sender: [u8; 32],
+        from_chain_id: u16,
+        current_count: u64,
+        code: u8,
+    ) -> Result<()> {
+        match self.binding.resolve() {
+            MessageBinding::Bound {
+                sender: bound_sender,
+                from_chain_id: bound_chain_id,
+                current_count: bound_count,
+                code: bound_code,
+                digest,
+            } => {
+                require!(
+                    bound_sender == sender
+                        && bound_chain_id == from_chain_id
+                        && bound_count == current_count
+                        && bound_code == code,
+                    MessengerError::TransactionMessageMismatch
+                );
+                require!(
+                    digest == self.digest()?,
+                    MessengerError::TransactionDigestMismatch
+                );
+                Ok(())
+            }
+            _ => err!(MessengerError::UnboundTransaction),
+        }
+    }
+}
+
+// The message a transaction was built from. Accounts from before version 7
+// read `Unspecified` and are `Unbound`; they cannot be executed.
+appended_enum! {
+    pub enum MessageBinding, "message binding", Unspecified => Unbound {
+        0 => Unbound,
+        1 => Bound {
+            sender: [u8; 32],
+            from_chain_id: u16,
+            current_count: u64,
+            code: u8,
+            // `Transaction::digest` when it was built.
+            digest: [u8; 32],
+        },
+    }
 }
 
 #[account]
+#[derive(Default)]
 // TODO: can_update and cancel are bools
 pub struct TransactionData {
     pub sender: [u8;32],
@@ -48,6 +317,92 @@ pub struct TransactionData {
     pub end_time: u64,
     pub can_update: bool,
     pub can_cancel: bool,
+    // Message code the payload was stored under.1
+    pub code: u8,
+    // Sequence of the VAA that carried the message.8
+    pub vaa_sequence: u64,
+    // Version of the target program the message selects.1
+    pub target_version: u8,
+    // Layout version; fields added since go after it.
+    pub account_version: AccountVersion,
+    // Since version 3.
+    pub bridge_back: BridgeBack,
+    // Since version 4.
+    pub payout: Payout,
+    // Since version 5.
+    pub clearance: Clearance,
+    // Since version 6.
+    pub transfer_terms: TransferTerms,
+}
+
+// Whether the tokens a message withdraws are sent back through the token
+// bridge, as a withdraw stream and bridge (21) message asks. Accounts from
+// before version 3 read `Unspecified`; no message stored then bridges back.
+appended_enum! {
+    pub enum BridgeBack, "bridge back", Unspecified => Off {
+        0 => Off,
+        1 => To {
+            target_chain: u16,
+            // Relayer fee as the payload carries it, in the mint's base units.
+            fee: u64,
+            // Balance of the receiver's token account when the withdraw was
+            // created; whatever is above it after execution gets bridged.
+            balance_before: u64,
+            bridged: bool,
+        },
+    }
+}
+
+// Whether the tokens a message withdraws are paid on to the receiver's
+// linked Solana wallet, as a withdraw stream payout (23) message asks.
+// Accounts from before version 4 read `Unspecified`.
+appended_enum! {
+    pub enum Payout, "payout", Unspecified => Off {
+        0 => Off,
+        1 => To {
+            // The receiver's payout wallet when the withdraw was created.
+            wallet: Pubkey,
+            // Balance of the receiver's token account then; whatever is above
+            // it after execution is paid out.
+            balance_before: u64,
+            paid: bool,
+        },
+    }
+}
+
+// Where a transfer the governor limits is in it, see `governor`. Accounts
+// from before version 5 read `Unspecified`.
+appended_enum! {
+    pub enum Clearance, "clearance", Unspecified => Pending {
+        // Not through the governor yet.
+        0 => Pending,
+        // Counted against the daily limits of its chain and mint.
+        1 => Counted,
+        // A large transfer, not counted but held until `release_at`.
+        2 => Held { release_at: i64 },
+    }
+}
+
+impl Clearance {
+    // Whether a held transfer still has to wait at `now`.
+    pub fn is_held(self, now: i64) -> bool {
+        matches!(self, Clearance::Held { release_at } if now < release_at)
+    }
+}
+
+// What a direct transfer (17) payload fixes about the token bridge transfer
+// it asks for. Payloads from before the fields existed read `Open`, and
+// accounts from before version 6 `Unspecified`; those can only be sent back
+// to the chain they came from, with no fee.
+appended_enum! {
+    pub enum TransferTerms, "transfer terms", Unspecified => Open {
+        0 => Open,
+        1 => Fixed {
+            target_chain: u16,
+            // Most the relayer may take, in base units like the amount.
+            max_fee: u64,
+        },
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -58,14 +413,254 @@ pub struct TransactionAccount {
 }
 
 #[account]
+#[derive(Default)]
 pub struct TransactionStatus {
     pub executed: bool,
+    // Layout version; must stay the last field.
+    pub account_version: AccountVersion,
+}
+
+// A message's transaction uploaded in chunks, see `staging`. Kept at
+// ["transaction_buffer", sender, count] until the message's create
+// instruction takes it.
+#[account]
+#[derive(Default)]
+pub struct TransactionBuffer {
+    // The relayer that started the upload; only it may add to the buffer.
+    pub uploader: Pubkey,
+    // Lengths of the whole transaction, declared up front.
+    pub accounts_len: u8,
+    pub data_len: u16,
+    pub accounts: Vec<TransactionAccount>,
+    pub data: Vec<u8>,
+    // Set by `finalize_transaction`, after which the buffer no longer changes.
+    pub finalized: bool,
+    // Layout version; must stay the last field.
+    pub account_version: AccountVersion,
+}
+
+impl TransactionBuffer {
+    // Size of a buffer for `accounts_len` accounts and `data_len` bytes of
+    // instruction data.
+    pub fn space(accounts_len: u8, data_len: u16) -> usize {
+        8 + 32
+            + 1
+            + 2
+            + (4 + accounts_len as usize * 34)
+            + (4 + data_len as usize)
+            + 1
+            + ACCOUNT_VERSION_LENGTH
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.accounts.len() == self.accounts_len as usize
+            && self.data.len() == self.data_len as usize
+    }
+}
+
+// A batch stream (20) message, stored by `store_batch_msg` in place of a
+// `TransactionData`. Each entry becomes its own Zebec stream.
+#[account]
+#[derive(Default)]
+pub struct BatchData {
+    pub sender: [u8; 32],
+    pub from_chain_id: u16,
+    pub token_mint: Pubkey,
+    pub vaa_sequence: u64,
+    pub target_version: u8,
+    // The relayer that stored the batch.
+    pub relayer: Pubkey,
+    pub entries: Vec<BatchEntry>,
+    // Layout version; must stay the last field.
+    pub account_version: AccountVersion,
+}
+
+impl BatchData {
+    pub fn is_finished(&self) -> bool {
+        self.entries.iter().all(|entry| entry.status.is_finished())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct BatchEntry {
+    pub receiver: [u8; 32],
+    pub amount: u64,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub can_cancel: bool,
+    pub can_update: bool,
+    pub status: BatchEntryStatus,
+    // Set once the entry's transaction is created.
+    pub transaction: Pubkey,
+    pub data_account: Pubkey,
+    // Why the entry failed, 0 unless it has.
+    pub error_code: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchEntryStatus {
+    Pending,
+    Created,
+    Executed,
+    Failed,
+}
+
+// `#[default]` on a variant needs a newer compiler than the BPF toolchain.
+#[allow(clippy::derivable_impls)]
+impl Default for BatchEntryStatus {
+    fn default() -> Self {
+        BatchEntryStatus::Pending
+    }
+}
+
+impl BatchEntryStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, BatchEntryStatus::Executed | BatchEntryStatus::Failed)
+    }
+}
+
+// The Solana wallet a source chain identity is paid out to, linked by a set
+// payout wallet (22) message. Kept at ["payout_profile", identity, chain_id].
+#[account]
+#[derive(Default)]
+pub struct PayoutProfile {
+    pub identity: [u8; 32],
+    pub chain_id: u16,
+    // The default key when unlinked.
+    pub wallet: Pubkey,
+    // Sequence of the VAA that set `wallet`; older ones are refused.
+    pub vaa_sequence: u64,
+    // Layout version; must stay the last field.
+    pub account_version: AccountVersion,
+}
+
+// Replay protection of the meta-transactions an EVM account signs, see
+// `meta`. Kept at ["meta_nonce", sender, chain_id].
+#[account]
+#[derive(Default)]
+pub struct MetaNonce {
+    // The nonce the next signed action has to carry.
+    pub nonce: u64,
+    // Layout version; must stay the last field.
+    pub account_version: AccountVersion,
+}
+
+// Amounts sent over the last 24 hours, one bucket per hour.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct RateWindow {
+    // Indexed by unix hour modulo `RATE_WINDOW_HOURS`.
+    pub hourly: [u64; RATE_WINDOW_HOURS],
+    // Unix hour of the newest bucket.
+    pub hour: i64,
+}
+
+impl RateWindow {
+    // Empties the buckets of the hours that fell out of the window by `now`.
+    fn roll(&mut self, now: i64) {
+        let hour = now.div_euclid(SECONDS_PER_HOUR);
+        if hour <= self.hour {
+            return;
+        }
+        if hour - self.hour >= RATE_WINDOW_HOURS as i64 {
+            self.hourly = [0; RATE_WINDOW_HOURS];
+        } else {
+            for stale in self.hour + 1..=hour {
+                self.hourly[stale.rem_euclid(RATE_WINDOW_HOURS as i64) as usize] = 0;
+            }
+        }
+        self.hour = hour;
+    }
+
+    // What was sent in the 24 hours up to `now`.
+    pub fn used(&self, now: i64) -> u64 {
+        let mut window = self.clone();
+        window.roll(now);
+        window
+            .hourly
+            .iter()
+            .fold(0u64, |used, amount| used.saturating_add(*amount))
+    }
+
+    // Adds `amount` at `now`, unless that takes the window past `limit`.
+    #[allow(clippy::result_large_err)]
+    pub fn add(&mut self, now: i64, amount: u64, limit: u64) -> Result<()> {
+        self.roll(now);
+        self.used(now)
+            .checked_add(amount)
+            .filter(|used| *used <= limit)
+            .ok_or(MessengerError::RateLimitExceeded)?;
+        let bucket = &mut self.hourly[self.hour.rem_euclid(RATE_WINDOW_HOURS as i64) as usize];
+        *bucket = bucket.saturating_add(amount);
+        Ok(())
+    }
+}
+
+// How much the governor lets out to a chain over 24 hours, in notional
+// units. Kept at ["chain_limit", chain_id].
+#[account]
+#[derive(Default)]
+pub struct ChainLimit {
+    pub chain_id: u16,
+    pub daily_limit: u64,
+    pub window: RateWindow,
+    // Layout version; must stay the last field.
+    pub account_version: AccountVersion,
+}
+
+// How much of a mint the governor lets out over 24 hours, in its base units,
+// and what a whole token is worth in the notional units of chain limits.
+// Kept at ["mint_limit", mint].
+#[account]
+#[derive(Default)]
+pub struct MintLimit {
+    pub mint: Pubkey,
+    pub daily_limit: u64,
+    // 0 if unpriced; such a mint cannot go to a chain with a limit.
+    pub notional_price: u64,
+    // Transfers of at least this many base units are held instead of counted.
+    pub large_transfer_threshold: u64,
+    pub hold_seconds: u32,
+    pub window: RateWindow,
+    // Layout version; must stay the last field.
+    pub account_version: AccountVersion,
+}
+
+// A mint messages may name, with what the registry knows about it. Kept at
+// ["mint_registry", mint].
+#[account]
+#[derive(Default)]
+pub struct MintRegistry {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    // Bounds of a single amount, in the mint's base units.
+    pub min_amount: u64,
+    pub max_amount: u64,
+    // Whether the token bridge minted it for a token of another chain.
+    pub wrapped: bool,
+    // The token it stands for; Solana and the mint itself unless wrapped.
+    pub origin_chain: u16,
+    pub origin_address: [u8; 32],
+    // Layout version; must stay the last field.
+    pub account_version: AccountVersion,
+}
+
+impl MintRegistry {
+    #[allow(clippy::result_large_err)]
+    pub fn check_amount(&self, amount: u64) -> Result<()> {
+        require!(
+            amount >= self.min_amount && amount <= self.max_amount,
+            MessengerError::AmountOutOfBounds
+        );
+        Ok(())
+    }
 }
 
 #[account]
 #[derive(Default)]
 pub struct Count {
     pub count: u64,
+    // Layout version; must stay the last field.
+    pub account_version: AccountVersion,
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
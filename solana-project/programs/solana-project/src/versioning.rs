@@ -0,0 +1,331 @@
+//! Layout versions of the program's state accounts.
+//!
+//! Every state account has an `AccountVersion` byte after its original
+//! fields. Accounts written before versioning do not have it and read as
+//! version 0. They are written back without it too, so handlers keep working
+//! on them until `migrate_account` reallocates them to the current layout.
+//!
+//! Fields added later go after the version byte and read a default from
+//! older layouts, which they are not written back into. Version 1 added the
+//! version byte, version 2 `EmitterAccount::address_format`, version 3
+//! `TransactionData::bridge_back`, version 4 `TransactionData::payout`,
+//! version 5 `TransactionData::clearance`, version 6
+//! `TransactionData::transfer_terms`, version 7 `Transaction::binding` and
+//! version 8 `Config::governance`.
+
+use std::io::{self, Write};
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_lang::Discriminator;
+
+use crate::errors::MessengerError;
+use crate::state::*;
+
+pub const CURRENT_ACCOUNT_VERSION: u8 = 8;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AccountVersion(pub u8);
+
+impl AccountVersion {
+    // Layout from before versioning, without the version byte.
+    pub const LEGACY: AccountVersion = AccountVersion(0);
+    pub const CURRENT: AccountVersion = AccountVersion(CURRENT_ACCOUNT_VERSION);
+}
+
+impl AnchorSerialize for AccountVersion {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if *self == AccountVersion::LEGACY {
+            return Ok(());
+        }
+        self.0.serialize(writer)
+    }
+}
+
+impl AnchorDeserialize for AccountVersion {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        if buf.is_empty() {
+            return Ok(AccountVersion::LEGACY);
+        }
+        u8::deserialize(buf).map(AccountVersion)
+    }
+}
+
+// The IDL only describes derived types, so these describe the hand-written
+// layouts to TypeScript clients. They match every account in the current
+// layout.
+pub mod idl {
+    use anchor_lang::prelude::*;
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct AccountVersion {
+        pub version: u8,
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub enum AddressFormat {
+        Evm,
+        Solana,
+        Aptos,
+        Sui,
+        Near,
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub enum BridgeBack {
+        Off,
+        To {
+            target_chain: u16,
+            fee: u64,
+            balance_before: u64,
+            bridged: bool,
+        },
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub enum Payout {
+        Off,
+        To {
+            wallet: Pubkey,
+            balance_before: u64,
+            paid: bool,
+        },
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub enum Clearance {
+        Pending,
+        Counted,
+        Held { release_at: i64 },
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub enum TransferTerms {
+        Open,
+        Fixed { target_chain: u16, max_fee: u64 },
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub enum MessageBinding {
+        Unbound,
+        Bound {
+            sender: [u8; 32],
+            from_chain_id: u16,
+            current_count: u64,
+            code: u8,
+            digest: [u8; 32],
+        },
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub enum Governance {
+        Off,
+        Emitter {
+            chain_id: u16,
+            emitter_address: [u8; 32],
+        },
+    }
+}
+
+pub trait Versioned {
+    fn account_version(&self) -> AccountVersion;
+    fn set_account_version(&mut self, version: AccountVersion);
+
+    // Sets the fields appended since the account's version to what they
+    // mean for an account written then.
+    fn fill_appended_fields(&mut self) {}
+}
+
+macro_rules! versioned {
+    ($($account:ty),*) => {
+        $(
+            impl Versioned for $account {
+                fn account_version(&self) -> AccountVersion {
+                    self.account_version
+                }
+
+                fn set_account_version(&mut self, version: AccountVersion) {
+                    self.account_version = version;
+                }
+            }
+        )*
+    };
+}
+
+impl Versioned for Config {
+    fn account_version(&self) -> AccountVersion {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: AccountVersion) {
+        self.account_version = version;
+    }
+
+    fn fill_appended_fields(&mut self) {
+        self.governance = self.governance.resolve();
+    }
+}
+
+impl Versioned for EmitterAccount {
+    fn account_version(&self) -> AccountVersion {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: AccountVersion) {
+        self.account_version = version;
+    }
+
+    fn fill_appended_fields(&mut self) {
+        self.address_format = self.address_format.resolve();
+    }
+}
+
+impl Versioned for Transaction {
+    fn account_version(&self) -> AccountVersion {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: AccountVersion) {
+        self.account_version = version;
+    }
+
+    fn fill_appended_fields(&mut self) {
+        self.binding = self.binding.resolve();
+    }
+}
+
+impl Versioned for TransactionData {
+    fn account_version(&self) -> AccountVersion {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: AccountVersion) {
+        self.account_version = version;
+    }
+
+    fn fill_appended_fields(&mut self) {
+        self.bridge_back = self.bridge_back.resolve();
+        self.payout = self.payout.resolve();
+        self.clearance = self.clearance.resolve();
+        self.transfer_terms = self.transfer_terms.resolve();
+    }
+}
+
+versioned!(
+    TargetProgram,
+    ProcessedVAA,
+    TransactionStatus,
+    TransactionBuffer,
+    BatchData,
+    PayoutProfile,
+    MetaNonce,
+    ChainLimit,
+    MintLimit,
+    MintRegistry,
+    Count
+);
+
+/// Marks a new account as current if it was allocated with room for the
+/// version byte. A `Transaction` allocated by an older client, sized for the
+/// legacy layout, stays at version 0.
+pub fn stamp<'info, T>(account: &mut Account<'info, T>)
+where
+    T: AccountSerialize + AccountDeserialize + Owner + Clone + Versioned,
+{
+    let data_len = account.to_account_info().data_len();
+    let mut data = Vec::new();
+    let mut current = (**account).clone();
+    current.set_account_version(AccountVersion::CURRENT);
+    current.fill_appended_fields();
+    if current.try_serialize(&mut data).is_ok() && data.len() <= data_len {
+        account.set_account_version(AccountVersion::CURRENT);
+        account.fill_appended_fields();
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn upgrade<'info, T>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<AccountVersion>
+where
+    T: AccountSerialize + AccountDeserialize + Versioned,
+{
+    let mut state = T::try_deserialize(&mut &account.data.borrow()[..])?;
+    let from = state.account_version();
+    require!(
+        from < AccountVersion::CURRENT,
+        MessengerError::AccountAlreadyMigrated
+    );
+    state.set_account_version(AccountVersion::CURRENT);
+    state.fill_appended_fields();
+
+    let mut data = Vec::new();
+    state.try_serialize(&mut data)?;
+    if data.len() > account.data_len() {
+        let rent = Rent::get()?.minimum_balance(data.len());
+        if account.lamports() < rent {
+            transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    Transfer {
+                        from: payer.clone(),
+                        to: account.clone(),
+                    },
+                ),
+                rent - account.lamports(),
+            )?;
+        }
+        account.realloc(data.len(), false)?;
+    }
+    account.data.borrow_mut()[..data.len()].copy_from_slice(&data);
+    Ok(from)
+}
+
+/// Rewrites any state account of this program in the current layout, in
+/// place, and returns the version it had.
+#[allow(clippy::result_large_err)]
+pub fn migrate<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<AccountVersion> {
+    let discriminator: [u8; 8] = match account.data.borrow().get(..8) {
+        Some(discriminator) => discriminator.try_into().unwrap(),
+        None => return err!(MessengerError::UnversionedAccount),
+    };
+    if discriminator == Config::discriminator() {
+        upgrade::<Config>(account, payer, system_program)
+    } else if discriminator == EmitterAccount::discriminator() {
+        upgrade::<EmitterAccount>(account, payer, system_program)
+    } else if discriminator == TargetProgram::discriminator() {
+        upgrade::<TargetProgram>(account, payer, system_program)
+    } else if discriminator == ProcessedVAA::discriminator() {
+        upgrade::<ProcessedVAA>(account, payer, system_program)
+    } else if discriminator == Transaction::discriminator() {
+        upgrade::<Transaction>(account, payer, system_program)
+    } else if discriminator == TransactionData::discriminator() {
+        upgrade::<TransactionData>(account, payer, system_program)
+    } else if discriminator == TransactionStatus::discriminator() {
+        upgrade::<TransactionStatus>(account, payer, system_program)
+    } else if discriminator == TransactionBuffer::discriminator() {
+        upgrade::<TransactionBuffer>(account, payer, system_program)
+    } else if discriminator == BatchData::discriminator() {
+        upgrade::<BatchData>(account, payer, system_program)
+    } else if discriminator == PayoutProfile::discriminator() {
+        upgrade::<PayoutProfile>(account, payer, system_program)
+    } else if discriminator == MetaNonce::discriminator() {
+        upgrade::<MetaNonce>(account, payer, system_program)
+    } else if discriminator == ChainLimit::discriminator() {
+        upgrade::<ChainLimit>(account, payer, system_program)
+    } else if discriminator == MintLimit::discriminator() {
+        upgrade::<MintLimit>(account, payer, system_program)
+    } else if discriminator == MintRegistry::discriminator() {
+        upgrade::<MintRegistry>(account, payer, system_program)
+    } else if discriminator == Count::discriminator() {
+        upgrade::<Count>(account, payer, system_program)
+    } else {
+        err!(MessengerError::UnversionedAccount)
+    }
+}
@@ -103,6 +103,12 @@ impl AnchorSerialize for PostedMessageData {
 
 impl AnchorDeserialize for PostedMessageData {
     fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        if buf.len() < 3 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Posted message is shorter than its prefix",
+            ));
+        }
         *buf = &buf[3..];
         Ok(PostedMessageData(
             <MessageData as BorshDeserialize>::deserialize(buf)?,
@@ -0,0 +1,117 @@
+#[cfg(feature = "test-bpf")]
+mod common;
+
+use solana_project::amount::{check_amount, denormalize_amount, normalize_amount};
+#[cfg(feature = "test-bpf")]
+use solana_project::errors::MessengerError;
+#[cfg(feature = "test-bpf")]
+use solana_project::state::{TokenAmount, TransactionStatus};
+
+#[cfg(feature = "test-bpf")]
+use common::*;
+
+#[test]
+fn amounts_normalize_as_the_token_bridge_does() {
+    assert_eq!(normalize_amount(123_456_789, 9), 12_345_678);
+    assert_eq!(denormalize_amount(12_345_678, 9).unwrap(), 123_456_780);
+    assert_eq!(normalize_amount(1_000_000_000_000_000_000, 18), 100_000_000);
+    for decimals in [0, 6, 8] {
+        assert_eq!(normalize_amount(500, decimals), 500);
+        assert_eq!(denormalize_amount(500, decimals).unwrap(), 500);
+    }
+
+    assert!(denormalize_amount(u64::MAX / 10, 9).is_ok());
+    assert!(denormalize_amount(u64::MAX / 10 + 1, 9).is_err());
+    assert!(denormalize_amount(1, 28).is_err());
+    assert_eq!(normalize_amount(u64::MAX, 28), 0);
+
+    assert!(check_amount(5_000, 5_000, 9).is_ok());
+    assert!(check_amount(5_000, 500, 9).is_err());
+    assert!(check_amount(5_001, 5_001, 9).is_err());
+    assert!(check_amount(5_001, 5_001, 8).is_ok());
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn zebec_amounts_are_the_payload_amounts() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = h.create_mint_with_decimals(9);
+    let count = h.store(deposit_payload(5_000, sender, mint), sender).await;
+    let accs = zebec_accounts(10, &[(1, pda_sender), (6, mint)], Some(pda_sender));
+
+    // `Messenger.sol` sends amounts in the mint's base units; they are not
+    // scaled.
+    assert_error(
+        h.create_execute(
+            CreateExecute::Deposit,
+            accs.clone(),
+            zebec_data(&TokenAmount { amount: 50_000 }),
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::AmountMismatch,
+    );
+    h.create_execute(
+        CreateExecute::Deposit,
+        accs,
+        zebec_data(&TokenAmount { amount: 5_000 }),
+        sender,
+        count,
+    )
+    .await
+    .unwrap();
+    let status: TransactionStatus = h.account(txn_status(&sender, count)).await.unwrap();
+    assert!(status.executed);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn amounts_past_eight_decimals_are_refused() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = h.create_mint_with_decimals(18);
+    let accs = zebec_accounts(12, &[(2, pda_sender), (7, mint)], Some(pda_sender));
+
+    // The payload amount matches, but Wormhole would drop its last digit.
+    let count = h
+        .store(
+            sender_withdraw_payload(200_000_000_001, sender, mint),
+            sender,
+        )
+        .await;
+    assert_error(
+        h.create(
+            Create::SenderWithdraw,
+            accs.clone(),
+            zebec_data(&TokenAmount {
+                amount: 200_000_000_001,
+            }),
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::AmountWouldTruncate,
+    );
+
+    let count = h
+        .store(
+            sender_withdraw_payload(200_000_000_000, sender, mint),
+            sender,
+        )
+        .await;
+    h.create(
+        Create::SenderWithdraw,
+        accs,
+        zebec_data(&TokenAmount {
+            amount: 200_000_000_000,
+        }),
+        sender,
+        count,
+    )
+    .await
+    .unwrap();
+}
@@ -0,0 +1,323 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountSerialize;
+use solana_project::errors::MessengerError;
+use solana_project::state::{
+    BatchData, BatchEntry, BatchEntryStatus, Stream, TransactionAccount, TransactionData,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+use common::*;
+
+fn stream_args(entry: &BatchEntry) -> Vec<u8> {
+    zebec_data(&Stream {
+        start_time: entry.start_time,
+        end_time: entry.end_time,
+        amount: entry.amount,
+        can_cancel: entry.can_cancel,
+        can_update: entry.can_update,
+    })
+}
+
+fn payroll() -> Vec<BatchEntry> {
+    vec![
+        batch_entry(wallet(2), 500, 100, 200, true, true),
+        batch_entry(wallet(3), 700, 100, 300, false, true),
+        batch_entry(wallet(4), 900, 150, 300, true, false),
+    ]
+}
+
+fn stream_accounts(
+    sender: &[u8; 32],
+    receiver: &[u8; 32],
+    mint: Pubkey,
+) -> Vec<TransactionAccount> {
+    let pda_sender = user_pda(sender, BSC_CHAIN_ID);
+    zebec_accounts(
+        15,
+        &[
+            (5, pda_sender),
+            (6, user_pda(receiver, BSC_CHAIN_ID)),
+            (9, mint),
+        ],
+        Some(pda_sender),
+    )
+}
+
+async fn statuses(h: &mut Harness, sender: &[u8; 32], count: u64) -> Vec<BatchEntryStatus> {
+    let batch: BatchData = h.account(batch_store(sender, count)).await.unwrap();
+    batch.entries.iter().map(|entry| entry.status).collect()
+}
+
+#[tokio::test]
+async fn batch_entries_are_created_and_executed_one_by_one() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let mint = Pubkey::new_unique();
+    let entries = payroll();
+    let count = h
+        .store_batch(batch_stream_payload(sender, mint, &entries), sender)
+        .await;
+
+    let batch: BatchData = h.account(batch_store(&sender, count)).await.unwrap();
+    assert_eq!(batch.sender, sender);
+    assert_eq!(batch.token_mint, mint);
+    assert_eq!(batch.relayer, h.owner());
+    assert_eq!(batch.entries.len(), 3);
+    assert_eq!(batch.entries[1].receiver, wallet(3));
+    assert!(!batch.entries[1].can_cancel && batch.entries[1].can_update);
+    assert!(!batch.is_finished());
+
+    let mut created = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let accs = stream_accounts(&sender, &entry.receiver, mint);
+        let transaction = h
+            .create_batch(accs.clone(), stream_args(entry), sender, count, index as u8)
+            .await
+            .unwrap();
+        created.push((transaction, accs));
+    }
+    assert_eq!(
+        statuses(&mut h, &sender, count).await,
+        vec![BatchEntryStatus::Created; 3]
+    );
+    let batch: BatchData = h.account(batch_store(&sender, count)).await.unwrap();
+    assert_eq!(batch.entries[2].transaction, created[2].0);
+    assert_eq!(batch.entries[2].data_account, created[2].1[0].pubkey);
+
+    for (index, (transaction, accs)) in created.iter().enumerate().rev() {
+        h.execute_batch(*transaction, accs, sender, count, index as u8)
+            .await
+            .unwrap();
+    }
+    let batch: BatchData = h.account(batch_store(&sender, count)).await.unwrap();
+    assert!(batch.is_finished());
+    assert_eq!(
+        statuses(&mut h, &sender, count).await,
+        vec![BatchEntryStatus::Executed; 3]
+    );
+
+    h.next_blockhash().await;
+    let (transaction, accs) = &created[0];
+    assert_error(
+        h.execute_batch(*transaction, accs, sender, count, 0).await,
+        MessengerError::BatchEntryNotCreated,
+    );
+    assert_error(
+        h.create_batch(accs.clone(), stream_args(&entries[0]), sender, count, 0)
+            .await,
+        MessengerError::BatchEntryNotPending,
+    );
+
+    // Batches take a count like any other message.
+    let next = h
+        .store(deposit_payload(500, sender, Pubkey::new_unique()), sender)
+        .await;
+    assert_eq!(next, count + 1);
+}
+
+#[tokio::test]
+async fn batch_entry_create_checks_the_entry() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let mint = Pubkey::new_unique();
+    let entries = payroll();
+    let count = h
+        .store_batch(batch_stream_payload(sender, mint, &entries), sender)
+        .await;
+
+    let accs = stream_accounts(&sender, &entries[1].receiver, mint);
+    let cases = vec![
+        (
+            stream_accounts(&sender, &entries[0].receiver, mint),
+            stream_args(&entries[1]),
+            1,
+            MessengerError::ReceiverDerivedKeyMismatch,
+        ),
+        (
+            stream_accounts(&sender, &entries[1].receiver, Pubkey::new_unique()),
+            stream_args(&entries[1]),
+            1,
+            MessengerError::MintKeyMismatch,
+        ),
+        (
+            accs.clone(),
+            stream_args(&entries[0]),
+            1,
+            MessengerError::AmountMismatch,
+        ),
+        (
+            accs.clone(),
+            stream_args(&BatchEntry {
+                can_cancel: true,
+                ..entries[1].clone()
+            }),
+            1,
+            MessengerError::CanCancelMismatch,
+        ),
+        (
+            accs.clone(),
+            stream_args(&entries[1]),
+            3,
+            MessengerError::InvalidBatchEntry,
+        ),
+    ];
+    for (accs, data, index, error) in cases {
+        assert_error(
+            h.create_batch(accs, data, sender, count, index).await,
+            error,
+        );
+    }
+
+    let transaction = h
+        .create_batch(accs.clone(), stream_args(&entries[1]), sender, count, 1)
+        .await
+        .unwrap();
+    // Each entry executes only its own transaction.
+    assert_eq!(transaction, batch_transaction(&sender, count, 1));
+    assert!(h
+        .execute_batch(transaction, &accs, sender, count, 0)
+        .await
+        .is_err());
+    let other = stream_accounts(&sender, &entries[0].receiver, mint);
+    let other_transaction = h
+        .create_batch(other, stream_args(&entries[0]), sender, count, 0)
+        .await
+        .unwrap();
+    assert!(h
+        .execute_batch(other_transaction, &accs, sender, count, 1)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn failed_batch_entries_are_recorded() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let mint = Pubkey::new_unique();
+    let entries = payroll();
+    let count = h
+        .store_batch(batch_stream_payload(sender, mint, &entries[..2]), sender)
+        .await;
+
+    let accs = stream_accounts(&sender, &entries[0].receiver, mint);
+    let mut data = stream_args(&entries[0]);
+    data[..8].copy_from_slice(&REJECTED_BY_ZEBEC);
+    let transaction = h
+        .create_batch(accs.clone(), data, sender, count, 0)
+        .await
+        .unwrap();
+    assert_error(
+        h.execute_batch(transaction, &accs, sender, count, 0).await,
+        MessengerError::InvalidCPI,
+    );
+    assert_eq!(
+        statuses(&mut h, &sender, count).await,
+        vec![BatchEntryStatus::Created, BatchEntryStatus::Pending]
+    );
+
+    // Not even whoever stored the batch may fail its entries.
+    let stranger = Keypair::new();
+    let mut batch: BatchData = h.account(batch_store(&sender, count)).await.unwrap();
+    batch.relayer = stranger.pubkey();
+    let mut stored = Vec::new();
+    batch.try_serialize(&mut stored).unwrap();
+    h.set_account(batch_store(&sender, count), solana_project::ID, stored);
+    let ix = h.fail_batch_entry_ix(stranger.pubkey(), sender, count, 0, 6005);
+    assert_error(
+        h.send(vec![ix], &[&stranger]).await,
+        MessengerError::InvalidCaller,
+    );
+    let ix = h.fail_batch_entry_ix(h.owner(), sender, count, 0, 6005);
+    h.send(vec![ix], &[]).await.unwrap();
+
+    let batch: BatchData = h.account(batch_store(&sender, count)).await.unwrap();
+    assert_eq!(batch.entries[0].status, BatchEntryStatus::Failed);
+    assert_eq!(batch.entries[0].error_code, 6005);
+    assert!(!batch.is_finished());
+
+    h.next_blockhash().await;
+    assert_error(
+        h.execute_batch(transaction, &accs, sender, count, 0).await,
+        MessengerError::BatchEntryNotCreated,
+    );
+    let ix = h.fail_batch_entry_ix(h.owner(), sender, count, 0, 1);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::BatchEntryFinished,
+    );
+
+    // The rest of the batch goes on.
+    let accs = stream_accounts(&sender, &entries[1].receiver, mint);
+    let transaction = h
+        .create_batch(accs.clone(), stream_args(&entries[1]), sender, count, 1)
+        .await
+        .unwrap();
+    h.execute_batch(transaction, &accs, sender, count, 1)
+        .await
+        .unwrap();
+    let batch: BatchData = h.account(batch_store(&sender, count)).await.unwrap();
+    assert_eq!(batch.entries[1].status, BatchEntryStatus::Executed);
+    assert!(batch.is_finished());
+}
+
+#[tokio::test]
+async fn batch_payloads_are_checked() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let mint = Pubkey::new_unique();
+    let entries = payroll();
+
+    let mut bad_flags = batch_stream_payload(sender, mint, &entries);
+    let last = bad_flags.len() - 1;
+    bad_flags[last] = 4;
+    let mut truncated = batch_stream_payload(sender, mint, &entries);
+    truncated.pop();
+    let too_many = vec![entries[0].clone(); 11];
+    let cases = vec![
+        (
+            stream_payload(100, 200, 500, sender, wallet(2), true, true, mint),
+            MessengerError::InvalidPayload,
+        ),
+        (
+            batch_stream_payload(sender, mint, &[]),
+            MessengerError::InvalidBatchSize,
+        ),
+        (
+            batch_stream_payload(sender, mint, &too_many),
+            MessengerError::InvalidBatchSize,
+        ),
+        (truncated, MessengerError::InvalidPayloadLength),
+        (bad_flags, MessengerError::InvalidBatchEntry),
+        (
+            batch_stream_payload(wallet(9), mint, &entries),
+            MessengerError::InvalidSenderWallet,
+        ),
+    ];
+    for (payload, error) in cases {
+        let posted = h.post(payload);
+        let count = h.next_count(sender).await;
+        assert_error(h.store_batch_msg(&posted, sender, count).await, error);
+    }
+
+    // `store_msg` leaves batches to `store_batch_msg`.
+    let posted = h.post(batch_stream_payload(sender, mint, &entries));
+    let count = h.next_count(sender).await;
+    assert_error(
+        h.store_msg(&posted, sender, count).await,
+        MessengerError::InvalidPayload,
+    );
+    assert!(h
+        .account::<TransactionData>(data_store(&sender, count))
+        .await
+        .is_none());
+
+    let mut versioned = batch_stream_payload(sender, mint, &entries);
+    versioned.push(3);
+    let count = h.store_batch(versioned, sender).await;
+    let batch: BatchData = h.account(batch_store(&sender, count)).await.unwrap();
+    assert_eq!(batch.target_version, 3);
+}
@@ -0,0 +1,134 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::hash;
+use solana_project::errors::MessengerError;
+use solana_project::state::{AddressFormat, EmitterAccount, TokenAmount, TransactionData};
+
+use common::*;
+
+/// A source chain with the Zebec emitter and a sender in its native form.
+struct Family {
+    chain_id: u16,
+    format: AddressFormat,
+    emitter: [u8; 32],
+    sender: [u8; 32],
+}
+
+fn families() -> Vec<Family> {
+    let mut aptos_emitter = [0u8; 32];
+    aptos_emitter[31] = 0x1f;
+    vec![
+        Family {
+            chain_id: 1,
+            format: AddressFormat::Solana,
+            emitter: Pubkey::new_unique().to_bytes(),
+            sender: Pubkey::new_unique().to_bytes(),
+        },
+        Family {
+            chain_id: 15,
+            format: AddressFormat::Near,
+            emitter: hash(b"zebec.near").to_bytes(),
+            sender: hash(b"alice.near").to_bytes(),
+        },
+        Family {
+            chain_id: 21,
+            format: AddressFormat::Sui,
+            emitter: [0x5e; 32],
+            sender: [0xa1; 32],
+        },
+        Family {
+            chain_id: 22,
+            format: AddressFormat::Aptos,
+            emitter: aptos_emitter,
+            sender: [0xa2; 32],
+        },
+    ]
+}
+
+#[tokio::test]
+async fn native_senders_from_each_chain_family() {
+    let mut h = Harness::new().await;
+    for family in families() {
+        let ix = h.register_chain_ix(family.chain_id, family.emitter, "", family.format);
+        h.send(vec![ix], &[]).await.unwrap();
+        let emitter: EmitterAccount = h.account(emitter_account(family.chain_id)).await.unwrap();
+        assert_eq!(emitter.address_format, family.format);
+
+        let sender = family.sender;
+        let mint = Pubkey::new_unique();
+        let posted = h.post_from(
+            family.chain_id,
+            family.emitter,
+            initialize_pda_payload(sender),
+        );
+        let ix = h.initialize_pda_ix(&posted, sender, family.chain_id);
+        h.send(vec![ix], &[]).await.unwrap();
+
+        let posted = h.post_from(
+            family.chain_id,
+            family.emitter,
+            deposit_payload(500, sender, mint),
+        );
+        let count = h.next_count(sender).await;
+        h.store_msg_from(&posted, sender, count, family.chain_id, family.emitter)
+            .await
+            .unwrap();
+        let stored: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+        assert_eq!(stored.sender, sender);
+        assert_eq!(stored.from_chain_id, family.chain_id);
+
+        // The sender's PDA is seeded with its full 32 byte address.
+        let pda_sender = user_pda(&sender, family.chain_id);
+        let accs = zebec_accounts(10, &[(1, pda_sender), (6, mint)], Some(pda_sender));
+        h.create_execute_from(
+            CreateExecute::Deposit,
+            accs,
+            zebec_data(&TokenAmount { amount: 500 }),
+            sender,
+            family.chain_id,
+            count,
+        )
+        .await
+        .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn evm_chain_refuses_native_senders() {
+    let mut h = Harness::new().await;
+    let sender = [0xa1; 32];
+
+    let posted = h.post(deposit_payload(500, sender, Pubkey::new_unique()));
+    assert_error(
+        h.store_msg(&posted, sender, 1).await,
+        MessengerError::InvalidSenderAddress,
+    );
+    let posted = h.post(initialize_pda_payload(sender));
+    assert_error(
+        h.initialize_pda(&posted, sender).await,
+        MessengerError::InvalidSenderAddress,
+    );
+}
+
+#[tokio::test]
+async fn native_chain_refuses_zero_sender() {
+    let mut h = Harness::new().await;
+    let family = &families()[3];
+    let ix = h.register_chain_ix(family.chain_id, family.emitter, "", family.format);
+    h.send(vec![ix], &[]).await.unwrap();
+
+    let sender = [0u8; 32];
+    let posted = h.post_from(
+        family.chain_id,
+        family.emitter,
+        deposit_payload(500, sender, Pubkey::new_unique()),
+    );
+    assert_error(
+        h.store_msg_from(&posted, sender, 1, family.chain_id, family.emitter)
+            .await,
+        MessengerError::InvalidSenderAddress,
+    );
+}
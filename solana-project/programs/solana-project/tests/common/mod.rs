@@ -0,0 +1,2134 @@
+#![allow(dead_code)]
+
+use std::str::FromStr;
+
+use anchor_lang::prelude::{AccountMeta, Clock, Pubkey};
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, instruction::Instruction,
+    program_error::ProgramError, system_program, sysvar,
+};
+use anchor_lang::{
+    AccountDeserialize, AccountSerialize, AnchorDeserialize, AnchorSerialize, InstructionData,
+    ToAccountMetas,
+};
+use anchor_spl::token::spl_token;
+use sha3::Digest;
+use solana_program_test::{processor, ProgramTest, ProgramTestBanksClientExt, ProgramTestContext};
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction as SolanaTransaction, TransactionError};
+
+use solana_project::constants::{
+    CORE_BRIDGE_ADDRESS, SOLANA_CHAIN_ID, TOKEN_BRIDGE_ADDRESS, ZEBEC_CONTRACT,
+};
+use solana_project::errors::MessengerError;
+use solana_project::governance::GovernanceAction;
+use solana_project::meta::SignedAction;
+use solana_project::state::{
+    AddressFormat, BatchData, BatchEntry, Count, MintRegistry, TransactionAccount, TransactionData,
+};
+use solana_project::versioning::AccountVersion;
+use solana_project::wormhole::{MessageData, PostedMessageData};
+use solana_project::{accounts, instruction, serialize_vaa};
+
+pub const BSC_CHAIN_ID: u16 = 4;
+pub const EMITTER_ADDRESS: [u8; 32] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x35, 0x1e, 0x0a, 0x2e, 0x3c, 0x43, 0x1c, 0x59, 0x26, 0x6a,
+    0x5a, 0x1c, 0x6f, 0x4f, 0x3f, 0x16, 0x57, 0x68, 0x40, 0x5c,
+];
+
+/// The DAO emitter `set_governance` names in the governance tests.
+pub const GOVERNANCE_CHAIN_ID: u16 = 2;
+pub const GOVERNANCE_EMITTER: [u8; 32] = [0x77; 32];
+
+/// First eight bytes of Zebec instruction data the stub program refuses,
+/// so the `InvalidCPI` path can be exercised.
+pub const REJECTED_BY_ZEBEC: [u8; 8] = [0xff; 8];
+
+pub fn core_bridge() -> Pubkey {
+    Pubkey::from_str(CORE_BRIDGE_ADDRESS).unwrap()
+}
+
+pub fn token_bridge() -> Pubkey {
+    Pubkey::from_str(TOKEN_BRIDGE_ADDRESS).unwrap()
+}
+
+pub fn zebec_program() -> Pubkey {
+    Pubkey::from_str(ZEBEC_CONTRACT).unwrap()
+}
+
+fn zebec_stub(_: &Pubkey, _: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if data.starts_with(&REJECTED_BY_ZEBEC) {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
+pub fn wallet(byte: u8) -> [u8; 32] {
+    let mut address = [0u8; 32];
+    address[12..].copy_from_slice(&[byte; 20]);
+    address
+}
+
+// PDAs
+
+pub fn config() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &solana_project::ID).0
+}
+
+pub fn emitter_account(chain_id: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"EmitterAddress", &chain_id.to_be_bytes()],
+        &solana_project::ID,
+    )
+    .0
+}
+
+pub fn processed_vaa(emitter_address: &[u8; 32], chain_id: u16, sequence: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            emitter_address,
+            &chain_id.to_be_bytes(),
+            &sequence.to_be_bytes(),
+        ],
+        &solana_project::ID,
+    )
+    .0
+}
+
+pub fn user_pda(user: &[u8; 32], chain_id: u16) -> Pubkey {
+    Pubkey::find_program_address(&[user, &chain_id.to_be_bytes()], &solana_project::ID).0
+}
+
+pub fn data_store(sender: &[u8; 32], count: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"data_store", sender, &count.to_be_bytes()],
+        &solana_project::ID,
+    )
+    .0
+}
+
+pub fn txn_status(sender: &[u8; 32], count: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"txn_status", sender, &count.to_be_bytes()],
+        &solana_project::ID,
+    )
+    .0
+}
+
+pub fn batch_store(sender: &[u8; 32], count: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"batch_store", sender, &count.to_be_bytes()],
+        &solana_project::ID,
+    )
+    .0
+}
+
+pub fn transaction(sender: &[u8; 32], count: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"transaction", sender, &count.to_be_bytes()],
+        &solana_project::ID,
+    )
+    .0
+}
+
+pub fn batch_transaction(sender: &[u8; 32], count: u64, index: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"transaction",
+            sender,
+            &count.to_be_bytes(),
+            &index.to_be_bytes(),
+        ],
+        &solana_project::ID,
+    )
+    .0
+}
+
+pub fn transaction_buffer(sender: &[u8; 32], count: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"transaction_buffer", sender, &count.to_be_bytes()],
+        &solana_project::ID,
+    )
+    .0
+}
+
+pub fn target_program(version: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"target_program", &version.to_be_bytes()],
+        &solana_project::ID,
+    )
+    .0
+}
+
+pub fn payout_profile(identity: &[u8; 32], chain_id: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"payout_profile", identity, &chain_id.to_be_bytes()],
+        &solana_project::ID,
+    )
+    .0
+}
+
+pub fn meta_nonce(sender: &[u8; 32], chain_id: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"meta_nonce", sender, &chain_id.to_be_bytes()],
+        &solana_project::ID,
+    )
+    .0
+}
+
+pub fn chain_limit(chain_id: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"chain_limit", &chain_id.to_be_bytes()],
+        &solana_project::ID,
+    )
+    .0
+}
+
+pub fn mint_limit(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"mint_limit", mint.as_ref()], &solana_project::ID).0
+}
+
+pub fn mint_registry(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"mint_registry", mint.as_ref()], &solana_project::ID).0
+}
+
+pub fn txn_count(sender: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"txn_count", sender], &solana_project::ID).0
+}
+
+pub fn posted_vaa_key(message: &MessageData) -> Pubkey {
+    let hash: [u8; 32] = sha3::Keccak256::digest(serialize_vaa(message)).into();
+    Pubkey::find_program_address(&[b"PostedVAA", &hash], &core_bridge()).0
+}
+
+// Payloads, laid out like Encoder.sol
+
+fn to_chain(chain: u64) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[24..].copy_from_slice(&chain.to_be_bytes());
+    bytes
+}
+
+fn flag(value: bool) -> [u8; 8] {
+    (value as u64).to_be_bytes()
+}
+
+pub fn deposit_payload(amount: u64, depositor: [u8; 32], mint: Pubkey) -> Vec<u8> {
+    [
+        &[6u8][..],
+        &amount.to_be_bytes(),
+        &to_chain(1),
+        &depositor,
+        mint.as_ref(),
+    ]
+    .concat()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn stream_payload(
+    start_time: u64,
+    end_time: u64,
+    amount: u64,
+    sender: [u8; 32],
+    receiver: [u8; 32],
+    can_cancel: bool,
+    can_update: bool,
+    mint: Pubkey,
+) -> Vec<u8> {
+    [
+        &[2u8][..],
+        &start_time.to_be_bytes(),
+        &end_time.to_be_bytes(),
+        &amount.to_be_bytes(),
+        &to_chain(1),
+        &sender,
+        &receiver,
+        &flag(can_cancel),
+        &flag(can_update),
+        mint.as_ref(),
+    ]
+    .concat()
+}
+
+pub fn withdraw_stream_payload(
+    withdrawer: [u8; 32],
+    mint: Pubkey,
+    sender: [u8; 32],
+    data_account: Pubkey,
+) -> Vec<u8> {
+    [
+        &[4u8][..],
+        &to_chain(1),
+        &withdrawer,
+        mint.as_ref(),
+        &sender,
+        data_account.as_ref(),
+    ]
+    .concat()
+}
+
+pub fn withdraw_stream_bridge_payload(
+    withdrawer: [u8; 32],
+    mint: Pubkey,
+    sender: [u8; 32],
+    data_account: Pubkey,
+    target_chain: u16,
+    fee: u64,
+) -> Vec<u8> {
+    let mut payload = withdraw_stream_payload(withdrawer, mint, sender, data_account);
+    payload[0] = 21;
+    payload.extend(target_chain.to_be_bytes());
+    payload.extend(fee.to_be_bytes());
+    payload
+}
+
+pub fn withdraw_stream_payout_payload(
+    withdrawer: [u8; 32],
+    mint: Pubkey,
+    sender: [u8; 32],
+    data_account: Pubkey,
+) -> Vec<u8> {
+    let mut payload = withdraw_stream_payload(withdrawer, mint, sender, data_account);
+    payload[0] = 23;
+    payload
+}
+
+pub fn pause_payload(
+    sender: [u8; 32],
+    mint: Pubkey,
+    receiver: [u8; 32],
+    data_account: Pubkey,
+) -> Vec<u8> {
+    [
+        &[8u8][..],
+        &to_chain(1),
+        &sender,
+        mint.as_ref(),
+        &receiver,
+        data_account.as_ref(),
+    ]
+    .concat()
+}
+
+pub fn cancel_payload(
+    sender: [u8; 32],
+    mint: Pubkey,
+    receiver: [u8; 32],
+    data_account: Pubkey,
+) -> Vec<u8> {
+    [
+        &[16u8][..],
+        &to_chain(1),
+        &sender,
+        mint.as_ref(),
+        &receiver,
+        data_account.as_ref(),
+    ]
+    .concat()
+}
+
+pub fn sender_withdraw_payload(amount: u64, withdrawer: [u8; 32], mint: Pubkey) -> Vec<u8> {
+    [
+        &[10u8][..],
+        &amount.to_be_bytes(),
+        &to_chain(1),
+        &withdrawer,
+        mint.as_ref(),
+    ]
+    .concat()
+}
+
+pub fn instant_transfer_payload(
+    amount: u64,
+    sender: [u8; 32],
+    mint: Pubkey,
+    receiver: [u8; 32],
+) -> Vec<u8> {
+    [
+        &[12u8][..],
+        &amount.to_be_bytes(),
+        &to_chain(1),
+        &sender,
+        mint.as_ref(),
+        &receiver,
+    ]
+    .concat()
+}
+
+pub fn direct_transfer_payload(
+    amount: u64,
+    sender: [u8; 32],
+    mint: Pubkey,
+    receiver: [u8; 32],
+) -> Vec<u8> {
+    [
+        &[17u8][..],
+        &amount.to_be_bytes(),
+        &to_chain(1),
+        &sender,
+        mint.as_ref(),
+        &receiver,
+    ]
+    .concat()
+}
+
+/// A direct transfer payload that fixes the target chain and maximum fee.
+pub fn direct_transfer_payload_with_terms(
+    amount: u64,
+    sender: [u8; 32],
+    mint: Pubkey,
+    receiver: [u8; 32],
+    target_chain: u16,
+    max_fee: u64,
+) -> Vec<u8> {
+    [
+        direct_transfer_payload(amount, sender, mint, receiver),
+        target_chain.to_be_bytes().to_vec(),
+        max_fee.to_be_bytes().to_vec(),
+    ]
+    .concat()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn stream_update_payload(
+    start_time: u64,
+    end_time: u64,
+    amount: u64,
+    sender: [u8; 32],
+    receiver: [u8; 32],
+    mint: Pubkey,
+    data_account: Pubkey,
+) -> Vec<u8> {
+    [
+        &[14u8][..],
+        &start_time.to_be_bytes(),
+        &end_time.to_be_bytes(),
+        &amount.to_be_bytes(),
+        &to_chain(1),
+        &sender,
+        &receiver,
+        mint.as_ref(),
+        data_account.as_ref(),
+    ]
+    .concat()
+}
+
+pub fn initialize_pda_payload(account: [u8; 32]) -> Vec<u8> {
+    [&[18u8][..], &account, &to_chain(1)].concat()
+}
+
+pub fn initialize_token_account_payload(account: [u8; 32], mint: Pubkey) -> Vec<u8> {
+    [&[19u8][..], &account, mint.as_ref(), &to_chain(1)].concat()
+}
+
+pub fn set_payout_wallet_payload(account: [u8; 32], wallet: Pubkey) -> Vec<u8> {
+    [&[22u8][..], &account, wallet.as_ref(), &to_chain(1)].concat()
+}
+
+/// Lays `action` out the way a governance VAA for the proxy carries it.
+pub fn governance_payload(action: &GovernanceAction) -> Vec<u8> {
+    let fields = match action {
+        GovernanceAction::RegisterChain {
+            chain_id,
+            emitter_addr,
+            address_format,
+            label,
+        } => [
+            &chain_id.to_be_bytes()[..],
+            emitter_addr,
+            &address_format.try_to_vec().unwrap(),
+            label.as_bytes(),
+        ]
+        .concat(),
+        GovernanceAction::UpdateChain { chain_id, enabled } => {
+            [&chain_id.to_be_bytes()[..], &[*enabled as u8]].concat()
+        }
+        GovernanceAction::SetChainLimit {
+            chain_id,
+            daily_limit,
+        } => [&chain_id.to_be_bytes()[..], &daily_limit.to_be_bytes()].concat(),
+        GovernanceAction::SetMintLimit {
+            mint,
+            daily_limit,
+            notional_price,
+            large_transfer_threshold,
+            hold_seconds,
+        } => [
+            mint.as_ref(),
+            &daily_limit.to_be_bytes(),
+            &notional_price.to_be_bytes(),
+            &large_transfer_threshold.to_be_bytes(),
+            &hold_seconds.to_be_bytes(),
+        ]
+        .concat(),
+        GovernanceAction::RegisterTargetProgram {
+            version,
+            program_id,
+        } => [&[*version][..], program_id.as_ref()].concat(),
+        GovernanceAction::RemoveTargetProgram { version } => vec![*version],
+        GovernanceAction::SetOwner { owner } => owner.to_bytes().to_vec(),
+    };
+    [&[action.code()][..], solana_project::ID.as_ref(), &fields].concat()
+}
+
+pub fn batch_entry(
+    receiver: [u8; 32],
+    amount: u64,
+    start_time: u64,
+    end_time: u64,
+    can_cancel: bool,
+    can_update: bool,
+) -> BatchEntry {
+    BatchEntry {
+        receiver,
+        amount,
+        start_time,
+        end_time,
+        can_cancel,
+        can_update,
+        ..BatchEntry::default()
+    }
+}
+
+pub fn batch_stream_payload(sender: [u8; 32], mint: Pubkey, entries: &[BatchEntry]) -> Vec<u8> {
+    let mut payload = [
+        &[20u8][..],
+        &to_chain(1),
+        &sender,
+        mint.as_ref(),
+        &[entries.len() as u8],
+    ]
+    .concat();
+    for entry in entries {
+        payload.extend_from_slice(&entry.receiver);
+        payload.extend_from_slice(&entry.amount.to_be_bytes());
+        payload.extend_from_slice(&entry.start_time.to_be_bytes());
+        payload.extend_from_slice(&entry.end_time.to_be_bytes());
+        payload.push(entry.can_cancel as u8 | (entry.can_update as u8) << 1);
+    }
+    payload
+}
+
+/// Replaces the uint256 target chain that starts at `offset`.
+pub fn with_to_chain(mut payload: Vec<u8>, offset: usize, chain: u64) -> Vec<u8> {
+    payload[offset..offset + 32].copy_from_slice(&to_chain(chain));
+    payload
+}
+
+// Zebec instruction data; the proxy only checks what follows the sighash.
+
+/// The mint a message payload names, the default key if it names none.
+pub fn payload_mint(payload: &[u8]) -> Pubkey {
+    let mut data = TransactionData::default();
+    if solana_project::decode_payload(payload, BSC_CHAIN_ID, [0; 32], &mut data).is_ok() {
+        return data.token_mint;
+    }
+    let mut batch = BatchData::default();
+    if solana_project::decode_batch_stream(payload, BSC_CHAIN_ID, [0; 32], &mut batch).is_ok() {
+        return batch.token_mint;
+    }
+    Pubkey::default()
+}
+
+/// A registry entry for a Solana mint that lets any amount through.
+pub fn open_registry(mint: Pubkey, decimals: u8) -> MintRegistry {
+    MintRegistry {
+        mint,
+        decimals,
+        min_amount: 0,
+        max_amount: u64::MAX,
+        wrapped: false,
+        origin_chain: SOLANA_CHAIN_ID,
+        origin_address: mint.to_bytes(),
+        account_version: AccountVersion::CURRENT,
+    }
+}
+
+pub fn zebec_data<T: AnchorSerialize>(args: &T) -> Vec<u8> {
+    let mut data = vec![1u8; 8];
+    data.extend(args.try_to_vec().unwrap());
+    data
+}
+
+/// `len` Zebec accounts with the given ones at fixed positions and
+/// placeholders everywhere else. `signer` is the PDA that signs the CPI.
+pub fn zebec_accounts(
+    len: usize,
+    at: &[(usize, Pubkey)],
+    signer: Option<Pubkey>,
+) -> Vec<TransactionAccount> {
+    (0..len)
+        .map(|i| {
+            let pubkey = at
+                .iter()
+                .find(|(index, _)| *index == i)
+                .map(|(_, key)| *key)
+                .unwrap_or_else(Pubkey::new_unique);
+            TransactionAccount {
+                pubkey,
+                is_signer: Some(pubkey) == signer,
+                is_writable: true,
+            }
+        })
+        .collect()
+}
+
+fn remaining_accounts(accs: &[TransactionAccount], pda_signer: &Pubkey) -> Vec<AccountMeta> {
+    let mut metas: Vec<AccountMeta> = accs
+        .iter()
+        .map(|acc| AccountMeta::new(acc.pubkey, acc.is_signer && acc.pubkey != *pda_signer))
+        .collect();
+    metas.push(AccountMeta::new_readonly(zebec_program(), false));
+    metas
+}
+
+#[derive(Clone, Copy)]
+pub enum CreateExecute {
+    Deposit,
+    StreamUpdate,
+    PauseResume,
+}
+
+#[derive(Clone, Copy)]
+pub enum Create {
+    Stream,
+    ReceiverWithdraw,
+    Cancel,
+    SenderWithdraw,
+    InstantTransfer,
+    /// A withdraw stream and bridge message, paying into the given token
+    /// account.
+    WithdrawBridge(Pubkey),
+    /// A withdraw stream payout message, paying into the given token
+    /// account.
+    WithdrawPayout(Pubkey),
+}
+
+pub struct Posted {
+    pub key: Pubkey,
+    pub message: MessageData,
+}
+
+/// A bank with the proxy, a stand-in core bridge, token bridge and Zebec
+/// program, an initialized config, BSC registered as an emitter chain and the
+/// Zebec program registered as target version 0.
+pub struct Harness {
+    pub ctx: ProgramTestContext,
+    sequence: u64,
+    /// Whether mints are registered, without bounds, as the tests name them.
+    pub register_mints: bool,
+}
+
+impl Harness {
+    pub async fn new() -> Self {
+        let mut program_test = ProgramTest::new(
+            "solana_project",
+            solana_project::ID,
+            processor!(solana_project::entry),
+        );
+        program_test.add_program(
+            "core_bridge_stand_in",
+            core_bridge(),
+            processor!(core_bridge_stand_in::process_instruction),
+        );
+        program_test.add_program(
+            "token_bridge_stand_in",
+            token_bridge(),
+            processor!(token_bridge_stand_in::process_instruction),
+        );
+        // A builtin, so it stays native when `cargo test-bpf` loads the rest
+        // from their shared objects.
+        program_test.add_builtin_program(
+            "zebec_stub",
+            zebec_program(),
+            processor!(zebec_stub).unwrap(),
+        );
+
+        let mut harness = Harness {
+            ctx: program_test.start_with_context().await,
+            sequence: 0,
+            register_mints: true,
+        };
+        let owner = harness.owner();
+        harness
+            .send(
+                vec![
+                    Instruction {
+                        program_id: solana_project::ID,
+                        accounts: accounts::Initialize {
+                            config: config(),
+                            owner,
+                            system_program: system_program::id(),
+                        }
+                        .to_account_metas(None),
+                        data: instruction::Initialize {}.data(),
+                    },
+                    harness.register_chain_ix(
+                        BSC_CHAIN_ID,
+                        EMITTER_ADDRESS,
+                        "bsc",
+                        AddressFormat::Evm,
+                    ),
+                    harness.register_target_program_ix(0, zebec_program()),
+                ],
+                &[],
+            )
+            .await
+            .unwrap();
+        harness
+    }
+
+    pub fn owner(&self) -> Pubkey {
+        self.ctx.payer.pubkey()
+    }
+
+    pub async fn send(
+        &mut self,
+        ixs: Vec<Instruction>,
+        signers: &[&Keypair],
+    ) -> Result<(), TransactionError> {
+        let blockhash = self.ctx.banks_client.get_latest_blockhash().await.unwrap();
+        self.send_with(ixs, signers, blockhash).await
+    }
+
+    /// Waits for a new blockhash so that resending an identical
+    /// transaction is not deduplicated by the bank.
+    pub async fn next_blockhash(&mut self) {
+        let last = self.ctx.banks_client.get_latest_blockhash().await.unwrap();
+        self.ctx
+            .banks_client
+            .get_new_latest_blockhash(&last)
+            .await
+            .unwrap();
+    }
+
+    pub async fn unix_timestamp(&mut self) -> i64 {
+        let clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp
+    }
+
+    /// Moves the cluster clock `seconds` forward.
+    pub async fn advance_clock(&mut self, seconds: i64) {
+        let mut clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += seconds;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    async fn send_with(
+        &mut self,
+        ixs: Vec<Instruction>,
+        signers: &[&Keypair],
+        blockhash: solana_sdk::hash::Hash,
+    ) -> Result<(), TransactionError> {
+        let mut all: Vec<&Keypair> = vec![&self.ctx.payer];
+        all.extend_from_slice(signers);
+        let tx = SolanaTransaction::new_signed_with_payer(
+            &ixs,
+            Some(&self.ctx.payer.pubkey()),
+            &all,
+            blockhash,
+        );
+        self.ctx
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .map_err(|e| e.unwrap())
+    }
+
+    pub async fn account<T: AccountDeserialize>(&mut self, key: Pubkey) -> Option<T> {
+        let account = self.ctx.banks_client.get_account(key).await.unwrap()?;
+        Some(T::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        let account = Account {
+            lamports: 1_000_000_000,
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx
+            .set_account(&key, &AccountSharedData::from(account));
+    }
+
+    /// Writes an SPL mint with 6 decimals.
+    pub fn create_mint(&mut self) -> Pubkey {
+        self.create_mint_with_decimals(6)
+    }
+
+    pub fn create_mint_with_decimals(&mut self, decimals: u8) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        self.write_mint(mint, decimals);
+        if self.register_mints {
+            self.write_mint_registry(open_registry(mint, decimals));
+        }
+        mint
+    }
+
+    /// Writes `registry` where `register_mint` would have put it.
+    pub fn write_mint_registry(&mut self, registry: MintRegistry) {
+        let mut data = Vec::new();
+        registry.try_serialize(&mut data).unwrap();
+        self.set_account(mint_registry(&registry.mint), solana_project::ID, data);
+    }
+
+    /// Registers `mint` without bounds unless the test turned that off or
+    /// registered it itself.
+    async fn ensure_registered(&mut self, mint: Pubkey) {
+        if !self.register_mints
+            || self
+                .ctx
+                .banks_client
+                .get_account(mint_registry(&mint))
+                .await
+                .unwrap()
+                .is_some()
+        {
+            return;
+        }
+        // Mints the test did not write get 6 decimals, as in `stored_mint`.
+        let decimals = match self.ctx.banks_client.get_account(mint).await.unwrap() {
+            Some(account) if account.owner == spl_token::ID => {
+                spl_token::state::Mint::unpack(&account.data).map_or(6, |mint| mint.decimals)
+            }
+            _ => 6,
+        };
+        self.write_mint_registry(open_registry(mint, decimals));
+    }
+
+    /// Writes `mint` as an SPL mint, replacing what is there.
+    pub fn write_mint(&mut self, mint: Pubkey, decimals: u8) {
+        let mut data = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            mint_authority: Some(self.owner()).into(),
+            supply: 0,
+            decimals,
+            is_initialized: true,
+            freeze_authority: None.into(),
+        }
+        .pack_into_slice(&mut data);
+        self.set_account(mint, spl_token::ID, data);
+    }
+
+    /// The mint of the stored message, written with 6 decimals if the test
+    /// did not create it.
+    async fn stored_mint(&mut self, sender: [u8; 32], current_count: u64) -> Pubkey {
+        match self
+            .account::<TransactionData>(data_store(&sender, current_count))
+            .await
+        {
+            Some(data) => self.ensure_mint(data.token_mint).await,
+            None => Pubkey::new_unique(),
+        }
+    }
+
+    /// `stored_mint` for a stored batch.
+    async fn batch_mint(&mut self, sender: [u8; 32], current_count: u64) -> Pubkey {
+        match self
+            .account::<BatchData>(batch_store(&sender, current_count))
+            .await
+        {
+            Some(batch) => self.ensure_mint(batch.token_mint).await,
+            None => Pubkey::new_unique(),
+        }
+    }
+
+    async fn ensure_mint(&mut self, mint: Pubkey) -> Pubkey {
+        if self
+            .ctx
+            .banks_client
+            .get_account(mint)
+            .await
+            .unwrap()
+            .is_none()
+        {
+            self.write_mint(mint, 6);
+        }
+        mint
+    }
+
+    pub fn create_token_account(&mut self, mint: Pubkey, owner: Pubkey, amount: u64) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.write_token_account(key, mint, owner, amount);
+        key
+    }
+
+    /// Writes `key` as an initialized token account, replacing what is there.
+    pub fn write_token_account(&mut self, key: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) {
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        self.set_account(key, spl_token::ID, data);
+    }
+
+    // Mock core bridge
+
+    /// Writes a `PostedVAA` account for `payload` from the registered emitter
+    /// where the core bridge would have put it.
+    pub fn post(&mut self, payload: Vec<u8>) -> Posted {
+        self.post_from(BSC_CHAIN_ID, EMITTER_ADDRESS, payload)
+    }
+
+    pub fn post_from(
+        &mut self,
+        emitter_chain: u16,
+        emitter_address: [u8; 32],
+        payload: Vec<u8>,
+    ) -> Posted {
+        self.sequence += 1;
+        let message = MessageData {
+            vaa_version: 1,
+            consistency_level: 1,
+            vaa_time: 1_650_000_000,
+            vaa_signature_account: Pubkey::new_unique(),
+            submission_time: 1_650_000_000,
+            nonce: 0,
+            sequence: self.sequence,
+            emitter_chain,
+            emitter_address,
+            payload,
+        };
+        let key = posted_vaa_key(&message);
+        self.post_at(key, message)
+    }
+
+    /// Writes a `PostedVAA` account at an arbitrary address.
+    pub fn post_at(&mut self, key: Pubkey, message: MessageData) -> Posted {
+        let data = PostedMessageData(message).try_to_vec().unwrap();
+        self.set_account(key, core_bridge(), data.clone());
+        Posted {
+            key,
+            message: PostedMessageData::try_from_slice(&data).unwrap().0,
+        }
+    }
+
+    /// Reads back a message the token bridge posted through the core bridge
+    /// stand-in.
+    pub async fn posted_message(&mut self, key: Pubkey) -> core_bridge_stand_in::MessageData {
+        let account = self
+            .ctx
+            .banks_client
+            .get_account(key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.owner, core_bridge());
+        core_bridge_stand_in::MessageData::from_account_data(&account.data).unwrap()
+    }
+
+    // Proxy instructions
+
+    pub fn register_chain_ix(
+        &self,
+        chain_id: u16,
+        emitter_addr: [u8; 32],
+        label: &str,
+        address_format: AddressFormat,
+    ) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::RegisterChain {
+                owner: self.owner(),
+                system_program: system_program::id(),
+                config: config(),
+                emitter_acc: emitter_account(chain_id),
+            }
+            .to_account_metas(None),
+            data: instruction::RegisterChain {
+                chain_id,
+                emitter_addr,
+                label: label.to_string(),
+                address_format,
+            }
+            .data(),
+        }
+    }
+
+    pub fn update_chain_ix(
+        &self,
+        chain_id: u16,
+        enabled: bool,
+        label: Option<&str>,
+    ) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::UpdateChain {
+                owner: self.owner(),
+                config: config(),
+                emitter_acc: emitter_account(chain_id),
+            }
+            .to_account_metas(None),
+            data: instruction::UpdateChain {
+                chain_id,
+                enabled,
+                label: label.map(str::to_string),
+            }
+            .data(),
+        }
+    }
+
+    pub fn set_chain_limit_ix(&self, chain_id: u16, daily_limit: u64) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::SetChainLimit {
+                owner: self.owner(),
+                system_program: system_program::id(),
+                config: config(),
+                chain_limit: chain_limit(chain_id),
+            }
+            .to_account_metas(None),
+            data: instruction::SetChainLimit {
+                chain_id,
+                daily_limit,
+            }
+            .data(),
+        }
+    }
+
+    pub fn set_mint_limit_ix(
+        &self,
+        mint: Pubkey,
+        daily_limit: u64,
+        notional_price: u64,
+        large_transfer_threshold: u64,
+        hold_seconds: u32,
+    ) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::SetMintLimit {
+                owner: self.owner(),
+                system_program: system_program::id(),
+                config: config(),
+                mint,
+                mint_limit: mint_limit(&mint),
+            }
+            .to_account_metas(None),
+            data: instruction::SetMintLimit {
+                daily_limit,
+                notional_price,
+                large_transfer_threshold,
+                hold_seconds,
+            }
+            .data(),
+        }
+    }
+
+    pub fn migrate_chain_ix(&self, chain_id: u16) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::MigrateChain {
+                owner: self.owner(),
+                system_program: system_program::id(),
+                config: config(),
+                emitter_acc: emitter_account(chain_id),
+            }
+            .to_account_metas(None),
+            data: instruction::MigrateChain { chain_id }.data(),
+        }
+    }
+
+    pub fn migrate_account_ix(&self, account: Pubkey) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::MigrateAccount {
+                payer: self.owner(),
+                system_program: system_program::id(),
+                account,
+            }
+            .to_account_metas(None),
+            data: instruction::MigrateAccount {}.data(),
+        }
+    }
+
+    pub fn register_target_program_ix(&self, version: u8, program_id: Pubkey) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::RegisterTargetProgram {
+                owner: self.owner(),
+                system_program: system_program::id(),
+                config: config(),
+                target_program: target_program(version),
+            }
+            .to_account_metas(None),
+            data: instruction::RegisterTargetProgram {
+                version,
+                target_program_id: program_id,
+            }
+            .data(),
+        }
+    }
+
+    pub fn remove_target_program_ix(&self, version: u8) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::RemoveTargetProgram {
+                owner: self.owner(),
+                config: config(),
+                target_program: target_program(version),
+            }
+            .to_account_metas(None),
+            data: instruction::RemoveTargetProgram { version }.data(),
+        }
+    }
+
+    pub fn set_governance_ix(&self, chain_id: u16, emitter_address: [u8; 32]) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::SetGovernance {
+                owner: self.owner(),
+                system_program: system_program::id(),
+                config: config(),
+            }
+            .to_account_metas(None),
+            data: instruction::SetGovernance {
+                chain_id,
+                emitter_address,
+            }
+            .data(),
+        }
+    }
+
+    /// Posts `action` from the governance emitter and applies it.
+    pub async fn govern(&mut self, action: GovernanceAction) -> Result<Posted, TransactionError> {
+        let posted = self.post_from(
+            GOVERNANCE_CHAIN_ID,
+            GOVERNANCE_EMITTER,
+            governance_payload(&action),
+        );
+        let claim = processed_vaa(
+            &GOVERNANCE_EMITTER,
+            GOVERNANCE_CHAIN_ID,
+            posted.message.sequence,
+        );
+        let ix = self.govern_ix(&posted, &action, claim);
+        self.send(vec![ix], &[]).await.map(|()| posted)
+    }
+
+    /// Applies the governance VAA `posted` with the `govern_*` instruction
+    /// of `action`, claiming it at `claim`.
+    pub fn govern_ix(
+        &self,
+        posted: &Posted,
+        action: &GovernanceAction,
+        claim: Pubkey,
+    ) -> Instruction {
+        let payer = self.owner();
+        let system_program = system_program::id();
+        let (accounts, data) = match *action {
+            GovernanceAction::RegisterChain { chain_id, .. } => (
+                accounts::GovernRegisterChain {
+                    payer,
+                    system_program,
+                    config: config(),
+                    processed_vaa: claim,
+                    core_bridge_vaa: posted.key,
+                    emitter_acc: emitter_account(chain_id),
+                }
+                .to_account_metas(None),
+                instruction::GovernRegisterChain { chain_id }.data(),
+            ),
+            GovernanceAction::UpdateChain { chain_id, .. } => (
+                accounts::GovernUpdateChain {
+                    payer,
+                    system_program,
+                    config: config(),
+                    processed_vaa: claim,
+                    core_bridge_vaa: posted.key,
+                    emitter_acc: emitter_account(chain_id),
+                }
+                .to_account_metas(None),
+                instruction::GovernUpdateChain { chain_id }.data(),
+            ),
+            GovernanceAction::SetChainLimit { chain_id, .. } => (
+                accounts::GovernSetChainLimit {
+                    payer,
+                    system_program,
+                    config: config(),
+                    processed_vaa: claim,
+                    core_bridge_vaa: posted.key,
+                    chain_limit: chain_limit(chain_id),
+                }
+                .to_account_metas(None),
+                instruction::GovernSetChainLimit { chain_id }.data(),
+            ),
+            GovernanceAction::SetMintLimit { mint, .. } => (
+                accounts::GovernSetMintLimit {
+                    payer,
+                    system_program,
+                    config: config(),
+                    processed_vaa: claim,
+                    core_bridge_vaa: posted.key,
+                    mint,
+                    mint_limit: mint_limit(&mint),
+                }
+                .to_account_metas(None),
+                instruction::GovernSetMintLimit {}.data(),
+            ),
+            GovernanceAction::RegisterTargetProgram { version, .. } => (
+                accounts::GovernRegisterTargetProgram {
+                    payer,
+                    system_program,
+                    config: config(),
+                    processed_vaa: claim,
+                    core_bridge_vaa: posted.key,
+                    target_program: target_program(version),
+                }
+                .to_account_metas(None),
+                instruction::GovernRegisterTargetProgram { version }.data(),
+            ),
+            GovernanceAction::RemoveTargetProgram { version } => (
+                accounts::GovernRemoveTargetProgram {
+                    payer,
+                    system_program,
+                    config: config(),
+                    processed_vaa: claim,
+                    core_bridge_vaa: posted.key,
+                    target_program: target_program(version),
+                }
+                .to_account_metas(None),
+                instruction::GovernRemoveTargetProgram { version }.data(),
+            ),
+            GovernanceAction::SetOwner { .. } => (
+                accounts::GovernConfig {
+                    payer,
+                    system_program,
+                    config: config(),
+                    processed_vaa: claim,
+                    core_bridge_vaa: posted.key,
+                }
+                .to_account_metas(None),
+                instruction::GovernConfig {}.data(),
+            ),
+        };
+        Instruction {
+            program_id: solana_project::ID,
+            accounts,
+            data,
+        }
+    }
+
+    pub fn register_mint_ix(
+        &self,
+        mint: Pubkey,
+        min_amount: u64,
+        max_amount: u64,
+        origin_chain: u16,
+        origin_address: [u8; 32],
+    ) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::RegisterMint {
+                owner: self.owner(),
+                system_program: system_program::id(),
+                config: config(),
+                mint,
+                mint_registry: mint_registry(&mint),
+            }
+            .to_account_metas(None),
+            data: instruction::RegisterMint {
+                min_amount,
+                max_amount,
+                origin_chain,
+                origin_address,
+            }
+            .data(),
+        }
+    }
+
+    pub fn remove_mint_ix(&self, mint: Pubkey) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::RemoveMint {
+                owner: self.owner(),
+                config: config(),
+                mint_registry: mint_registry(&mint),
+            }
+            .to_account_metas(None),
+            data: instruction::RemoveMint { mint }.data(),
+        }
+    }
+
+    pub async fn initialize_pda(
+        &mut self,
+        posted: &Posted,
+        sender: [u8; 32],
+    ) -> Result<(), TransactionError> {
+        let ix = self.initialize_pda_ix(posted, sender, BSC_CHAIN_ID);
+        self.send(vec![ix], &[]).await
+    }
+
+    pub fn initialize_pda_ix(
+        &self,
+        posted: &Posted,
+        sender: [u8; 32],
+        chain_id: u16,
+    ) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::InitializePDA {
+                zebec_eoa: self.owner(),
+                system_program: system_program::id(),
+                processed_vaa: processed_vaa(
+                    &posted.message.emitter_address,
+                    chain_id,
+                    posted.message.sequence,
+                ),
+                emitter_acc: emitter_account(chain_id),
+                core_bridge_vaa: posted.key,
+                pda_account: user_pda(&sender, chain_id),
+            }
+            .to_account_metas(None),
+            data: instruction::InitializePda {
+                _sender: sender,
+                _chain_id: chain_id,
+            }
+            .data(),
+        }
+    }
+
+    pub async fn initialize_pda_token_account(
+        &mut self,
+        posted: &Posted,
+        sender: [u8; 32],
+        mint: Pubkey,
+    ) -> Result<(), TransactionError> {
+        let pda_account = user_pda(&sender, BSC_CHAIN_ID);
+        self.ensure_registered(mint).await;
+        let ix = Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::InitializePDATokenAccount {
+                zebec_eoa: self.owner(),
+                system_program: system_program::id(),
+                rent: sysvar::rent::id(),
+                token_program: spl_token::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+                processed_vaa: processed_vaa(
+                    &EMITTER_ADDRESS,
+                    BSC_CHAIN_ID,
+                    posted.message.sequence,
+                ),
+                emitter_acc: emitter_account(BSC_CHAIN_ID),
+                core_bridge_vaa: posted.key,
+                pda_account,
+                pda_token_account: anchor_spl::associated_token::get_associated_token_address(
+                    &pda_account,
+                    &mint,
+                ),
+                token_mint: mint,
+                mint_registry: mint_registry(&mint),
+            }
+            .to_account_metas(None),
+            data: instruction::InitializePdaTokenAccount {
+                _sender: sender,
+                _chain_id: BSC_CHAIN_ID,
+            }
+            .data(),
+        };
+        self.send(vec![ix], &[]).await
+    }
+
+    pub async fn set_payout_wallet(
+        &mut self,
+        posted: &Posted,
+        sender: [u8; 32],
+    ) -> Result<(), TransactionError> {
+        let ix = self.set_payout_wallet_ix(posted, sender, BSC_CHAIN_ID);
+        self.send(vec![ix], &[]).await
+    }
+
+    pub fn set_payout_wallet_ix(
+        &self,
+        posted: &Posted,
+        sender: [u8; 32],
+        chain_id: u16,
+    ) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::SetPayoutWallet {
+                zebec_eoa: self.owner(),
+                system_program: system_program::id(),
+                processed_vaa: processed_vaa(
+                    &posted.message.emitter_address,
+                    chain_id,
+                    posted.message.sequence,
+                ),
+                emitter_acc: emitter_account(chain_id),
+                core_bridge_vaa: posted.key,
+                payout_profile: payout_profile(&sender, chain_id),
+            }
+            .to_account_metas(None),
+            data: instruction::SetPayoutWallet { sender, chain_id }.data(),
+        }
+    }
+
+    pub async fn next_count(&mut self, sender: [u8; 32]) -> u64 {
+        self.account::<Count>(txn_count(&sender))
+            .await
+            .map_or(0, |c| c.count)
+            + 1
+    }
+
+    pub async fn store_msg(
+        &mut self,
+        posted: &Posted,
+        sender: [u8; 32],
+        current_count: u64,
+    ) -> Result<(), TransactionError> {
+        self.store_msg_from(posted, sender, current_count, BSC_CHAIN_ID, EMITTER_ADDRESS)
+            .await
+    }
+
+    /// `store_msg` against the emitter registered for `chain_id`.
+    pub async fn store_msg_from(
+        &mut self,
+        posted: &Posted,
+        sender: [u8; 32],
+        current_count: u64,
+        chain_id: u16,
+        emitter_address: [u8; 32],
+    ) -> Result<(), TransactionError> {
+        self.ensure_registered(payload_mint(&posted.message.payload))
+            .await;
+        let ix = self.store_msg_ix(posted, sender, current_count, chain_id, emitter_address);
+        self.send(vec![ix], &[]).await
+    }
+
+    pub fn store_msg_ix(
+        &self,
+        posted: &Posted,
+        sender: [u8; 32],
+        current_count: u64,
+        chain_id: u16,
+        emitter_address: [u8; 32],
+    ) -> Instruction {
+        let mint = payload_mint(&posted.message.payload);
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::StoreMsg {
+                payer: self.owner(),
+                system_program: system_program::id(),
+                processed_vaa: processed_vaa(&emitter_address, chain_id, posted.message.sequence),
+                emitter_acc: emitter_account(chain_id),
+                core_bridge_vaa: posted.key,
+                data_storage: data_store(&sender, current_count),
+                txn_count: txn_count(&sender),
+                txn_status: txn_status(&sender, current_count),
+                mint_registry: mint_registry(&mint),
+            }
+            .to_account_metas(None),
+            data: instruction::StoreMsg {
+                current_count,
+                sender,
+            }
+            .data(),
+        }
+    }
+
+    /// `store_signed_msg`; the instruction before it has to verify the
+    /// signature.
+    pub fn store_signed_msg_ix(
+        &self,
+        action: SignedAction,
+        sender: [u8; 32],
+        nonce: u64,
+        current_count: u64,
+    ) -> Instruction {
+        let mint = action.token_mint();
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::StoreSignedMsg {
+                payer: self.owner(),
+                system_program: system_program::id(),
+                emitter_acc: emitter_account(BSC_CHAIN_ID),
+                meta_nonce: meta_nonce(&sender, BSC_CHAIN_ID),
+                instructions: sysvar::instructions::id(),
+                data_storage: data_store(&sender, current_count),
+                txn_count: txn_count(&sender),
+                txn_status: txn_status(&sender, current_count),
+                mint_registry: mint_registry(&mint),
+            }
+            .to_account_metas(None),
+            data: instruction::StoreSignedMsg {
+                action,
+                sender,
+                chain_id: BSC_CHAIN_ID,
+                nonce,
+                current_count,
+            }
+            .data(),
+        }
+    }
+
+    /// Posts `payload` and stores it under the sender's next count.
+    pub async fn store(&mut self, payload: Vec<u8>, sender: [u8; 32]) -> u64 {
+        let posted = self.post(payload);
+        let count = self.next_count(sender).await;
+        self.store_msg(&posted, sender, count).await.unwrap();
+        count
+    }
+
+    /// The registry version the stored message selects, 0 if none is stored.
+    pub async fn target_version(&mut self, sender: [u8; 32], current_count: u64) -> u8 {
+        self.account::<TransactionData>(data_store(&sender, current_count))
+            .await
+            .map_or(0, |data| data.target_version)
+    }
+
+    pub async fn create_execute(
+        &mut self,
+        kind: CreateExecute,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+        sender: [u8; 32],
+        current_count: u64,
+    ) -> Result<Pubkey, TransactionError> {
+        self.create_execute_from(kind, accs, data, sender, BSC_CHAIN_ID, current_count)
+            .await
+    }
+
+    /// `create_execute` for a sender on `chain_id`.
+    pub async fn create_execute_from(
+        &mut self,
+        kind: CreateExecute,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+        sender: [u8; 32],
+        chain_id: u16,
+        current_count: u64,
+    ) -> Result<Pubkey, TransactionError> {
+        let version = self.target_version(sender, current_count).await;
+        let mint = self.stored_mint(sender, current_count).await;
+        let ix = self.create_execute_ix(
+            kind,
+            accs,
+            data,
+            sender,
+            chain_id,
+            current_count,
+            version,
+            mint,
+        );
+        self.send(vec![ix], &[]).await?;
+        Ok(transaction(&sender, current_count))
+    }
+
+    /// The instruction `create_execute_from` sends, for a message selecting
+    /// `version` and naming `mint`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_execute_ix(
+        &self,
+        kind: CreateExecute,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+        sender: [u8; 32],
+        chain_id: u16,
+        current_count: u64,
+        version: u8,
+        mint: Pubkey,
+    ) -> Instruction {
+        let transaction = transaction(&sender, current_count);
+        let pda_signer = user_pda(&sender, chain_id);
+        let mut metas = accounts::CETransaction {
+            transaction,
+            zebec_eoa: self.owner(),
+            system_program: system_program::id(),
+            data_storage: data_store(&sender, current_count),
+            pda_signer,
+            txn_status: txn_status(&sender, current_count),
+            target_program: target_program(version),
+            mint,
+            config: config(),
+        }
+        .to_account_metas(None);
+        metas.extend(remaining_accounts(&accs, &pda_signer));
+
+        let data = match kind {
+            CreateExecute::Deposit => instruction::TransactionDeposit {
+                accs,
+                data,
+                chain_id,
+                sender,
+                current_count,
+            }
+            .data(),
+            CreateExecute::StreamUpdate => instruction::TransactionStreamUpdate {
+                accs,
+                data,
+                chain_id,
+                sender,
+                current_count,
+            }
+            .data(),
+            CreateExecute::PauseResume => instruction::TransactionPauseResume {
+                accs,
+                data,
+                chain_id,
+                sender,
+                current_count,
+            }
+            .data(),
+        };
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: metas,
+            data,
+        }
+    }
+
+    pub async fn create(
+        &mut self,
+        kind: Create,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+        sender: [u8; 32],
+        current_count: u64,
+    ) -> Result<Pubkey, TransactionError> {
+        let ix = self
+            .create_ix(kind, accs, data, sender, current_count)
+            .await;
+        self.send(vec![ix], &[]).await?;
+        Ok(transaction(&sender, current_count))
+    }
+
+    /// `create` from the message's finalized transaction buffer.
+    pub async fn create_staged(
+        &mut self,
+        kind: Create,
+        sender: [u8; 32],
+        current_count: u64,
+    ) -> Result<Pubkey, TransactionError> {
+        let mut ix = self
+            .create_ix(kind, vec![], vec![], sender, current_count)
+            .await;
+        ix.accounts.push(AccountMeta::new(
+            transaction_buffer(&sender, current_count),
+            false,
+        ));
+        self.send(vec![ix], &[]).await?;
+        Ok(transaction(&sender, current_count))
+    }
+
+    pub async fn create_ix(
+        &mut self,
+        kind: Create,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+        sender: [u8; 32],
+        current_count: u64,
+    ) -> Instruction {
+        let version = self.target_version(sender, current_count).await;
+        let mint = self.stored_mint(sender, current_count).await;
+        self.create_ix_for(kind, accs, data, sender, current_count, version, mint)
+    }
+
+    /// `create_ix` for a message selecting `version` and naming `mint`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_ix_for(
+        &self,
+        kind: Create,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+        sender: [u8; 32],
+        current_count: u64,
+        version: u8,
+        mint: Pubkey,
+    ) -> Instruction {
+        let transaction = transaction(&sender, current_count);
+        let data_storage = data_store(&sender, current_count);
+        let status = txn_status(&sender, current_count);
+
+        let (accounts, data) = match kind {
+            Create::ReceiverWithdraw => (
+                accounts::CreateTransactionReceiver {
+                    transaction,
+                    zebec_eoa: self.owner(),
+                    system_program: system_program::id(),
+                    data_storage,
+                    txn_status: status,
+                    target_program: target_program(version),
+                    mint,
+                    config: config(),
+                }
+                .to_account_metas(None),
+                instruction::CreateTransactionReceiverWithdraw {
+                    accs,
+                    data,
+                    sender,
+                    current_count,
+                }
+                .data(),
+            ),
+            Create::WithdrawBridge(receiver_token_account) => (
+                accounts::CreateTransactionWithdrawBridge {
+                    transaction,
+                    zebec_eoa: self.owner(),
+                    system_program: system_program::id(),
+                    data_storage,
+                    txn_status: status,
+                    target_program: target_program(version),
+                    mint,
+                    receiver_token_account,
+                    config: config(),
+                }
+                .to_account_metas(None),
+                instruction::CreateTransactionWithdrawBridge {
+                    accs,
+                    data,
+                    sender,
+                    current_count,
+                }
+                .data(),
+            ),
+            Create::WithdrawPayout(receiver_token_account) => (
+                accounts::CreateTransactionWithdrawPayout {
+                    transaction,
+                    zebec_eoa: self.owner(),
+                    system_program: system_program::id(),
+                    data_storage,
+                    txn_status: status,
+                    target_program: target_program(version),
+                    mint,
+                    receiver_token_account,
+                    payout_profile: payout_profile(&sender, BSC_CHAIN_ID),
+                    config: config(),
+                }
+                .to_account_metas(None),
+                instruction::CreateTransactionWithdrawPayout {
+                    accs,
+                    data,
+                    sender,
+                    current_count,
+                }
+                .data(),
+            ),
+            Create::SenderWithdraw | Create::InstantTransfer => {
+                let accounts = accounts::CreateOutboundTransaction {
+                    transaction,
+                    zebec_eoa: self.owner(),
+                    system_program: system_program::id(),
+                    data_storage,
+                    txn_status: status,
+                    target_program: target_program(version),
+                    mint,
+                    chain_limit: chain_limit(SOLANA_CHAIN_ID),
+                    mint_limit: mint_limit(&mint),
+                    config: config(),
+                }
+                .to_account_metas(None);
+                let data = match kind {
+                    Create::SenderWithdraw => instruction::CreateTransactionSenderWithdraw {
+                        accs,
+                        data,
+                        sender,
+                        current_count,
+                    }
+                    .data(),
+                    _ => instruction::CreateTransactionInstantTransfer {
+                        accs,
+                        data,
+                        sender,
+                        current_count,
+                    }
+                    .data(),
+                };
+                (accounts, data)
+            }
+            kind => {
+                let accounts = accounts::CreateTransaction {
+                    transaction,
+                    zebec_eoa: self.owner(),
+                    system_program: system_program::id(),
+                    data_storage,
+                    txn_status: status,
+                    target_program: target_program(version),
+                    mint,
+                    config: config(),
+                }
+                .to_account_metas(None);
+                let data = match kind {
+                    Create::Stream => instruction::CreateTransactionStream {
+                        accs,
+                        data,
+                        sender,
+                        current_count,
+                    }
+                    .data(),
+                    Create::Cancel => instruction::CreateTransactionCancel {
+                        accs,
+                        data,
+                        sender,
+                        current_count,
+                    }
+                    .data(),
+                    Create::ReceiverWithdraw
+                    | Create::WithdrawBridge(_)
+                    | Create::WithdrawPayout(_)
+                    | Create::SenderWithdraw
+                    | Create::InstantTransfer => unreachable!(),
+                };
+                (accounts, data)
+            }
+        };
+        Instruction {
+            program_id: solana_project::ID,
+            accounts,
+            data,
+        }
+    }
+
+    // Chunked uploads
+
+    pub fn init_transaction_ix(
+        &self,
+        sender: [u8; 32],
+        current_count: u64,
+        accounts_len: u8,
+        data_len: u16,
+    ) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::InitTransaction {
+                transaction_buffer: transaction_buffer(&sender, current_count),
+                zebec_eoa: self.owner(),
+                system_program: system_program::id(),
+                txn_status: txn_status(&sender, current_count),
+                config: config(),
+            }
+            .to_account_metas(None),
+            data: instruction::InitTransaction {
+                _sender: sender,
+                _current_count: current_count,
+                accounts_len,
+                data_len,
+            }
+            .data(),
+        }
+    }
+
+    pub fn append_accounts_ix(
+        &self,
+        uploader: Pubkey,
+        sender: [u8; 32],
+        current_count: u64,
+        accs: Vec<TransactionAccount>,
+    ) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::AppendTransaction {
+                transaction_buffer: transaction_buffer(&sender, current_count),
+                uploader,
+            }
+            .to_account_metas(None),
+            data: instruction::AppendAccounts {
+                _sender: sender,
+                _current_count: current_count,
+                accs,
+            }
+            .data(),
+        }
+    }
+
+    pub fn append_data_ix(
+        &self,
+        uploader: Pubkey,
+        sender: [u8; 32],
+        current_count: u64,
+        data: Vec<u8>,
+    ) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::AppendTransaction {
+                transaction_buffer: transaction_buffer(&sender, current_count),
+                uploader,
+            }
+            .to_account_metas(None),
+            data: instruction::AppendData {
+                _sender: sender,
+                _current_count: current_count,
+                data,
+            }
+            .data(),
+        }
+    }
+
+    pub fn finalize_transaction_ix(
+        &self,
+        uploader: Pubkey,
+        sender: [u8; 32],
+        current_count: u64,
+    ) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::AppendTransaction {
+                transaction_buffer: transaction_buffer(&sender, current_count),
+                uploader,
+            }
+            .to_account_metas(None),
+            data: instruction::FinalizeTransaction {
+                _sender: sender,
+                _current_count: current_count,
+            }
+            .data(),
+        }
+    }
+
+    /// Uploads `accs` and `data` to the message's transaction buffer, eight
+    /// accounts and 256 bytes at a time, and finalizes it.
+    pub async fn upload(
+        &mut self,
+        accs: &[TransactionAccount],
+        data: &[u8],
+        sender: [u8; 32],
+        current_count: u64,
+    ) -> Result<(), TransactionError> {
+        let owner = self.owner();
+        let ix =
+            self.init_transaction_ix(sender, current_count, accs.len() as u8, data.len() as u16);
+        self.send(vec![ix], &[]).await?;
+        for chunk in accs.chunks(8) {
+            let ix = self.append_accounts_ix(owner, sender, current_count, chunk.to_vec());
+            self.send(vec![ix], &[]).await?;
+        }
+        for chunk in data.chunks(256) {
+            let ix = self.append_data_ix(owner, sender, current_count, chunk.to_vec());
+            self.send(vec![ix], &[]).await?;
+        }
+        let ix = self.finalize_transaction_ix(owner, sender, current_count);
+        self.send(vec![ix], &[]).await
+    }
+
+    pub fn execute_ix(
+        &self,
+        transaction: Pubkey,
+        accs: &[TransactionAccount],
+        eth_add: [u8; 32],
+        current_count: u64,
+        version: u8,
+    ) -> Instruction {
+        let pda_signer = user_pda(&eth_add, BSC_CHAIN_ID);
+        let mut metas = accounts::ExecuteTransaction {
+            system_program: system_program::id(),
+            pda_signer,
+            transaction,
+            txn_status: txn_status(&eth_add, current_count),
+            data_storage: data_store(&eth_add, current_count),
+            target_program: target_program(version),
+        }
+        .to_account_metas(None);
+        metas.extend(remaining_accounts(accs, &pda_signer));
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: metas,
+            data: instruction::ExecuteTransaction {
+                eth_add,
+                from_chain_id: BSC_CHAIN_ID,
+                current_count,
+            }
+            .data(),
+        }
+    }
+
+    pub async fn execute(
+        &mut self,
+        transaction: Pubkey,
+        accs: &[TransactionAccount],
+        eth_add: [u8; 32],
+        current_count: u64,
+    ) -> Result<(), TransactionError> {
+        let version = self.target_version(eth_add, current_count).await;
+        let ix = self.execute_ix(transaction, accs, eth_add, current_count, version);
+        self.send(vec![ix], &[]).await
+    }
+
+    // Relays
+
+    /// `relay` running `steps`, each as if it were sent on its own.
+    pub fn relay_ix(&self, steps: &[Instruction]) -> Instruction {
+        zebec_wormhole_client::instructions::relay(&solana_project::ID, &self.owner(), steps)
+    }
+
+    /// Relays `steps` for a message naming `mint`, with the compute a relay
+    /// needs.
+    pub async fn relay(
+        &mut self,
+        steps: &[Instruction],
+        mint: Pubkey,
+    ) -> Result<(), TransactionError> {
+        self.ensure_registered(mint).await;
+        let ix = self.relay_ix(steps);
+        self.send(
+            vec![ComputeBudgetInstruction::request_units(1_000_000, 0), ix],
+            &[],
+        )
+        .await
+    }
+
+    // Batch streams
+
+    pub async fn store_batch_msg(
+        &mut self,
+        posted: &Posted,
+        sender: [u8; 32],
+        current_count: u64,
+    ) -> Result<(), TransactionError> {
+        let mint = payload_mint(&posted.message.payload);
+        self.ensure_registered(mint).await;
+        let ix = Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::StoreBatchMsg {
+                payer: self.owner(),
+                system_program: system_program::id(),
+                processed_vaa: processed_vaa(
+                    &EMITTER_ADDRESS,
+                    BSC_CHAIN_ID,
+                    posted.message.sequence,
+                ),
+                emitter_acc: emitter_account(BSC_CHAIN_ID),
+                core_bridge_vaa: posted.key,
+                batch_storage: batch_store(&sender, current_count),
+                txn_count: txn_count(&sender),
+                mint_registry: mint_registry(&mint),
+            }
+            .to_account_metas(None),
+            data: instruction::StoreBatchMsg {
+                current_count,
+                sender,
+            }
+            .data(),
+        };
+        self.send(vec![ix], &[]).await
+    }
+
+    /// Posts a batch `payload` and stores it under the sender's next count.
+    pub async fn store_batch(&mut self, payload: Vec<u8>, sender: [u8; 32]) -> u64 {
+        let posted = self.post(payload);
+        let count = self.next_count(sender).await;
+        self.store_batch_msg(&posted, sender, count).await.unwrap();
+        count
+    }
+
+    async fn batch_target_version(&mut self, sender: [u8; 32], current_count: u64) -> u8 {
+        self.account::<BatchData>(batch_store(&sender, current_count))
+            .await
+            .map_or(0, |batch| batch.target_version)
+    }
+
+    pub async fn create_batch(
+        &mut self,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+        sender: [u8; 32],
+        current_count: u64,
+        index: u8,
+    ) -> Result<Pubkey, TransactionError> {
+        let transaction = batch_transaction(&sender, current_count, index);
+        let version = self.batch_target_version(sender, current_count).await;
+        let mint = self.batch_mint(sender, current_count).await;
+        let ix = Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::CreateBatchTransaction {
+                transaction,
+                zebec_eoa: self.owner(),
+                system_program: system_program::id(),
+                batch_storage: batch_store(&sender, current_count),
+                target_program: target_program(version),
+                mint,
+                config: config(),
+            }
+            .to_account_metas(None),
+            data: instruction::CreateTransactionBatchStream {
+                accs,
+                data,
+                sender,
+                current_count,
+                index,
+            }
+            .data(),
+        };
+        self.send(vec![ix], &[]).await?;
+        Ok(transaction)
+    }
+
+    pub async fn execute_batch(
+        &mut self,
+        transaction: Pubkey,
+        accs: &[TransactionAccount],
+        eth_add: [u8; 32],
+        current_count: u64,
+        index: u8,
+    ) -> Result<(), TransactionError> {
+        let version = self.batch_target_version(eth_add, current_count).await;
+        let pda_signer = user_pda(&eth_add, BSC_CHAIN_ID);
+        let mut metas = accounts::ExecuteBatchTransaction {
+            system_program: system_program::id(),
+            pda_signer,
+            transaction,
+            batch_storage: batch_store(&eth_add, current_count),
+            target_program: target_program(version),
+        }
+        .to_account_metas(None);
+        metas.extend(remaining_accounts(accs, &pda_signer));
+        let ix = Instruction {
+            program_id: solana_project::ID,
+            accounts: metas,
+            data: instruction::ExecuteBatchTransaction {
+                eth_add,
+                from_chain_id: BSC_CHAIN_ID,
+                current_count,
+                index,
+            }
+            .data(),
+        };
+        self.send(vec![ix], &[]).await
+    }
+
+    pub fn fail_batch_entry_ix(
+        &self,
+        authority: Pubkey,
+        sender: [u8; 32],
+        current_count: u64,
+        index: u8,
+        error_code: u32,
+    ) -> Instruction {
+        Instruction {
+            program_id: solana_project::ID,
+            accounts: accounts::FailBatchEntry {
+                authority,
+                config: config(),
+                batch_storage: batch_store(&sender, current_count),
+            }
+            .to_account_metas(None),
+            data: instruction::FailBatchEntry {
+                sender,
+                current_count,
+                index,
+                error_code,
+            }
+            .data(),
+        }
+    }
+}
+
+pub fn assert_error<T: std::fmt::Debug>(
+    result: Result<T, TransactionError>,
+    expected: MessengerError,
+) {
+    let code: u32 = expected.into();
+    match result {
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(actual))) => {
+            assert_eq!(actual, code, "expected error {}", code)
+        }
+        other => panic!("expected error {}, got {:?}", code, other),
+    }
+}
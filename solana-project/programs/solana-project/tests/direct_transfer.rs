@@ -0,0 +1,1010 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{AnchorSerialize, InstructionData, ToAccountMetas};
+use anchor_spl::token::{spl_token, Mint, TokenAccount};
+use primitive_types::U256;
+use solana_sdk::signature::{Keypair, Signer};
+
+use solana_project::constants::SOLANA_CHAIN_ID;
+use solana_project::errors::MessengerError;
+use solana_project::portal::{DeserializePayload, PayloadTransfer};
+use solana_project::state::{
+    BridgeBack, Clearance, Config, MintRegistry, TransactionAccount, TransactionData,
+    TransactionStatus, TransferTerms,
+};
+use solana_project::{accounts, instruction};
+use token_bridge_stand_in::WrappedMeta;
+
+use common::*;
+
+fn bridge_pda(seeds: &[&[u8]], program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(seeds, program).0
+}
+
+// Target chain of the withdraw stream and bridge payloads below.
+const BRIDGE_TARGET_CHAIN: u16 = 2;
+
+fn transfer_accounts(
+    zebec_eoa: Pubkey,
+    portal_message: Pubkey,
+    sender: [u8; 32],
+    current_count: u64,
+    target_chain: u16,
+    mint: Pubkey,
+    from: Pubkey,
+) -> Vec<AccountMeta> {
+    let portal_emitter = bridge_pda(&[b"emitter"], &token_bridge());
+    accounts::DirectTransfer {
+        zebec_eoa,
+        data_storage: data_store(&sender, current_count),
+        txn_status: txn_status(&sender, current_count),
+        chain_limit: chain_limit(target_chain),
+        mint_limit: mint_limit(&mint),
+        mint_registry: mint_registry(&mint),
+        pda_signer: user_pda(&sender, BSC_CHAIN_ID),
+        config: config(),
+        portal_config: bridge_pda(&[b"config"], &token_bridge()),
+        from,
+        mint,
+        portal_custody: bridge_pda(&[mint.as_ref()], &token_bridge()),
+        portal_authority_signer: bridge_pda(&[b"authority_signer"], &token_bridge()),
+        portal_custody_signer: bridge_pda(&[b"custody_signer"], &token_bridge()),
+        wrapped_meta: bridge_pda(&[b"meta", mint.as_ref()], &token_bridge()),
+        bridge_config: bridge_pda(&[b"Bridge"], &core_bridge()),
+        portal_message,
+        portal_emitter,
+        portal_sequence: bridge_pda(&[b"Sequence", portal_emitter.as_ref()], &core_bridge()),
+        bridge_fee_collector: bridge_pda(&[b"fee_collector"], &core_bridge()),
+        clock: sysvar::clock::id(),
+        rent: sysvar::rent::id(),
+        system_program: system_program::id(),
+        portal_bridge_program: token_bridge(),
+        core_bridge_program: core_bridge(),
+        token_program: spl_token::ID,
+    }
+    .to_account_metas(None)
+}
+
+fn transfer_ix(
+    zebec_eoa: Pubkey,
+    portal_message: Pubkey,
+    sender: [u8; 32],
+    current_count: u64,
+    mint: Pubkey,
+    from: Pubkey,
+) -> Instruction {
+    transfer_ix_to(
+        zebec_eoa,
+        portal_message,
+        sender,
+        current_count,
+        mint,
+        from,
+        BSC_CHAIN_ID,
+        0,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn transfer_ix_to(
+    zebec_eoa: Pubkey,
+    portal_message: Pubkey,
+    sender: [u8; 32],
+    current_count: u64,
+    mint: Pubkey,
+    from: Pubkey,
+    target_chain: u16,
+    fee: u64,
+) -> Instruction {
+    Instruction {
+        program_id: solana_project::ID,
+        accounts: transfer_accounts(
+            zebec_eoa,
+            portal_message,
+            sender,
+            current_count,
+            target_chain,
+            mint,
+            from,
+        ),
+        data: instruction::TransactionDirectTransfer {
+            sender,
+            chain_id: BSC_CHAIN_ID,
+            current_count,
+            target_chain,
+            fee,
+        }
+        .data(),
+    }
+}
+
+fn withdraw_bridge_ix(
+    zebec_eoa: Pubkey,
+    portal_message: Pubkey,
+    receiver: [u8; 32],
+    current_count: u64,
+    mint: Pubkey,
+    from: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: solana_project::ID,
+        accounts: transfer_accounts(
+            zebec_eoa,
+            portal_message,
+            receiver,
+            current_count,
+            BRIDGE_TARGET_CHAIN,
+            mint,
+            from,
+        ),
+        data: instruction::TransactionWithdrawBridge {
+            sender: receiver,
+            chain_id: BSC_CHAIN_ID,
+            current_count,
+        }
+        .data(),
+    }
+}
+
+/// Settle accounts for a receiver withdraw that Zebec pays into `to`.
+fn withdraw_accounts(
+    sender: &[u8; 32],
+    receiver: &[u8; 32],
+    data_account: Pubkey,
+    mint: Pubkey,
+    to: Pubkey,
+) -> Vec<TransactionAccount> {
+    let pda_receiver = user_pda(receiver, BSC_CHAIN_ID);
+    zebec_accounts(
+        16,
+        &[
+            (1, pda_receiver),
+            (2, user_pda(sender, BSC_CHAIN_ID)),
+            (6, data_account),
+            (12, mint),
+            (14, to),
+        ],
+        Some(pda_receiver),
+    )
+}
+
+fn wrapped_mint(token_chain: u16, token_address: [u8; 32]) -> Pubkey {
+    bridge_pda(
+        &[b"wrapped", &token_chain.to_be_bytes(), &token_address],
+        &token_bridge(),
+    )
+}
+
+/// Writes a wrapped mint and its meta account where the token bridge keeps
+/// them, and registers the mint as wrapped.
+fn create_wrapped(
+    h: &mut Harness,
+    token_chain: u16,
+    token_address: [u8; 32],
+    supply: u64,
+) -> Pubkey {
+    let mint = wrapped_mint(token_chain, token_address);
+    let mut data = vec![0u8; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: Some(bridge_pda(&[b"mint_signer"], &token_bridge())).into(),
+        supply,
+        decimals: 8,
+        is_initialized: true,
+        freeze_authority: None.into(),
+    }
+    .pack_into_slice(&mut data);
+    h.set_account(mint, spl_token::ID, data);
+
+    let meta = WrappedMeta {
+        chain: token_chain,
+        token_address,
+        original_decimals: 18,
+    };
+    h.set_account(
+        bridge_pda(&[b"meta", mint.as_ref()], &token_bridge()),
+        token_bridge(),
+        meta.try_to_vec().unwrap(),
+    );
+    h.write_mint_registry(MintRegistry {
+        wrapped: true,
+        origin_chain: token_chain,
+        origin_address: token_address,
+        ..open_registry(mint, 8)
+    });
+    mint
+}
+
+#[tokio::test]
+async fn direct_transfer_native_locks_tokens_and_posts_transfer() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = h.create_mint();
+    let from = h.create_token_account(mint, user_pda(&sender, BSC_CHAIN_ID), 500);
+    let count = h
+        .store(direct_transfer_payload(500, sender, mint, receiver), sender)
+        .await;
+
+    let portal_message = Keypair::new();
+    let ix = transfer_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        sender,
+        count,
+        mint,
+        from,
+    );
+    h.send(vec![ix], &[&portal_message]).await.unwrap();
+
+    let from: TokenAccount = h.account(from).await.unwrap();
+    assert_eq!(from.amount, 0);
+    let custody: TokenAccount = h
+        .account(bridge_pda(&[mint.as_ref()], &token_bridge()))
+        .await
+        .unwrap();
+    assert_eq!(custody.amount, 500);
+    assert_eq!(
+        custody.owner,
+        bridge_pda(&[b"custody_signer"], &token_bridge())
+    );
+
+    let posted = h.posted_message(portal_message.pubkey()).await;
+    assert_eq!(posted.sequence, 0);
+    assert_eq!(posted.nonce, 0);
+    assert_eq!(
+        posted.emitter_address,
+        bridge_pda(&[b"emitter"], &token_bridge()).to_bytes()
+    );
+    let transfer = PayloadTransfer::deserialize(&mut posted.payload.as_slice()).unwrap();
+    assert_eq!(
+        transfer,
+        PayloadTransfer {
+            amount: U256::from(500),
+            token_address: mint.to_bytes(),
+            token_chain: 1,
+            to: receiver,
+            to_chain: BSC_CHAIN_ID,
+            fee: U256::zero(),
+        }
+    );
+
+    let status: TransactionStatus = h.account(txn_status(&sender, count)).await.unwrap();
+    assert!(status.executed);
+    let state: Config = h.account(config()).await.unwrap();
+    assert_eq!(state.nonce, 1);
+}
+
+#[tokio::test]
+async fn direct_transfer_native_amounts_are_in_mint_units() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = h.create_mint_with_decimals(9);
+    let from = h.create_token_account(mint, user_pda(&sender, BSC_CHAIN_ID), 5_001);
+
+    // The bridge would drop the last digit of an amount with 9 decimals.
+    let count = h
+        .store(
+            direct_transfer_payload(5_001, sender, mint, receiver),
+            sender,
+        )
+        .await;
+    let portal_message = Keypair::new();
+    let ix = transfer_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        sender,
+        count,
+        mint,
+        from,
+    );
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::AmountWouldTruncate,
+    );
+
+    let count = h
+        .store(
+            direct_transfer_payload(5_000, sender, mint, receiver),
+            sender,
+        )
+        .await;
+    let portal_message = Keypair::new();
+    let ix = transfer_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        sender,
+        count,
+        mint,
+        from,
+    );
+    h.send(vec![ix], &[&portal_message]).await.unwrap();
+
+    let from: TokenAccount = h.account(from).await.unwrap();
+    assert_eq!(from.amount, 1);
+    let custody: TokenAccount = h
+        .account(bridge_pda(&[mint.as_ref()], &token_bridge()))
+        .await
+        .unwrap();
+    assert_eq!(custody.amount, 5_000);
+    let posted = h.posted_message(portal_message.pubkey()).await;
+    let transfer = PayloadTransfer::deserialize(&mut posted.payload.as_slice()).unwrap();
+    assert_eq!(transfer.amount, U256::from(500));
+}
+
+#[tokio::test]
+async fn direct_transfer_wrapped_burns_tokens_and_posts_transfer() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let token_address = wallet(7);
+    let mint = create_wrapped(&mut h, BSC_CHAIN_ID, token_address, 800);
+    let from = h.create_token_account(mint, user_pda(&sender, BSC_CHAIN_ID), 800);
+    let count = h
+        .store(direct_transfer_payload(300, sender, mint, receiver), sender)
+        .await;
+
+    let portal_message = Keypair::new();
+    let ix = transfer_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        sender,
+        count,
+        mint,
+        from,
+    );
+    h.send(vec![ix], &[&portal_message]).await.unwrap();
+
+    let from: TokenAccount = h.account(from).await.unwrap();
+    assert_eq!(from.amount, 500);
+    let mint_state: Mint = h.account(mint).await.unwrap();
+    assert_eq!(mint_state.supply, 500);
+
+    let posted = h.posted_message(portal_message.pubkey()).await;
+    let transfer = PayloadTransfer::deserialize(&mut posted.payload.as_slice()).unwrap();
+    assert_eq!(transfer.amount, U256::from(300));
+    assert_eq!(transfer.token_address, token_address);
+    assert_eq!(transfer.token_chain, BSC_CHAIN_ID);
+    assert_eq!(transfer.to, receiver);
+
+    let status: TransactionStatus = h.account(txn_status(&sender, count)).await.unwrap();
+    assert!(status.executed);
+}
+
+#[tokio::test]
+async fn direct_transfer_requires_config_owner() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = h.create_mint();
+    let from = h.create_token_account(mint, user_pda(&sender, BSC_CHAIN_ID), 500);
+    let count = h
+        .store(direct_transfer_payload(500, sender, mint, receiver), sender)
+        .await;
+
+    let relayer = Keypair::new();
+    h.set_account(relayer.pubkey(), system_program::id(), vec![]);
+    let portal_message = Keypair::new();
+    let ix = transfer_ix(
+        relayer.pubkey(),
+        portal_message.pubkey(),
+        sender,
+        count,
+        mint,
+        from,
+    );
+    assert_error(
+        h.send(vec![ix], &[&relayer, &portal_message]).await,
+        MessengerError::InvalidCaller,
+    );
+
+    let status: TransactionStatus = h.account(txn_status(&sender, count)).await.unwrap();
+    assert!(!status.executed);
+}
+
+#[tokio::test]
+async fn direct_transfer_checks_mint() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = h.create_mint();
+    let other_mint = h.create_mint();
+    let from = h.create_token_account(other_mint, user_pda(&sender, BSC_CHAIN_ID), 500);
+    let count = h
+        .store(direct_transfer_payload(500, sender, mint, receiver), sender)
+        .await;
+
+    let portal_message = Keypair::new();
+    let ix = transfer_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        sender,
+        count,
+        other_mint,
+        from,
+    );
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::MintKeyMismatch,
+    );
+}
+
+#[tokio::test]
+async fn direct_transfer_takes_only_direct_transfer_messages() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let mint = h.create_mint();
+    let from = h.create_token_account(mint, user_pda(&sender, BSC_CHAIN_ID), 500);
+    let count = h.store(deposit_payload(500, sender, mint), sender).await;
+
+    let portal_message = Keypair::new();
+    let ix = transfer_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        sender,
+        count,
+        mint,
+        from,
+    );
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::MessageCodeMismatch,
+    );
+
+    let from: TokenAccount = h.account(from).await.unwrap();
+    assert_eq!(from.amount, 500);
+}
+
+#[tokio::test]
+async fn direct_transfer_checks_the_mint_registry() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = h.create_mint();
+    let from = h.create_token_account(mint, user_pda(&sender, BSC_CHAIN_ID), 500);
+    let count = h
+        .store(direct_transfer_payload(500, sender, mint, receiver), sender)
+        .await;
+
+    // Bounds set after the message was stored still apply.
+    let ix = h.register_mint_ix(mint, 0, 499, SOLANA_CHAIN_ID, mint.to_bytes());
+    h.send(vec![ix], &[]).await.unwrap();
+    let portal_message = Keypair::new();
+    let ix = transfer_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        sender,
+        count,
+        mint,
+        from,
+    );
+    assert_error(
+        h.send(vec![ix.clone()], &[&portal_message]).await,
+        MessengerError::AmountOutOfBounds,
+    );
+
+    // So does removing the mint.
+    let remove = h.remove_mint_ix(mint);
+    h.send(vec![remove], &[]).await.unwrap();
+    h.next_blockhash().await;
+    assert_error(
+        h.send(vec![ix.clone()], &[&portal_message]).await,
+        MessengerError::MintNotAllowed,
+    );
+
+    // A native transfer cannot take a mint registered as wrapped.
+    h.write_mint_registry(MintRegistry {
+        wrapped: true,
+        ..open_registry(mint, 6)
+    });
+    h.next_blockhash().await;
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::MintOriginMismatch,
+    );
+}
+
+#[tokio::test]
+async fn direct_transfer_checks_the_wrapped_meta() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = h.create_mint();
+    let from = h.create_token_account(mint, user_pda(&sender, BSC_CHAIN_ID), 500);
+    let count = h
+        .store(direct_transfer_payload(500, sender, mint, receiver), sender)
+        .await;
+
+    // A meta whose origin does not derive the mint.
+    let meta = WrappedMeta {
+        chain: BSC_CHAIN_ID,
+        token_address: wallet(7),
+        original_decimals: 18,
+    };
+    h.set_account(
+        bridge_pda(&[b"meta", mint.as_ref()], &token_bridge()),
+        token_bridge(),
+        meta.try_to_vec().unwrap(),
+    );
+    let portal_message = Keypair::new();
+    let ix = transfer_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        sender,
+        count,
+        mint,
+        from,
+    );
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::WrappedMetaMismatch,
+    );
+}
+
+#[tokio::test]
+async fn direct_transfer_keeps_to_the_payload_terms() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = h.create_mint_with_decimals(9);
+    let from = h.create_token_account(mint, user_pda(&sender, BSC_CHAIN_ID), 5_000);
+    let payload = direct_transfer_payload_with_terms(5_000, sender, mint, receiver, 2, 30);
+    let count = h.store(payload, sender).await;
+    let data: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+    assert_eq!(
+        data.transfer_terms,
+        TransferTerms::Fixed {
+            target_chain: 2,
+            max_fee: 30
+        }
+    );
+
+    let send = |target_chain: u16, fee: u64, portal_message: &Keypair, h: &Harness| {
+        transfer_ix_to(
+            h.owner(),
+            portal_message.pubkey(),
+            sender,
+            count,
+            mint,
+            from,
+            target_chain,
+            fee,
+        )
+    };
+    let portal_message = Keypair::new();
+    let ix = send(BSC_CHAIN_ID, 0, &portal_message, &h);
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::TargetChainMismatch,
+    );
+    // The cap is in the mint's base units like the amount.
+    let ix = send(2, 31, &portal_message, &h);
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::FeeExceedsMaximum,
+    );
+
+    let ix = send(2, 30, &portal_message, &h);
+    h.send(vec![ix], &[&portal_message]).await.unwrap();
+    let posted = h.posted_message(portal_message.pubkey()).await;
+    let transfer = PayloadTransfer::deserialize(&mut posted.payload.as_slice()).unwrap();
+    assert_eq!(transfer.to_chain, 2);
+    assert_eq!(transfer.amount, U256::from(500));
+    assert_eq!(transfer.fee, U256::from(3));
+}
+
+#[tokio::test]
+async fn direct_transfer_sends_payloads_without_terms_back_for_free() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = h.create_mint();
+    let from = h.create_token_account(mint, user_pda(&sender, BSC_CHAIN_ID), 500);
+    let count = h
+        .store(direct_transfer_payload(500, sender, mint, receiver), sender)
+        .await;
+    let data: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+    assert_eq!(data.transfer_terms, TransferTerms::Open);
+
+    let send = |target_chain: u16, fee: u64, portal_message: &Keypair, h: &Harness| {
+        transfer_ix_to(
+            h.owner(),
+            portal_message.pubkey(),
+            sender,
+            count,
+            mint,
+            from,
+            target_chain,
+            fee,
+        )
+    };
+    let portal_message = Keypair::new();
+    let ix = send(2, 0, &portal_message, &h);
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::TargetChainMismatch,
+    );
+    let ix = send(BSC_CHAIN_ID, 1, &portal_message, &h);
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::FeeExceedsMaximum,
+    );
+
+    let ix = send(BSC_CHAIN_ID, 0, &portal_message, &h);
+    h.send(vec![ix], &[&portal_message]).await.unwrap();
+    let posted = h.posted_message(portal_message.pubkey()).await;
+    let transfer = PayloadTransfer::deserialize(&mut posted.payload.as_slice()).unwrap();
+    assert_eq!(transfer.to_chain, BSC_CHAIN_ID);
+    assert_eq!(transfer.fee, U256::zero());
+}
+
+#[tokio::test]
+async fn direct_transfer_rejects_a_fee_above_the_amount() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = h.create_mint();
+    let from = h.create_token_account(mint, user_pda(&sender, BSC_CHAIN_ID), 500);
+    let count = h
+        .store(direct_transfer_payload(500, sender, mint, receiver), sender)
+        .await;
+
+    let portal_message = Keypair::new();
+    let ix = transfer_ix_to(
+        h.owner(),
+        portal_message.pubkey(),
+        sender,
+        count,
+        mint,
+        from,
+        BSC_CHAIN_ID,
+        501,
+    );
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::FeeExceedsAmount,
+    );
+}
+
+#[tokio::test]
+async fn direct_transfer_waits_out_a_hold() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = h.create_mint();
+    let from = h.create_token_account(mint, user_pda(&sender, BSC_CHAIN_ID), 500);
+    let ix = h.set_mint_limit_ix(mint, u64::MAX, 0, 500, 600);
+    h.send(vec![ix], &[]).await.unwrap();
+    let count = h
+        .store(direct_transfer_payload(500, sender, mint, receiver), sender)
+        .await;
+
+    let send = |portal_message: &Keypair, h: &Harness| {
+        transfer_ix(
+            h.owner(),
+            portal_message.pubkey(),
+            sender,
+            count,
+            mint,
+            from,
+        )
+    };
+    let portal_message = Keypair::new();
+    let ix = send(&portal_message, &h);
+    h.send(vec![ix], &[&portal_message]).await.unwrap();
+    let now = h.unix_timestamp().await;
+    let data: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+    assert_eq!(
+        data.clearance,
+        Clearance::Held {
+            release_at: now + 600
+        }
+    );
+    let status: TransactionStatus = h.account(txn_status(&sender, count)).await.unwrap();
+    assert!(!status.executed);
+    let balance: TokenAccount = h.account(from).await.unwrap();
+    assert_eq!(balance.amount, 500);
+
+    let portal_message = Keypair::new();
+    let ix = send(&portal_message, &h);
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::TransferHeld,
+    );
+
+    h.advance_clock(600).await;
+    let portal_message = Keypair::new();
+    let ix = send(&portal_message, &h);
+    h.send(vec![ix], &[&portal_message]).await.unwrap();
+    let status: TransactionStatus = h.account(txn_status(&sender, count)).await.unwrap();
+    assert!(status.executed);
+    let balance: TokenAccount = h.account(from).await.unwrap();
+    assert_eq!(balance.amount, 0);
+}
+
+#[tokio::test]
+async fn withdraw_stream_bridge_sends_the_withdrawal_on() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let pda_receiver = user_pda(&receiver, BSC_CHAIN_ID);
+    let mint = h.create_mint();
+    let data_account = Pubkey::new_unique();
+    // Already in the account before the withdraw; it stays there.
+    let from = h.create_token_account(mint, pda_receiver, 300);
+    let count = h
+        .store(
+            withdraw_stream_bridge_payload(receiver, mint, sender, data_account, 2, 10),
+            receiver,
+        )
+        .await;
+    let accs = withdraw_accounts(&sender, &receiver, data_account, mint, from);
+
+    assert_error(
+        h.create(
+            Create::ReceiverWithdraw,
+            accs.clone(),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await,
+        MessengerError::MessageCodeMismatch,
+    );
+    let transaction = h
+        .create(
+            Create::WithdrawBridge(from),
+            accs.clone(),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await
+        .unwrap();
+    let data: TransactionData = h.account(data_store(&receiver, count)).await.unwrap();
+    assert_eq!(
+        data.bridge_back,
+        BridgeBack::To {
+            target_chain: 2,
+            fee: 10,
+            balance_before: 300,
+            bridged: false,
+        }
+    );
+
+    let portal_message = Keypair::new();
+    let ix = withdraw_bridge_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        receiver,
+        count,
+        mint,
+        from,
+    );
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::TransactionNotExecuted,
+    );
+
+    h.execute(transaction, &accs, receiver, count)
+        .await
+        .unwrap();
+    // What the Zebec stub would have paid out.
+    h.write_token_account(from, mint, pda_receiver, 1_000);
+
+    let ix = withdraw_bridge_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        receiver,
+        count,
+        mint,
+        from,
+    );
+    h.send(vec![ix], &[&portal_message]).await.unwrap();
+
+    let from_state: TokenAccount = h.account(from).await.unwrap();
+    assert_eq!(from_state.amount, 300);
+    let custody: TokenAccount = h
+        .account(bridge_pda(&[mint.as_ref()], &token_bridge()))
+        .await
+        .unwrap();
+    assert_eq!(custody.amount, 700);
+    let posted = h.posted_message(portal_message.pubkey()).await;
+    let transfer = PayloadTransfer::deserialize(&mut posted.payload.as_slice()).unwrap();
+    assert_eq!(transfer.amount, U256::from(700));
+    assert_eq!(transfer.fee, U256::from(10));
+    assert_eq!(transfer.to, receiver);
+    assert_eq!(transfer.to_chain, 2);
+
+    let data: TransactionData = h.account(data_store(&receiver, count)).await.unwrap();
+    assert!(matches!(
+        data.bridge_back,
+        BridgeBack::To { bridged: true, .. }
+    ));
+
+    let portal_message = Keypair::new();
+    let ix = withdraw_bridge_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        receiver,
+        count,
+        mint,
+        from,
+    );
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::AlreadyBridged,
+    );
+}
+
+#[tokio::test]
+async fn withdraw_stream_bridge_checks() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let pda_receiver = user_pda(&receiver, BSC_CHAIN_ID);
+    let mint = h.create_mint();
+    let data_account = Pubkey::new_unique();
+    let from = h.create_token_account(mint, pda_receiver, 0);
+
+    // Solana is where the withdrawal already is.
+    let posted = h.post(withdraw_stream_bridge_payload(
+        receiver,
+        mint,
+        sender,
+        data_account,
+        1,
+        0,
+    ));
+    let count = h.next_count(receiver).await;
+    assert_error(
+        h.store_msg(&posted, receiver, count).await,
+        MessengerError::InvalidToChainId,
+    );
+
+    let count = h
+        .store(
+            withdraw_stream_bridge_payload(receiver, mint, sender, data_account, 2, 50),
+            receiver,
+        )
+        .await;
+    let accs = withdraw_accounts(&sender, &receiver, data_account, mint, from);
+    let other = h.create_token_account(mint, pda_receiver, 0);
+    let strangers = h.create_token_account(mint, Pubkey::new_unique(), 0);
+    let cases = vec![
+        (accs.clone(), other),
+        (
+            withdraw_accounts(&sender, &receiver, data_account, mint, strangers),
+            strangers,
+        ),
+    ];
+    for (accs, receiver_token_account) in cases {
+        assert_error(
+            h.create(
+                Create::WithdrawBridge(receiver_token_account),
+                accs,
+                vec![1u8; 8],
+                receiver,
+                count,
+            )
+            .await,
+            MessengerError::ReceiverTokenAccountMismatch,
+        );
+    }
+
+    let transaction = h
+        .create(
+            Create::WithdrawBridge(from),
+            accs.clone(),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await
+        .unwrap();
+    h.execute(transaction, &accs, receiver, count)
+        .await
+        .unwrap();
+
+    let portal_message = Keypair::new();
+    let ix = withdraw_bridge_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        receiver,
+        count,
+        mint,
+        from,
+    );
+    assert_error(
+        h.send(vec![ix.clone()], &[&portal_message]).await,
+        MessengerError::NothingWithdrawn,
+    );
+
+    h.write_token_account(from, mint, pda_receiver, 20);
+    h.next_blockhash().await;
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::FeeExceedsAmount,
+    );
+
+    // Messages without a bridge back cannot be bridged.
+    let count = h
+        .store(
+            withdraw_stream_payload(receiver, mint, sender, data_account),
+            receiver,
+        )
+        .await;
+    let transaction = h
+        .create(
+            Create::ReceiverWithdraw,
+            accs.clone(),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await
+        .unwrap();
+    h.execute(transaction, &accs, receiver, count)
+        .await
+        .unwrap();
+    let ix = withdraw_bridge_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        receiver,
+        count,
+        mint,
+        from,
+    );
+    assert_error(
+        h.send(vec![ix], &[&portal_message]).await,
+        MessengerError::BridgeBackMismatch,
+    );
+}
+
+#[tokio::test]
+async fn withdraw_stream_bridge_burns_wrapped_tokens() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let pda_receiver = user_pda(&receiver, BSC_CHAIN_ID);
+    let token_address = wallet(7);
+    let mint = create_wrapped(&mut h, BSC_CHAIN_ID, token_address, 400);
+    let data_account = Pubkey::new_unique();
+    let from = h.create_token_account(mint, pda_receiver, 0);
+    let count = h
+        .store(
+            withdraw_stream_bridge_payload(receiver, mint, sender, data_account, 2, 0),
+            receiver,
+        )
+        .await;
+
+    let accs = withdraw_accounts(&sender, &receiver, data_account, mint, from);
+    let transaction = h
+        .create(
+            Create::WithdrawBridge(from),
+            accs.clone(),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await
+        .unwrap();
+    h.execute(transaction, &accs, receiver, count)
+        .await
+        .unwrap();
+    h.write_token_account(from, mint, pda_receiver, 400);
+
+    let portal_message = Keypair::new();
+    let ix = withdraw_bridge_ix(
+        h.owner(),
+        portal_message.pubkey(),
+        receiver,
+        count,
+        mint,
+        from,
+    );
+    h.send(vec![ix], &[&portal_message]).await.unwrap();
+
+    let from: TokenAccount = h.account(from).await.unwrap();
+    assert_eq!(from.amount, 0);
+    let mint_state: Mint = h.account(mint).await.unwrap();
+    assert_eq!(mint_state.supply, 0);
+    let posted = h.posted_message(portal_message.pubkey()).await;
+    let transfer = PayloadTransfer::deserialize(&mut posted.payload.as_slice()).unwrap();
+    assert_eq!(transfer.amount, U256::from(400));
+    assert_eq!(transfer.token_address, token_address);
+    assert_eq!(transfer.to, receiver);
+    assert_eq!(transfer.to_chain, 2);
+}
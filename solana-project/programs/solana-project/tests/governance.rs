@@ -0,0 +1,280 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountSerialize;
+use solana_project::constants::CONFIG_SPACE;
+use solana_project::errors::MessengerError;
+use solana_project::governance::GovernanceAction;
+use solana_project::state::{
+    AddressFormat, ChainLimit, Config, EmitterAccount, Governance, MintLimit, TargetProgram,
+};
+use solana_project::versioning::AccountVersion;
+use solana_sdk::signature::{Keypair, Signer};
+
+use common::*;
+
+async fn set_governance(h: &mut Harness) {
+    let ix = h.set_governance_ix(GOVERNANCE_CHAIN_ID, GOVERNANCE_EMITTER);
+    h.send(vec![ix], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn governance_vaas_make_admin_changes() {
+    let mut h = Harness::new().await;
+    set_governance(&mut h).await;
+    let stored: Config = h.account(config()).await.unwrap();
+    assert_eq!(
+        stored.governance,
+        Governance::Emitter {
+            chain_id: GOVERNANCE_CHAIN_ID,
+            emitter_address: GOVERNANCE_EMITTER,
+        }
+    );
+
+    h.govern(GovernanceAction::RegisterChain {
+        chain_id: 22,
+        emitter_addr: [0xa2; 32],
+        address_format: AddressFormat::Aptos,
+        label: "aptos".to_string(),
+    })
+    .await
+    .unwrap();
+    h.govern(GovernanceAction::UpdateChain {
+        chain_id: 22,
+        enabled: false,
+    })
+    .await
+    .unwrap();
+    let emitter: EmitterAccount = h.account(emitter_account(22)).await.unwrap();
+    assert_eq!(emitter.emitter_addr, [0xa2; 32]);
+    assert_eq!(emitter.address_format, AddressFormat::Aptos);
+    assert_eq!(emitter.label, "aptos");
+    assert!(!emitter.enabled);
+
+    let mint = h.create_mint();
+    h.govern(GovernanceAction::SetChainLimit {
+        chain_id: BSC_CHAIN_ID,
+        daily_limit: 1_000,
+    })
+    .await
+    .unwrap();
+    h.govern(GovernanceAction::SetMintLimit {
+        mint,
+        daily_limit: 500,
+        notional_price: 3,
+        large_transfer_threshold: 400,
+        hold_seconds: 60,
+    })
+    .await
+    .unwrap();
+    let limit: ChainLimit = h.account(chain_limit(BSC_CHAIN_ID)).await.unwrap();
+    assert_eq!(limit.daily_limit, 1_000);
+    let limit: MintLimit = h.account(mint_limit(&mint)).await.unwrap();
+    assert_eq!(limit.daily_limit, 500);
+    assert_eq!(limit.notional_price, 3);
+    assert_eq!(limit.large_transfer_threshold, 400);
+    assert_eq!(limit.hold_seconds, 60);
+
+    let program_id = Pubkey::new_unique();
+    h.govern(GovernanceAction::RegisterTargetProgram {
+        version: 1,
+        program_id,
+    })
+    .await
+    .unwrap();
+    let target: TargetProgram = h.account(target_program(1)).await.unwrap();
+    assert_eq!(target.program_id, program_id);
+    h.govern(GovernanceAction::RemoveTargetProgram { version: 1 })
+        .await
+        .unwrap();
+    assert!(h
+        .ctx
+        .banks_client
+        .get_account(target_program(1))
+        .await
+        .unwrap()
+        .is_none());
+
+    // Once the owner is rotated, the old key can no longer make changes.
+    let owner = Pubkey::new_unique();
+    h.govern(GovernanceAction::SetOwner { owner })
+        .await
+        .unwrap();
+    let stored: Config = h.account(config()).await.unwrap();
+    assert_eq!(stored.owner, owner);
+    let ix = h.set_chain_limit_ix(BSC_CHAIN_ID, u64::MAX);
+    assert!(h.send(vec![ix], &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn governance_vaas_apply_once() {
+    let mut h = Harness::new().await;
+    set_governance(&mut h).await;
+
+    let action = GovernanceAction::SetChainLimit {
+        chain_id: BSC_CHAIN_ID,
+        daily_limit: 1_000,
+    };
+    let posted = h.govern(action.clone()).await.unwrap();
+    let ix = h.set_chain_limit_ix(BSC_CHAIN_ID, 2_000);
+    h.send(vec![ix], &[]).await.unwrap();
+
+    h.next_blockhash().await;
+    let claim = processed_vaa(
+        &GOVERNANCE_EMITTER,
+        GOVERNANCE_CHAIN_ID,
+        posted.message.sequence,
+    );
+    let ix = h.govern_ix(&posted, &action, claim);
+    assert!(h.send(vec![ix], &[]).await.is_err());
+    let limit: ChainLimit = h.account(chain_limit(BSC_CHAIN_ID)).await.unwrap();
+    assert_eq!(limit.daily_limit, 2_000);
+}
+
+#[tokio::test]
+async fn only_the_governance_emitter_governs() {
+    let mut h = Harness::new().await;
+    let action = GovernanceAction::SetChainLimit {
+        chain_id: BSC_CHAIN_ID,
+        daily_limit: 1_000,
+    };
+
+    // Without a governance emitter, VAAs are claimed under chain 0.
+    let posted = h.post_from(
+        GOVERNANCE_CHAIN_ID,
+        GOVERNANCE_EMITTER,
+        governance_payload(&action),
+    );
+    let ix = h.govern_ix(
+        &posted,
+        &action,
+        processed_vaa(&[0; 32], 0, posted.message.sequence),
+    );
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::GovernanceNotSet,
+    );
+
+    set_governance(&mut h).await;
+    let posted = h.post_from(BSC_CHAIN_ID, EMITTER_ADDRESS, governance_payload(&action));
+    let claim = processed_vaa(
+        &GOVERNANCE_EMITTER,
+        GOVERNANCE_CHAIN_ID,
+        posted.message.sequence,
+    );
+    let ix = h.govern_ix(&posted, &action, claim);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::GovernanceEmitterMismatch,
+    );
+
+    // Payloads name the proxy they govern.
+    let mut payload = governance_payload(&action);
+    payload[1..33].copy_from_slice(Pubkey::new_unique().as_ref());
+    let posted = h.post_from(GOVERNANCE_CHAIN_ID, GOVERNANCE_EMITTER, payload);
+    let claim = processed_vaa(
+        &GOVERNANCE_EMITTER,
+        GOVERNANCE_CHAIN_ID,
+        posted.message.sequence,
+    );
+    let ix = h.govern_ix(&posted, &action, claim);
+    assert_error(h.send(vec![ix], &[]).await, MessengerError::InvalidPayload);
+
+    // The instruction has to be the one of the action.
+    let posted = h.post_from(
+        GOVERNANCE_CHAIN_ID,
+        GOVERNANCE_EMITTER,
+        governance_payload(&action),
+    );
+    let claim = processed_vaa(
+        &GOVERNANCE_EMITTER,
+        GOVERNANCE_CHAIN_ID,
+        posted.message.sequence,
+    );
+    let other = GovernanceAction::SetChainLimit {
+        chain_id: 22,
+        daily_limit: 1_000,
+    };
+    let ix = h.govern_ix(&posted, &other, claim);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::GovernanceActionMismatch,
+    );
+    let ix = h.govern_ix(
+        &posted,
+        &GovernanceAction::RemoveTargetProgram { version: 0 },
+        claim,
+    );
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::GovernanceActionMismatch,
+    );
+    let ix = h.govern_ix(&posted, &action, claim);
+    h.send(vec![ix], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn governance_is_set_by_the_config_owner() {
+    let mut h = Harness::new().await;
+
+    let stranger = Keypair::new();
+    h.set_account(stranger.pubkey(), solana_sdk::system_program::id(), vec![]);
+    let mut ix = h.set_governance_ix(GOVERNANCE_CHAIN_ID, GOVERNANCE_EMITTER);
+    ix.accounts[0].pubkey = stranger.pubkey();
+    assert!(h.send(vec![ix], &[&stranger]).await.is_err());
+
+    let ix = h.set_governance_ix(GOVERNANCE_CHAIN_ID, [0; 32]);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::InvalidEmitterAddress,
+    );
+
+    // Chain 0 turns governance off again.
+    set_governance(&mut h).await;
+    let ix = h.set_governance_ix(0, [0; 32]);
+    h.send(vec![ix], &[]).await.unwrap();
+    let stored: Config = h.account(config()).await.unwrap();
+    assert_eq!(stored.governance, Governance::Off);
+}
+
+#[tokio::test]
+async fn migrated_configs_make_room_for_governance() {
+    let mut h = Harness::new().await;
+    let owner = h.owner();
+    let mut old = Vec::new();
+    Config {
+        owner,
+        nonce: 1,
+        account_version: AccountVersion(7),
+        governance: Governance::Unspecified,
+    }
+    .try_serialize(&mut old)
+    .unwrap();
+    h.set_account(config(), solana_project::ID, old);
+
+    let ix = h.set_governance_ix(GOVERNANCE_CHAIN_ID, GOVERNANCE_EMITTER);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::AccountNotMigrated,
+    );
+
+    let ix = h.migrate_account_ix(config());
+    h.send(vec![ix], &[]).await.unwrap();
+    set_governance(&mut h).await;
+    let account = h
+        .ctx
+        .banks_client
+        .get_account(config())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), CONFIG_SPACE);
+    h.govern(GovernanceAction::SetChainLimit {
+        chain_id: BSC_CHAIN_ID,
+        daily_limit: 1_000,
+    })
+    .await
+    .unwrap();
+}
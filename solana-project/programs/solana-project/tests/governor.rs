@@ -0,0 +1,191 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+
+use solana_project::constants::SOLANA_CHAIN_ID;
+use solana_project::errors::MessengerError;
+use solana_project::state::{ChainLimit, Clearance, MintLimit, TokenAmount, TransactionData};
+
+use common::*;
+
+const DAY: i64 = 24 * 3600;
+
+async fn sender_withdraw(
+    h: &mut Harness,
+    sender: [u8; 32],
+    mint: Pubkey,
+    amount: u64,
+) -> (u64, Result<Pubkey, TransactionError>) {
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let count = h
+        .store(sender_withdraw_payload(amount, sender, mint), sender)
+        .await;
+    let created = h
+        .create(
+            Create::SenderWithdraw,
+            zebec_accounts(12, &[(2, pda_sender), (7, mint)], Some(pda_sender)),
+            zebec_data(&TokenAmount { amount }),
+            sender,
+            count,
+        )
+        .await;
+    (count, created)
+}
+
+#[tokio::test]
+async fn limits_are_set_by_the_config_owner() {
+    let mut h = Harness::new().await;
+    let mint = h.create_mint();
+
+    let ix = h.set_chain_limit_ix(BSC_CHAIN_ID, 1_000);
+    h.send(vec![ix], &[]).await.unwrap();
+    let ix = h.set_mint_limit_ix(mint, 500, 3, 400, 60);
+    h.send(vec![ix], &[]).await.unwrap();
+
+    let limit: ChainLimit = h.account(chain_limit(BSC_CHAIN_ID)).await.unwrap();
+    assert_eq!(limit.chain_id, BSC_CHAIN_ID);
+    assert_eq!(limit.daily_limit, 1_000);
+    let limit: MintLimit = h.account(mint_limit(&mint)).await.unwrap();
+    assert_eq!(limit.mint, mint);
+    assert_eq!(limit.daily_limit, 500);
+    assert_eq!(limit.notional_price, 3);
+    assert_eq!(limit.large_transfer_threshold, 400);
+    assert_eq!(limit.hold_seconds, 60);
+
+    let stranger = Keypair::new();
+    h.set_account(stranger.pubkey(), solana_sdk::system_program::id(), vec![]);
+    let mut ix = h.set_chain_limit_ix(BSC_CHAIN_ID, u64::MAX);
+    ix.accounts[0].pubkey = stranger.pubkey();
+    assert!(h.send(vec![ix], &[&stranger]).await.is_err());
+}
+
+#[tokio::test]
+async fn transfers_count_against_the_mint_limit() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let mint = h.create_mint();
+    let ix = h.set_mint_limit_ix(mint, 1_000, 0, u64::MAX, 0);
+    h.send(vec![ix], &[]).await.unwrap();
+
+    let (count, created) = sender_withdraw(&mut h, sender, mint, 600).await;
+    created.unwrap();
+    let data: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+    assert_eq!(data.clearance, Clearance::Counted);
+
+    let (over, created) = sender_withdraw(&mut h, sender, mint, 500).await;
+    assert_error(created, MessengerError::RateLimitExceeded);
+    let (_, created) = sender_withdraw(&mut h, sender, mint, 400).await;
+    created.unwrap();
+    let now = h.unix_timestamp().await;
+    let limit: MintLimit = h.account(mint_limit(&mint)).await.unwrap();
+    assert_eq!(limit.window.used(now), 1_000);
+
+    // A day later the window has room again.
+    h.advance_clock(DAY).await;
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    h.create(
+        Create::SenderWithdraw,
+        zebec_accounts(12, &[(2, pda_sender), (7, mint)], Some(pda_sender)),
+        zebec_data(&TokenAmount { amount: 500 }),
+        sender,
+        over,
+    )
+    .await
+    .unwrap();
+    let now = h.unix_timestamp().await;
+    let limit: MintLimit = h.account(mint_limit(&mint)).await.unwrap();
+    assert_eq!(limit.window.used(now), 500);
+}
+
+#[tokio::test]
+async fn large_transfers_are_held_then_released() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let (pda_sender, pda_receiver) = (
+        user_pda(&sender, BSC_CHAIN_ID),
+        user_pda(&receiver, BSC_CHAIN_ID),
+    );
+    let mint = h.create_mint();
+    let ix = h.set_mint_limit_ix(mint, 100, 0, 1_000, 3_600);
+    h.send(vec![ix], &[]).await.unwrap();
+
+    let count = h
+        .store(
+            instant_transfer_payload(5_000, sender, mint, receiver),
+            sender,
+        )
+        .await;
+    let accs = zebec_accounts(
+        12,
+        &[(1, pda_receiver), (2, pda_sender), (8, mint)],
+        Some(pda_sender),
+    );
+    let transaction = h
+        .create(
+            Create::InstantTransfer,
+            accs.clone(),
+            zebec_data(&TokenAmount { amount: 5_000 }),
+            sender,
+            count,
+        )
+        .await
+        .unwrap();
+    let now = h.unix_timestamp().await;
+    let data: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+    assert_eq!(
+        data.clearance,
+        Clearance::Held {
+            release_at: now + 3_600
+        }
+    );
+    // Held transfers do not use up the limit.
+    let limit: MintLimit = h.account(mint_limit(&mint)).await.unwrap();
+    assert_eq!(limit.window.used(now), 0);
+
+    assert_error(
+        h.execute(transaction, &accs, sender, count).await,
+        MessengerError::TransferHeld,
+    );
+    h.advance_clock(3_600).await;
+    h.next_blockhash().await;
+    h.execute(transaction, &accs, sender, count).await.unwrap();
+}
+
+#[tokio::test]
+async fn chain_limits_value_transfers_at_the_mint_price() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let mint = h.create_mint();
+    let ix = h.set_chain_limit_ix(SOLANA_CHAIN_ID, 10_000);
+    h.send(vec![ix], &[]).await.unwrap();
+
+    // Without a price there is no telling what the transfer is worth.
+    let (unpriced, created) = sender_withdraw(&mut h, sender, mint, 1_000_000).await;
+    assert_error(created, MessengerError::UnpricedMint);
+
+    // 2 notional units a token of 6 decimals.
+    let ix = h.set_mint_limit_ix(mint, u64::MAX, 2, u64::MAX, 0);
+    h.send(vec![ix], &[]).await.unwrap();
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    h.create(
+        Create::SenderWithdraw,
+        zebec_accounts(12, &[(2, pda_sender), (7, mint)], Some(pda_sender)),
+        zebec_data(&TokenAmount { amount: 1_000_000 }),
+        sender,
+        unpriced,
+    )
+    .await
+    .unwrap();
+    let (_, created) = sender_withdraw(&mut h, sender, mint, 3_000_000_000).await;
+    created.unwrap();
+    let now = h.unix_timestamp().await;
+    let limit: ChainLimit = h.account(chain_limit(SOLANA_CHAIN_ID)).await.unwrap();
+    assert_eq!(limit.window.used(now), 6_002);
+
+    let (_, created) = sender_withdraw(&mut h, sender, mint, 2_000_000_000).await;
+    assert_error(created, MessengerError::RateLimitExceeded);
+}
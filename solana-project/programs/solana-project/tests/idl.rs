@@ -0,0 +1,115 @@
+use std::fmt::Debug;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AnchorDeserialize, AnchorSerialize};
+use solana_project::state::{
+    AddressFormat, BridgeBack, Clearance, Governance, MessageBinding, Payout, TransferTerms,
+};
+use solana_project::versioning::{idl, AccountVersion};
+
+/// Checks `value` is laid out as its mirror in `versioning::idl` says.
+fn assert_mirrored<T, M>(value: T, mirror: M)
+where
+    T: AnchorSerialize + AnchorDeserialize + PartialEq + Debug,
+    M: AnchorSerialize,
+{
+    let mirrored = mirror.try_to_vec().unwrap();
+    assert_eq!(value.try_to_vec().unwrap(), mirrored);
+    assert_eq!(T::try_from_slice(&mirrored).unwrap(), value);
+}
+
+#[test]
+fn idl_mirrors_match_the_account_layouts() {
+    assert_mirrored(AccountVersion(3), idl::AccountVersion { version: 3 });
+
+    let formats = [
+        (AddressFormat::Evm, idl::AddressFormat::Evm),
+        (AddressFormat::Solana, idl::AddressFormat::Solana),
+        (AddressFormat::Aptos, idl::AddressFormat::Aptos),
+        (AddressFormat::Sui, idl::AddressFormat::Sui),
+        (AddressFormat::Near, idl::AddressFormat::Near),
+    ];
+    for (format, mirror) in formats {
+        assert_mirrored(format, mirror);
+    }
+
+    assert_mirrored(BridgeBack::Off, idl::BridgeBack::Off);
+    assert_mirrored(
+        BridgeBack::To {
+            target_chain: 5,
+            fee: 10,
+            balance_before: 300,
+            bridged: true,
+        },
+        idl::BridgeBack::To {
+            target_chain: 5,
+            fee: 10,
+            balance_before: 300,
+            bridged: true,
+        },
+    );
+
+    let wallet = Pubkey::new_unique();
+    assert_mirrored(Payout::Off, idl::Payout::Off);
+    assert_mirrored(
+        Payout::To {
+            wallet,
+            balance_before: 300,
+            paid: true,
+        },
+        idl::Payout::To {
+            wallet,
+            balance_before: 300,
+            paid: true,
+        },
+    );
+
+    assert_mirrored(Clearance::Pending, idl::Clearance::Pending);
+    assert_mirrored(Clearance::Counted, idl::Clearance::Counted);
+    assert_mirrored(
+        Clearance::Held { release_at: -7 },
+        idl::Clearance::Held { release_at: -7 },
+    );
+
+    assert_mirrored(TransferTerms::Open, idl::TransferTerms::Open);
+    assert_mirrored(
+        TransferTerms::Fixed {
+            target_chain: 5,
+            max_fee: 10,
+        },
+        idl::TransferTerms::Fixed {
+            target_chain: 5,
+            max_fee: 10,
+        },
+    );
+
+    assert_mirrored(MessageBinding::Unbound, idl::MessageBinding::Unbound);
+    assert_mirrored(
+        MessageBinding::Bound {
+            sender: [1; 32],
+            from_chain_id: 4,
+            current_count: 9,
+            code: 12,
+            digest: [2; 32],
+        },
+        idl::MessageBinding::Bound {
+            sender: [1; 32],
+            from_chain_id: 4,
+            current_count: 9,
+            code: 12,
+            digest: [2; 32],
+        },
+    );
+
+    assert_mirrored(Governance::Off, idl::Governance::Off);
+    assert_mirrored(
+        Governance::Emitter {
+            chain_id: 2,
+            emitter_address: [3; 32],
+        },
+        idl::Governance::Emitter {
+            chain_id: 2,
+            emitter_address: [3; 32],
+        },
+    );
+}
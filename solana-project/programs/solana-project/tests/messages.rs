@@ -0,0 +1,425 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountSerialize;
+use solana_project::errors::MessengerError;
+use solana_project::state::{
+    AddressFormat, Count, EmitterAccount, EmitterAddrAccount, ProcessedVAA, TransactionData,
+    TransactionStatus,
+};
+use solana_project::wormhole::MessageData;
+
+use common::*;
+
+#[tokio::test]
+async fn register_chain_checks_emitter_and_label() {
+    let mut h = Harness::new().await;
+    let ix = h.register_chain_ix(2, [0; 32], "", AddressFormat::Evm);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::InvalidEmitterAddress,
+    );
+    // An EVM emitter has to be a 20 byte address left-padded to 32.
+    let ix = h.register_chain_ix(2, [0x11; 32], "", AddressFormat::Evm);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::InvalidEmitterAddress,
+    );
+    let ix = h.register_chain_ix(2, EMITTER_ADDRESS, &"x".repeat(33), AddressFormat::Evm);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::InvalidEmitterLabel,
+    );
+
+    let emitter: EmitterAccount = h.account(emitter_account(BSC_CHAIN_ID)).await.unwrap();
+    assert_eq!(emitter.chain_id, BSC_CHAIN_ID);
+    assert_eq!(emitter.emitter_addr, EMITTER_ADDRESS);
+    assert_eq!(emitter.label, "bsc");
+    assert_eq!(emitter.address_format, AddressFormat::Evm);
+    assert!(emitter.enabled);
+    assert!(emitter.registered_at > 0);
+}
+
+#[tokio::test]
+async fn disabled_chain_is_refused() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let ix = h.update_chain_ix(BSC_CHAIN_ID, false, None);
+    h.send(vec![ix], &[]).await.unwrap();
+
+    let posted = h.post(deposit_payload(500, sender, Pubkey::new_unique()));
+    assert_error(
+        h.store_msg(&posted, sender, 1).await,
+        MessengerError::EmitterDisabled,
+    );
+
+    let ix = h.update_chain_ix(BSC_CHAIN_ID, true, Some("bnb"));
+    h.send(vec![ix], &[]).await.unwrap();
+    h.next_blockhash().await;
+    h.store_msg(&posted, sender, 1).await.unwrap();
+    let emitter: EmitterAccount = h.account(emitter_account(BSC_CHAIN_ID)).await.unwrap();
+    assert_eq!(emitter.label, "bnb");
+}
+
+#[tokio::test]
+async fn migrate_chain_rewrites_legacy_emitter() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let chain_id = 2;
+    let mut data = Vec::new();
+    EmitterAddrAccount {
+        chain_id,
+        emitter_addr: hex::encode(EMITTER_ADDRESS),
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    h.set_account(emitter_account(chain_id), solana_project::ID, data);
+
+    let ix = h.migrate_chain_ix(chain_id);
+    h.send(vec![ix], &[]).await.unwrap();
+    let emitter: EmitterAccount = h.account(emitter_account(chain_id)).await.unwrap();
+    assert_eq!(emitter.chain_id, chain_id);
+    assert_eq!(emitter.emitter_addr, EMITTER_ADDRESS);
+    assert!(emitter.enabled);
+
+    // VAAs from the migrated chain are accepted under the same address.
+    let posted = h.post_from(chain_id, EMITTER_ADDRESS, initialize_pda_payload(sender));
+    let ix = h.initialize_pda_ix(&posted, sender, chain_id);
+    h.send(vec![ix], &[]).await.unwrap();
+
+    h.next_blockhash().await;
+    let ix = h.migrate_chain_ix(chain_id);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::EmitterAlreadyMigrated,
+    );
+}
+
+#[tokio::test]
+async fn initialize_pda() {
+    let mut h = Harness::new().await;
+    let user = wallet(1);
+    let posted = h.post(initialize_pda_payload(user));
+    h.initialize_pda(&posted, user).await.unwrap();
+
+    let pda = h
+        .ctx
+        .banks_client
+        .get_account(user_pda(&user, BSC_CHAIN_ID))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(pda.lamports > 0);
+
+    // The processed_vaa account makes the VAA single use.
+    h.next_blockhash().await;
+    assert!(h.initialize_pda(&posted, user).await.is_err());
+}
+
+#[tokio::test]
+async fn initialize_pda_checks_vaa_key() {
+    let mut h = Harness::new().await;
+    let user = wallet(1);
+    let genuine = h.post(initialize_pda_payload(user));
+    let copy = MessageData {
+        payload: genuine.message.payload.clone(),
+        sequence: genuine.message.sequence,
+        emitter_chain: BSC_CHAIN_ID,
+        emitter_address: EMITTER_ADDRESS,
+        vaa_version: 1,
+        consistency_level: 1,
+        vaa_time: genuine.message.vaa_time,
+        submission_time: genuine.message.submission_time,
+        ..Default::default()
+    };
+    let forged = h.post_at(Pubkey::new_unique(), copy);
+    assert_error(
+        h.initialize_pda(&forged, user).await,
+        MessengerError::VAAKeyMismatch,
+    );
+}
+
+#[tokio::test]
+async fn initialize_pda_checks_emitter() {
+    let mut h = Harness::new().await;
+    let user = wallet(1);
+    let mut other = EMITTER_ADDRESS;
+    other[31] ^= 1;
+    let posted = h.post_from(BSC_CHAIN_ID, other, initialize_pda_payload(user));
+    assert_error(
+        h.initialize_pda(&posted, user).await,
+        MessengerError::VAAEmitterMismatch,
+    );
+}
+
+#[tokio::test]
+async fn initialize_pda_checks_payload() {
+    let mut h = Harness::new().await;
+    let user = wallet(1);
+
+    let posted = h.post(initialize_token_account_payload(user, Pubkey::new_unique()));
+    assert_error(
+        h.initialize_pda(&posted, user).await,
+        MessengerError::InvalidPayload,
+    );
+
+    let posted = h.post(initialize_pda_payload(user));
+    assert_error(
+        h.initialize_pda(&posted, wallet(2)).await,
+        MessengerError::InvalidPDAAccount,
+    );
+
+    let posted = h.post(with_to_chain(initialize_pda_payload(user), 33, 2));
+    assert_error(
+        h.initialize_pda(&posted, user).await,
+        MessengerError::InvalidToChainId,
+    );
+}
+
+#[tokio::test]
+async fn initialize_pda_token_account() {
+    let mut h = Harness::new().await;
+    let user = wallet(1);
+    let mint = h.create_mint();
+
+    let posted = h.post(initialize_token_account_payload(user, mint));
+    h.initialize_pda_token_account(&posted, user, mint)
+        .await
+        .unwrap();
+    let ata = anchor_spl::associated_token::get_associated_token_address(
+        &user_pda(&user, BSC_CHAIN_ID),
+        &mint,
+    );
+    assert!(h.ctx.banks_client.get_account(ata).await.unwrap().is_some());
+
+    let other_mint = h.create_mint();
+    let posted = h.post(initialize_token_account_payload(user, mint));
+    assert_error(
+        h.initialize_pda_token_account(&posted, user, other_mint)
+            .await,
+        MessengerError::MintKeyMismatch,
+    );
+
+    let posted = h.post(initialize_token_account_payload(user, mint));
+    assert_error(
+        h.initialize_pda_token_account(&posted, wallet(2), mint)
+            .await,
+        MessengerError::InvalidPDAAccount,
+    );
+
+    let posted = h.post(with_to_chain(
+        initialize_token_account_payload(user, mint),
+        65,
+        5,
+    ));
+    assert_error(
+        h.initialize_pda_token_account(&posted, user, mint).await,
+        MessengerError::InvalidToChainId,
+    );
+}
+
+#[tokio::test]
+async fn store_msg_records_every_code() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let receiver = wallet(2);
+    let mint = Pubkey::new_unique();
+    let data_account = Pubkey::new_unique();
+
+    let payloads = vec![
+        stream_payload(10, 20, 500, sender, receiver, true, false, mint),
+        deposit_payload(500, sender, mint),
+        pause_payload(sender, mint, receiver, data_account),
+        sender_withdraw_payload(500, sender, mint),
+        instant_transfer_payload(500, sender, mint, receiver),
+        stream_update_payload(10, 30, 600, sender, receiver, mint, data_account),
+        cancel_payload(sender, mint, receiver, data_account),
+        direct_transfer_payload(500, sender, mint, receiver),
+    ];
+    for (i, payload) in payloads.into_iter().enumerate() {
+        let code = payload[0];
+        let posted = h.post(payload);
+        let count = i as u64 + 1;
+        h.store_msg(&posted, sender, count).await.unwrap();
+
+        let stored: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+        assert_eq!(stored.sender, sender, "code {}", code);
+        assert_eq!(stored.token_mint, mint, "code {}", code);
+        assert_eq!(stored.from_chain_id, BSC_CHAIN_ID);
+        assert_eq!(stored.code, code);
+        assert_eq!(stored.vaa_sequence, posted.message.sequence);
+
+        let processed: ProcessedVAA = h
+            .account(processed_vaa(
+                &EMITTER_ADDRESS,
+                BSC_CHAIN_ID,
+                posted.message.sequence,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(processed.transaction_count, count);
+        let status: TransactionStatus = h.account(txn_status(&sender, count)).await.unwrap();
+        assert!(!status.executed);
+    }
+
+    let stream: TransactionData = h.account(data_store(&sender, 1)).await.unwrap();
+    assert_eq!(
+        (stream.start_time, stream.end_time, stream.amount),
+        (10, 20, 500)
+    );
+    assert!(stream.can_cancel && !stream.can_update);
+    assert_eq!(stream.receiver, receiver);
+
+    let update: TransactionData = h.account(data_store(&sender, 6)).await.unwrap();
+    assert_eq!(update.data_account, data_account);
+
+    let count: Count = h.account(txn_count(&sender)).await.unwrap();
+    assert_eq!(count.count, 8);
+}
+
+#[tokio::test]
+async fn store_msg_withdraw_stream_is_keyed_by_withdrawer() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = Pubkey::new_unique();
+    let data_account = Pubkey::new_unique();
+
+    let count = h
+        .store(
+            withdraw_stream_payload(receiver, mint, sender, data_account),
+            receiver,
+        )
+        .await;
+    let stored: TransactionData = h.account(data_store(&receiver, count)).await.unwrap();
+    assert_eq!(stored.sender, sender);
+    assert_eq!(stored.receiver, receiver);
+    assert_eq!(stored.data_account, data_account);
+}
+
+#[tokio::test]
+async fn store_msg_checks_vaa() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let payload = deposit_payload(1, sender, Pubkey::new_unique());
+
+    let forged = h.post_at(
+        Pubkey::new_unique(),
+        MessageData {
+            sequence: 99,
+            emitter_chain: BSC_CHAIN_ID,
+            emitter_address: EMITTER_ADDRESS,
+            payload: payload.clone(),
+            ..Default::default()
+        },
+    );
+    assert_error(
+        h.store_msg(&forged, sender, 1).await,
+        MessengerError::VAAKeyMismatch,
+    );
+
+    let posted = h.post_from(2, EMITTER_ADDRESS, payload);
+    assert_error(
+        h.store_msg(&posted, sender, 1).await,
+        MessengerError::VAAEmitterMismatch,
+    );
+}
+
+#[tokio::test]
+async fn store_msg_checks_sender_and_count() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let mint = Pubkey::new_unique();
+
+    let posted = h.post(deposit_payload(1, sender, mint));
+    assert_error(
+        h.store_msg(&posted, wallet(9), 1).await,
+        MessengerError::InvalidSenderWallet,
+    );
+
+    let posted = h.post(deposit_payload(1, sender, mint));
+    assert_error(
+        h.store_msg(&posted, sender, 2).await,
+        MessengerError::InvalidCount,
+    );
+
+    let posted = h.post(with_to_chain(deposit_payload(1, sender, mint), 9, 2));
+    assert_error(
+        h.store_msg(&posted, sender, 1).await,
+        MessengerError::InvalidToChainId,
+    );
+
+    let posted = h.post(vec![3u8; 105]);
+    assert_error(
+        h.store_msg(&posted, sender, 1).await,
+        MessengerError::InvalidPayload,
+    );
+
+    // Nothing above was stored, so the count is still free.
+    let posted = h.post(deposit_payload(1, sender, mint));
+    h.store_msg(&posted, sender, 1).await.unwrap();
+
+    // Replaying the same VAA hits the existing processed_vaa account.
+    assert!(h.store_msg(&posted, sender, 2).await.is_err());
+}
+
+#[tokio::test]
+async fn payloads_check_length() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = Pubkey::new_unique();
+
+    let mut payload = instant_transfer_payload(500, sender, mint, receiver);
+    payload.truncate(100);
+    let posted = h.post(payload);
+    assert_error(
+        h.store_msg(&posted, sender, 1).await,
+        MessengerError::InvalidPayloadLength,
+    );
+
+    let mut payload = deposit_payload(1, sender, mint);
+    payload.extend([0, 0]);
+    let posted = h.post(payload);
+    assert_error(
+        h.store_msg(&posted, sender, 1).await,
+        MessengerError::InvalidPayloadLength,
+    );
+
+    let posted = h.post(vec![]);
+    assert_error(
+        h.store_msg(&posted, sender, 1).await,
+        MessengerError::InvalidPayloadLength,
+    );
+
+    let mut payload = initialize_pda_payload(sender);
+    payload.truncate(40);
+    let posted = h.post(payload);
+    assert_error(
+        h.initialize_pda(&posted, sender).await,
+        MessengerError::InvalidPayloadLength,
+    );
+}
+
+#[tokio::test]
+async fn store_msg_checks_count_overflow() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+
+    let mut data = Vec::new();
+    anchor_lang::AccountSerialize::try_serialize(
+        &Count {
+            count: u64::MAX,
+            ..Count::default()
+        },
+        &mut data,
+    )
+    .unwrap();
+    h.set_account(txn_count(&sender), solana_project::ID, data);
+
+    let posted = h.post(deposit_payload(1, sender, Pubkey::new_unique()));
+    assert_error(
+        h.store_msg(&posted, sender, 0).await,
+        MessengerError::Overflow,
+    );
+}
@@ -0,0 +1,153 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use libsecp256k1::{PublicKey, SecretKey};
+use solana_sdk::secp256k1_instruction::{construct_eth_pubkey, new_secp256k1_instruction};
+
+use solana_project::errors::MessengerError;
+use solana_project::meta::{signed_message, SignedAction};
+use solana_project::state::{MetaNonce, TransactionData};
+
+use common::*;
+
+/// An EVM key and the 32 byte sender its address stands for.
+fn evm_key(byte: u8) -> (SecretKey, [u8; 32]) {
+    let secret = SecretKey::parse(&[byte; 32]).unwrap();
+    let mut sender = [0u8; 32];
+    sender[12..].copy_from_slice(&construct_eth_pubkey(&PublicKey::from_secret_key(&secret)));
+    (secret, sender)
+}
+
+fn sign(secret: &SecretKey, action: &SignedAction, nonce: u64) -> Instruction {
+    new_secp256k1_instruction(
+        secret,
+        &signed_message(&solana_project::ID, BSC_CHAIN_ID, nonce, action),
+    )
+}
+
+fn pause(receiver: [u8; 32], mint: Pubkey, data_account: Pubkey) -> SignedAction {
+    SignedAction::Pause {
+        token_mint: mint,
+        receiver,
+        data_account,
+    }
+}
+
+#[tokio::test]
+async fn signed_pause_is_stored_like_a_message() {
+    let mut h = Harness::new().await;
+    let (secret, sender) = evm_key(7);
+    let receiver = wallet(2);
+    let (pda_sender, pda_receiver) = (
+        user_pda(&sender, BSC_CHAIN_ID),
+        user_pda(&receiver, BSC_CHAIN_ID),
+    );
+    let mint = h.create_mint();
+    let data_account = Pubkey::new_unique();
+    let action = pause(receiver, mint, data_account);
+
+    let count = h.next_count(sender).await;
+    let ix = h.store_signed_msg_ix(action.clone(), sender, 0, count);
+    h.send(vec![sign(&secret, &action, 0), ix], &[])
+        .await
+        .unwrap();
+
+    let data: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+    assert_eq!(data.code, 8);
+    assert_eq!(data.sender, sender);
+    assert_eq!(data.receiver, receiver);
+    assert_eq!(data.from_chain_id, BSC_CHAIN_ID);
+    assert_eq!(data.token_mint, mint);
+    assert_eq!(data.data_account, data_account);
+    let nonce: MetaNonce = h.account(meta_nonce(&sender, BSC_CHAIN_ID)).await.unwrap();
+    assert_eq!(nonce.nonce, 1);
+
+    // From here on it is a pause like any other.
+    h.create_execute(
+        CreateExecute::PauseResume,
+        zebec_accounts(
+            3,
+            &[(0, pda_sender), (1, pda_receiver), (2, data_account)],
+            Some(pda_sender),
+        ),
+        vec![1u8; 8],
+        sender,
+        count,
+    )
+    .await
+    .unwrap();
+
+    // The next action signs the next nonce.
+    let action = SignedAction::Withdraw {
+        token_mint: mint,
+        sender: wallet(3),
+        data_account,
+    };
+    let count = h.next_count(sender).await;
+    let ix = h.store_signed_msg_ix(action.clone(), sender, 1, count);
+    h.send(vec![sign(&secret, &action, 1), ix], &[])
+        .await
+        .unwrap();
+    let data: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+    assert_eq!(data.code, 4);
+    assert_eq!(data.receiver, sender);
+    assert_eq!(data.sender, wallet(3));
+}
+
+#[tokio::test]
+async fn signed_messages_check_signer_and_nonce() {
+    let mut h = Harness::new().await;
+    let (secret, sender) = evm_key(7);
+    let (other, _) = evm_key(8);
+    let mint = h.create_mint();
+    let action = pause(wallet(2), mint, Pubkey::new_unique());
+    let count = h.next_count(sender).await;
+
+    let ix = h.store_signed_msg_ix(action.clone(), sender, 0, count);
+    assert_error(
+        h.send(vec![ix.clone()], &[]).await,
+        MessengerError::InvalidSignatureInstruction,
+    );
+    assert_error(
+        h.send(vec![sign(&other, &action, 0), ix.clone()], &[])
+            .await,
+        MessengerError::SignatureMismatch,
+    );
+    // Signed for a different stream than the one submitted.
+    let signed = pause(wallet(2), mint, Pubkey::new_unique());
+    assert_error(
+        h.send(vec![sign(&secret, &signed, 0), ix.clone()], &[])
+            .await,
+        MessengerError::SignatureMismatch,
+    );
+    // Signed for a nonce other than the one submitted.
+    assert_error(
+        h.send(vec![sign(&secret, &action, 1), ix.clone()], &[])
+            .await,
+        MessengerError::SignatureMismatch,
+    );
+
+    h.send(vec![sign(&secret, &action, 0), ix], &[])
+        .await
+        .unwrap();
+
+    // A signature is only good once.
+    let count = h.next_count(sender).await;
+    let ix = h.store_signed_msg_ix(action.clone(), sender, 0, count);
+    assert_error(
+        h.send(vec![sign(&secret, &action, 0), ix], &[]).await,
+        MessengerError::MetaNonceMismatch,
+    );
+
+    // The sender has to be an EVM address.
+    let mut padded = sender;
+    padded[0] = 1;
+    let ix = h.store_signed_msg_ix(action.clone(), padded, 0, 1);
+    assert_error(
+        h.send(vec![sign(&secret, &action, 0), ix], &[]).await,
+        MessengerError::InvalidSenderAddress,
+    );
+}
@@ -0,0 +1,163 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+use solana_project::allowlist::wrapped_mint_address;
+use solana_project::constants::SOLANA_CHAIN_ID;
+use solana_project::errors::MessengerError;
+use solana_project::state::{MintRegistry, TransactionData};
+
+use common::*;
+
+#[tokio::test]
+async fn mints_are_registered_by_the_config_owner() {
+    let mut h = Harness::new().await;
+    h.register_mints = false;
+    let mint = h.create_mint_with_decimals(9);
+
+    let ix = h.register_mint_ix(mint, 10, 1_000, SOLANA_CHAIN_ID, mint.to_bytes());
+    h.send(vec![ix], &[]).await.unwrap();
+    let registry: MintRegistry = h.account(mint_registry(&mint)).await.unwrap();
+    assert_eq!(registry.mint, mint);
+    assert_eq!(registry.decimals, 9);
+    assert_eq!(registry.min_amount, 10);
+    assert_eq!(registry.max_amount, 1_000);
+    assert!(!registry.wrapped);
+    assert_eq!(registry.origin_chain, SOLANA_CHAIN_ID);
+
+    // Registering again updates the entry.
+    h.next_blockhash().await;
+    let ix = h.register_mint_ix(mint, 0, 5_000, SOLANA_CHAIN_ID, mint.to_bytes());
+    h.send(vec![ix], &[]).await.unwrap();
+    let registry: MintRegistry = h.account(mint_registry(&mint)).await.unwrap();
+    assert_eq!(registry.min_amount, 0);
+    assert_eq!(registry.max_amount, 5_000);
+
+    let ix = h.register_mint_ix(mint, 6_000, 5_000, SOLANA_CHAIN_ID, mint.to_bytes());
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::AmountOutOfBounds,
+    );
+
+    let stranger = Keypair::new();
+    h.set_account(stranger.pubkey(), solana_sdk::system_program::id(), vec![]);
+    let mut ix = h.remove_mint_ix(mint);
+    ix.accounts[0].pubkey = stranger.pubkey();
+    assert!(h.send(vec![ix], &[&stranger]).await.is_err());
+
+    let ix = h.remove_mint_ix(mint);
+    h.send(vec![ix], &[]).await.unwrap();
+    assert!(h
+        .account::<MintRegistry>(mint_registry(&mint))
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn registered_origins_must_match_the_mint() {
+    let mut h = Harness::new().await;
+    h.register_mints = false;
+    let mint = h.create_mint();
+
+    // A Solana mint is its own origin.
+    let ix = h.register_mint_ix(mint, 0, u64::MAX, SOLANA_CHAIN_ID, wallet(5));
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::MintOriginMismatch,
+    );
+    // Any other origin chain has to be where the token bridge wraps it.
+    let ix = h.register_mint_ix(mint, 0, u64::MAX, BSC_CHAIN_ID, mint.to_bytes());
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::MintOriginMismatch,
+    );
+
+    let wrapped = wrapped_mint_address(BSC_CHAIN_ID, &wallet(5));
+    h.write_mint(wrapped, 8);
+    let ix = h.register_mint_ix(wrapped, 0, u64::MAX, BSC_CHAIN_ID, wallet(5));
+    h.send(vec![ix], &[]).await.unwrap();
+    let registry: MintRegistry = h.account(mint_registry(&wrapped)).await.unwrap();
+    assert!(registry.wrapped);
+    assert_eq!(registry.origin_chain, BSC_CHAIN_ID);
+    assert_eq!(registry.origin_address, wallet(5));
+}
+
+#[tokio::test]
+async fn messages_naming_unregistered_mints_are_refused() {
+    let mut h = Harness::new().await;
+    h.register_mints = false;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = h.create_mint();
+
+    let posted = h.post(deposit_payload(500, sender, mint));
+    assert_error(
+        h.store_msg(&posted, sender, 1).await,
+        MessengerError::MintNotAllowed,
+    );
+    let posted = h.post(batch_stream_payload(
+        sender,
+        mint,
+        &[batch_entry(receiver, 100, 10, 20, true, true)],
+    ));
+    assert_error(
+        h.store_batch_msg(&posted, sender, 1).await,
+        MessengerError::MintNotAllowed,
+    );
+    let posted = h.post(initialize_token_account_payload(sender, mint));
+    assert_error(
+        h.initialize_pda_token_account(&posted, sender, mint).await,
+        MessengerError::MintNotAllowed,
+    );
+
+    let ix = h.register_mint_ix(mint, 0, u64::MAX, SOLANA_CHAIN_ID, mint.to_bytes());
+    h.send(vec![ix], &[]).await.unwrap();
+    let count = h.store(deposit_payload(500, sender, mint), sender).await;
+    let data: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+    assert_eq!(data.token_mint, mint);
+}
+
+#[tokio::test]
+async fn amounts_are_bounded_in_base_units() {
+    let mut h = Harness::new().await;
+    h.register_mints = false;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = h.create_mint_with_decimals(10);
+    let ix = h.register_mint_ix(mint, 1_000, 100_000, SOLANA_CHAIN_ID, mint.to_bytes());
+    h.send(vec![ix], &[]).await.unwrap();
+
+    for amount in [999, 100_001] {
+        let posted = h.post(deposit_payload(amount, sender, mint));
+        assert_error(
+            h.store_msg(&posted, sender, 1).await,
+            MessengerError::AmountOutOfBounds,
+        );
+    }
+    h.store(deposit_payload(1_000, sender, mint), sender).await;
+    h.store(deposit_payload(100_000, sender, mint), sender)
+        .await;
+
+    // Messages without an amount only need the mint registered.
+    h.store(
+        pause_payload(sender, mint, receiver, Pubkey::new_unique()),
+        sender,
+    )
+    .await;
+
+    // Every batch entry is checked.
+    let posted = h.post(batch_stream_payload(
+        sender,
+        mint,
+        &[
+            batch_entry(receiver, 50_000, 10, 20, true, true),
+            batch_entry(wallet(3), 500_000, 10, 20, true, true),
+        ],
+    ));
+    let count = h.next_count(sender).await;
+    assert_error(
+        h.store_batch_msg(&posted, sender, count).await,
+        MessengerError::AmountOutOfBounds,
+    );
+}
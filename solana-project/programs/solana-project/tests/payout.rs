@@ -0,0 +1,364 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::{spl_token, TokenAccount};
+
+use solana_project::errors::MessengerError;
+use solana_project::state::{Payout, PayoutProfile, TransactionAccount, TransactionData};
+use solana_project::{accounts, instruction};
+
+use common::*;
+
+fn payout_ix(
+    zebec_eoa: Pubkey,
+    receiver: [u8; 32],
+    current_count: u64,
+    mint: Pubkey,
+    wallet: Pubkey,
+) -> Instruction {
+    let pda_receiver = user_pda(&receiver, BSC_CHAIN_ID);
+    Instruction {
+        program_id: solana_project::ID,
+        accounts: accounts::WithdrawPayout {
+            zebec_eoa,
+            data_storage: data_store(&receiver, current_count),
+            txn_status: txn_status(&receiver, current_count),
+            pda_signer: pda_receiver,
+            from: get_associated_token_address(&pda_receiver, &mint),
+            mint,
+            wallet,
+            wallet_token_account: get_associated_token_address(&wallet, &mint),
+            system_program: system_program::id(),
+            token_program: spl_token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            rent: sysvar::rent::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::TransactionWithdrawPayout {
+            sender: receiver,
+            chain_id: BSC_CHAIN_ID,
+            current_count,
+        }
+        .data(),
+    }
+}
+
+/// Settle accounts for a receiver withdraw that Zebec pays into `to`.
+fn withdraw_accounts(
+    sender: &[u8; 32],
+    receiver: &[u8; 32],
+    data_account: Pubkey,
+    mint: Pubkey,
+    to: Pubkey,
+) -> Vec<TransactionAccount> {
+    let pda_receiver = user_pda(receiver, BSC_CHAIN_ID);
+    zebec_accounts(
+        16,
+        &[
+            (1, pda_receiver),
+            (2, user_pda(sender, BSC_CHAIN_ID)),
+            (6, data_account),
+            (12, mint),
+            (14, to),
+        ],
+        Some(pda_receiver),
+    )
+}
+
+async fn link(h: &mut Harness, account: [u8; 32], wallet: Pubkey) {
+    let posted = h.post(set_payout_wallet_payload(account, wallet));
+    h.set_payout_wallet(&posted, account).await.unwrap();
+}
+
+#[tokio::test]
+async fn set_payout_wallet_links_and_unlinks() {
+    let mut h = Harness::new().await;
+    let account = wallet(2);
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let posted = h.post(set_payout_wallet_payload(account, first));
+    h.set_payout_wallet(&posted, account).await.unwrap();
+    let profile: PayoutProfile = h
+        .account(payout_profile(&account, BSC_CHAIN_ID))
+        .await
+        .unwrap();
+    assert_eq!(profile.identity, account);
+    assert_eq!(profile.chain_id, BSC_CHAIN_ID);
+    assert_eq!(profile.wallet, first);
+    assert_eq!(profile.vaa_sequence, posted.message.sequence);
+
+    link(&mut h, account, second).await;
+    let profile: PayoutProfile = h
+        .account(payout_profile(&account, BSC_CHAIN_ID))
+        .await
+        .unwrap();
+    assert_eq!(profile.wallet, second);
+
+    link(&mut h, account, Pubkey::default()).await;
+    let profile: PayoutProfile = h
+        .account(payout_profile(&account, BSC_CHAIN_ID))
+        .await
+        .unwrap();
+    assert_eq!(profile.wallet, Pubkey::default());
+}
+
+#[tokio::test]
+async fn set_payout_wallet_checks() {
+    let mut h = Harness::new().await;
+    let account = wallet(2);
+    let (older, newer) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    // Relayed out of order, the older link does not win.
+    let first = h.post(set_payout_wallet_payload(account, older));
+    let second = h.post(set_payout_wallet_payload(account, newer));
+    h.set_payout_wallet(&second, account).await.unwrap();
+    assert_error(
+        h.set_payout_wallet(&first, account).await,
+        MessengerError::StalePayoutWallet,
+    );
+    let profile: PayoutProfile = h
+        .account(payout_profile(&account, BSC_CHAIN_ID))
+        .await
+        .unwrap();
+    assert_eq!(profile.wallet, newer);
+
+    // The wallet only links the account the message is from.
+    let posted = h.post(set_payout_wallet_payload(account, older));
+    assert_error(
+        h.set_payout_wallet(&posted, wallet(3)).await,
+        MessengerError::InvalidSenderWallet,
+    );
+
+    let posted = h.post(with_to_chain(
+        set_payout_wallet_payload(account, older),
+        65,
+        2,
+    ));
+    assert_error(
+        h.set_payout_wallet(&posted, account).await,
+        MessengerError::InvalidToChainId,
+    );
+
+    // Links are not transactions.
+    let posted = h.post(set_payout_wallet_payload(account, older));
+    let count = h.next_count(account).await;
+    assert_error(
+        h.store_msg(&posted, account, count).await,
+        MessengerError::InvalidPayload,
+    );
+}
+
+#[tokio::test]
+async fn withdraw_stream_payout_pays_the_wallet() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let pda_receiver = user_pda(&receiver, BSC_CHAIN_ID);
+    let payout_wallet = Pubkey::new_unique();
+    let mint = h.create_mint();
+    let data_account = Pubkey::new_unique();
+    // Already in the account before the withdraw; it stays there.
+    let from = get_associated_token_address(&pda_receiver, &mint);
+    h.write_token_account(from, mint, pda_receiver, 300);
+    link(&mut h, receiver, payout_wallet).await;
+
+    let count = h
+        .store(
+            withdraw_stream_payout_payload(receiver, mint, sender, data_account),
+            receiver,
+        )
+        .await;
+    let accs = withdraw_accounts(&sender, &receiver, data_account, mint, from);
+
+    assert_error(
+        h.create(
+            Create::ReceiverWithdraw,
+            accs.clone(),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await,
+        MessengerError::MessageCodeMismatch,
+    );
+    // Nor is it bridged back.
+    assert_error(
+        h.create(
+            Create::WithdrawBridge(from),
+            accs.clone(),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await,
+        MessengerError::MessageCodeMismatch,
+    );
+    let transaction = h
+        .create(
+            Create::WithdrawPayout(from),
+            accs.clone(),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await
+        .unwrap();
+    let data: TransactionData = h.account(data_store(&receiver, count)).await.unwrap();
+    assert_eq!(
+        data.payout,
+        Payout::To {
+            wallet: payout_wallet,
+            balance_before: 300,
+            paid: false,
+        }
+    );
+
+    let ix = payout_ix(h.owner(), receiver, count, mint, payout_wallet);
+    assert_error(
+        h.send(vec![ix.clone()], &[]).await,
+        MessengerError::TransactionNotExecuted,
+    );
+
+    h.execute(transaction, &accs, receiver, count)
+        .await
+        .unwrap();
+    // What the Zebec stub would have paid out.
+    h.write_token_account(from, mint, pda_receiver, 1_000);
+
+    h.send(vec![ix.clone()], &[]).await.unwrap();
+    let from_state: TokenAccount = h.account(from).await.unwrap();
+    assert_eq!(from_state.amount, 300);
+    let paid: TokenAccount = h
+        .account(get_associated_token_address(&payout_wallet, &mint))
+        .await
+        .unwrap();
+    assert_eq!(paid.owner, payout_wallet);
+    assert_eq!(paid.amount, 700);
+
+    let data: TransactionData = h.account(data_store(&receiver, count)).await.unwrap();
+    assert!(matches!(data.payout, Payout::To { paid: true, .. }));
+
+    h.next_blockhash().await;
+    assert_error(h.send(vec![ix], &[]).await, MessengerError::AlreadyPaidOut);
+}
+
+#[tokio::test]
+async fn withdraw_stream_payout_checks() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let pda_receiver = user_pda(&receiver, BSC_CHAIN_ID);
+    let payout_wallet = Pubkey::new_unique();
+    let mint = h.create_mint();
+    let data_account = Pubkey::new_unique();
+    let from = get_associated_token_address(&pda_receiver, &mint);
+    h.write_token_account(from, mint, pda_receiver, 0);
+
+    let count = h
+        .store(
+            withdraw_stream_payout_payload(receiver, mint, sender, data_account),
+            receiver,
+        )
+        .await;
+    let accs = withdraw_accounts(&sender, &receiver, data_account, mint, from);
+
+    // Unlinked identities have nowhere to be paid out to.
+    link(&mut h, receiver, payout_wallet).await;
+    link(&mut h, receiver, Pubkey::default()).await;
+    assert_error(
+        h.create(
+            Create::WithdrawPayout(from),
+            accs.clone(),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await,
+        MessengerError::NoPayoutWallet,
+    );
+    link(&mut h, receiver, payout_wallet).await;
+
+    // Zebec has to pay into the receiver PDA's associated token account.
+    let other = h.create_token_account(mint, pda_receiver, 0);
+    assert_error(
+        h.create(
+            Create::WithdrawPayout(other),
+            withdraw_accounts(&sender, &receiver, data_account, mint, other),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await,
+        MessengerError::ReceiverTokenAccountMismatch,
+    );
+
+    let transaction = h
+        .create(
+            Create::WithdrawPayout(from),
+            accs.clone(),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await
+        .unwrap();
+    h.execute(transaction, &accs, receiver, count)
+        .await
+        .unwrap();
+
+    assert_error(
+        h.send(
+            vec![payout_ix(
+                h.owner(),
+                receiver,
+                count,
+                mint,
+                Pubkey::new_unique(),
+            )],
+            &[],
+        )
+        .await,
+        MessengerError::PayoutMismatch,
+    );
+    assert_error(
+        h.send(
+            vec![payout_ix(h.owner(), receiver, count, mint, payout_wallet)],
+            &[],
+        )
+        .await,
+        MessengerError::NothingWithdrawn,
+    );
+
+    // Plain withdraw stream messages are not paid out.
+    let count = h
+        .store(
+            withdraw_stream_payload(receiver, mint, sender, data_account),
+            receiver,
+        )
+        .await;
+    let transaction = h
+        .create(
+            Create::ReceiverWithdraw,
+            accs.clone(),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await
+        .unwrap();
+    h.execute(transaction, &accs, receiver, count)
+        .await
+        .unwrap();
+    assert_error(
+        h.send(
+            vec![payout_ix(h.owner(), receiver, count, mint, payout_wallet)],
+            &[],
+        )
+        .await,
+        MessengerError::PayoutMismatch,
+    );
+}
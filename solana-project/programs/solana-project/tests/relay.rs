@@ -0,0 +1,204 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use solana_project::errors::MessengerError;
+use solana_project::state::{
+    Count, Stream, TokenAmount, Transaction, TransactionData, TransactionStatus,
+};
+
+use common::*;
+
+#[tokio::test]
+async fn relay_stores_and_executes_a_deposit() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = Pubkey::new_unique();
+    let posted = h.post(deposit_payload(500, sender, mint));
+    let count = h.next_count(sender).await;
+
+    let accs = zebec_accounts(10, &[(1, pda_sender), (6, mint)], Some(pda_sender));
+    let steps = [
+        h.store_msg_ix(&posted, sender, count, BSC_CHAIN_ID, EMITTER_ADDRESS),
+        h.create_execute_ix(
+            CreateExecute::Deposit,
+            accs,
+            zebec_data(&TokenAmount { amount: 500 }),
+            sender,
+            BSC_CHAIN_ID,
+            count,
+            0,
+            mint,
+        ),
+    ];
+    h.relay(&steps, mint).await.unwrap();
+
+    let stored: Count = h.account(txn_count(&sender)).await.unwrap();
+    assert_eq!(stored.count, count);
+    let data: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+    assert_eq!(data.token_mint, mint);
+    let status: TransactionStatus = h.account(txn_status(&sender, count)).await.unwrap();
+    assert!(status.executed);
+    let transaction: Transaction = h.account(transaction(&sender, count)).await.unwrap();
+    assert!(transaction.did_execute);
+
+    // The VAA is processed, so it cannot be relayed again.
+    h.next_blockhash().await;
+    assert!(h.relay(&steps, mint).await.is_err());
+}
+
+#[tokio::test]
+async fn relay_stores_creates_and_executes_a_stream() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let (pda_sender, pda_receiver) = (
+        user_pda(&sender, BSC_CHAIN_ID),
+        user_pda(&receiver, BSC_CHAIN_ID),
+    );
+    let mint = Pubkey::new_unique();
+    let posted = h.post(stream_payload(
+        100, 200, 500, sender, receiver, true, true, mint,
+    ));
+    let count = h.next_count(sender).await;
+
+    let accs = zebec_accounts(
+        15,
+        &[(5, pda_sender), (6, pda_receiver), (9, mint)],
+        Some(pda_sender),
+    );
+    let data = zebec_data(&Stream {
+        start_time: 100,
+        end_time: 200,
+        amount: 500,
+        can_cancel: true,
+        can_update: true,
+    });
+    let steps = [
+        h.store_msg_ix(&posted, sender, count, BSC_CHAIN_ID, EMITTER_ADDRESS),
+        h.create_ix_for(Create::Stream, accs.clone(), data, sender, count, 0, mint),
+        h.execute_ix(transaction(&sender, count), &accs, sender, count, 0),
+    ];
+    h.relay(&steps, mint).await.unwrap();
+
+    let status: TransactionStatus = h.account(txn_status(&sender, count)).await.unwrap();
+    assert!(status.executed);
+    let transaction: Transaction = h.account(transaction(&sender, count)).await.unwrap();
+    assert!(transaction.did_execute);
+}
+
+#[tokio::test]
+async fn relay_takes_only_whole_messages() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = Pubkey::new_unique();
+    let posted = h.post(deposit_payload(500, sender, mint));
+    let count = h.next_count(sender).await;
+
+    let store = h.store_msg_ix(&posted, sender, count, BSC_CHAIN_ID, EMITTER_ADDRESS);
+    let deposit = h.create_execute_ix(
+        CreateExecute::Deposit,
+        zebec_accounts(10, &[(1, pda_sender), (6, mint)], Some(pda_sender)),
+        zebec_data(&TokenAmount { amount: 500 }),
+        sender,
+        BSC_CHAIN_ID,
+        count,
+        0,
+        mint,
+    );
+    let mut short = deposit.clone();
+    short.accounts.pop();
+    let cases = vec![
+        vec![store.clone()],
+        vec![deposit.clone()],
+        vec![deposit.clone(), store.clone()],
+        vec![store.clone(), deposit.clone(), deposit.clone()],
+        vec![store.clone(), h.set_chain_limit_ix(BSC_CHAIN_ID, 1)],
+    ];
+    for steps in cases {
+        assert_error(
+            h.relay(&steps, mint).await,
+            MessengerError::InvalidRelayStep,
+        );
+    }
+
+    // Every account belongs to a step.
+    let mut ix = h.relay_ix(&[store.clone(), deposit]);
+    ix.accounts.push(ix.accounts[1].clone());
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::InvalidRelayStep,
+    );
+
+    // A step that fails fails the relay, storing nothing.
+    assert!(h.relay(&[store, short], mint).await.is_err());
+    assert!(h
+        .account::<TransactionData>(data_store(&sender, count))
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn relay_steps_handle_one_message() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let (pda_sender, pda_receiver) = (
+        user_pda(&sender, BSC_CHAIN_ID),
+        user_pda(&receiver, BSC_CHAIN_ID),
+    );
+    let mint = Pubkey::new_unique();
+    let posted = h.post(deposit_payload(500, sender, mint));
+    let count = h.next_count(sender).await;
+    let store = h.store_msg_ix(&posted, sender, count, BSC_CHAIN_ID, EMITTER_ADDRESS);
+
+    // A deposit of another sender, or of another count, is another message.
+    let deposit = |sender: [u8; 32], count: u64| {
+        let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+        h.create_execute_ix(
+            CreateExecute::Deposit,
+            zebec_accounts(10, &[(1, pda_sender), (6, mint)], Some(pda_sender)),
+            zebec_data(&TokenAmount { amount: 500 }),
+            sender,
+            BSC_CHAIN_ID,
+            count,
+            0,
+            mint,
+        )
+    };
+    let cases = vec![
+        vec![store.clone(), deposit(receiver, count)],
+        vec![store.clone(), deposit(sender, count + 1)],
+    ];
+
+    // Likewise an execute of another message's transaction.
+    let accs = zebec_accounts(
+        15,
+        &[(5, pda_sender), (6, pda_receiver), (9, mint)],
+        Some(pda_sender),
+    );
+    let data = zebec_data(&Stream {
+        start_time: 100,
+        end_time: 200,
+        amount: 500,
+        can_cancel: true,
+        can_update: true,
+    });
+    let create = h.create_ix_for(Create::Stream, accs.clone(), data, sender, count, 0, mint);
+    let cases = cases.into_iter().chain([vec![
+        store.clone(),
+        create,
+        h.execute_ix(transaction(&sender, count + 1), &accs, sender, count + 1, 0),
+    ]]);
+    for steps in cases {
+        assert_error(
+            h.relay(&steps, mint).await,
+            MessengerError::InvalidRelayStep,
+        );
+    }
+    assert!(h
+        .account::<TransactionData>(data_store(&sender, count))
+        .await
+        .is_none());
+}
@@ -0,0 +1,195 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use solana_project::errors::MessengerError;
+use solana_project::state::{MessageBinding, Transaction, TransactionAccount, TransactionBuffer};
+use solana_sdk::signature::{Keypair, Signer};
+
+use common::*;
+
+// More Zebec accounts and data than fit in one create instruction.
+fn large_withdraw(data_account: Pubkey, mint: Pubkey) -> (Vec<TransactionAccount>, Vec<u8>) {
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let pda_receiver = user_pda(&receiver, BSC_CHAIN_ID);
+    let accs = zebec_accounts(
+        20,
+        &[
+            (1, pda_receiver),
+            (2, user_pda(&sender, BSC_CHAIN_ID)),
+            (6, data_account),
+            (12, mint),
+        ],
+        Some(pda_receiver),
+    );
+    let data = (0..600u16).map(|i| (i % 251) as u8).collect();
+    (accs, data)
+}
+
+#[tokio::test]
+async fn large_receiver_withdraw_is_uploaded_in_chunks() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = Pubkey::new_unique();
+    let data_account = Pubkey::new_unique();
+    let count = h
+        .store(
+            withdraw_stream_payload(receiver, mint, sender, data_account),
+            receiver,
+        )
+        .await;
+    let (accs, data) = large_withdraw(data_account, mint);
+
+    h.upload(&accs, &data, receiver, count).await.unwrap();
+    let buffer: TransactionBuffer = h
+        .account(transaction_buffer(&receiver, count))
+        .await
+        .unwrap();
+    assert!(buffer.finalized);
+    assert!(buffer.is_complete());
+
+    let transaction = h
+        .create_staged(Create::ReceiverWithdraw, receiver, count)
+        .await
+        .unwrap();
+    assert!(h
+        .ctx
+        .banks_client
+        .get_account(transaction_buffer(&receiver, count))
+        .await
+        .unwrap()
+        .is_none());
+    let account = h
+        .ctx
+        .banks_client
+        .get_account(transaction)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        account.data.len(),
+        Transaction::space(accs.len(), data.len())
+    );
+    let stored: Transaction = h.account(transaction).await.unwrap();
+    assert_eq!(stored.accounts.len(), accs.len());
+    assert_eq!(stored.data, data);
+    assert!(matches!(stored.binding, MessageBinding::Bound { .. }));
+
+    h.execute(transaction, &accs, receiver, count)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn uploads_are_checked_when_created() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = Pubkey::new_unique();
+    let data_account = Pubkey::new_unique();
+    let count = h
+        .store(cancel_payload(sender, mint, receiver, data_account), sender)
+        .await;
+
+    // Nothing is checked while uploading.
+    let (accs, data) = large_withdraw(Pubkey::new_unique(), mint);
+    h.upload(&accs, &data, sender, count).await.unwrap();
+    assert_error(
+        h.create_staged(Create::Cancel, sender, count).await,
+        MessengerError::DataAccountMismatch,
+    );
+
+    // Only the buffer of the message itself is read.
+    let other = h
+        .store(cancel_payload(sender, mint, receiver, data_account), sender)
+        .await;
+    let (accs, data) = large_withdraw(data_account, mint);
+    h.upload(&accs, &data, sender, other).await.unwrap();
+    let mut ix = h
+        .create_ix(Create::Cancel, vec![], vec![], sender, count)
+        .await;
+    ix.accounts
+        .push(AccountMeta::new(transaction_buffer(&sender, other), false));
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::TransactionBufferMismatch,
+    );
+    h.create_staged(Create::Cancel, sender, other)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn only_the_uploader_fills_the_buffer() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = Pubkey::new_unique();
+    let data_account = Pubkey::new_unique();
+    let count = h
+        .store(cancel_payload(sender, mint, receiver, data_account), sender)
+        .await;
+    let (accs, data) = large_withdraw(data_account, mint);
+    let owner = h.owner();
+
+    // Nobody else can take the buffer's seeds and leave it unfinished.
+    let stranger = Keypair::new();
+    h.set_account(stranger.pubkey(), solana_sdk::system_program::id(), vec![]);
+    let mut ix = h.init_transaction_ix(sender, count, accs.len() as u8, data.len() as u16);
+    ix.accounts[1].pubkey = stranger.pubkey();
+    assert!(h.send(vec![ix], &[&stranger]).await.is_err());
+
+    let ix = h.init_transaction_ix(sender, count, accs.len() as u8, data.len() as u16);
+    h.send(vec![ix], &[]).await.unwrap();
+    let ix = h.append_accounts_ix(stranger.pubkey(), sender, count, accs[..8].to_vec());
+    assert_error(
+        h.send(vec![ix], &[&stranger]).await,
+        MessengerError::InvalidCaller,
+    );
+
+    let ix = h.append_accounts_ix(owner, sender, count, accs[..16].to_vec());
+    h.send(vec![ix], &[]).await.unwrap();
+    let ix = h.append_accounts_ix(owner, sender, count, accs[..8].to_vec());
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::TransactionTooLarge,
+    );
+    let ix = h.append_accounts_ix(owner, sender, count, accs[16..].to_vec());
+    h.send(vec![ix], &[]).await.unwrap();
+    let ix = h.append_data_ix(owner, sender, count, data.clone());
+    h.send(vec![ix], &[]).await.unwrap();
+
+    assert_error(
+        h.create_staged(Create::Cancel, sender, count).await,
+        MessengerError::TransactionNotFinalized,
+    );
+    let ix = h.finalize_transaction_ix(owner, sender, count);
+    h.send(vec![ix], &[]).await.unwrap();
+    let ix = h.append_data_ix(owner, sender, count, vec![1]);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::TransactionBufferFinalized,
+    );
+}
+
+#[tokio::test]
+async fn finalize_needs_the_whole_transaction() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let mint = Pubkey::new_unique();
+    let data_account = Pubkey::new_unique();
+    let count = h
+        .store(cancel_payload(sender, mint, receiver, data_account), sender)
+        .await;
+    let (accs, data) = large_withdraw(data_account, mint);
+    let owner = h.owner();
+
+    let ix = h.init_transaction_ix(sender, count, accs.len() as u8, data.len() as u16);
+    h.send(vec![ix], &[]).await.unwrap();
+    let ix = h.append_accounts_ix(owner, sender, count, accs);
+    h.send(vec![ix], &[]).await.unwrap();
+    let ix = h.finalize_transaction_ix(owner, sender, count);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::TransactionIncomplete,
+    );
+}
@@ -0,0 +1,839 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountSerialize;
+use solana_project::errors::MessengerError;
+use solana_project::state::{
+    MessageBinding, Stream, StreamUpdate, TokenAmount, Transaction, TransactionData,
+    TransactionStatus,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+use common::*;
+
+fn stream_args(
+    start_time: u64,
+    end_time: u64,
+    amount: u64,
+    can_cancel: bool,
+    can_update: bool,
+) -> Vec<u8> {
+    zebec_data(&Stream {
+        start_time,
+        end_time,
+        amount,
+        can_cancel,
+        can_update,
+    })
+}
+
+#[tokio::test]
+async fn deposit_executes_once() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = Pubkey::new_unique();
+    let count = h.store(deposit_payload(500, sender, mint), sender).await;
+
+    let accs = zebec_accounts(10, &[(1, pda_sender), (6, mint)], Some(pda_sender));
+    let data = zebec_data(&TokenAmount { amount: 500 });
+    let transaction = h
+        .create_execute(
+            CreateExecute::Deposit,
+            accs.clone(),
+            data.clone(),
+            sender,
+            count,
+        )
+        .await
+        .unwrap();
+
+    let status: TransactionStatus = h.account(txn_status(&sender, count)).await.unwrap();
+    assert!(status.executed);
+    let stored: Transaction = h.account(transaction).await.unwrap();
+    assert!(stored.did_execute);
+    assert_eq!(stored.program_id, zebec_program());
+
+    // The message's transaction account already exists.
+    assert!(h
+        .create_execute(CreateExecute::Deposit, accs, data, sender, count)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn deposit_checks_accounts_and_data() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = Pubkey::new_unique();
+    let count = h.store(deposit_payload(500, sender, mint), sender).await;
+    let data = zebec_data(&TokenAmount { amount: 500 });
+
+    let wrong_mint = zebec_accounts(
+        10,
+        &[(1, pda_sender), (6, Pubkey::new_unique())],
+        Some(pda_sender),
+    );
+    assert_error(
+        h.create_execute(
+            CreateExecute::Deposit,
+            wrong_mint,
+            data.clone(),
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::MintKeyMismatch,
+    );
+
+    let other = user_pda(&wallet(2), BSC_CHAIN_ID);
+    let wrong_pda = zebec_accounts(10, &[(1, other), (6, mint)], Some(pda_sender));
+    assert_error(
+        h.create_execute(CreateExecute::Deposit, wrong_pda, data, sender, count)
+            .await,
+        MessengerError::SenderDerivedKeyMismatch,
+    );
+
+    let accs = zebec_accounts(10, &[(1, pda_sender), (6, mint)], Some(pda_sender));
+    assert_error(
+        h.create_execute(
+            CreateExecute::Deposit,
+            accs.clone(),
+            zebec_data(&TokenAmount { amount: 501 }),
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::AmountMismatch,
+    );
+
+    let mut rejected = zebec_data(&TokenAmount { amount: 500 });
+    rejected[..8].copy_from_slice(&REJECTED_BY_ZEBEC);
+    assert_error(
+        h.create_execute(CreateExecute::Deposit, accs, rejected, sender, count)
+            .await,
+        MessengerError::InvalidCPI,
+    );
+}
+
+#[tokio::test]
+async fn stream_create_checks_every_field() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let (pda_sender, pda_receiver) = (
+        user_pda(&sender, BSC_CHAIN_ID),
+        user_pda(&receiver, BSC_CHAIN_ID),
+    );
+    let mint = Pubkey::new_unique();
+    let count = h
+        .store(
+            stream_payload(100, 200, 500, sender, receiver, true, true, mint),
+            sender,
+        )
+        .await;
+
+    let accs = |receiver_at_6: Pubkey, mint_at_9: Pubkey| {
+        zebec_accounts(
+            15,
+            &[(5, pda_sender), (6, receiver_at_6), (9, mint_at_9)],
+            Some(pda_sender),
+        )
+    };
+    let cases = vec![
+        (
+            accs(pda_receiver, Pubkey::new_unique()),
+            stream_args(100, 200, 500, true, true),
+            MessengerError::MintKeyMismatch,
+        ),
+        (
+            accs(pda_sender, mint),
+            stream_args(100, 200, 500, true, true),
+            MessengerError::ReceiverDerivedKeyMismatch,
+        ),
+        (
+            accs(pda_receiver, mint),
+            stream_args(100, 200, 499, true, true),
+            MessengerError::AmountMismatch,
+        ),
+        (
+            accs(pda_receiver, mint),
+            stream_args(101, 200, 500, true, true),
+            MessengerError::StartTimeMismatch,
+        ),
+        (
+            accs(pda_receiver, mint),
+            stream_args(100, 201, 500, true, true),
+            MessengerError::EndTimeMismatch,
+        ),
+        (
+            accs(pda_receiver, mint),
+            stream_args(100, 200, 500, false, true),
+            MessengerError::CanCancelMismatch,
+        ),
+        (
+            accs(pda_receiver, mint),
+            stream_args(100, 200, 500, true, false),
+            MessengerError::CanUpdateMismatch,
+        ),
+    ];
+    for (accs, data, error) in cases {
+        assert_error(
+            h.create(Create::Stream, accs, data, sender, count).await,
+            error,
+        );
+    }
+
+    let accs = accs(pda_receiver, mint);
+    let transaction = h
+        .create(
+            Create::Stream,
+            accs.clone(),
+            stream_args(100, 200, 500, true, true),
+            sender,
+            count,
+        )
+        .await
+        .unwrap();
+    let stored: Transaction = h.account(transaction).await.unwrap();
+    assert!(!stored.did_execute);
+
+    h.execute(transaction, &accs, sender, count).await.unwrap();
+    let status: TransactionStatus = h.account(txn_status(&sender, count)).await.unwrap();
+    assert!(status.executed);
+
+    h.next_blockhash().await;
+    assert_error(
+        h.execute(transaction, &accs, sender, count).await,
+        MessengerError::TransactionAlreadyExecuted,
+    );
+}
+
+#[tokio::test]
+async fn execute_rejects_spent_transaction() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = Pubkey::new_unique();
+
+    let first = h.store(deposit_payload(500, sender, mint), sender).await;
+    let accs = zebec_accounts(10, &[(1, pda_sender), (6, mint)], Some(pda_sender));
+    let spent = h
+        .create_execute(
+            CreateExecute::Deposit,
+            accs.clone(),
+            zebec_data(&TokenAmount { amount: 500 }),
+            sender,
+            first,
+        )
+        .await
+        .unwrap();
+
+    // A fresh status account does not make an executed transaction usable
+    // again: the transaction of one message never passes for another's.
+    let second = h
+        .store(sender_withdraw_payload(500, sender, mint), sender)
+        .await;
+    assert!(h.execute(spent, &accs, sender, second).await.is_err());
+}
+
+#[tokio::test]
+async fn transactions_are_derived_from_the_message() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = Pubkey::new_unique();
+    let first = h
+        .store(sender_withdraw_payload(500, sender, mint), sender)
+        .await;
+    let second = h
+        .store(sender_withdraw_payload(700, sender, mint), sender)
+        .await;
+
+    let accs = zebec_accounts(12, &[(2, pda_sender), (7, mint)], Some(pda_sender));
+    let first_data = zebec_data(&TokenAmount { amount: 500 });
+    let transaction = h
+        .create(
+            Create::SenderWithdraw,
+            accs.clone(),
+            first_data.clone(),
+            sender,
+            first,
+        )
+        .await
+        .unwrap();
+    assert_eq!(transaction, common::transaction(&sender, first));
+    let account = h
+        .ctx
+        .banks_client
+        .get_account(transaction)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        account.data.len(),
+        Transaction::space(accs.len(), first_data.len())
+    );
+
+    let second_transaction = h
+        .create(
+            Create::SenderWithdraw,
+            accs.clone(),
+            zebec_data(&TokenAmount { amount: 700 }),
+            sender,
+            second,
+        )
+        .await
+        .unwrap();
+    assert!(h
+        .execute(second_transaction, &accs, sender, first)
+        .await
+        .is_err());
+    h.execute(transaction, &accs, sender, first).await.unwrap();
+    h.execute(second_transaction, &accs, sender, second)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn transactions_are_built_by_the_owner() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = Pubkey::new_unique();
+    let count = h
+        .store(sender_withdraw_payload(500, sender, mint), sender)
+        .await;
+    let accs = zebec_accounts(12, &[(2, pda_sender), (7, mint)], Some(pda_sender));
+    let data = zebec_data(&TokenAmount { amount: 500 });
+
+    // Anyone else could take the message's seeds with a transaction that
+    // cannot run.
+    let stranger = Keypair::new();
+    h.set_account(stranger.pubkey(), solana_sdk::system_program::id(), vec![]);
+    let mut ix = h
+        .create_ix(
+            Create::SenderWithdraw,
+            accs.clone(),
+            data.clone(),
+            sender,
+            count,
+        )
+        .await;
+    ix.accounts[1].pubkey = stranger.pubkey();
+    assert!(h.send(vec![ix], &[&stranger]).await.is_err());
+
+    let transaction = h
+        .create(Create::SenderWithdraw, accs.clone(), data, sender, count)
+        .await
+        .unwrap();
+    h.execute(transaction, &accs, sender, count).await.unwrap();
+}
+
+#[tokio::test]
+async fn execute_checks_the_message_binding() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = Pubkey::new_unique();
+    let count = h
+        .store(sender_withdraw_payload(500, sender, mint), sender)
+        .await;
+    let message: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+
+    let accs = zebec_accounts(12, &[(2, pda_sender), (7, mint)], Some(pda_sender));
+    let transaction = h
+        .create(
+            Create::SenderWithdraw,
+            accs.clone(),
+            zebec_data(&TokenAmount { amount: 500 }),
+            sender,
+            count,
+        )
+        .await
+        .unwrap();
+    let built: Transaction = h.account(transaction).await.unwrap();
+    assert_eq!(
+        built.binding,
+        MessageBinding::Bound {
+            sender,
+            from_chain_id: BSC_CHAIN_ID,
+            current_count: count,
+            code: message.code,
+            digest: built.digest().unwrap(),
+        }
+    );
+
+    let mut unbound = built.clone();
+    unbound.binding = MessageBinding::Unbound;
+    let mut other_count = built.clone();
+    if let MessageBinding::Bound { current_count, .. } = &mut other_count.binding {
+        *current_count += 1;
+    }
+    let mut changed = built.clone();
+    changed.data[8] ^= 1;
+    let cases = [
+        (unbound, MessengerError::UnboundTransaction),
+        (other_count, MessengerError::TransactionMessageMismatch),
+        (changed, MessengerError::TransactionDigestMismatch),
+    ];
+    for (tampered, error) in cases {
+        let mut data = Vec::new();
+        tampered.try_serialize(&mut data).unwrap();
+        h.set_account(transaction, solana_project::ID, data);
+        h.next_blockhash().await;
+        assert_error(h.execute(transaction, &accs, sender, count).await, error);
+    }
+
+    let mut data = Vec::new();
+    built.try_serialize(&mut data).unwrap();
+    h.set_account(transaction, solana_project::ID, data);
+    h.next_blockhash().await;
+    h.execute(transaction, &accs, sender, count).await.unwrap();
+}
+
+#[tokio::test]
+async fn execute_surfaces_failed_cpi() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = Pubkey::new_unique();
+    let count = h
+        .store(sender_withdraw_payload(500, sender, mint), sender)
+        .await;
+
+    let accs = zebec_accounts(12, &[(2, pda_sender), (7, mint)], Some(pda_sender));
+    let mut data = zebec_data(&TokenAmount { amount: 500 });
+    data[..8].copy_from_slice(&REJECTED_BY_ZEBEC);
+    let transaction = h
+        .create(Create::SenderWithdraw, accs.clone(), data, sender, count)
+        .await
+        .unwrap();
+    assert_error(
+        h.execute(transaction, &accs, sender, count).await,
+        MessengerError::InvalidCPI,
+    );
+}
+
+#[tokio::test]
+async fn stream_update_and_pause_check_data_account() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let (pda_sender, pda_receiver) = (
+        user_pda(&sender, BSC_CHAIN_ID),
+        user_pda(&receiver, BSC_CHAIN_ID),
+    );
+    let mint = Pubkey::new_unique();
+    let data_account = Pubkey::new_unique();
+
+    let count = h
+        .store(
+            stream_update_payload(100, 300, 700, sender, receiver, mint, data_account),
+            sender,
+        )
+        .await;
+    let update = |data_at_0: Pubkey| {
+        zebec_accounts(
+            6,
+            &[
+                (0, data_at_0),
+                (2, pda_sender),
+                (3, pda_receiver),
+                (4, mint),
+            ],
+            Some(pda_sender),
+        )
+    };
+    let args = zebec_data(&StreamUpdate {
+        start_time: 100,
+        end_time: 300,
+        amount: 700,
+    });
+    // Each message is created only as the transaction it asks for.
+    assert_error(
+        h.create(
+            Create::Stream,
+            update(data_account),
+            stream_args(100, 300, 700, true, true),
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::MessageCodeMismatch,
+    );
+    assert_error(
+        h.create_execute(
+            CreateExecute::PauseResume,
+            update(data_account),
+            vec![1u8; 8],
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::MessageCodeMismatch,
+    );
+    assert_error(
+        h.create_execute(
+            CreateExecute::StreamUpdate,
+            update(Pubkey::new_unique()),
+            args.clone(),
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::DataAccountMismatch,
+    );
+    h.create_execute(
+        CreateExecute::StreamUpdate,
+        update(data_account),
+        args.clone(),
+        sender,
+        count,
+    )
+    .await
+    .unwrap();
+
+    let count = h
+        .store(pause_payload(sender, mint, receiver, data_account), sender)
+        .await;
+    let pause = |data_at_2: Pubkey| {
+        zebec_accounts(
+            3,
+            &[(0, pda_sender), (1, pda_receiver), (2, data_at_2)],
+            Some(pda_sender),
+        )
+    };
+    assert_error(
+        h.create_execute(
+            CreateExecute::StreamUpdate,
+            pause(data_account),
+            args,
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::MessageCodeMismatch,
+    );
+    assert_error(
+        h.create_execute(
+            CreateExecute::PauseResume,
+            pause(Pubkey::new_unique()),
+            vec![1u8; 8],
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::DataAccountMismatch,
+    );
+    assert_error(
+        h.create_execute(
+            CreateExecute::PauseResume,
+            zebec_accounts(
+                3,
+                &[(0, pda_sender), (1, pda_sender), (2, data_account)],
+                Some(pda_sender),
+            ),
+            vec![1u8; 8],
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::ReceiverDerivedKeyMismatch,
+    );
+    h.create_execute(
+        CreateExecute::PauseResume,
+        pause(data_account),
+        vec![1u8; 8],
+        sender,
+        count,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn receiver_withdraw_and_cancel_check_roles() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let (pda_sender, pda_receiver) = (
+        user_pda(&sender, BSC_CHAIN_ID),
+        user_pda(&receiver, BSC_CHAIN_ID),
+    );
+    let mint = Pubkey::new_unique();
+    let data_account = Pubkey::new_unique();
+    let settle = |data_at_6: Pubkey, signer: Pubkey| {
+        zebec_accounts(
+            16,
+            &[
+                (1, pda_receiver),
+                (2, pda_sender),
+                (6, data_at_6),
+                (12, mint),
+            ],
+            Some(signer),
+        )
+    };
+
+    // The withdraw message is keyed by the receiver, who signs it. Laid out
+    // like a cancel, it still cannot be created as one.
+    let count = h
+        .store(
+            withdraw_stream_payload(receiver, mint, sender, data_account),
+            receiver,
+        )
+        .await;
+    assert_error(
+        h.create(
+            Create::Cancel,
+            settle(data_account, pda_receiver),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await,
+        MessengerError::MessageCodeMismatch,
+    );
+    assert_error(
+        h.create(
+            Create::ReceiverWithdraw,
+            settle(Pubkey::new_unique(), pda_receiver),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await,
+        MessengerError::DataAccountMismatch,
+    );
+    let accs = settle(data_account, pda_receiver);
+    let transaction = h
+        .create(
+            Create::ReceiverWithdraw,
+            accs.clone(),
+            vec![1u8; 8],
+            receiver,
+            count,
+        )
+        .await
+        .unwrap();
+    h.execute(transaction, &accs, receiver, count)
+        .await
+        .unwrap();
+
+    // An instant transfer cannot be turned into a receiver withdrawal.
+    let count = h
+        .store(instant_transfer_payload(5, sender, mint, receiver), sender)
+        .await;
+    assert_error(
+        h.create(
+            Create::ReceiverWithdraw,
+            settle(Pubkey::default(), pda_sender),
+            vec![1u8; 8],
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::MessageCodeMismatch,
+    );
+
+    let count = h
+        .store(cancel_payload(sender, mint, receiver, data_account), sender)
+        .await;
+    let accs = settle(data_account, pda_sender);
+    let transaction = h
+        .create(Create::Cancel, accs.clone(), vec![1u8; 8], sender, count)
+        .await
+        .unwrap();
+    h.execute(transaction, &accs, sender, count).await.unwrap();
+}
+
+#[tokio::test]
+async fn instant_transfer_and_sender_withdraw() {
+    let mut h = Harness::new().await;
+    let (sender, receiver) = (wallet(1), wallet(2));
+    let (pda_sender, pda_receiver) = (
+        user_pda(&sender, BSC_CHAIN_ID),
+        user_pda(&receiver, BSC_CHAIN_ID),
+    );
+    let mint = Pubkey::new_unique();
+
+    let count = h
+        .store(instant_transfer_payload(50, sender, mint, receiver), sender)
+        .await;
+    let accs = zebec_accounts(
+        12,
+        &[(1, pda_receiver), (2, pda_sender), (8, mint)],
+        Some(pda_sender),
+    );
+    assert_error(
+        h.create(
+            Create::SenderWithdraw,
+            accs.clone(),
+            zebec_data(&TokenAmount { amount: 50 }),
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::MessageCodeMismatch,
+    );
+    assert_error(
+        h.create(
+            Create::InstantTransfer,
+            accs.clone(),
+            zebec_data(&TokenAmount { amount: 51 }),
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::AmountMismatch,
+    );
+    let wrong_receiver = zebec_accounts(
+        12,
+        &[(1, pda_sender), (2, pda_sender), (8, mint)],
+        Some(pda_sender),
+    );
+    assert_error(
+        h.create(
+            Create::InstantTransfer,
+            wrong_receiver,
+            zebec_data(&TokenAmount { amount: 50 }),
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::ReceiverDerivedKeyMismatch,
+    );
+    let transaction = h
+        .create(
+            Create::InstantTransfer,
+            accs.clone(),
+            zebec_data(&TokenAmount { amount: 50 }),
+            sender,
+            count,
+        )
+        .await
+        .unwrap();
+    h.execute(transaction, &accs, sender, count).await.unwrap();
+
+    let count = h
+        .store(sender_withdraw_payload(20, sender, mint), sender)
+        .await;
+    let accs = zebec_accounts(12, &[(2, pda_sender), (7, mint)], Some(pda_sender));
+    let wrong_pda = zebec_accounts(12, &[(2, pda_receiver), (7, mint)], Some(pda_sender));
+    assert_error(
+        h.create(
+            Create::InstantTransfer,
+            accs.clone(),
+            zebec_data(&TokenAmount { amount: 20 }),
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::MessageCodeMismatch,
+    );
+    assert_error(
+        h.create(
+            Create::SenderWithdraw,
+            wrong_pda,
+            zebec_data(&TokenAmount { amount: 20 }),
+            sender,
+            count,
+        )
+        .await,
+        MessengerError::SenderDerivedKeyMismatch,
+    );
+    let transaction = h
+        .create(
+            Create::SenderWithdraw,
+            accs.clone(),
+            zebec_data(&TokenAmount { amount: 20 }),
+            sender,
+            count,
+        )
+        .await
+        .unwrap();
+    h.execute(transaction, &accs, sender, count).await.unwrap();
+}
+
+#[tokio::test]
+async fn payload_selects_target_program_version() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = Pubkey::new_unique();
+
+    let mut payload = deposit_payload(500, sender, mint);
+    payload.push(1);
+    let count = h.store(payload, sender).await;
+    let stored: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+    assert_eq!(stored.target_version, 1);
+
+    // Version 1 is not registered yet, so there is nothing to build against.
+    let accs = zebec_accounts(10, &[(1, pda_sender), (6, mint)], Some(pda_sender));
+    let data = zebec_data(&TokenAmount { amount: 500 });
+    assert!(h
+        .create_execute(
+            CreateExecute::Deposit,
+            accs.clone(),
+            data.clone(),
+            sender,
+            count
+        )
+        .await
+        .is_err());
+
+    let ix = h.register_target_program_ix(1, zebec_program());
+    h.send(vec![ix], &[]).await.unwrap();
+    let transaction = h
+        .create_execute(CreateExecute::Deposit, accs, data, sender, count)
+        .await
+        .unwrap();
+    let stored: Transaction = h.account(transaction).await.unwrap();
+    assert_eq!(stored.program_id, zebec_program());
+    assert!(stored.did_execute);
+}
+
+#[tokio::test]
+async fn execute_rejects_unregistered_target_program() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = Pubkey::new_unique();
+    let count = h
+        .store(sender_withdraw_payload(500, sender, mint), sender)
+        .await;
+
+    let accs = zebec_accounts(12, &[(2, pda_sender), (7, mint)], Some(pda_sender));
+    let transaction = h
+        .create(
+            Create::SenderWithdraw,
+            accs.clone(),
+            zebec_data(&TokenAmount { amount: 500 }),
+            sender,
+            count,
+        )
+        .await
+        .unwrap();
+
+    // Version 0 now points somewhere else; the transaction built against the
+    // old entry may no longer run.
+    let remove = h.remove_target_program_ix(0);
+    let register = h.register_target_program_ix(0, Pubkey::new_unique());
+    h.send(vec![remove], &[]).await.unwrap();
+    h.send(vec![register], &[]).await.unwrap();
+    assert_error(
+        h.execute(transaction, &accs, sender, count).await,
+        MessengerError::UnregisteredTargetProgram,
+    );
+}
+
+#[tokio::test]
+async fn proxy_cannot_target_itself() {
+    let mut h = Harness::new().await;
+    let ix = h.register_target_program_ix(1, solana_project::ID);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::UnregisteredTargetProgram,
+    );
+}
@@ -0,0 +1,445 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, AccountSerialize};
+use solana_project::constants::{CONFIG_SPACE, SOLANA_CHAIN_ID};
+use solana_project::errors::MessengerError;
+use solana_project::state::{
+    AddressFormat, BatchData, ChainLimit, Config, Count, EmitterAccount, MetaNonce, MintLimit,
+    MintRegistry, PayoutProfile, ProcessedVAA, TargetProgram, TokenAmount, Transaction,
+    TransactionBuffer, TransactionData, TransactionStatus,
+};
+use solana_project::versioning::{AccountVersion, Versioned, CURRENT_ACCOUNT_VERSION};
+
+use common::*;
+
+async fn data(h: &mut Harness, key: Pubkey) -> Vec<u8> {
+    h.ctx
+        .banks_client
+        .get_account(key)
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+}
+
+fn serialize<T: AccountSerialize>(value: &T) -> Vec<u8> {
+    let mut data = Vec::new();
+    value.try_serialize(&mut data).unwrap();
+    data
+}
+
+/// Rewrites an account of the proxy as it was laid out before versioning.
+async fn make_legacy<T>(h: &mut Harness, key: Pubkey) -> Vec<u8>
+where
+    T: AccountSerialize + AccountDeserialize + Versioned,
+{
+    let mut value: T = h.account(key).await.unwrap();
+    value.set_account_version(AccountVersion::LEGACY);
+    let legacy = serialize(&value);
+    h.set_account(key, solana_project::ID, legacy.clone());
+    legacy
+}
+
+/// Migrates `value` written at version `from`, which must gain the version
+/// byte and `appended` fields.
+async fn check_migration<T>(h: &mut Harness, mut value: T, from: AccountVersion, appended: &[u8])
+where
+    T: AccountSerialize + AccountDeserialize + Versioned,
+{
+    value.set_account_version(from);
+    let old = serialize(&value);
+    let key = Pubkey::new_unique();
+    h.set_account(key, solana_project::ID, old.clone());
+
+    let ix = h.migrate_account_ix(key);
+    h.send(vec![ix], &[]).await.unwrap();
+    let base = if from == AccountVersion::LEGACY {
+        &old[..]
+    } else {
+        &old[..old.len() - 1]
+    };
+    assert_eq!(
+        data(h, key).await,
+        [base, &[CURRENT_ACCOUNT_VERSION], appended].concat()
+    );
+    let migrated: T = h.account(key).await.unwrap();
+    assert_eq!(migrated.account_version(), AccountVersion::CURRENT);
+
+    h.next_blockhash().await;
+    let ix = h.migrate_account_ix(key);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::AccountAlreadyMigrated,
+    );
+}
+
+#[tokio::test]
+async fn every_account_migrates_from_each_older_version() {
+    let mut h = Harness::new().await;
+    for from in [
+        AccountVersion::LEGACY,
+        AccountVersion(1),
+        AccountVersion(2),
+        AccountVersion(3),
+        AccountVersion(4),
+        AccountVersion(5),
+        AccountVersion(6),
+        AccountVersion(7),
+    ] {
+        check_migration(
+            &mut h,
+            Config {
+                owner: Pubkey::new_unique(),
+                nonce: 5,
+                ..Config::default()
+            },
+            from,
+            &[0],
+        )
+        .await;
+        // Version 2 emitters already carry their address format.
+        if from.0 < 2 {
+            check_migration(
+                &mut h,
+                EmitterAccount {
+                    chain_id: 2,
+                    emitter_addr: [0xaa; 32],
+                    registered_at: 1_650_000_000,
+                    enabled: true,
+                    label: "ethereum".to_string(),
+                    ..EmitterAccount::default()
+                },
+                from,
+                &[0],
+            )
+            .await;
+        }
+        check_migration(
+            &mut h,
+            TargetProgram {
+                version: 3,
+                program_id: Pubkey::new_unique(),
+                ..TargetProgram::default()
+            },
+            from,
+            &[],
+        )
+        .await;
+        check_migration(
+            &mut h,
+            ProcessedVAA {
+                transaction_count: 9,
+                ..ProcessedVAA::default()
+            },
+            from,
+            &[],
+        )
+        .await;
+        // Version 7 transactions already carry their binding.
+        if from.0 < 7 {
+            check_migration(
+                &mut h,
+                Transaction {
+                    program_id: zebec_program(),
+                    accounts: zebec_accounts(3, &[], None),
+                    data: vec![1, 2, 3],
+                    ..Transaction::default()
+                },
+                from,
+                &[0],
+            )
+            .await;
+        }
+        // Version 3 transaction data already carries its bridge back.
+        if from.0 < 3 {
+            check_migration(
+                &mut h,
+                TransactionData {
+                    sender: wallet(1),
+                    amount: 500,
+                    code: 6,
+                    ..TransactionData::default()
+                },
+                from,
+                &[0, 0, 0, 0],
+            )
+            .await;
+        }
+        check_migration(
+            &mut h,
+            TransactionStatus {
+                executed: true,
+                ..TransactionStatus::default()
+            },
+            from,
+            &[],
+        )
+        .await;
+        check_migration(
+            &mut h,
+            TransactionBuffer {
+                uploader: Pubkey::new_unique(),
+                accounts_len: 3,
+                data_len: 3,
+                accounts: zebec_accounts(2, &[], None),
+                ..TransactionBuffer::default()
+            },
+            from,
+            &[],
+        )
+        .await;
+        check_migration(
+            &mut h,
+            BatchData {
+                sender: wallet(1),
+                entries: vec![batch_entry(wallet(2), 500, 100, 200, true, false)],
+                ..BatchData::default()
+            },
+            from,
+            &[],
+        )
+        .await;
+        check_migration(
+            &mut h,
+            PayoutProfile {
+                identity: wallet(2),
+                chain_id: BSC_CHAIN_ID,
+                wallet: Pubkey::new_unique(),
+                vaa_sequence: 4,
+                ..PayoutProfile::default()
+            },
+            from,
+            &[],
+        )
+        .await;
+        check_migration(
+            &mut h,
+            MetaNonce {
+                nonce: 3,
+                ..MetaNonce::default()
+            },
+            from,
+            &[],
+        )
+        .await;
+        check_migration(
+            &mut h,
+            ChainLimit {
+                chain_id: BSC_CHAIN_ID,
+                daily_limit: 1_000,
+                ..ChainLimit::default()
+            },
+            from,
+            &[],
+        )
+        .await;
+        check_migration(
+            &mut h,
+            MintLimit {
+                mint: Pubkey::new_unique(),
+                daily_limit: 1_000,
+                notional_price: 2,
+                large_transfer_threshold: 500,
+                hold_seconds: 60,
+                ..MintLimit::default()
+            },
+            from,
+            &[],
+        )
+        .await;
+        let mint = Pubkey::new_unique();
+        check_migration(
+            &mut h,
+            MintRegistry {
+                mint,
+                decimals: 6,
+                min_amount: 10,
+                max_amount: 1_000,
+                origin_chain: SOLANA_CHAIN_ID,
+                origin_address: mint.to_bytes(),
+                ..MintRegistry::default()
+            },
+            from,
+            &[],
+        )
+        .await;
+        check_migration(
+            &mut h,
+            Count {
+                count: 7,
+                ..Count::default()
+            },
+            from,
+            &[],
+        )
+        .await;
+    }
+}
+
+#[tokio::test]
+async fn migrate_refuses_newer_and_unknown_accounts() {
+    let mut h = Harness::new().await;
+
+    let newer = Pubkey::new_unique();
+    h.set_account(
+        newer,
+        solana_project::ID,
+        serialize(&Count {
+            count: 1,
+            account_version: AccountVersion(CURRENT_ACCOUNT_VERSION + 1),
+        }),
+    );
+    let ix = h.migrate_account_ix(newer);
+    assert_error(
+        h.send(vec![ix], &[]).await,
+        MessengerError::AccountAlreadyMigrated,
+    );
+
+    for unknown in [vec![0u8; 16], vec![0u8; 4]] {
+        let key = Pubkey::new_unique();
+        h.set_account(key, solana_project::ID, unknown);
+        let ix = h.migrate_account_ix(key);
+        assert_error(
+            h.send(vec![ix], &[]).await,
+            MessengerError::UnversionedAccount,
+        );
+    }
+}
+
+#[tokio::test]
+async fn new_accounts_are_created_current() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = Pubkey::new_unique();
+
+    assert_eq!(data(&mut h, config()).await.len(), CONFIG_SPACE);
+    let stored_config: Config = h.account(config()).await.unwrap();
+    assert_eq!(stored_config.account_version, AccountVersion::CURRENT);
+    let emitter: EmitterAccount = h.account(emitter_account(BSC_CHAIN_ID)).await.unwrap();
+    assert_eq!(emitter.account_version, AccountVersion::CURRENT);
+    let target: TargetProgram = h.account(target_program(0)).await.unwrap();
+    assert_eq!(target.account_version, AccountVersion::CURRENT);
+
+    let posted = h.post(deposit_payload(500, sender, mint));
+    let count = h.next_count(sender).await;
+    h.store_msg(&posted, sender, count).await.unwrap();
+    let processed: ProcessedVAA = h
+        .account(processed_vaa(
+            &EMITTER_ADDRESS,
+            BSC_CHAIN_ID,
+            posted.message.sequence,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(processed.account_version, AccountVersion::CURRENT);
+    let stored: TransactionData = h.account(data_store(&sender, count)).await.unwrap();
+    assert_eq!(stored.account_version, AccountVersion::CURRENT);
+    let status: TransactionStatus = h.account(txn_status(&sender, count)).await.unwrap();
+    assert_eq!(status.account_version, AccountVersion::CURRENT);
+    let txn_count: Count = h.account(txn_count(&sender)).await.unwrap();
+    assert_eq!(txn_count.account_version, AccountVersion::CURRENT);
+
+    let accs = zebec_accounts(10, &[(1, pda_sender), (6, mint)], Some(pda_sender));
+    let transaction = h
+        .create_execute(
+            CreateExecute::Deposit,
+            accs,
+            zebec_data(&TokenAmount { amount: 500 }),
+            sender,
+            count,
+        )
+        .await
+        .unwrap();
+    let transaction: Transaction = h.account(transaction).await.unwrap();
+    assert_eq!(transaction.account_version, AccountVersion::CURRENT);
+}
+
+#[tokio::test]
+async fn handlers_accept_legacy_layouts() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let pda_sender = user_pda(&sender, BSC_CHAIN_ID);
+    let mint = Pubkey::new_unique();
+
+    let count = h
+        .store(sender_withdraw_payload(20, sender, mint), sender)
+        .await;
+    let stored = make_legacy::<TransactionData>(&mut h, data_store(&sender, count)).await;
+    make_legacy::<TransactionStatus>(&mut h, txn_status(&sender, count)).await;
+
+    let accs = zebec_accounts(12, &[(2, pda_sender), (7, mint)], Some(pda_sender));
+    let transaction = h
+        .create(
+            Create::SenderWithdraw,
+            accs.clone(),
+            zebec_data(&TokenAmount { amount: 20 }),
+            sender,
+            count,
+        )
+        .await
+        .unwrap();
+    h.execute(transaction, &accs, sender, count).await.unwrap();
+
+    // Handlers write legacy accounts back in their own layout.
+    assert_eq!(data(&mut h, data_store(&sender, count)).await, stored);
+    let status: TransactionStatus = h.account(txn_status(&sender, count)).await.unwrap();
+    assert!(status.executed);
+    assert_eq!(status.account_version, AccountVersion::LEGACY);
+    let transaction: Transaction = h.account(transaction).await.unwrap();
+    assert!(transaction.did_execute);
+}
+
+#[tokio::test]
+async fn legacy_count_is_migrated_before_store() {
+    let mut h = Harness::new().await;
+    let sender = wallet(1);
+    let mint = Pubkey::new_unique();
+
+    h.store(deposit_payload(1, sender, mint), sender).await;
+    make_legacy::<Count>(&mut h, txn_count(&sender)).await;
+
+    // `init_if_needed` refuses a count allocated for the legacy layout.
+    let posted = h.post(deposit_payload(2, sender, mint));
+    let count = h.next_count(sender).await;
+    assert_eq!(count, 2);
+    assert!(h.store_msg(&posted, sender, count).await.is_err());
+
+    let ix = h.migrate_account_ix(txn_count(&sender));
+    h.send(vec![ix], &[]).await.unwrap();
+    h.next_blockhash().await;
+    h.store_msg(&posted, sender, count).await.unwrap();
+    let txn_count: Count = h.account(txn_count(&sender)).await.unwrap();
+    assert_eq!(txn_count.count, 2);
+    assert_eq!(txn_count.account_version, AccountVersion::CURRENT);
+}
+
+#[tokio::test]
+async fn older_emitters_are_read_as_evm() {
+    let mut h = Harness::new().await;
+    for from in [AccountVersion::LEGACY, AccountVersion(1)] {
+        let mut emitter: EmitterAccount = h.account(emitter_account(BSC_CHAIN_ID)).await.unwrap();
+        emitter.account_version = from;
+        emitter.address_format = AddressFormat::Unspecified;
+        let old = serialize(&emitter);
+        h.set_account(
+            emitter_account(BSC_CHAIN_ID),
+            solana_project::ID,
+            old.clone(),
+        );
+
+        let sender = wallet(from.0 + 1);
+        h.store(deposit_payload(1, sender, Pubkey::new_unique()), sender)
+            .await;
+        let native = [0xa1; 32];
+        let posted = h.post(deposit_payload(1, native, Pubkey::new_unique()));
+        assert_error(
+            h.store_msg(&posted, native, 1).await,
+            MessengerError::InvalidSenderAddress,
+        );
+        assert_eq!(data(&mut h, emitter_account(BSC_CHAIN_ID)).await, old);
+    }
+}
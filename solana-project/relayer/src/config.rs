@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::path::Path;
+use std::str::FromStr;
+
+use solana_project::constants::{CORE_BRIDGE_ADDRESS, TOKEN_BRIDGE_ADDRESS, ZEBEC_CONTRACT};
+
+use crate::error::RelayerError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SourceConfig {
+    /// A directory of VAA files (raw, hex or base64), one VAA per file.
+    Directory { path: String },
+    /// A TCP stream of newline-delimited hex or base64 VAAs, as emitted by a
+    /// spy-compatible forwarder.
+    Spy { addr: String },
+    /// An HTTP endpoint returning a JSON array of hex or base64 VAAs.
+    Http { url: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayerConfig {
+    pub rpc_url: String,
+    pub keypair_path: String,
+    #[serde(default = "default_program_id")]
+    pub program_id: String,
+    #[serde(default = "default_core_bridge")]
+    pub core_bridge: String,
+    #[serde(default = "default_token_bridge")]
+    pub token_bridge: String,
+    #[serde(default = "default_zebec_program")]
+    pub zebec_program: String,
+    /// Fee owner of the Zebec fee vault used for streams.
+    pub fee_owner: String,
+    pub state_path: String,
+    pub sources: Vec<SourceConfig>,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// Relayer fee passed to direct transfers, in the mint's base units.
+    /// Lowered to the maximum a payload fixes; payloads that fix none pay
+    /// nothing.
+    #[serde(default)]
+    pub direct_transfer_fee: u64,
+    /// Serve Prometheus metrics on this address when set.
+    #[serde(default)]
+    pub metrics_addr: Option<String>,
+    /// Address lookup table to relay messages in one version 0 transaction
+    /// with. Without one every message is staged.
+    #[serde(default)]
+    pub lookup_table: Option<String>,
+}
+
+fn default_program_id() -> String {
+    solana_project::ID.to_string()
+}
+
+fn default_core_bridge() -> String {
+    CORE_BRIDGE_ADDRESS.to_string()
+}
+
+fn default_token_bridge() -> String {
+    TOKEN_BRIDGE_ADDRESS.to_string()
+}
+
+fn default_zebec_program() -> String {
+    ZEBEC_CONTRACT.to_string()
+}
+
+fn default_max_retries() -> u32 {
+    5
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    500
+}
+
+fn default_poll_interval_ms() -> u64 {
+    2000
+}
+
+fn parse_pubkey(field: &str, value: &str) -> Result<Pubkey, RelayerError> {
+    Pubkey::from_str(value)
+        .map_err(|_| RelayerError::Config(format!("{} is not a valid pubkey: {}", field, value)))
+}
+
+impl RelayerConfig {
+    pub fn load(path: &Path) -> Result<Self, RelayerError> {
+        let raw = std::fs::read_to_string(path)?;
+        let config: RelayerConfig = serde_json::from_str(&raw)?;
+        config.program_id()?;
+        config.core_bridge()?;
+        config.token_bridge()?;
+        config.zebec_program()?;
+        config.fee_owner()?;
+        config.lookup_table()?;
+        Ok(config)
+    }
+
+    pub fn program_id(&self) -> Result<Pubkey, RelayerError> {
+        parse_pubkey("program_id", &self.program_id)
+    }
+
+    pub fn core_bridge(&self) -> Result<Pubkey, RelayerError> {
+        parse_pubkey("core_bridge", &self.core_bridge)
+    }
+
+    pub fn token_bridge(&self) -> Result<Pubkey, RelayerError> {
+        parse_pubkey("token_bridge", &self.token_bridge)
+    }
+
+    pub fn zebec_program(&self) -> Result<Pubkey, RelayerError> {
+        parse_pubkey("zebec_program", &self.zebec_program)
+    }
+
+    pub fn fee_owner(&self) -> Result<Pubkey, RelayerError> {
+        parse_pubkey("fee_owner", &self.fee_owner)
+    }
+
+    pub fn lookup_table(&self) -> Result<Option<Pubkey>, RelayerError> {
+        self.lookup_table
+            .as_deref()
+            .map(|value| parse_pubkey("lookup_table", value))
+            .transpose()
+    }
+}
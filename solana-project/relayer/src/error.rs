@@ -0,0 +1,70 @@
+use solana_client::client_error::ClientError as RpcError;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+use thiserror::Error;
+use zebec_wormhole_client::ClientError;
+
+#[derive(Debug, Error)]
+pub enum RelayerError {
+    #[error(transparent)]
+    Message(#[from] ClientError),
+
+    #[error("rpc error: {0}")]
+    Rpc(Box<RpcError>),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("source error: {0}")]
+    Source(String),
+
+    #[error("state file error: {0}")]
+    Store(#[from] serde_json::Error),
+
+    #[error("config error: {0}")]
+    Config(String),
+
+    #[error("missing account {0}")]
+    MissingAccount(String),
+
+    #[error("could not decode {0}")]
+    Decode(String),
+
+    #[error("message targets {0}, not the configured Zebec program")]
+    TargetProgram(String),
+
+    #[error("transfer held by the rate limits until {0}")]
+    Held(i64),
+}
+
+impl From<RpcError> for RelayerError {
+    fn from(e: RpcError) -> Self {
+        RelayerError::Rpc(Box::new(e))
+    }
+}
+
+impl RelayerError {
+    /// Transport hiccups are worth retrying; a transaction the program
+    /// rejected or a malformed message is not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            RelayerError::Rpc(e) => e.get_transaction_error().is_none(),
+            RelayerError::Io(_) | RelayerError::Source(_) | RelayerError::MissingAccount(_) => true,
+            _ => false,
+        }
+    }
+
+    /// The error a program returned when it refused a transaction: the
+    /// custom error code, or `u32::MAX` for any other instruction error.
+    /// `None` when no instruction failed.
+    pub fn program_error_code(&self) -> Option<u32> {
+        match self {
+            RelayerError::Rpc(e) => match e.get_transaction_error()? {
+                TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(code),
+                TransactionError::InstructionError(..) => Some(u32::MAX),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
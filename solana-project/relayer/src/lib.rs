@@ -0,0 +1,17 @@
+//! Relayer daemon for the Zebec Wormhole proxy.
+//!
+//! VAAs come in from a [`source::VaaSource`], get posted to the core bridge if
+//! needed, and are then walked through `store_msg`, the matching
+//! `create_transaction_*`/`transaction_*` call and `execute_transaction`.
+//! Progress is persisted after every step so a restart resumes where it left off.
+
+pub mod config;
+pub mod error;
+pub mod metrics;
+pub mod relayer;
+pub mod source;
+pub mod store;
+
+pub use config::RelayerConfig;
+pub use error::RelayerError;
+pub use relayer::Relayer;
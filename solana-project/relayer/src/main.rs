@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+use std::process::exit;
+use std::sync::{Arc, Mutex};
+
+use clap::Parser;
+use solana_sdk::signature::read_keypair_file;
+
+use zebec_relayer::config::RelayerConfig;
+use zebec_relayer::error::RelayerError;
+use zebec_relayer::metrics;
+use zebec_relayer::relayer::Relayer;
+use zebec_relayer::source::{self, VaaSource};
+use zebec_relayer::store::ProgressStore;
+
+/// Relays Zebec VAAs from other Wormhole chains to the Solana proxy program.
+#[derive(Parser)]
+#[clap(name = "zebec-relayer")]
+struct Opts {
+    /// Path to the JSON config file.
+    #[clap(short, long, default_value = "relayer.json")]
+    config: PathBuf,
+    /// Drain every source once and exit instead of polling forever.
+    #[clap(long)]
+    once: bool,
+}
+
+fn run(opts: Opts) -> Result<(), RelayerError> {
+    let config = RelayerConfig::load(&opts.config)?;
+    let payer = read_keypair_file(&config.keypair_path)
+        .map_err(|e| RelayerError::Config(format!("keypair {}: {}", config.keypair_path, e)))?;
+    let store = Arc::new(Mutex::new(ProgressStore::open(config.state_path.as_ref())?));
+
+    if let Some(addr) = config.metrics_addr.as_deref() {
+        metrics::serve(addr, store.clone())?;
+        log::info!("serving metrics on {}", addr);
+    }
+
+    let mut sources: Vec<Box<dyn VaaSource>> =
+        config.sources.iter().map(source::from_config).collect();
+    Relayer::new(config, payer, store)?.run(&mut sources, opts.once)
+}
+
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    if let Err(e) = run(Opts::parse()) {
+        log::error!("{}", e);
+        exit(1);
+    }
+}
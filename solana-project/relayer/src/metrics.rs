@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::store::ProgressStore;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Metrics {
+    pub vaas_received: u64,
+    pub vaas_completed: u64,
+    pub vaas_failed: u64,
+    pub vaas_skipped: u64,
+    pub transactions_sent: u64,
+    pub retries: u64,
+    /// Completed messages per code.
+    pub completed_by_code: std::collections::BTreeMap<u8, u64>,
+}
+
+impl Metrics {
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            ("zebec_relayer_vaas_received_total", self.vaas_received),
+            ("zebec_relayer_vaas_completed_total", self.vaas_completed),
+            ("zebec_relayer_vaas_failed_total", self.vaas_failed),
+            ("zebec_relayer_vaas_skipped_total", self.vaas_skipped),
+            (
+                "zebec_relayer_transactions_sent_total",
+                self.transactions_sent,
+            ),
+            ("zebec_relayer_retries_total", self.retries),
+        ];
+        for (name, value) in counters.iter() {
+            writeln!(out, "# TYPE {} counter", name).unwrap();
+            writeln!(out, "{} {}", name, value).unwrap();
+        }
+        writeln!(out, "# TYPE zebec_relayer_completed_by_code_total counter").unwrap();
+        for (code, value) in self.completed_by_code.iter() {
+            writeln!(
+                out,
+                "zebec_relayer_completed_by_code_total{{code=\"{}\"}} {}",
+                code, value
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+/// Serves the current counters as Prometheus text on every connection.
+pub fn serve(addr: &str, store: Arc<Mutex<ProgressStore>>) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut stream = stream;
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let body = store.lock().unwrap().metrics().render_prometheus();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+    Ok(())
+}
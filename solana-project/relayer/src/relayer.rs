@@ -0,0 +1,1358 @@
+use std::slice;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anchor_lang::{AccountDeserialize, AnchorDeserialize};
+use serde_json::json;
+use solana_client::client_error::ClientError as RpcError;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::Message;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction as SolanaTransaction, VersionedTransaction};
+
+use solana_project::state::{
+    BatchData, BatchEntry, BatchEntryStatus, BridgeBack, Clearance, Count, Payout, ProcessedVAA,
+    TargetProgram, TransactionAccount, TransactionBuffer, TransactionData, TransactionStatus,
+};
+use solana_project::versioning::AccountVersion;
+use zebec_wormhole_client::bridge::{self, GuardianSetData};
+use zebec_wormhole_client::instructions::{self, CreateExecuteKind, CreateKind, DirectTransfer};
+use zebec_wormhole_client::versioned::{self, LookupTable};
+use zebec_wormhole_client::zebec::{self, ZebecAccounts, STREAM_TOKEN_SIZE};
+use zebec_wormhole_client::{message, pda, SignedVaa, ZebecMessage};
+
+use crate::config::RelayerConfig;
+use crate::error::RelayerError;
+use crate::metrics::Metrics;
+use crate::source::VaaSource;
+use crate::store::{ProgressRecord, ProgressStore, Stage};
+
+/// Compute units requested for a `relay`, which runs three instructions'
+/// worth of work in one.
+const RELAY_COMPUTE_UNITS: u32 = 1_000_000;
+
+/// What has to happen on Solana after `store_msg` for a given message.
+enum Plan {
+    /// `transaction_*`: build and execute in one instruction.
+    CreateExecute(CreateExecuteKind, Vec<TransactionAccount>, Vec<u8>),
+    /// `create_transaction_*` now, `execute_transaction` later.
+    Create(CreateKind, Vec<TransactionAccount>, Vec<u8>),
+    DirectTransfer,
+}
+
+fn decode_account<T: AccountDeserialize>(data: &[u8]) -> Result<T, RelayerError> {
+    T::try_deserialize(&mut &data[..]).map_err(|e| RelayerError::Decode(e.to_string()))
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey, RelayerError> {
+    Pubkey::from_str(value).map_err(|_| RelayerError::Decode(format!("pubkey {}", value)))
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+/// Where a message goes once its transaction has executed: withdrawals that
+/// are bridged on or paid out still have that to do.
+fn executed_stage(message: &ZebecMessage) -> Stage {
+    match message {
+        ZebecMessage::WithdrawStreamBridge { .. } | ZebecMessage::WithdrawStreamPayout { .. } => {
+            Stage::Executed
+        }
+        _ => Stage::Done,
+    }
+}
+
+pub struct Relayer {
+    rpc: RpcClient,
+    payer: Keypair,
+    config: RelayerConfig,
+    program_id: Pubkey,
+    core_bridge: Pubkey,
+    token_bridge: Pubkey,
+    zebec: ZebecAccounts,
+    store: Arc<Mutex<ProgressStore>>,
+}
+
+impl Relayer {
+    pub fn new(
+        config: RelayerConfig,
+        payer: Keypair,
+        store: Arc<Mutex<ProgressStore>>,
+    ) -> Result<Self, RelayerError> {
+        Ok(Relayer {
+            rpc: RpcClient::new_with_commitment(
+                config.rpc_url.clone(),
+                CommitmentConfig::confirmed(),
+            ),
+            payer,
+            program_id: config.program_id()?,
+            core_bridge: config.core_bridge()?,
+            token_bridge: config.token_bridge()?,
+            zebec: ZebecAccounts::new(config.zebec_program()?, config.fee_owner()?),
+            config,
+            store,
+        })
+    }
+
+    /// Resumes unfinished VAAs from the progress store, then polls every
+    /// source until stopped. With `once` set, each source is drained a single
+    /// time, which is what the end-to-end tests use.
+    pub fn run(&self, sources: &mut [Box<dyn VaaSource>], once: bool) -> Result<(), RelayerError> {
+        self.resume_pending();
+        loop {
+            for source in sources.iter_mut() {
+                match source.poll() {
+                    Ok(vaas) => {
+                        for raw in vaas {
+                            if let Err(e) = self.process(&raw) {
+                                log::error!("{}: {}", source.name(), e);
+                            }
+                        }
+                    }
+                    Err(e) => log::warn!("{}: {}", source.name(), e),
+                }
+            }
+            self.resume_released();
+            if once {
+                return Ok(());
+            }
+            sleep(Duration::from_millis(self.config.poll_interval_ms));
+        }
+    }
+
+    pub fn resume_pending(&self) {
+        let pending = self.store.lock().unwrap().pending();
+        for (key, record) in pending {
+            let raw = match base64::decode(&record.vaa) {
+                Ok(raw) => raw,
+                Err(e) => {
+                    log::error!("{}: stored VAA is not base64: {}", key, e);
+                    continue;
+                }
+            };
+            log::info!("resuming {} at {:?}", key, record.stage);
+            if let Err(e) = self.process(&raw) {
+                log::error!("{}: {}", key, e);
+            }
+        }
+    }
+
+    /// Picks up VAAs whose transfer the rate limits held, once released.
+    fn resume_released(&self) {
+        let released = self.store.lock().unwrap().released(unix_now());
+        for (key, record) in released {
+            let raw = match base64::decode(&record.vaa) {
+                Ok(raw) => raw,
+                Err(e) => {
+                    log::error!("{}: stored VAA is not base64: {}", key, e);
+                    continue;
+                }
+            };
+            log::info!("{} released at {:?}", key, record.stage);
+            if let Err(e) = self.process(&raw) {
+                log::error!("{}: {}", key, e);
+            }
+        }
+    }
+
+    /// Runs a VAA through as many stages as it can and returns the stage it
+    /// ended at. Progress is saved after every stage.
+    pub fn process(&self, raw: &[u8]) -> Result<Stage, RelayerError> {
+        let vaa = SignedVaa::parse(raw)?;
+        let key = hex::encode(vaa.hash());
+
+        let existing = self.store.lock().unwrap().get(&key).cloned();
+        let mut record = match existing {
+            Some(record) if record.stage == Stage::Done => {
+                self.with_metrics(|m| m.vaas_skipped += 1);
+                return Ok(Stage::Done);
+            }
+            Some(record) => record,
+            None => {
+                self.with_metrics(|m| m.vaas_received += 1);
+                ProgressRecord {
+                    vaa: base64::encode(raw),
+                    code: vaa.payload.first().copied().unwrap_or_default(),
+                    emitter_chain: vaa.emitter_chain,
+                    sequence: vaa.sequence,
+                    ..Default::default()
+                }
+            }
+        };
+
+        let message = match ZebecMessage::decode(&vaa.payload) {
+            Ok(message) => message,
+            Err(e) => {
+                record.last_error = Some(e.to_string());
+                self.save(&key, &record)?;
+                self.with_metrics(|m| m.vaas_failed += 1);
+                return Err(e.into());
+            }
+        };
+
+        while record.stage != Stage::Done {
+            if let Err(e) = self.advance(&key, &vaa, &message, &mut record) {
+                if let RelayerError::Held(until) = e {
+                    record.held_until = Some(until);
+                }
+                record.attempts += 1;
+                record.last_error = Some(e.to_string());
+                self.save(&key, &record)?;
+                self.with_metrics(|m| m.vaas_failed += 1);
+                return Err(e);
+            }
+            record.last_error = None;
+            record.held_until = None;
+            self.save(&key, &record)?;
+            log::info!("{} (code {}) -> {:?}", key, record.code, record.stage);
+        }
+
+        let code = record.code;
+        self.with_metrics(|m| {
+            m.vaas_completed += 1;
+            *m.completed_by_code.entry(code).or_default() += 1;
+        });
+        self.save(&key, &record)?;
+        Ok(Stage::Done)
+    }
+
+    fn advance(
+        &self,
+        key: &str,
+        vaa: &SignedVaa,
+        message: &ZebecMessage,
+        record: &mut ProgressRecord,
+    ) -> Result<(), RelayerError> {
+        match record.stage {
+            Stage::Received => {
+                self.retry("post_vaa", || self.post_vaa(vaa))?;
+                record.stage = Stage::Posted;
+            }
+            Stage::Posted => match message {
+                ZebecMessage::InitializePda { account } => {
+                    self.retry("initialize_pda", || self.initialize_pda(vaa, *account))?;
+                    record.stage = Stage::Done;
+                }
+                ZebecMessage::InitializeTokenAccount {
+                    account,
+                    token_mint,
+                } => {
+                    self.retry("initialize_pda_token_account", || {
+                        self.initialize_token_account(vaa, *account, token_mint)
+                    })?;
+                    record.stage = Stage::Done;
+                }
+                ZebecMessage::SetPayoutWallet { account, .. } => {
+                    self.retry("set_payout_wallet", || {
+                        self.set_payout_wallet(vaa, *account)
+                    })?;
+                    record.stage = Stage::Done;
+                }
+                _ => match self.relay(key, vaa, message, record) {
+                    Ok(true) => {}
+                    relayed => {
+                        // Whatever a failed relay did is picked up as if an
+                        // earlier run had staged it.
+                        if let Err(e) = relayed {
+                            log::warn!("{}: relay failed, staging instead: {}", key, e);
+                        }
+                        let count = self.retry("store_msg", || self.store_msg(vaa, message))?;
+                        record.current_count = Some(count);
+                        record.stage = Stage::Stored;
+                    }
+                },
+            },
+            Stage::Stored => match message {
+                ZebecMessage::BatchStream { .. } => self.relay_batch(vaa, message, record)?,
+                _ => self.build(key, vaa, message, record)?,
+            },
+            Stage::Created => {
+                let count = self.current_count(record)?;
+                let signer = message.signer();
+                if !self.executed(&signer, count)? {
+                    let stream_account = record
+                        .data_account
+                        .as_deref()
+                        .map(parse_pubkey)
+                        .transpose()?;
+                    if let Plan::Create(_, accs, _) =
+                        self.plan(message, vaa.emitter_chain, stream_account)?
+                    {
+                        self.check_held(&signer, count)?;
+                        let target_version = self.target_version(vaa)?;
+                        let ix = instructions::execute_transaction(
+                            &self.program_id,
+                            &self.zebec.program_id,
+                            &accs,
+                            signer,
+                            vaa.emitter_chain,
+                            count,
+                            target_version,
+                        );
+                        self.retry("execute_transaction", || {
+                            self.send(slice::from_ref(&ix), &[])
+                        })?;
+                    }
+                }
+                record.stage = executed_stage(message);
+            }
+            Stage::Executed => {
+                let count = self.current_count(record)?;
+                match message {
+                    ZebecMessage::WithdrawStreamPayout { .. } => self
+                        .retry("withdraw_payout", || {
+                            self.withdraw_payout(message, vaa.emitter_chain, count)
+                        })?,
+                    _ => self.retry("withdraw_bridge", || {
+                        self.withdraw_bridge(message, vaa.emitter_chain, count)
+                    })?,
+                }
+                record.stage = Stage::Done;
+            }
+            Stage::Done => {}
+        }
+        Ok(())
+    }
+
+    /// Stores, creates and executes the message in one version 0 transaction
+    /// through `relay`, which loads what it can from the configured lookup
+    /// table. Returns whether it did: without a table, for batches and direct
+    /// transfers, or when even the table does not make it fit, the message
+    /// is staged instead.
+    fn relay(
+        &self,
+        key: &str,
+        vaa: &SignedVaa,
+        message: &ZebecMessage,
+        record: &mut ProgressRecord,
+    ) -> Result<bool, RelayerError> {
+        let table_key = match self.config.lookup_table()? {
+            Some(table_key) => table_key,
+            None => return Ok(false),
+        };
+        if matches!(
+            message,
+            ZebecMessage::BatchStream { .. } | ZebecMessage::DirectTransfer { .. }
+        ) || self.processed(vaa)?.is_some()
+        {
+            return Ok(false);
+        }
+        let table = match self.account(&table_key)? {
+            Some(account) => LookupTable::decode(table_key, &account.data)?,
+            None => {
+                return Err(RelayerError::MissingAccount(format!(
+                    "lookup table {}",
+                    table_key
+                )))
+            }
+        };
+
+        let payer = self.payer.pubkey();
+        let signer = message.signer();
+        let chain = vaa.emitter_chain;
+        let mint = message.token_mint().unwrap_or_default();
+        let target_version = self.target_version(vaa)?;
+        let (count, migrate) = self.next_count(&signer)?;
+        let stream_account = Keypair::new();
+
+        let mut ixs = vec![ComputeBudgetInstruction::request_units(
+            RELAY_COMPUTE_UNITS,
+            0,
+        )];
+        ixs.extend(migrate);
+        let mut signers: Vec<&dyn Signer> = vec![&self.payer];
+        let mut steps = vec![instructions::store_msg(
+            &self.program_id,
+            &self.core_bridge,
+            &payer,
+            vaa,
+            signer,
+            &mint,
+            count,
+        )];
+        match self.plan(message, chain, Some(stream_account.pubkey()))? {
+            Plan::CreateExecute(kind, accs, data) => {
+                steps.push(instructions::create_and_execute_transaction(
+                    kind,
+                    &self.program_id,
+                    &self.zebec.program_id,
+                    &payer,
+                    &mint,
+                    accs,
+                    data,
+                    chain,
+                    signer,
+                    count,
+                    target_version,
+                ));
+            }
+            Plan::Create(kind, accs, data) => {
+                if kind == CreateKind::Stream {
+                    ixs.push(self.allocate(
+                        &stream_account,
+                        STREAM_TOKEN_SIZE,
+                        &self.zebec.program_id,
+                    )?);
+                    signers.push(&stream_account);
+                }
+                steps.push(instructions::create_transaction(
+                    kind,
+                    &self.program_id,
+                    &payer,
+                    &mint,
+                    accs.clone(),
+                    data,
+                    signer,
+                    count,
+                    target_version,
+                ));
+                steps.push(instructions::execute_transaction(
+                    &self.program_id,
+                    &self.zebec.program_id,
+                    &accs,
+                    signer,
+                    chain,
+                    count,
+                    target_version,
+                ));
+            }
+            Plan::DirectTransfer => return Ok(false),
+        }
+        ixs.push(instructions::relay(&self.program_id, &payer, &steps));
+
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let compiled = versioned::compile(&ixs, &payer, &[table], blockhash);
+        if !versioned::fits(&compiled) {
+            log::info!("{}: too large to relay, staging", key);
+            return Ok(false);
+        }
+        if signers.len() > 1 {
+            record.data_account = Some(stream_account.pubkey().to_string());
+            self.save(key, record)?;
+        }
+        let tx = versioned::sign(compiled, &signers)?;
+        self.send_versioned(&tx)?;
+        record.current_count = Some(count);
+        record.stage = executed_stage(message);
+        Ok(true)
+    }
+
+    /// The step after `store_msg`: build the Zebec transaction, and execute it
+    /// too when the proxy does both in one instruction.
+    fn build(
+        &self,
+        key: &str,
+        vaa: &SignedVaa,
+        message: &ZebecMessage,
+        record: &mut ProgressRecord,
+    ) -> Result<(), RelayerError> {
+        let count = self.current_count(record)?;
+        let signer = message.signer();
+        let chain = vaa.emitter_chain;
+
+        // Sent before a crash; only the progress write was lost.
+        if self
+            .account(&pda::transaction(&self.program_id, &signer, count))?
+            .is_some()
+        {
+            record.stage = Stage::Created;
+            return Ok(());
+        }
+        if self.executed(&signer, count)? {
+            record.stage = executed_stage(message);
+            return Ok(());
+        }
+
+        // Every message that gets here names its mint.
+        let mint = message.token_mint().unwrap_or_default();
+        let stream_account = Keypair::new();
+        let plan = self.plan(message, chain, Some(stream_account.pubkey()))?;
+        let target_version = match plan {
+            Plan::DirectTransfer => 0,
+            _ => self.target_version(vaa)?,
+        };
+        match plan {
+            Plan::CreateExecute(kind, accs, data) => {
+                let mut ix = instructions::create_and_execute_transaction(
+                    kind,
+                    &self.program_id,
+                    &self.zebec.program_id,
+                    &self.payer.pubkey(),
+                    &mint,
+                    accs.clone(),
+                    data.clone(),
+                    chain,
+                    signer,
+                    count,
+                    target_version,
+                );
+                if !self.fits(&[ix.clone()], 0) {
+                    self.upload(&accs, &data, signer, count)?;
+                    ix = instructions::create_and_execute_staged_transaction(
+                        kind,
+                        &self.program_id,
+                        &self.zebec.program_id,
+                        &self.payer.pubkey(),
+                        &mint,
+                        &accs,
+                        chain,
+                        signer,
+                        count,
+                        target_version,
+                    );
+                }
+                self.retry("create_execute_transaction", || {
+                    self.send(slice::from_ref(&ix), &[])
+                })?;
+                record.stage = Stage::Done;
+            }
+            Plan::Create(kind, accs, data) => {
+                let mut ixs = vec![];
+                let mut signers = vec![];
+                if kind == CreateKind::Stream {
+                    ixs.push(self.allocate(
+                        &stream_account,
+                        STREAM_TOKEN_SIZE,
+                        &self.zebec.program_id,
+                    )?);
+                    signers.push(&stream_account);
+                    record.data_account = Some(stream_account.pubkey().to_string());
+                    self.save(key, record)?;
+                }
+                ixs.push(instructions::create_transaction(
+                    kind,
+                    &self.program_id,
+                    &self.payer.pubkey(),
+                    &mint,
+                    accs.clone(),
+                    data.clone(),
+                    signer,
+                    count,
+                    target_version,
+                ));
+                if !self.fits(&ixs, signers.len()) {
+                    self.upload(&accs, &data, signer, count)?;
+                    ixs.pop();
+                    ixs.push(instructions::create_staged_transaction(
+                        kind,
+                        &self.program_id,
+                        &self.payer.pubkey(),
+                        &mint,
+                        &accs,
+                        signer,
+                        count,
+                        target_version,
+                    ));
+                }
+
+                self.retry("create_transaction", || self.send(&ixs, &signers))?;
+                record.stage = Stage::Created;
+            }
+            Plan::DirectTransfer => {
+                self.retry("direct_transfer", || {
+                    self.direct_transfer(message, chain, count)
+                })?;
+                record.stage = Stage::Done;
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates and executes every entry of a stored batch that is not done
+    /// yet. The batch account tracks each entry, so a restart picks up where
+    /// the last run stopped. An entry the proxy or Zebec refuses is marked
+    /// failed and the rest go on.
+    fn relay_batch(
+        &self,
+        vaa: &SignedVaa,
+        message: &ZebecMessage,
+        record: &mut ProgressRecord,
+    ) -> Result<(), RelayerError> {
+        let count = self.current_count(record)?;
+        let sender = message.signer();
+        let batch_key = pda::batch_store(&self.program_id, &sender, count);
+        let batch: BatchData = match self.account(&batch_key)? {
+            Some(account) => decode_account(&account.data)?,
+            None => return Err(RelayerError::MissingAccount(format!("batch {}", batch_key))),
+        };
+        let target_version = self.target_version(vaa)?;
+
+        for (index, entry) in batch.entries.iter().enumerate() {
+            let index = index as u8;
+            let result = match entry.status {
+                BatchEntryStatus::Pending => self
+                    .create_batch_entry(&batch, entry, count, index, target_version)
+                    .and_then(|accs| {
+                        self.execute_batch_entry(&batch, &accs, count, index, target_version)
+                    }),
+                BatchEntryStatus::Created => self
+                    .batch_entry_plan(&batch, entry, entry.data_account)
+                    .and_then(|(accs, _)| {
+                        self.execute_batch_entry(&batch, &accs, count, index, target_version)
+                    }),
+                BatchEntryStatus::Executed | BatchEntryStatus::Failed => continue,
+            };
+            let error = match result {
+                Ok(()) => continue,
+                Err(e) => e,
+            };
+            let error_code = match error.program_error_code() {
+                Some(code) => code,
+                None => return Err(error),
+            };
+            log::warn!("batch entry {} of {} failed: {}", index, batch_key, error);
+            let ix = instructions::fail_batch_entry(
+                &self.program_id,
+                &self.payer.pubkey(),
+                sender,
+                count,
+                index,
+                error_code,
+            );
+            self.retry("fail_batch_entry", || self.send(slice::from_ref(&ix), &[]))?;
+        }
+        record.stage = Stage::Done;
+        Ok(())
+    }
+
+    fn create_batch_entry(
+        &self,
+        batch: &BatchData,
+        entry: &BatchEntry,
+        count: u64,
+        index: u8,
+        target_version: u8,
+    ) -> Result<Vec<TransactionAccount>, RelayerError> {
+        let stream_account = Keypair::new();
+        let (accs, data) = self.batch_entry_plan(batch, entry, stream_account.pubkey())?;
+        let ixs = vec![
+            self.allocate(&stream_account, STREAM_TOKEN_SIZE, &self.zebec.program_id)?,
+            instructions::create_transaction_batch_stream(
+                &self.program_id,
+                &self.payer.pubkey(),
+                &batch.token_mint,
+                accs.clone(),
+                data,
+                batch.sender,
+                count,
+                index,
+                target_version,
+            ),
+        ];
+        self.retry("create_transaction_batch_stream", || {
+            self.send(&ixs, &[&stream_account])
+        })?;
+        Ok(accs)
+    }
+
+    fn execute_batch_entry(
+        &self,
+        batch: &BatchData,
+        accs: &[TransactionAccount],
+        count: u64,
+        index: u8,
+        target_version: u8,
+    ) -> Result<(), RelayerError> {
+        let ix = instructions::execute_batch_transaction(
+            &self.program_id,
+            &self.zebec.program_id,
+            accs,
+            batch.sender,
+            batch.from_chain_id,
+            count,
+            index,
+            target_version,
+        );
+        self.retry("execute_batch_transaction", || {
+            self.send(slice::from_ref(&ix), &[])
+        })?;
+        Ok(())
+    }
+
+    /// The Zebec stream accounts and data of one batch entry.
+    fn batch_entry_plan(
+        &self,
+        batch: &BatchData,
+        entry: &BatchEntry,
+        stream_account: Pubkey,
+    ) -> Result<(Vec<TransactionAccount>, Vec<u8>), RelayerError> {
+        let user = |who: &[u8; 32]| pda::user_pda(&self.program_id, who, batch.from_chain_id);
+        let accs = self.zebec.stream(
+            &stream_account,
+            &user(&batch.sender),
+            &user(&entry.receiver),
+            &batch.token_mint,
+        );
+        let data = zebec::stream_data(
+            entry.start_time,
+            entry.end_time,
+            entry.amount,
+            entry.can_cancel,
+            entry.can_update,
+        );
+        Ok((accs, data))
+    }
+
+    fn plan(
+        &self,
+        message: &ZebecMessage,
+        chain: u16,
+        stream_account: Option<Pubkey>,
+    ) -> Result<Plan, RelayerError> {
+        let user = |who: &[u8; 32]| pda::user_pda(&self.program_id, who, chain);
+
+        let plan = match message {
+            ZebecMessage::Deposit {
+                amount,
+                depositor,
+                token_mint,
+            } => Plan::CreateExecute(
+                CreateExecuteKind::Deposit,
+                self.zebec.deposit(&user(depositor), token_mint),
+                zebec::deposit_data(*amount),
+            ),
+            ZebecMessage::StreamUpdate {
+                start_time,
+                end_time,
+                amount,
+                sender,
+                receiver,
+                token_mint,
+                data_account,
+            } => Plan::CreateExecute(
+                CreateExecuteKind::StreamUpdate,
+                self.zebec
+                    .stream_update(data_account, &user(sender), &user(receiver), token_mint),
+                zebec::stream_update_data(*start_time, *end_time, *amount),
+            ),
+            ZebecMessage::PauseResume {
+                sender,
+                receiver,
+                data_account,
+                ..
+            } => Plan::CreateExecute(
+                CreateExecuteKind::PauseResume,
+                self.zebec
+                    .pause_resume(data_account, &user(sender), &user(receiver)),
+                zebec::pause_resume_data(),
+            ),
+            ZebecMessage::TokenStream {
+                start_time,
+                end_time,
+                amount,
+                sender,
+                receiver,
+                can_cancel,
+                can_update,
+                token_mint,
+            } => {
+                let stream_account = stream_account.ok_or_else(|| {
+                    RelayerError::MissingAccount("stream data account".to_string())
+                })?;
+                Plan::Create(
+                    CreateKind::Stream,
+                    self.zebec
+                        .stream(&stream_account, &user(sender), &user(receiver), token_mint),
+                    zebec::stream_data(*start_time, *end_time, *amount, *can_cancel, *can_update),
+                )
+            }
+            ZebecMessage::WithdrawStream {
+                withdrawer,
+                token_mint,
+                sender,
+                data_account,
+            } => Plan::Create(
+                CreateKind::ReceiverWithdraw,
+                self.zebec.receiver_withdraw(
+                    data_account,
+                    &user(sender),
+                    &user(withdrawer),
+                    token_mint,
+                ),
+                zebec::receiver_withdraw_data(),
+            ),
+            // Zebec pays into the withdrawer's token account, which has to
+            // exist already; the `Executed` stage bridges it on from there.
+            ZebecMessage::WithdrawStreamBridge {
+                withdrawer,
+                token_mint,
+                sender,
+                data_account,
+                ..
+            } => Plan::Create(
+                CreateKind::WithdrawBridge,
+                self.zebec.receiver_withdraw(
+                    data_account,
+                    &user(sender),
+                    &user(withdrawer),
+                    token_mint,
+                ),
+                zebec::receiver_withdraw_data(),
+            ),
+            // Likewise, but paid out to the withdrawer's payout wallet, which
+            // has to be linked before the message is built.
+            ZebecMessage::WithdrawStreamPayout {
+                withdrawer,
+                token_mint,
+                sender,
+                data_account,
+            } => Plan::Create(
+                CreateKind::WithdrawPayout(chain),
+                self.zebec.receiver_withdraw(
+                    data_account,
+                    &user(sender),
+                    &user(withdrawer),
+                    token_mint,
+                ),
+                zebec::receiver_withdraw_data(),
+            ),
+            ZebecMessage::Cancel {
+                sender,
+                token_mint,
+                receiver,
+                data_account,
+            } => Plan::Create(
+                CreateKind::Cancel,
+                self.zebec
+                    .cancel(data_account, &user(sender), &user(receiver), token_mint),
+                zebec::cancel_data(),
+            ),
+            ZebecMessage::SenderWithdraw {
+                amount,
+                withdrawer,
+                token_mint,
+            } => Plan::Create(
+                CreateKind::SenderWithdraw,
+                self.zebec.sender_withdraw(&user(withdrawer), token_mint),
+                zebec::sender_withdraw_data(*amount),
+            ),
+            ZebecMessage::InstantTransfer {
+                amount,
+                sender,
+                token_mint,
+                receiver,
+            } => Plan::Create(
+                CreateKind::InstantTransfer,
+                self.zebec
+                    .instant_transfer(&user(sender), &user(receiver), token_mint),
+                zebec::instant_transfer_data(*amount),
+            ),
+            ZebecMessage::DirectTransfer { .. } => Plan::DirectTransfer,
+            ZebecMessage::InitializePda { .. }
+            | ZebecMessage::InitializeTokenAccount { .. }
+            | ZebecMessage::SetPayoutWallet { .. }
+            | ZebecMessage::BatchStream { .. } => {
+                return Err(RelayerError::Decode(format!(
+                    "code {} has no transaction to build",
+                    message.code()
+                )))
+            }
+        };
+        Ok(plan)
+    }
+
+    fn post_vaa(&self, vaa: &SignedVaa) -> Result<(), RelayerError> {
+        let posted = pda::posted_vaa(&self.core_bridge, &vaa.hash());
+        if self.account(&posted)?.is_some() {
+            return Ok(());
+        }
+
+        let guardian_set_key = pda::guardian_set(&self.core_bridge, vaa.guardian_set_index);
+        let guardian_set = self.account(&guardian_set_key)?.ok_or_else(|| {
+            RelayerError::MissingAccount(format!("guardian set {}", guardian_set_key))
+        })?;
+        let guardian_set = GuardianSetData::deserialize(&mut guardian_set.data.as_slice())?;
+
+        let signature_set = Keypair::new();
+        for batch in bridge::verify_signatures_batches(
+            &self.core_bridge,
+            &self.payer.pubkey(),
+            &signature_set.pubkey(),
+            &guardian_set,
+            vaa,
+        ) {
+            self.send(&batch, &[&signature_set])?;
+        }
+        self.send(
+            &[bridge::post_vaa_instruction(
+                &self.core_bridge,
+                &self.payer.pubkey(),
+                &signature_set.pubkey(),
+                vaa,
+            )],
+            &[],
+        )?;
+        Ok(())
+    }
+
+    fn processed(&self, vaa: &SignedVaa) -> Result<Option<Account>, RelayerError> {
+        self.account(&pda::processed_vaa(
+            &self.program_id,
+            &vaa.emitter_address,
+            vaa.emitter_chain,
+            vaa.sequence,
+        ))
+    }
+
+    fn initialize_pda(&self, vaa: &SignedVaa, account: [u8; 32]) -> Result<(), RelayerError> {
+        if self.processed(vaa)?.is_some() {
+            return Ok(());
+        }
+        let ix = instructions::initialize_pda(
+            &self.program_id,
+            &self.core_bridge,
+            &self.payer.pubkey(),
+            vaa,
+            account,
+        );
+        self.send(&[ix], &[])?;
+        Ok(())
+    }
+
+    fn set_payout_wallet(&self, vaa: &SignedVaa, account: [u8; 32]) -> Result<(), RelayerError> {
+        if self.processed(vaa)?.is_some() {
+            return Ok(());
+        }
+        let ix = instructions::set_payout_wallet(
+            &self.program_id,
+            &self.core_bridge,
+            &self.payer.pubkey(),
+            vaa,
+            account,
+        );
+        self.send(&[ix], &[])?;
+        Ok(())
+    }
+
+    fn initialize_token_account(
+        &self,
+        vaa: &SignedVaa,
+        account: [u8; 32],
+        token_mint: &Pubkey,
+    ) -> Result<(), RelayerError> {
+        if self.processed(vaa)?.is_some() {
+            return Ok(());
+        }
+        let ix = instructions::initialize_pda_token_account(
+            &self.program_id,
+            &self.core_bridge,
+            &self.payer.pubkey(),
+            vaa,
+            account,
+            token_mint,
+        );
+        self.send(&[ix], &[])?;
+        Ok(())
+    }
+
+    /// Stores the message and returns the count it was stored under.
+    fn store_msg(&self, vaa: &SignedVaa, message: &ZebecMessage) -> Result<u64, RelayerError> {
+        if let Some(account) = self.processed(vaa)? {
+            // An earlier run stored it but died before saving progress.
+            return Ok(decode_account::<ProcessedVAA>(&account.data)?.transaction_count);
+        }
+
+        let sender = message.signer();
+        let (current_count, migrate) = self.next_count(&sender)?;
+        let mut ixs: Vec<Instruction> = migrate.into_iter().collect();
+        // Every stored message names a mint, checked against its allowlist
+        // entry.
+        let token_mint = message.token_mint().unwrap_or_default();
+        ixs.push(match message {
+            ZebecMessage::BatchStream { .. } => instructions::store_batch_msg(
+                &self.program_id,
+                &self.core_bridge,
+                &self.payer.pubkey(),
+                vaa,
+                sender,
+                &token_mint,
+                current_count,
+            ),
+            _ => instructions::store_msg(
+                &self.program_id,
+                &self.core_bridge,
+                &self.payer.pubkey(),
+                vaa,
+                sender,
+                &token_mint,
+                current_count,
+            ),
+        });
+        self.send(&ixs, &[])?;
+        Ok(current_count)
+    }
+
+    /// The count the next message of `sender` is stored under, and the
+    /// `migrate_account` its count needs first, if any: `store_msg` only
+    /// accepts a count already in the current layout.
+    fn next_count(&self, sender: &[u8; 32]) -> Result<(u64, Option<Instruction>), RelayerError> {
+        let txn_count = pda::txn_count(&self.program_id, sender);
+        let count = match self.account(&txn_count)? {
+            Some(account) => Some(decode_account::<Count>(&account.data)?),
+            None => None,
+        };
+        let current_count = count
+            .as_ref()
+            .map_or(0, |count| count.count)
+            .checked_add(1)
+            .ok_or_else(|| RelayerError::Decode("txn_count overflow".to_string()))?;
+        let migrate = count
+            .filter(|count| count.account_version < AccountVersion::CURRENT)
+            .map(|_| {
+                instructions::migrate_account(&self.program_id, &self.payer.pubkey(), txn_count)
+            });
+        Ok((current_count, migrate))
+    }
+
+    fn direct_transfer(
+        &self,
+        message: &ZebecMessage,
+        chain: u16,
+        count: u64,
+    ) -> Result<(), RelayerError> {
+        let (sender, token_mint, target_chain, max_fee) = match message {
+            ZebecMessage::DirectTransfer {
+                sender,
+                token_mint,
+                target_chain,
+                max_fee,
+                ..
+            } => (*sender, *token_mint, *target_chain, *max_fee),
+            _ => return Ok(()),
+        };
+        // The program refuses any other chain or a higher fee than the
+        // sender fixed. Payloads without terms go back to their chain for
+        // free.
+        let fee = self.config.direct_transfer_fee.min(max_fee.unwrap_or(0));
+
+        let portal_message = Keypair::new();
+        let params = DirectTransfer {
+            program_id: self.program_id,
+            core_bridge: self.core_bridge,
+            token_bridge: self.token_bridge,
+            zebec_eoa: self.payer.pubkey(),
+            portal_message: portal_message.pubkey(),
+            sender,
+            sender_chain: chain,
+            current_count: count,
+            target_chain: target_chain.unwrap_or(chain),
+            fee,
+        };
+
+        let ix = instructions::direct_transfer(&params, &token_mint);
+        self.send(&[ix], &[&portal_message])?;
+        self.check_held(&sender, count)
+    }
+
+    /// Bridges what the receiver withdraw of a withdraw stream and bridge
+    /// message paid out, unless that was done before a restart.
+    fn withdraw_bridge(
+        &self,
+        message: &ZebecMessage,
+        chain: u16,
+        count: u64,
+    ) -> Result<(), RelayerError> {
+        let (withdrawer, token_mint) = match message {
+            ZebecMessage::WithdrawStreamBridge {
+                withdrawer,
+                token_mint,
+                ..
+            } => (*withdrawer, *token_mint),
+            _ => return Ok(()),
+        };
+        let data_store = pda::data_store(&self.program_id, &withdrawer, count);
+        let stored: TransactionData = match self.account(&data_store)? {
+            Some(account) => decode_account(&account.data)?,
+            None => {
+                return Err(RelayerError::MissingAccount(format!(
+                    "data store {}",
+                    data_store
+                )))
+            }
+        };
+        let target_chain = match stored.bridge_back {
+            BridgeBack::To { bridged: true, .. } => return Ok(()),
+            BridgeBack::To { target_chain, .. } => target_chain,
+            _ => chain,
+        };
+
+        let portal_message = Keypair::new();
+        let params = DirectTransfer {
+            program_id: self.program_id,
+            core_bridge: self.core_bridge,
+            token_bridge: self.token_bridge,
+            zebec_eoa: self.payer.pubkey(),
+            portal_message: portal_message.pubkey(),
+            sender: withdrawer,
+            sender_chain: chain,
+            current_count: count,
+            target_chain,
+            fee: 0,
+        };
+        let ix = instructions::withdraw_bridge(&params, &token_mint);
+        self.send(&[ix], &[&portal_message])?;
+        self.check_held(&withdrawer, count)
+    }
+
+    /// Pays an executed withdraw stream payout on to the wallet stored with
+    /// it, unless that already happened.
+    fn withdraw_payout(
+        &self,
+        message: &ZebecMessage,
+        chain: u16,
+        count: u64,
+    ) -> Result<(), RelayerError> {
+        let (withdrawer, token_mint) = match message {
+            ZebecMessage::WithdrawStreamPayout {
+                withdrawer,
+                token_mint,
+                ..
+            } => (*withdrawer, *token_mint),
+            _ => return Ok(()),
+        };
+        let data_store = pda::data_store(&self.program_id, &withdrawer, count);
+        let stored: TransactionData = match self.account(&data_store)? {
+            Some(account) => decode_account(&account.data)?,
+            None => {
+                return Err(RelayerError::MissingAccount(format!(
+                    "data store {}",
+                    data_store
+                )))
+            }
+        };
+        let wallet = match stored.payout {
+            Payout::To {
+                wallet,
+                paid: false,
+                ..
+            } => wallet,
+            _ => return Ok(()),
+        };
+
+        let ix = instructions::withdraw_payout(
+            &self.program_id,
+            &self.payer.pubkey(),
+            withdrawer,
+            chain,
+            count,
+            &token_mint,
+            &wallet,
+        );
+        self.send(&[ix], &[])?;
+        Ok(())
+    }
+
+    /// The target program version the message selects. Its registry entry
+    /// has to be the Zebec program the relayer builds accounts for.
+    fn target_version(&self, vaa: &SignedVaa) -> Result<u8, RelayerError> {
+        let version = message::target_version(&vaa.payload);
+        let entry = pda::target_program(&self.program_id, version);
+        let target: TargetProgram = match self.account(&entry)? {
+            Some(account) => decode_account(&account.data)?,
+            None => {
+                return Err(RelayerError::MissingAccount(format!(
+                    "target program version {} ({})",
+                    version, entry
+                )))
+            }
+        };
+        if target.program_id != self.zebec.program_id {
+            return Err(RelayerError::TargetProgram(format!(
+                "version {} ({})",
+                version, target.program_id
+            )));
+        }
+        Ok(version)
+    }
+
+    fn current_count(&self, record: &ProgressRecord) -> Result<u64, RelayerError> {
+        record
+            .current_count
+            .ok_or_else(|| RelayerError::Decode("stored message has no count".to_string()))
+    }
+
+    fn executed(&self, sender: &[u8; 32], count: u64) -> Result<bool, RelayerError> {
+        match self.account(&pda::txn_status(&self.program_id, sender, count))? {
+            Some(account) => Ok(decode_account::<TransactionStatus>(&account.data)?.executed),
+            None => Ok(false),
+        }
+    }
+
+    /// Fails with `RelayerError::Held` while the rate limits hold the
+    /// transfer of a stored message.
+    fn check_held(&self, sender: &[u8; 32], count: u64) -> Result<(), RelayerError> {
+        let stored = match self.account(&pda::data_store(&self.program_id, sender, count))? {
+            Some(account) => decode_account::<TransactionData>(&account.data)?,
+            None => return Ok(()),
+        };
+        match stored.clearance {
+            Clearance::Held { release_at } if release_at > unix_now() => {
+                Err(RelayerError::Held(release_at))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Whether `ixs` fit in one Solana transaction signed by the payer and
+    // `extra_signers` more.
+    fn fits(&self, ixs: &[Instruction], extra_signers: usize) -> bool {
+        let message = Message::new(ixs, Some(&self.payer.pubkey()));
+        1 + (1 + extra_signers) * 64 + message.serialize().len() <= PACKET_DATA_SIZE
+    }
+
+    /// Uploads a transaction too large to create in one go to its buffer,
+    /// after whatever an earlier run already uploaded.
+    fn upload(
+        &self,
+        accs: &[TransactionAccount],
+        data: &[u8],
+        sender: [u8; 32],
+        count: u64,
+    ) -> Result<(), RelayerError> {
+        let payer = self.payer.pubkey();
+        let buffer_key = pda::transaction_buffer(&self.program_id, &sender, count);
+        let buffer: TransactionBuffer = match self.account(&buffer_key)? {
+            Some(account) => decode_account(&account.data)?,
+            None => {
+                let ix = instructions::init_transaction(
+                    &self.program_id,
+                    &payer,
+                    sender,
+                    count,
+                    accs.len() as u8,
+                    data.len() as u16,
+                );
+                self.retry("init_transaction", || self.send(slice::from_ref(&ix), &[]))?;
+                TransactionBuffer::default()
+            }
+        };
+        if buffer.finalized {
+            return Ok(());
+        }
+
+        let accs = accs.get(buffer.accounts.len()..).unwrap_or_default();
+        for chunk in accs.chunks(instructions::UPLOAD_ACCOUNTS_CHUNK) {
+            let ix = instructions::append_accounts(
+                &self.program_id,
+                &payer,
+                sender,
+                count,
+                chunk.to_vec(),
+            );
+            self.retry("append_accounts", || self.send(slice::from_ref(&ix), &[]))?;
+        }
+        let data = data.get(buffer.data.len()..).unwrap_or_default();
+        for chunk in data.chunks(instructions::UPLOAD_DATA_CHUNK) {
+            let ix =
+                instructions::append_data(&self.program_id, &payer, sender, count, chunk.to_vec());
+            self.retry("append_data", || self.send(slice::from_ref(&ix), &[]))?;
+        }
+        let ix = instructions::finalize_transaction(&self.program_id, &payer, sender, count);
+        self.retry("finalize_transaction", || {
+            self.send(slice::from_ref(&ix), &[])
+        })?;
+        Ok(())
+    }
+
+    fn allocate(
+        &self,
+        account: &Keypair,
+        space: usize,
+        owner: &Pubkey,
+    ) -> Result<Instruction, RelayerError> {
+        let lamports = self.rpc.get_minimum_balance_for_rent_exemption(space)?;
+        Ok(system_instruction::create_account(
+            &self.payer.pubkey(),
+            &account.pubkey(),
+            lamports,
+            space as u64,
+            owner,
+        ))
+    }
+
+    fn account(&self, key: &Pubkey) -> Result<Option<Account>, RelayerError> {
+        Ok(self
+            .rpc
+            .get_account_with_commitment(key, self.rpc.commitment())?
+            .value)
+    }
+
+    fn send(
+        &self,
+        ixs: &[Instruction],
+        extra_signers: &[&Keypair],
+    ) -> Result<Signature, RelayerError> {
+        let mut signers: Vec<&dyn Signer> = vec![&self.payer];
+        for signer in extra_signers {
+            signers.push(*signer);
+        }
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let tx = SolanaTransaction::new_signed_with_payer(
+            ixs,
+            Some(&self.payer.pubkey()),
+            &signers,
+            blockhash,
+        );
+        let signature = self.rpc.send_and_confirm_transaction(&tx)?;
+        self.with_metrics(|m| m.transactions_sent += 1);
+        Ok(signature)
+    }
+
+    // The RPC client of this Solana version only sends legacy transactions.
+    fn send_versioned(&self, tx: &VersionedTransaction) -> Result<Signature, RelayerError> {
+        let encoded = base64::encode(versioned::serialize(tx));
+        let _: String = self.rpc.send(
+            RpcRequest::SendTransaction,
+            json!([encoded, { "encoding": "base64" }]),
+        )?;
+        let signature = tx.signatures[0];
+        self.rpc.poll_for_signature(&signature)?;
+        if let Some(Err(e)) = self.rpc.get_signature_status(&signature)? {
+            return Err(RpcError::from(e).into());
+        }
+        self.with_metrics(|m| m.transactions_sent += 1);
+        Ok(signature)
+    }
+
+    fn retry<T>(
+        &self,
+        step: &str,
+        mut f: impl FnMut() -> Result<T, RelayerError>,
+    ) -> Result<T, RelayerError> {
+        let mut attempt: u32 = 0;
+        loop {
+            match f() {
+                Ok(value) => return Ok(value),
+                Err(e) if e.is_retryable() && attempt < self.config.max_retries => {
+                    attempt += 1;
+                    self.with_metrics(|m| m.retries += 1);
+                    log::warn!("{} failed (attempt {}): {}", step, attempt, e);
+                    let backoff = self.config.retry_backoff_ms << (attempt - 1).min(10);
+                    sleep(Duration::from_millis(backoff));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn save(&self, key: &str, record: &ProgressRecord) -> Result<(), RelayerError> {
+        self.store.lock().unwrap().put(key, record.clone())
+    }
+
+    fn with_metrics(&self, f: impl FnOnce(&mut Metrics)) {
+        f(self.store.lock().unwrap().metrics_mut());
+    }
+}
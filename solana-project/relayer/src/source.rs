@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use zebec_wormhole_client::vaa::decode_encoded;
+
+use crate::config::SourceConfig;
+use crate::error::RelayerError;
+
+/// Somewhere VAAs come from. `poll` returns whatever is new since the last
+/// call and must not block for long; the relayer loops over all sources.
+pub trait VaaSource {
+    fn name(&self) -> String;
+    fn poll(&mut self) -> Result<Vec<Vec<u8>>, RelayerError>;
+}
+
+pub fn from_config(config: &SourceConfig) -> Box<dyn VaaSource> {
+    match config {
+        SourceConfig::Directory { path } => Box::new(DirectorySource::new(PathBuf::from(path))),
+        SourceConfig::Spy { addr } => Box::new(SpySource::new(addr.clone())),
+        SourceConfig::Http { url } => Box::new(HttpSource::new(url.clone())),
+    }
+}
+
+/// Picks up every file in a directory once. Files are left in place; the
+/// progress store dedups VAAs across restarts.
+pub struct DirectorySource {
+    dir: PathBuf,
+    seen: HashSet<PathBuf>,
+}
+
+impl DirectorySource {
+    pub fn new(dir: PathBuf) -> Self {
+        DirectorySource {
+            dir,
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl VaaSource for DirectorySource {
+    fn name(&self) -> String {
+        format!("dir:{}", self.dir.display())
+    }
+
+    fn poll(&mut self) -> Result<Vec<Vec<u8>>, RelayerError> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && !self.seen.contains(path))
+            .collect();
+        paths.sort();
+
+        let mut vaas = Vec::with_capacity(paths.len());
+        for path in paths {
+            let raw = std::fs::read(&path)?;
+            vaas.push(decode_encoded(&raw));
+            self.seen.insert(path);
+        }
+        Ok(vaas)
+    }
+}
+
+/// Reads newline-delimited VAAs from a TCP forwarder and reconnects whenever
+/// the connection drops.
+pub struct SpySource {
+    addr: String,
+    reader: Option<BufReader<TcpStream>>,
+    pending: String,
+}
+
+impl SpySource {
+    pub fn new(addr: String) -> Self {
+        SpySource {
+            addr,
+            reader: None,
+            pending: String::new(),
+        }
+    }
+
+    fn connect(&mut self) -> Result<(), RelayerError> {
+        let stream = TcpStream::connect(&self.addr)
+            .map_err(|e| RelayerError::Source(format!("connect {}: {}", self.addr, e)))?;
+        stream.set_read_timeout(Some(Duration::from_millis(200)))?;
+        self.reader = Some(BufReader::new(stream));
+        Ok(())
+    }
+}
+
+impl VaaSource for SpySource {
+    fn name(&self) -> String {
+        format!("spy:{}", self.addr)
+    }
+
+    fn poll(&mut self) -> Result<Vec<Vec<u8>>, RelayerError> {
+        if self.reader.is_none() {
+            self.connect()?;
+        }
+        let reader = self.reader.as_mut().unwrap();
+
+        let mut vaas = vec![];
+        loop {
+            match reader.read_line(&mut self.pending) {
+                Ok(0) => {
+                    self.reader = None;
+                    break;
+                }
+                Ok(_) => {
+                    if self.pending.ends_with('\n') {
+                        let line = std::mem::take(&mut self.pending);
+                        if !line.trim().is_empty() {
+                            vaas.push(decode_encoded(line.as_bytes()));
+                        }
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                    break
+                }
+                Err(e) => {
+                    self.reader = None;
+                    return Err(RelayerError::Source(format!("read {}: {}", self.addr, e)));
+                }
+            }
+        }
+        Ok(vaas)
+    }
+}
+
+/// Polls an HTTP endpoint that stands in for the guardian REST API.
+pub struct HttpSource {
+    url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl HttpSource {
+    pub fn new(url: String) -> Self {
+        HttpSource {
+            url,
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+}
+
+impl VaaSource for HttpSource {
+    fn name(&self) -> String {
+        format!("http:{}", self.url)
+    }
+
+    fn poll(&mut self) -> Result<Vec<Vec<u8>>, RelayerError> {
+        let encoded: Vec<String> = self
+            .client
+            .get(&self.url)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json())
+            .map_err(|e| RelayerError::Source(format!("GET {}: {}", self.url, e)))?;
+        Ok(encoded
+            .iter()
+            .map(|vaa| decode_encoded(vaa.as_bytes()))
+            .collect())
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::RelayerError;
+use crate::metrics::Metrics;
+
+/// How far a VAA has got through the relay sequence.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    #[default]
+    Received,
+    Posted,
+    Stored,
+    Created,
+    /// Executed, with a withdrawal still to bridge.
+    Executed,
+    Done,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProgressRecord {
+    /// base64 of the signed VAA, so pending work can resume after a restart.
+    pub vaa: String,
+    pub code: u8,
+    pub emitter_chain: u16,
+    pub sequence: u64,
+    pub stage: Stage,
+    pub current_count: Option<u64>,
+    /// Zebec stream data account created alongside a new stream.
+    pub data_account: Option<String>,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    /// Unix time a transfer the rate limits hold is released at.
+    pub held_until: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoreState {
+    records: BTreeMap<String, ProgressRecord>,
+    metrics: Metrics,
+}
+
+/// JSON file holding per-VAA progress and the relayer counters, keyed by the
+/// hex VAA hash. Written atomically after every step.
+pub struct ProgressStore {
+    path: PathBuf,
+    state: StoreState,
+}
+
+impl ProgressStore {
+    pub fn open(path: &Path) -> Result<Self, RelayerError> {
+        let state = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(path)?)?
+        } else {
+            StoreState::default()
+        };
+        Ok(ProgressStore {
+            path: path.to_path_buf(),
+            state,
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&ProgressRecord> {
+        self.state.records.get(key)
+    }
+
+    pub fn put(&mut self, key: &str, record: ProgressRecord) -> Result<(), RelayerError> {
+        self.state.records.insert(key.to_string(), record);
+        self.save()
+    }
+
+    /// VAAs that were seen but not finished, oldest sequence first.
+    pub fn pending(&self) -> Vec<(String, ProgressRecord)> {
+        let mut pending: Vec<(String, ProgressRecord)> = self
+            .state
+            .records
+            .iter()
+            .filter(|(_, record)| record.stage != Stage::Done)
+            .map(|(key, record)| (key.clone(), record.clone()))
+            .collect();
+        pending.sort_by_key(|(_, record)| (record.emitter_chain, record.sequence));
+        pending
+    }
+
+    /// Pending VAAs whose held transfer was released by `now`.
+    pub fn released(&self, now: i64) -> Vec<(String, ProgressRecord)> {
+        self.pending()
+            .into_iter()
+            .filter(|(_, record)| record.held_until.is_some_and(|until| until <= now))
+            .collect()
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.state.metrics
+    }
+
+    pub fn metrics_mut(&mut self) -> &mut Metrics {
+        &mut self.state.metrics
+    }
+
+    pub fn save(&self) -> Result<(), RelayerError> {
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&self.state)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
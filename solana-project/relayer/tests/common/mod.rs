@@ -0,0 +1,63 @@
+#![allow(dead_code)]
+
+use zebec_wormhole_client::vaa::{GuardianSignature, SignedVaa};
+
+/// Private key of the single devnet / tilt guardian.
+pub const DEVNET_GUARDIAN_KEY: &str =
+    "cfb12303a19cde580bb4dd771639b0d26bc68353645571a8cff516ab2ee113a0";
+
+/// Builds a VAA for `payload` from `emitter_chain` and signs it with the
+/// devnet guardian.
+pub fn signed_vaa(
+    emitter_chain: u16,
+    emitter_address: [u8; 32],
+    sequence: u64,
+    payload: Vec<u8>,
+) -> SignedVaa {
+    let mut vaa = SignedVaa {
+        version: 1,
+        guardian_set_index: 0,
+        signatures: vec![],
+        timestamp: 1_650_000_000,
+        nonce: 0,
+        emitter_chain,
+        emitter_address,
+        sequence,
+        consistency_level: 1,
+        payload,
+    };
+
+    let key: [u8; 32] = hex::decode(DEVNET_GUARDIAN_KEY)
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let secret = libsecp256k1::SecretKey::parse(&key).unwrap();
+    let message = libsecp256k1::Message::parse(&vaa.digest());
+    let (signature, recovery_id) = libsecp256k1::sign(&message, &secret);
+
+    let mut bytes = [0u8; 65];
+    bytes[..64].copy_from_slice(&signature.serialize());
+    bytes[64] = recovery_id.serialize();
+    vaa.signatures.push(GuardianSignature {
+        index: 0,
+        signature: bytes,
+    });
+    vaa
+}
+
+/// Code 18 payload: initialize the PDA of `account` on Solana.
+pub fn initialize_pda_payload(account: [u8; 32]) -> Vec<u8> {
+    let mut payload = vec![18u8];
+    payload.extend_from_slice(&account);
+    let mut to_chain = [0u8; 32];
+    to_chain[31] = 1;
+    payload.extend_from_slice(&to_chain);
+    payload
+}
+
+/// A left-padded EVM address.
+pub fn evm_address(byte: u8) -> [u8; 32] {
+    let mut address = [0u8; 32];
+    address[12..].copy_from_slice(&[byte; 20]);
+    address
+}
@@ -0,0 +1,89 @@
+//! End-to-end relay against a local validator running the proxy, the core
+//! bridge (initialized with the devnet guardian) and Zebec. Run with
+//! `cargo test -p zebec-relayer -- --ignored` once `solana-test-validator`
+//! is up on 127.0.0.1:8899 and the Ethereum emitter is registered.
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::signature::read_keypair_file;
+
+use zebec_relayer::config::{RelayerConfig, SourceConfig};
+use zebec_relayer::relayer::Relayer;
+use zebec_relayer::source::{DirectorySource, VaaSource};
+use zebec_relayer::store::{ProgressStore, Stage};
+use zebec_wormhole_client::pda;
+
+use common::{evm_address, initialize_pda_payload, signed_vaa};
+
+const RPC_URL: &str = "http://127.0.0.1:8899";
+const ETHEREUM_CHAIN_ID: u16 = 2;
+
+fn local_config(dir: &std::path::Path) -> RelayerConfig {
+    let keypair_path = std::env::var("RELAYER_KEYPAIR")
+        .unwrap_or_else(|_| format!("{}/.config/solana/id.json", std::env::var("HOME").unwrap()));
+    serde_json::from_value(serde_json::json!({
+        "rpc_url": RPC_URL,
+        "keypair_path": keypair_path,
+        "fee_owner": solana_project::ID.to_string(),
+        "state_path": dir.join("state.json").to_str().unwrap(),
+        "sources": [SourceConfig::Directory { path: dir.join("vaas").to_str().unwrap().to_string() }],
+        "max_retries": 2,
+        "retry_backoff_ms": 200,
+    }))
+    .unwrap()
+}
+
+#[test]
+#[ignore]
+fn relays_initialize_pda() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("vaas")).unwrap();
+    let config = local_config(dir.path());
+
+    let emitter = std::env::var("ZEBEC_EVM_EMITTER")
+        .map(|hex_address| hex::decode(hex_address).unwrap().try_into().unwrap())
+        .unwrap_or_else(|_| evm_address(0xee));
+    let account = evm_address(0x42);
+    let sequence = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let vaa = signed_vaa(
+        ETHEREUM_CHAIN_ID,
+        emitter,
+        sequence,
+        initialize_pda_payload(account),
+    );
+    std::fs::write(dir.path().join("vaas/0.hex"), hex::encode(vaa.serialize())).unwrap();
+
+    let payer = read_keypair_file(&config.keypair_path).unwrap();
+    let store = Arc::new(Mutex::new(
+        ProgressStore::open(config.state_path.as_ref()).unwrap(),
+    ));
+    let program_id = config.program_id().unwrap();
+    let relayer = Relayer::new(config, payer, store.clone()).unwrap();
+
+    let mut sources: Vec<Box<dyn VaaSource>> =
+        vec![Box::new(DirectorySource::new(dir.path().join("vaas")))];
+    relayer.run(&mut sources, true).unwrap();
+
+    let key = hex::encode(vaa.hash());
+    assert_eq!(store.lock().unwrap().get(&key).unwrap().stage, Stage::Done);
+
+    let rpc = RpcClient::new(RPC_URL.to_string());
+    let processed = pda::processed_vaa(
+        &program_id,
+        &vaa.emitter_address,
+        vaa.emitter_chain,
+        vaa.sequence,
+    );
+    assert!(rpc.get_account(&processed).is_ok());
+
+    // Feeding the same VAA again is a no-op.
+    let raw = vaa.serialize();
+    assert_eq!(relayer.process(&raw).unwrap(), Stage::Done);
+    assert_eq!(store.lock().unwrap().metrics().vaas_skipped, 1);
+}
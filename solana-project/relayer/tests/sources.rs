@@ -0,0 +1,158 @@
+mod common;
+
+use std::io::Write;
+use std::net::TcpListener;
+
+use zebec_relayer::metrics::Metrics;
+use zebec_relayer::source::{DirectorySource, SpySource, VaaSource};
+use zebec_relayer::store::{ProgressRecord, ProgressStore, Stage};
+use zebec_wormhole_client::{SignedVaa, ZebecMessage};
+
+use common::{evm_address, initialize_pda_payload, signed_vaa};
+
+#[test]
+fn directory_source_reads_every_encoding_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let vaa = signed_vaa(
+        2,
+        evm_address(0xaa),
+        7,
+        initialize_pda_payload(evm_address(1)),
+    );
+    let raw = vaa.serialize();
+
+    std::fs::write(dir.path().join("a.bin"), &raw).unwrap();
+    std::fs::write(
+        dir.path().join("b.hex"),
+        format!("0x{}\n", hex::encode(&raw)),
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("c.b64"), base64::encode(&raw)).unwrap();
+
+    let mut source = DirectorySource::new(dir.path().to_path_buf());
+    let vaas = source.poll().unwrap();
+    assert_eq!(vaas.len(), 3);
+    for bytes in vaas.iter() {
+        assert_eq!(SignedVaa::parse(bytes).unwrap(), vaa);
+    }
+    assert!(source.poll().unwrap().is_empty());
+}
+
+#[test]
+fn spy_source_splits_lines() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+
+    let first = signed_vaa(
+        2,
+        evm_address(0xaa),
+        1,
+        initialize_pda_payload(evm_address(1)),
+    );
+    let second = signed_vaa(
+        2,
+        evm_address(0xaa),
+        2,
+        initialize_pda_payload(evm_address(2)),
+    );
+    let lines = format!(
+        "{}\n\n{}\n",
+        hex::encode(first.serialize()),
+        base64::encode(second.serialize())
+    );
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.write_all(lines.as_bytes()).unwrap();
+    });
+
+    let mut source = SpySource::new(addr);
+    let mut vaas = vec![];
+    while vaas.len() < 2 {
+        vaas.extend(source.poll().unwrap());
+    }
+    server.join().unwrap();
+
+    assert_eq!(SignedVaa::parse(&vaas[0]).unwrap(), first);
+    assert_eq!(SignedVaa::parse(&vaas[1]).unwrap(), second);
+}
+
+#[test]
+fn decodes_initialize_pda() {
+    let vaa = signed_vaa(
+        2,
+        evm_address(0xaa),
+        1,
+        initialize_pda_payload(evm_address(1)),
+    );
+    assert_eq!(
+        ZebecMessage::decode(&vaa.payload).unwrap(),
+        ZebecMessage::InitializePda {
+            account: evm_address(1)
+        }
+    );
+}
+
+#[test]
+fn store_survives_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+
+    let mut store = ProgressStore::open(&path).unwrap();
+    for (key, sequence, stage) in [
+        ("b", 2, Stage::Stored),
+        ("a", 1, Stage::Posted),
+        ("c", 3, Stage::Done),
+    ] {
+        let record = ProgressRecord {
+            emitter_chain: 2,
+            sequence,
+            stage,
+            ..Default::default()
+        };
+        store.put(key, record).unwrap();
+    }
+    store.metrics_mut().vaas_received = 3;
+    store.save().unwrap();
+
+    let store = ProgressStore::open(&path).unwrap();
+    let pending: Vec<String> = store.pending().into_iter().map(|(key, _)| key).collect();
+    assert_eq!(pending, vec!["a", "b"]);
+    assert_eq!(store.get("c").unwrap().stage, Stage::Done);
+    assert_eq!(store.metrics().vaas_received, 3);
+}
+
+#[test]
+fn store_releases_held_records_when_due() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = ProgressStore::open(&dir.path().join("state.json")).unwrap();
+    for (key, sequence, held_until) in [("a", 1, Some(100)), ("b", 2, Some(200)), ("c", 3, None)] {
+        let record = ProgressRecord {
+            sequence,
+            stage: Stage::Created,
+            held_until,
+            ..Default::default()
+        };
+        store.put(key, record).unwrap();
+    }
+
+    let released: Vec<String> = store
+        .released(150)
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect();
+    assert_eq!(released, vec!["a"]);
+    assert_eq!(store.released(200).len(), 2);
+}
+
+#[test]
+fn renders_prometheus() {
+    let mut metrics = Metrics {
+        vaas_completed: 4,
+        ..Default::default()
+    };
+    metrics.completed_by_code.insert(18, 4);
+
+    let text = metrics.render_prometheus();
+    assert!(text.contains("zebec_relayer_vaas_completed_total 4\n"));
+    assert!(text.contains("zebec_relayer_completed_by_code_total{code=\"18\"} 4\n"));
+}
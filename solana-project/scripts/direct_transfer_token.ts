@@ -297,6 +297,10 @@ const direct_transfer_native = async () => {
     [Buffer.from("custody_signer")],
     portalBridgeProgram
   );
+  const [wrappedMeta] = PublicKey.findProgramAddressSync(
+    [Buffer.from("meta"), mint.toBuffer()],
+    portalBridgeProgram
+  );
   const [portalEmitter] = PublicKey.findProgramAddressSync(
     [Buffer.from("emitter")],
     portalBridgeProgram
@@ -338,7 +342,7 @@ const direct_transfer_native = async () => {
   console.log("transferFeeIxn", transferFeeIxn);
 
   const tx = await program.methods
-    .transactionDirectTransferNative(
+    .transactionDirectTransfer(
       Buffer.from(depositorHash),
       Buffer.from(CHAIN_ID_BSC.toString()),
       current_count - 1,
@@ -357,6 +361,7 @@ const direct_transfer_native = async () => {
       portalCustody: portalCustody,
       portalAuthoritySigner: portalAuthoritySigner,
       portalCustodySigner: portalCustodySigner,
+      wrappedMeta: wrappedMeta,
       bridgeConfig: bridgeConfig,
       portalMessage: portalMessage,
       portalEmitter: portalEmitter,
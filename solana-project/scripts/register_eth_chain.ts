@@ -36,7 +36,12 @@ async function register_eth_address() {
   );
 
   await program.methods
-    .registerChain(CHAIN_ID_BSC, ethAddress)
+    .registerChain(
+      CHAIN_ID_BSC,
+      Array.from(Buffer.from(ethAddress, 'hex')),
+      'bsc',
+      { evm: {} }
+    )
     .accounts({
       owner: KEYPAIR.publicKey,
       systemProgram: anchor.web3.SystemProgram.programId,
@@ -0,0 +1,278 @@
+//! Stand-in for the Wormhole core bridge, for local validators and
+//! `solana-program-test` only.
+//!
+//! It keeps the real program id, instruction encoding, account order and PDA
+//! seeds, but only implements `PostMessage`. Messages are written to the
+//! message account exactly as the real bridge lays them out (`msg` followed by
+//! the borsh `MessageData`), so every call can be read back and asserted on.
+//! There are no guardians: nothing here produces a VAA.
+//!
+//! The entrypoint is only compiled with the `local-bridge` feature, so this
+//! crate can never be built into a deployable program by accident.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+solana_program::declare_id!("worm2ZoG2kUd4vFXhvjh93UUH596ayRfgQ2MgjNMTth");
+
+#[cfg(all(feature = "local-bridge", not(feature = "no-entrypoint")))]
+solana_program::entrypoint!(process_instruction);
+
+/// Chain id the bridge stamps on messages emitted from Solana.
+pub const CHAIN_ID_SOLANA: u16 = 1;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum Instruction {
+    Initialize,
+    PostMessage,
+    PostVAA,
+    SetFees,
+    TransferFees,
+    UpgradeContract,
+    UpgradeGuardianSet,
+    VerifySignatures,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct PostMessageData {
+    /// Unique nonce for this message
+    pub nonce: u32,
+
+    /// Message payload
+    pub payload: Vec<u8>,
+
+    /// Commitment Level required for an attestation to be produced
+    pub consistency_level: ConsistencyLevel,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub enum ConsistencyLevel {
+    Confirmed,
+    Finalized,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct BridgeData {
+    /// The current guardian set index, used to decide which signature sets to accept.
+    pub guardian_set_index: u32,
+
+    /// Lamports in the collection account
+    pub last_lamports: u64,
+
+    /// Bridge configuration, which is set once upon initialization.
+    pub config: BridgeConfig,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct BridgeConfig {
+    /// Period for how long a guardian set is valid after it has been replaced by a new one.
+    pub guardian_set_expiration_time: u32,
+
+    /// Amount of lamports that needs to be paid to the protocol to post a message
+    pub fee: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct SequenceTracker {
+    pub sequence: u64,
+}
+
+#[derive(Debug, Default, BorshDeserialize, BorshSerialize)]
+pub struct MessageData {
+    /// Header of the posted VAA
+    pub vaa_version: u8,
+
+    /// Level of consistency requested by the emitter
+    pub consistency_level: u8,
+
+    /// Time the vaa was submitted
+    pub vaa_time: u32,
+
+    /// Account where signatures are stored
+    pub vaa_signature_account: Pubkey,
+
+    /// Time the posted message was created
+    pub submission_time: u32,
+
+    /// Unique nonce for this message
+    pub nonce: u32,
+
+    /// Sequence number of this message
+    pub sequence: u64,
+
+    /// Emitter of the message
+    pub emitter_chain: u16,
+
+    /// Emitter of the message
+    pub emitter_address: [u8; 32],
+
+    /// Message payload
+    pub payload: Vec<u8>,
+}
+
+impl MessageData {
+    /// Decodes a message account written by `PostMessage`.
+    pub fn from_account_data(data: &[u8]) -> Result<Self, ProgramError> {
+        match data.strip_prefix(b"msg") {
+            Some(mut rest) => {
+                MessageData::deserialize(&mut rest).map_err(|_| ProgramError::InvalidAccountData)
+            }
+            None => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+/// Returns the bump of the PDA `seeds` derive under `program_id`, or
+/// `InvalidSeeds` if `account` is not that PDA.
+pub fn check_pda(
+    account: &AccountInfo,
+    seeds: &[&[u8]],
+    program_id: &Pubkey,
+) -> Result<u8, ProgramError> {
+    let (key, bump) = Pubkey::find_program_address(seeds, program_id);
+    if *account.key != key {
+        msg!("{} is not the expected PDA {}", account.key, key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(bump)
+}
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let mut data = data;
+    let instruction =
+        Instruction::deserialize(&mut data).map_err(|_| ProgramError::InvalidInstructionData)?;
+    match instruction {
+        Instruction::PostMessage => {
+            let data = PostMessageData::try_from_slice(data)
+                .map_err(|_| ProgramError::InvalidInstructionData)?;
+            post_message(program_id, accounts, data)
+        }
+        _ => {
+            msg!("Instruction not implemented by the core bridge stand-in");
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+}
+
+fn post_message(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: PostMessageData,
+) -> ProgramResult {
+    let accounts = &mut accounts.iter();
+    let bridge = next_account_info(accounts)?;
+    let message = next_account_info(accounts)?;
+    let emitter = next_account_info(accounts)?;
+    let sequence = next_account_info(accounts)?;
+    let payer = next_account_info(accounts)?;
+    let fee_collector = next_account_info(accounts)?;
+    let _clock = next_account_info(accounts)?;
+    let _rent = next_account_info(accounts)?;
+    let system_program = next_account_info(accounts)?;
+
+    check_pda(bridge, &[b"Bridge"], program_id)?;
+    check_pda(fee_collector, &[b"fee_collector"], program_id)?;
+    let sequence_bump = check_pda(sequence, &[b"Sequence", emitter.key.as_ref()], program_id)?;
+
+    if !message.is_signer || !emitter.is_signer || !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !message.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // The bridge is only initialized when a test sets it up; without it
+    // posting is free, as with a zero fee.
+    if !bridge.data_is_empty() {
+        let mut state = BridgeData::try_from_slice(&bridge.try_borrow_data()?)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        let paid = fee_collector.lamports().saturating_sub(state.last_lamports);
+        if paid < state.config.fee {
+            msg!("Message fee of {} lamports was not paid", state.config.fee);
+            return Err(ProgramError::InsufficientFunds);
+        }
+        state.last_lamports = fee_collector.lamports();
+        state
+            .serialize(&mut &mut bridge.try_borrow_mut_data()?[..])
+            .map_err(|_| ProgramError::AccountDataTooSmall)?;
+    }
+
+    let rent = Rent::get()?;
+    let mut tracker = if sequence.data_is_empty() {
+        let space = SequenceTracker::default().try_to_vec().unwrap().len();
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                sequence.key,
+                rent.minimum_balance(space),
+                space as u64,
+                program_id,
+            ),
+            &[payer.clone(), sequence.clone(), system_program.clone()],
+            &[&[b"Sequence", emitter.key.as_ref(), &[sequence_bump]]],
+        )?;
+        SequenceTracker::default()
+    } else {
+        SequenceTracker::try_from_slice(&sequence.try_borrow_data()?)
+            .map_err(|_| ProgramError::InvalidAccountData)?
+    };
+
+    let posted = MessageData {
+        vaa_version: 0,
+        consistency_level: match data.consistency_level {
+            ConsistencyLevel::Confirmed => 1,
+            ConsistencyLevel::Finalized => 32,
+        },
+        vaa_time: 0,
+        vaa_signature_account: Pubkey::default(),
+        submission_time: Clock::get()?.unix_timestamp as u32,
+        nonce: data.nonce,
+        sequence: tracker.sequence,
+        emitter_chain: CHAIN_ID_SOLANA,
+        emitter_address: emitter.key.to_bytes(),
+        payload: data.payload,
+    };
+    let mut record = b"msg".to_vec();
+    posted
+        .serialize(&mut record)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    invoke(
+        &system_instruction::create_account(
+            payer.key,
+            message.key,
+            rent.minimum_balance(record.len()),
+            record.len() as u64,
+            program_id,
+        ),
+        &[payer.clone(), message.clone(), system_program.clone()],
+    )?;
+    message.try_borrow_mut_data()?.copy_from_slice(&record);
+
+    msg!(
+        "Posted message {} from {} with sequence {}",
+        message.key,
+        emitter.key,
+        tracker.sequence
+    );
+    tracker.sequence += 1;
+    tracker
+        .serialize(&mut &mut sequence.try_borrow_mut_data()?[..])
+        .map_err(|_| ProgramError::AccountDataTooSmall)?;
+    Ok(())
+}
@@ -0,0 +1,519 @@
+//! Stand-in for the Wormhole token bridge, for local validators and
+//! `solana-program-test` only.
+//!
+//! It keeps the real program id, instruction encoding, account order and PDA
+//! seeds for `TransferNative`, `TransferWrapped`, `TransferNativeWithPayload`
+//! and `TransferWrappedWithPayload`. Native tokens are locked in the custody
+//! account and wrapped tokens are burned, through the `authority_signer`
+//! delegate, and the transfer is posted through the core bridge (normally
+//! `core_bridge_stand_in`) from the `emitter` PDA. The posted message account
+//! is the record of the call.
+//!
+//! The `config` account is only checked for its address, so no `Initialize`
+//! is needed. The entrypoint is only compiled with the `local-bridge` feature.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction as SolanaInstruction},
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use core_bridge_stand_in::{
+    check_pda, BridgeData, ConsistencyLevel, PostMessageData, CHAIN_ID_SOLANA,
+};
+
+solana_program::declare_id!("wormDTUJ6AWPNvk59vGQbDvGJmqbDTdgWgAqcLBCgUb");
+
+#[cfg(all(feature = "local-bridge", not(feature = "no-entrypoint")))]
+solana_program::entrypoint!(process_instruction);
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum Instruction {
+    Initialize,
+    AttestToken,
+    CompleteNative,
+    CompleteWrapped,
+    TransferWrapped,
+    TransferNative,
+    RegisterChain,
+    CreateWrapped,
+    UpgradeContract,
+    CompleteNativeWithPayload,
+    CompleteWrappedWithPayload,
+    TransferWrappedWithPayload,
+    TransferNativeWithPayload,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct TransferNativeData {
+    pub nonce: u32,
+    pub amount: u64,
+    pub fee: u64,
+    pub target_address: [u8; 32],
+    pub target_chain: u16,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct TransferWrappedData {
+    pub nonce: u32,
+    pub amount: u64,
+    pub fee: u64,
+    pub target_address: [u8; 32],
+    pub target_chain: u16,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct TransferNativeWithPayloadData {
+    pub nonce: u32,
+    pub amount: u64,
+    pub target_address: [u8; 32],
+    pub target_chain: u16,
+    pub payload: Vec<u8>,
+    pub cpi_program_id: Option<Pubkey>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct TransferWrappedWithPayloadData {
+    pub nonce: u32,
+    pub amount: u64,
+    pub target_address: [u8; 32],
+    pub target_chain: u16,
+    pub payload: Vec<u8>,
+    pub cpi_program_id: Option<Pubkey>,
+}
+
+/// Contents of the `["meta", wrapped_mint]` account.
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct WrappedMeta {
+    pub chain: u16,
+    pub token_address: [u8; 32],
+    pub original_decimals: u8,
+}
+
+/// A transfer as the four instructions share it.
+struct Outgoing {
+    nonce: u32,
+    amount: u64,
+    fee: u64,
+    target_address: [u8; 32],
+    target_chain: u16,
+    /// Payload and CPI program id of the `*WithPayload` variants.
+    with_payload: Option<(Vec<u8>, Option<Pubkey>)>,
+}
+
+impl From<TransferNativeData> for Outgoing {
+    fn from(data: TransferNativeData) -> Self {
+        Outgoing {
+            nonce: data.nonce,
+            amount: data.amount,
+            fee: data.fee,
+            target_address: data.target_address,
+            target_chain: data.target_chain,
+            with_payload: None,
+        }
+    }
+}
+
+impl From<TransferWrappedData> for Outgoing {
+    fn from(data: TransferWrappedData) -> Self {
+        Outgoing {
+            nonce: data.nonce,
+            amount: data.amount,
+            fee: data.fee,
+            target_address: data.target_address,
+            target_chain: data.target_chain,
+            with_payload: None,
+        }
+    }
+}
+
+impl From<TransferNativeWithPayloadData> for Outgoing {
+    fn from(data: TransferNativeWithPayloadData) -> Self {
+        Outgoing {
+            nonce: data.nonce,
+            amount: data.amount,
+            fee: 0,
+            target_address: data.target_address,
+            target_chain: data.target_chain,
+            with_payload: Some((data.payload, data.cpi_program_id)),
+        }
+    }
+}
+
+impl From<TransferWrappedWithPayloadData> for Outgoing {
+    fn from(data: TransferWrappedWithPayloadData) -> Self {
+        Outgoing {
+            nonce: data.nonce,
+            amount: data.amount,
+            fee: 0,
+            target_address: data.target_address,
+            target_chain: data.target_chain,
+            with_payload: Some((data.payload, data.cpi_program_id)),
+        }
+    }
+}
+
+fn decode<T: BorshDeserialize>(data: &[u8]) -> Result<T, ProgramError> {
+    T::try_from_slice(data).map_err(|_| ProgramError::InvalidInstructionData)
+}
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let mut data = data;
+    let instruction =
+        Instruction::deserialize(&mut data).map_err(|_| ProgramError::InvalidInstructionData)?;
+    match instruction {
+        Instruction::TransferNative => transfer_native(
+            program_id,
+            accounts,
+            decode::<TransferNativeData>(data)?.into(),
+        ),
+        Instruction::TransferWrapped => transfer_wrapped(
+            program_id,
+            accounts,
+            decode::<TransferWrappedData>(data)?.into(),
+        ),
+        Instruction::TransferNativeWithPayload => transfer_native(
+            program_id,
+            accounts,
+            decode::<TransferNativeWithPayloadData>(data)?.into(),
+        ),
+        Instruction::TransferWrappedWithPayload => transfer_wrapped(
+            program_id,
+            accounts,
+            decode::<TransferWrappedWithPayloadData>(data)?.into(),
+        ),
+        _ => {
+            msg!("Instruction not implemented by the token bridge stand-in");
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+}
+
+/// The core bridge accounts both transfers end with.
+struct MessageAccounts<'a, 'b> {
+    bridge: &'a AccountInfo<'b>,
+    message: &'a AccountInfo<'b>,
+    emitter: &'a AccountInfo<'b>,
+    sequence: &'a AccountInfo<'b>,
+    fee_collector: &'a AccountInfo<'b>,
+    clock: &'a AccountInfo<'b>,
+    sender: Option<&'a AccountInfo<'b>>,
+    rent: &'a AccountInfo<'b>,
+    system_program: &'a AccountInfo<'b>,
+    core_bridge_program: &'a AccountInfo<'b>,
+    token_program: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> MessageAccounts<'a, 'b> {
+    fn next<I: Iterator<Item = &'a AccountInfo<'b>>>(
+        accounts: &mut I,
+        with_sender: bool,
+    ) -> Result<Self, ProgramError> {
+        Ok(MessageAccounts {
+            bridge: next_account_info(accounts)?,
+            message: next_account_info(accounts)?,
+            emitter: next_account_info(accounts)?,
+            sequence: next_account_info(accounts)?,
+            fee_collector: next_account_info(accounts)?,
+            clock: next_account_info(accounts)?,
+            sender: if with_sender {
+                Some(next_account_info(accounts)?)
+            } else {
+                None
+            },
+            rent: next_account_info(accounts)?,
+            system_program: next_account_info(accounts)?,
+            core_bridge_program: next_account_info(accounts)?,
+            token_program: next_account_info(accounts)?,
+        })
+    }
+}
+
+fn transfer_native(program_id: &Pubkey, accounts: &[AccountInfo], data: Outgoing) -> ProgramResult {
+    let accounts = &mut accounts.iter();
+    let payer = next_account_info(accounts)?;
+    let config = next_account_info(accounts)?;
+    let from = next_account_info(accounts)?;
+    let mint = next_account_info(accounts)?;
+    let custody = next_account_info(accounts)?;
+    let authority_signer = next_account_info(accounts)?;
+    let custody_signer = next_account_info(accounts)?;
+    let accs = MessageAccounts::next(accounts, data.with_payload.is_some())?;
+
+    check_pda(config, &[b"config"], program_id)?;
+    let custody_bump = check_pda(custody, &[mint.key.as_ref()], program_id)?;
+    let authority_bump = check_pda(authority_signer, &[b"authority_signer"], program_id)?;
+    check_pda(custody_signer, &[b"custody_signer"], program_id)?;
+    if *accs.token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Amounts are truncated to 8 decimals, as the real bridge does.
+    let decimals = spl_token::state::Mint::unpack(&mint.try_borrow_data()?)?.decimals;
+    let truncation = 10u64
+        .checked_pow(decimals.saturating_sub(8) as u32)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let amount = data.amount - data.amount % truncation;
+    let fee = data.fee - data.fee % truncation;
+    if fee > amount {
+        msg!("Fee {} is larger than the amount {}", fee, amount);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if custody.data_is_empty() {
+        let rent = Rent::get()?;
+        let space = spl_token::state::Account::LEN;
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                custody.key,
+                rent.minimum_balance(space),
+                space as u64,
+                &spl_token::id(),
+            ),
+            &[payer.clone(), custody.clone(), accs.system_program.clone()],
+            &[&[mint.key.as_ref(), &[custody_bump]]],
+        )?;
+        invoke(
+            &spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                custody.key,
+                mint.key,
+                custody_signer.key,
+            )?,
+            &[
+                custody.clone(),
+                mint.clone(),
+                custody_signer.clone(),
+                accs.rent.clone(),
+                accs.token_program.clone(),
+            ],
+        )?;
+    }
+
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            &spl_token::id(),
+            from.key,
+            custody.key,
+            authority_signer.key,
+            &[],
+            amount,
+        )?,
+        &[
+            from.clone(),
+            custody.clone(),
+            authority_signer.clone(),
+            accs.token_program.clone(),
+        ],
+        &[&[b"authority_signer", &[authority_bump]]],
+    )?;
+
+    let payload = transfer_payload(
+        &data,
+        amount / truncation,
+        fee / truncation,
+        mint.key.to_bytes(),
+        CHAIN_ID_SOLANA,
+        accs.sender,
+    )?;
+    post(program_id, payer, &accs, data.nonce, payload)
+}
+
+fn transfer_wrapped(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: Outgoing,
+) -> ProgramResult {
+    let accounts = &mut accounts.iter();
+    let payer = next_account_info(accounts)?;
+    let config = next_account_info(accounts)?;
+    let from = next_account_info(accounts)?;
+    let from_owner = next_account_info(accounts)?;
+    let mint = next_account_info(accounts)?;
+    let wrapped_meta = next_account_info(accounts)?;
+    let authority_signer = next_account_info(accounts)?;
+    let accs = MessageAccounts::next(accounts, data.with_payload.is_some())?;
+
+    check_pda(config, &[b"config"], program_id)?;
+    check_pda(wrapped_meta, &[b"meta", mint.key.as_ref()], program_id)?;
+    let authority_bump = check_pda(authority_signer, &[b"authority_signer"], program_id)?;
+    if *accs.token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let meta = WrappedMeta::try_from_slice(&wrapped_meta.try_borrow_data()?)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    check_pda(
+        mint,
+        &[b"wrapped", &meta.chain.to_be_bytes(), &meta.token_address],
+        program_id,
+    )?;
+
+    let token_account = spl_token::state::Account::unpack(&from.try_borrow_data()?)?;
+    if token_account.owner != *from_owner.key {
+        msg!("{} does not own {}", from_owner.key, from.key);
+        return Err(ProgramError::IllegalOwner);
+    }
+    if data.fee > data.amount {
+        msg!("Fee {} is larger than the amount {}", data.fee, data.amount);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    invoke_signed(
+        &spl_token::instruction::burn(
+            &spl_token::id(),
+            from.key,
+            mint.key,
+            authority_signer.key,
+            &[],
+            data.amount,
+        )?,
+        &[
+            from.clone(),
+            mint.clone(),
+            authority_signer.clone(),
+            accs.token_program.clone(),
+        ],
+        &[&[b"authority_signer", &[authority_bump]]],
+    )?;
+
+    let payload = transfer_payload(
+        &data,
+        data.amount,
+        data.fee,
+        meta.token_address,
+        meta.chain,
+        accs.sender,
+    )?;
+    post(program_id, payer, &accs, data.nonce, payload)
+}
+
+/// Encodes payload 1 (`Transfer`) or, for the `*WithPayload` variants,
+/// payload 3 (`TransferWithPayload`), as the EVM side decodes them.
+fn transfer_payload(
+    data: &Outgoing,
+    amount: u64,
+    fee: u64,
+    token_address: [u8; 32],
+    token_chain: u16,
+    sender: Option<&AccountInfo>,
+) -> Result<Vec<u8>, ProgramError> {
+    let mut payload = Vec::new();
+    payload.push(if data.with_payload.is_some() { 3 } else { 1 });
+    payload.extend_from_slice(&[0u8; 24]);
+    payload.extend_from_slice(&amount.to_be_bytes());
+    payload.extend_from_slice(&token_address);
+    payload.extend_from_slice(&token_chain.to_be_bytes());
+    payload.extend_from_slice(&data.target_address);
+    payload.extend_from_slice(&data.target_chain.to_be_bytes());
+
+    match (&data.with_payload, sender) {
+        (None, _) => {
+            payload.extend_from_slice(&[0u8; 24]);
+            payload.extend_from_slice(&fee.to_be_bytes());
+        }
+        (Some((extra, cpi_program_id)), Some(sender)) => {
+            if !sender.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            // A program sends through its `sender` PDA and is named as the
+            // sender itself; anyone else is named by their own key.
+            let from_address = match cpi_program_id {
+                Some(cpi_program_id) => {
+                    check_pda(sender, &[b"sender"], cpi_program_id)?;
+                    *cpi_program_id
+                }
+                None => *sender.key,
+            };
+            payload.extend_from_slice(from_address.as_ref());
+            payload.extend_from_slice(extra);
+        }
+        (Some(_), None) => return Err(ProgramError::NotEnoughAccountKeys),
+    }
+    Ok(payload)
+}
+
+/// Pays the message fee, if the core bridge charges one, and posts `payload`
+/// from the `emitter` PDA.
+fn post<'a, 'b>(
+    program_id: &Pubkey,
+    payer: &'a AccountInfo<'b>,
+    accs: &MessageAccounts<'a, 'b>,
+    nonce: u32,
+    payload: Vec<u8>,
+) -> ProgramResult {
+    let emitter_bump = check_pda(accs.emitter, &[b"emitter"], program_id)?;
+    if *accs.core_bridge_program.key != core_bridge_stand_in::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if !accs.bridge.data_is_empty() {
+        let bridge = BridgeData::try_from_slice(&accs.bridge.try_borrow_data()?)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if bridge.config.fee > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, accs.fee_collector.key, bridge.config.fee),
+                &[
+                    payer.clone(),
+                    accs.fee_collector.clone(),
+                    accs.system_program.clone(),
+                ],
+            )?;
+        }
+    }
+
+    let ix = SolanaInstruction {
+        program_id: core_bridge_stand_in::id(),
+        accounts: vec![
+            AccountMeta::new(*accs.bridge.key, false),
+            AccountMeta::new(*accs.message.key, true),
+            AccountMeta::new_readonly(*accs.emitter.key, true),
+            AccountMeta::new(*accs.sequence.key, false),
+            AccountMeta::new(*payer.key, true),
+            AccountMeta::new(*accs.fee_collector.key, false),
+            AccountMeta::new_readonly(*accs.clock.key, false),
+            AccountMeta::new_readonly(*accs.rent.key, false),
+            AccountMeta::new_readonly(*accs.system_program.key, false),
+        ],
+        data: (
+            core_bridge_stand_in::Instruction::PostMessage,
+            PostMessageData {
+                nonce,
+                payload,
+                consistency_level: ConsistencyLevel::Finalized,
+            },
+        )
+            .try_to_vec()
+            .map_err(|_| ProgramError::InvalidInstructionData)?,
+    };
+    invoke_signed(
+        &ix,
+        &[
+            accs.bridge.clone(),
+            accs.message.clone(),
+            accs.emitter.clone(),
+            accs.sequence.clone(),
+            payer.clone(),
+            accs.fee_collector.clone(),
+            accs.clock.clone(),
+            accs.rent.clone(),
+            accs.system_program.clone(),
+            accs.core_bridge_program.clone(),
+        ],
+        &[&[b"emitter", &[emitter_bump]]],
+    )
+}